@@ -0,0 +1,256 @@
+# English messages; the fallback for every other locale
+
+## Console
+
+error = Error: { $message }
+interrupt-received = Received interrupt signal. Cleaning up...
+interrupted-by-user = Interrupted by user!
+dat-found = Found DAT file: { $path }
+dat-several = { $count } DATs found; using { $path } (choose another with --dat)
+dat-choose-header = { $count } DATs found:
+dat-choose-prompt = Audit against which one? [1-{ $count }]
+dat-verified = Verified DAT against { $path }
+dat-mame = Detected MAME XML DAT ({ $kind })
+dat-type = DAT type: { $kind }
+dat-forced-type = DAT type: { $kind }, as its header says
+dat-wants-zipped = The DAT's header asks for zipped sets; --output=zip or --output=torrentzip organizes them that way
+dat-wants-unzipped = The DAT's header asks for unzipped sets; --output=files organizes them that way
+dat-parsed = Parsed { $games } games from DAT file
+dat-excluded-tags = Left out { $games } games tagged { $tags }
+dat-excluded-categories = Left out { $games } games in the categories { $categories } (catver.ini)
+dat-language-filter = Language filter: counting { $games } games in { $languages }
+dat-one-g1r = 1G1R: counting the best of each parent/clone group, { $games } games ({ $regions })
+dat-header = DAT: { $header }
+dat-header-skipper = Header skipper: { $name }, as the DAT's header says
+dat-header-skipper-missing = The DAT's header names the header skipper { $file }, which isn't built in or in --header-skippers; trying all of them
+scan-hash-subset = DAT only uses { $algorithms }; skipping other hash algorithms
+scan-quick-no-crc = The DAT has no CRC32 hashes; --quick is ignored
+scan-incremental = Incremental scan: { $total } total files, { $scanning } need scanning, { $cached } cached
+scan-start = Scanning { $files } files to identify games and calculate hashes...
+scan-slow-note = This may take a while for large collections.
+scan-prepass = Fast pre-pass: { $reused } files had content seen before, { $hashed } were fully hashed
+scan-size-prefilter = Size prefilter: { $skipped } files match no ROM size in the DAT and were not hashed
+scan-headerless = Matched { $files } headered dumps by their content without the copier header
+scan-joined = Matched { $files } split files (.001/.002, .z01) by their joined parts
+scan-converted = Matched { $files } byte-swapped or interleaved dumps (.v64, .n64, .smd) by their converted content
+scan-interrupted = Process interrupted during scanning.
+organize-start = Organizing ROMs for { $games } games...
+organize-progress = Organizing files...
+organize-packed = Packed { $files } files into { $archives } game archives
+organize-interrupted = Process interrupted. Partial results may have been saved.
+organize-complete = Organization complete!
+progress-processing = Processing
+progress-hashing = Hashing
+progress-copying = Copying
+scan-games-present = Found { $games } games with files present
+remote-checked = Checked { $files } remote files
+donor-copied = Copied { $roms } missing ROMs
+daemon-listening = Listening on { $socket }
+daemon-progress-elsewhere = Warning: progress already goes to --progress-json/--progress-fd; `status` won't include it
+daemon-connection-failed = Warning: daemon connection failed: { $error }
+organize-confirm-header = About to change files in { $dir }:
+organize-confirm-organized = { $count } copied into { $rom_dir } and the originals deleted
+organize-confirm-duplicates = { $count } moved to a { $prefix }N folder
+organize-confirm-unknown = { $count } moved to an { $prefix }N folder
+organize-confirm-continue = Continue? [y/N]
+organize-confirm-cancelled = Cancelled; nothing was moved or deleted.
+renames-prompt-header = { $count } files will be renamed:
+renames-prompt = Apply these renames? [y/N]
+# Answers to [y/N] prompts that mean yes, separated by commas
+answer-yes = y, yes
+clean-nothing = No duplicate or unknown folders to clean
+clean-folder = { $folder } ({ $files } files, { $size })
+clean-deleted = Deleted { $folders } folders ({ $size })
+clean-dry-run = { $folders } folders, { $size } (dry run, use --apply to delete them)
+layout-interrupted = Layout check interrupted
+layout-misplaced = Misplaced: { $from } -> { $to }
+layout-extra-copy = Extra copy (expected paths already filled): { $path }
+layout-unknown = Unknown (not in DAT): { $path }
+layout-nothing = No misplaced files to fix
+layout-moved = Moved { $files } files to their expected paths
+layout-dry-run = { $files } misplaced files (use --fix to move them)
+verify-failed = { $files } organized files no longer match the DAT
+db-stale-notice = { $entries } entries in { $db } are not in this DAT (run `db prune` to review)
+db-prune-nothing = { $db } matches the DAT, nothing to prune
+db-pruned = Removed { $entries } stale entries from { $db }
+db-prune-dry-run = { $entries } stale entries (dry run, use --apply to remove them)
+db-rebuild-hashing = Hashing { $files } files in { $dir }...
+db-rebuild-interrupted = Rebuild interrupted, { $db } left unchanged
+db-rebuilt = Rebuilt { $db } with { $roms } ROMs
+db-rebuild-misplaced = Misplaced (in DAT, wrong location): { $path }
+db-exported = Exported { $roms } ROMs to { $path }
+db-import-other-dat = { $path } was exported for DAT "{ $dat }" ({ $games } games, sha1 { $sha1 }), not "{ $current }" ({ $current_games } games, sha1 { $current_sha1 })
+db-import-refused = { $message }; use --force to import anyway
+db-import-forced = Warning: { $message }
+db-imported = Imported { $roms } ROMs into { $db }
+db-merge-conflict = Conflict: { $game } / { $rom } is { $ours } here but { $theirs } in { $other } (kept { $ours })
+db-merged = Merged { $entries } new entries from { $other }, { $conflicts } conflicts
+diff-none = No differences in games or ROMs
+diff-summary = Added: { $added }, removed: { $removed }, renamed: { $renamed }, changed: { $changed }
+diff-added = Added games ({ $count }):
+diff-removed = Removed games ({ $count }):
+diff-renamed = Renamed games ({ $count }):
+diff-changed = Games with changed ROMs ({ $count }):
+one-g1r-written = Wrote { $games } of { $total } games, one per parent/clone group, to { $path }
+search-no-match = No games match "{ $query }"
+search-db-only = db only
+search-have = have
+search-missing = missing
+search-not-in-dat = { $game } (in { $db } but not in this DAT)
+search-devices = needs device sets: { $devices }
+volume-plan = Volume { $number }: { $games } games, { $size } ({ $first } .. { $last })
+volume-too-large = Warning: { $game } alone is larger than the volume size
+volumes-written = Wrote { $volumes } volumes to { $dest }
+volume-manifest-header = Volume { $number } of { $count }: { $games } games, { $size }
+volume-manifest-too-large = Warning: larger than the { $size } volume size; this game doesn't fit on one volume
+
+## Summary
+
+summary-complete = Audit complete!
+summary-found = Found: { $have } / { $total } ROMs ({ $percent }%)
+summary-duplicates = Duplicates: { $duplicates }, Unknown: { $unknown }
+quick-unverified = Quick audit: matched by CRC32 and size only, so these results are unverified; run again without --quick to confirm them
+summary-size = Collection size: { $size }, still missing: { $missing } (check { $logs }/stats.txt for details)
+summary-partial = Partial games: { $count } (check { $logs }/partial.txt for missing files)
+summary-shared = Shared ROMs: { $count } (check { $logs }/shared.txt for details)
+summary-folders = Games in folders: { $count } (check { $logs }/folders.txt for details)
+summary-logs = Check the { $logs }/ directory for detailed results.
+summary-unreadable = Unreadable: { $count } skipped (check { $logs }/unreadable.txt for details)
+summary-collisions = Name collisions: { $count } (check { $logs }/collisions.txt for details)
+summary-unknown = Unknown files by likely system: { $count } (check { $logs }/unknown.txt)
+summary-corrupt-zips = Corrupt ZIP archives: { $count } (check { $logs }/corrupt_zips.txt and download them again)
+summary-overdumps = Overdumps: { $count } (check { $logs }/overdumps.txt for details)
+summary-trimmed = Present but trimmed: { $count } (check { $logs }/trimmed.txt for details)
+summary-size-mismatch = Size mismatches: { $count } (check { $logs }/size_mismatch.txt for details)
+summary-bad-dumps = Known bad dumps: { $count } (check { $logs }/bad_dumps.txt for details)
+summary-scrub-ok = Scrub: { $checked } of { $total } organized files verified
+summary-scrub-failed = Scrub: { $failed } of { $checked } verified files FAILED (check { $logs }/scrub.txt for details)
+summary-confirm-ok = Quick audit: { $checked } earlier matches confirmed with full hashes
+summary-confirm-failed = Quick audit: { $failed } of { $checked } earlier matches FAILED with full hashes (check { $logs }/unconfirmed.txt for details)
+summary-dry-run = Dry run: { $count } changes would be made; nothing was changed (check { $logs }/dry_run.txt for the list)
+summary-torrent = Torrent: { $good } of { $files } files good, { $complete } DAT games complete, { $incomplete } incomplete (check { $logs }/torrent.txt for details)
+summary-remote = Remote: { $games } of { $total } games present on { $source } (check { $logs }/remote.txt for details)
+summary-images = Disc images: { $count } (check { $logs }/images.txt for details)
+summary-convertible = Convertible images: { $count } (check { $logs }/convertible.txt for details)
+summary-convertible-counted = { $count } of them are DAT images present in NKit form (not counted as missing)
+summary-convertible-missing = { $count } of them are DAT images present in NKit form (--nkit-as-present leaves them out of missing.txt)
+summary-probable = Probable nodump matches: { $count } (review { $logs }/probable.txt)
+summary-retroachievements = RetroAchievements hashes: { $count } (check { $logs }/retroachievements.txt)
+summary-renamed = Renamed: { $count } (check { $logs }/renames.txt for the rename map)
+summary-would-rename = Would rename: { $count } (check { $logs }/renames.txt for the rename map)
+summary-renames-declined = Renames declined: { $count } (check { $logs }/renames.txt for the rename map)
+
+## Reports
+
+have-header = ROMs Found: { $have } / { $total }
+have-dat-description = { $dat }: the { $have } of { $total } games present
+have-paths-header = # Games found: { $games }, total size: { $size }
+stats-title = Collection size statistics:
+stats-organized = Organized collection: { $size } in { $games } games
+stats-average = Average game size: { $size }
+stats-missing = Missing content: { $size } in { $roms } ROM files
+stats-missing-unsized = ({ $roms } missing ROM files have no size in the DAT and are not counted)
+stats-complete = Complete set: { $size }
+stats-per-system = Per system:
+stats-system = { $system }: have { $have }, missing { $missing }
+missing-header = Missing ROMs: { $missing } / { $total }
+missing-optional = (optional)
+missing-not-dumped = (not dumped; not needed)
+missing-by-size-header = Missing ROM files by size: { $files } files, { $size }
+unknown-size = unknown size
+partial-header = Partial games (some ROM files still missing): { $count }
+partial-game = { $game } ({ $present }/{ $total } ROMs present)
+partial-optional-header = Optional missing (not needed to run the game): { $count }
+shared-title = Shared ROMs (same file content used by multiple games - each has its own copy):
+shared-hash = Hash: { $hash }
+shared-by = Shared by { $games } games:
+shared-rom-names = ROM name(s): { $names }
+shared-summary = Summary:
+shared-total-files = Total shared files: { $count }
+shared-total-games = Total games affected: { $count }
+dedup-title = Dedup savings estimate (hardlinks or solid/merged archives for identical files):
+dedup-shared-files = Shared files: { $count }
+dedup-stored-copies = Stored copies: { $count }
+dedup-space-used = Space used by shared content: { $size }
+dedup-savings = Potential savings: { $size }
+dedup-offenders = Biggest offenders:
+dedup-entry = { $savings } saved ({ $copies } copies of { $size })
+dedup-games = Games: { $games }
+dedup-more = ... and { $count } smaller shared files
+dat-issues-header = DAT entries skipped because they could not be parsed: { $count }
+dat-issue = Line { $line }: { $message }
+unreadable-header = Skipped because they couldn't be read: { $count }
+dry-run-header = Changes organizing would make: { $count }. Nothing was copied, moved or deleted.
+collisions-header = Files with the same name that games without folders would have shared: { $count }
+collisions-folder = Each of these games was given a folder of its own.
+collisions-suffix = Each file was named after its game, e.g. rom (Game).bin.
+unknown-header = Files not in the DAT: { $count }, grouped by the system they most likely belong to
+unknown-note = Paths are where the files were found; they were moved to the numbered unknown folders.
+unknown-note-read-only = Read-only mode; the files were left where they are.
+unknown-note-dry-run = Dry run; the files were left where they are, and would have been moved to the numbered unknown folders.
+unknown-unclassified = Unclassified
+corrupt-zips-header = Corrupt ZIP archives: { $count }
+corrupt-zips-note = These were left in place; download them again before trusting their contents.
+overdumps-header = Overdumps (larger than the DAT entry, their leading bytes matching it): { $count }
+overdumps-trimmed = Trimmed copies were verified and organized; the originals were moved with the unknown files.
+overdumps-hint = They were left in place. Truncate them as suggested, or run with --trim-overdumps to organize verified, trimmed copies.
+overdump-sizes = { $size } bytes, verified size { $verified } + { $padding } bytes of { $byte } padding
+overdump-extra = { $size } bytes, verified size { $verified } + { $extra } extra bytes
+overdump-truncate = suggested truncation: keep the first { $verified } bytes, cutting { $extra }
+overdump-matches = matches { $game } / { $rom }
+trimmed-header = Present but trimmed (smaller than the cartridge, matching once padded back to its size): { $count }
+trimmed-repadded = Padded copies were verified and organized; the originals were moved with the unknown files.
+trimmed-hint = They were left in place. Run with --repad-trimmed to organize verified, padded copies.
+trimmed-sizes = { $system } dump of { $size } bytes, cartridge size { $cart } ({ $missing } bytes of 0xFF padding cut)
+size-mismatch-header = Files matching the DAT by hash but not by size: { $count }
+size-mismatch-note = They were organized as their hash says; a truncated or overdumped file whose CRC32 collides, or a wrong size in the DAT, looks like this.
+size-mismatch-expected = matches { $game } / { $rom }, which the DAT says is { $size } bytes
+bad-dumps-header = Files matching DAT entries marked as bad dumps: { $count }
+bad-dumps-note = No good dump of these is known; they were organized, but are worth replacing once one is.
+scrub-header = Scrub: verified { $checked } of { $total } organized files, { $failed } failed
+confirm-header = Quick-audit matches: checked { $checked } of { $total }, { $failed } failed
+folders-title = Games stored in subfolders:
+folders-multiple = - Games with multiple ROM files
+folders-renamed = - Single ROM games where ROM filename differs from game name
+folders-renamed-note = (ROM keeps its original name from DAT, placed in game-named folder)
+torrent-header = Torrent { $name }: { $good } of { $files } files good, { $pieces } of { $total } pieces verified
+torrent-all-good = Every file verifies; safe to keep seeding.
+torrent-redownload-note = Re-download the files below before seeding.
+torrent-redownload = Re-download: { $count }
+torrent-file-missing = missing
+torrent-file-failed = failed pieces: { $pieces }
+torrent-file-complete = complete
+torrent-would-provide = would provide { $roms }
+torrent-good-in-dat = Good files in the DAT: { $count }
+torrent-good-not-in-dat = Good files not in the DAT: { $count }
+remote-header = Remote audit of { $source }
+remote-files = Files: { $files } ({ $matched } matched the DAT, { $remote } checked with the remote's own hashes)
+remote-games = Games present: { $games } / { $total }
+remote-game = { $game } ({ $present }/{ $total } ROMs)
+remote-not-in-dat = Not in the DAT: { $count }
+images-header = Disc images scanned without mounting: { $count }
+images-note = Files inside images are not organized or counted as owned.
+images-image = { $path } ({ $matched } of { $files } files in the DAT)
+convertible-header = Convertible format detected (convert to ISO to match the DAT): { $count }
+convertible-hint = Set --convert-command to convert them with an external tool.
+convertible-image = { $format } image, ID { $id }, title { $title }
+convertible-unknown = unknown
+convertible-present = present (NKit): { $game } / { $rom }
+probable-header = Probable dumps of nodump entries (matched by name and size only, NOT verified): { $count }
+probable-note = Review these by hand; they are not counted as owned.
+probable-from = from { $path } ({ $size } bytes)
+probable-placed = placed at { $path }
+probable-would-place = would be placed at { $path }
+ra-whole = whole file
+ra-header-skipped = { $bytes } byte header skipped
+ra-big-endian = converted to big-endian
+renames-applied = Files renamed to their DAT names: { $count }
+renames-read-only = Read-only mode; these files would be renamed: { $count }
+renames-dry-run = Dry run; these files would be renamed: { $count }
+renames-declined = Renames declined; these files were left in place: { $count }
+pack-summary-header = DAT pack summary: { $dats } DATs
+pack-audited = { $name } (duplicates: { $duplicates }, unknown: { $unknown })
+pack-no-folder = { $name } (no folder)
+pack-interrupted = { $name } (interrupted)
+pack-failed = { $name } (failed: { $error })
+pack-total = Total: { $have } / { $total } games ({ $percent }%)
@@ -0,0 +1,256 @@
+# Mensajes en español
+
+## Consola
+
+error = Error: { $message }
+interrupt-received = Señal de interrupción recibida. Limpiando...
+interrupted-by-user = ¡Interrumpido por el usuario!
+dat-found = Archivo DAT encontrado: { $path }
+dat-several = { $count } DATs encontrados; se usa { $path } (elige otro con --dat)
+dat-choose-header = { $count } DATs encontrados:
+dat-choose-prompt = ¿Contra cuál auditar? [1-{ $count }]
+dat-verified = DAT verificado con { $path }
+dat-mame = DAT XML de MAME detectado ({ $kind })
+dat-type = Tipo de DAT: { $kind }
+dat-forced-type = Tipo de DAT: { $kind }, según su cabecera
+dat-wants-zipped = La cabecera del DAT pide juegos comprimidos; --output=zip o --output=torrentzip los organiza así
+dat-wants-unzipped = La cabecera del DAT pide juegos sin comprimir; --output=files los organiza así
+dat-parsed = { $games } juegos leídos del archivo DAT
+dat-excluded-tags = Se omiten { $games } juegos con las etiquetas { $tags }
+dat-excluded-categories = Se omiten { $games } juegos de las categorías { $categories } (catver.ini)
+dat-language-filter = Filtro de idioma: se cuentan { $games } juegos en { $languages }
+dat-one-g1r = 1G1R: se cuenta la mejor versión de cada grupo padre/clon, { $games } juegos ({ $regions })
+dat-header = DAT: { $header }
+dat-header-skipper = Omisor de cabecera: { $name }, según la cabecera del DAT
+dat-header-skipper-missing = La cabecera del DAT indica el omisor de cabecera { $file }, que no está incluido ni en --header-skippers; se prueban todos
+scan-hash-subset = El DAT solo usa { $algorithms }; se omiten los demás algoritmos de hash
+scan-quick-no-crc = El DAT no tiene hashes CRC32; se ignora --quick
+scan-incremental = Escaneo incremental: { $total } archivos en total, { $scanning } por escanear, { $cached } en caché
+scan-start = Escaneando { $files } archivos para identificar juegos y calcular hashes...
+scan-slow-note = Esto puede tardar en colecciones grandes.
+scan-prepass = Prepaso rápido: { $reused } archivos tenían contenido ya visto, { $hashed } se calcularon por completo
+scan-size-prefilter = Filtro por tamaño: { $skipped } archivos no coinciden con ningún tamaño de ROM del DAT y no se calcularon
+scan-headerless = Se identificaron { $files } volcados con cabecera por su contenido sin la cabecera del copiador
+scan-joined = Se identificaron { $files } archivos divididos (.001/.002, .z01) por sus partes unidas
+scan-converted = Se identificaron { $files } volcados con bytes intercambiados o entrelazados (.v64, .n64, .smd) por su contenido convertido
+scan-interrupted = Proceso interrumpido durante el escaneo.
+organize-start = Organizando ROMs de { $games } juegos...
+organize-progress = Organizando archivos...
+organize-packed = { $files } archivos empaquetados en { $archives } archivos de juego
+organize-interrupted = Proceso interrumpido. Puede que se hayan guardado resultados parciales.
+organize-complete = ¡Organización completa!
+progress-processing = Procesando
+progress-hashing = Calculando hashes
+progress-copying = Copiando
+scan-games-present = { $games } juegos con archivos presentes
+remote-checked = { $files } archivos remotos comprobados
+donor-copied = { $roms } ROMs que faltaban copiadas
+daemon-listening = Escuchando en { $socket }
+daemon-progress-elsewhere = Aviso: el progreso ya va a --progress-json/--progress-fd; `status` no lo incluirá
+daemon-connection-failed = Aviso: falló una conexión con el daemon: { $error }
+organize-confirm-header = Se van a cambiar archivos en { $dir }:
+organize-confirm-organized = { $count } copiados a { $rom_dir } y los originales borrados
+organize-confirm-duplicates = { $count } movidos a una carpeta { $prefix }N
+organize-confirm-unknown = { $count } movidos a una carpeta { $prefix }N
+organize-confirm-continue = ¿Continuar? [s/N]
+organize-confirm-cancelled = Cancelado; no se movió ni se borró nada.
+renames-prompt-header = Se renombrarán { $count } archivos:
+renames-prompt = ¿Aplicar estos cambios de nombre? [s/N]
+# Respuestas a las preguntas [s/N] que significan sí, separadas por comas
+answer-yes = s, si, sí, y, yes
+clean-nothing = No hay carpetas de duplicados ni de desconocidos que limpiar
+clean-folder = { $folder } ({ $files } archivos, { $size })
+clean-deleted = { $folders } carpetas borradas ({ $size })
+clean-dry-run = { $folders } carpetas, { $size } (simulación, usa --apply para borrarlas)
+layout-interrupted = Comprobación de la estructura interrumpida
+layout-misplaced = Mal ubicado: { $from } -> { $to }
+layout-extra-copy = Copia de más (las rutas esperadas ya están ocupadas): { $path }
+layout-unknown = Desconocido (no está en el DAT): { $path }
+layout-nothing = No hay archivos mal ubicados que corregir
+layout-moved = { $files } archivos movidos a sus rutas esperadas
+layout-dry-run = { $files } archivos mal ubicados (usa --fix para moverlos)
+verify-failed = { $files } archivos organizados ya no coinciden con el DAT
+db-stale-notice = { $entries } entradas de { $db } no están en este DAT (ejecuta `db prune` para revisarlas)
+db-prune-nothing = { $db } coincide con el DAT, no hay nada que podar
+db-pruned = { $entries } entradas obsoletas eliminadas de { $db }
+db-prune-dry-run = { $entries } entradas obsoletas (simulación, usa --apply para eliminarlas)
+db-rebuild-hashing = Calculando hashes de { $files } archivos en { $dir }...
+db-rebuild-interrupted = Reconstrucción interrumpida, { $db } no se modificó
+db-rebuilt = { $db } reconstruido con { $roms } ROMs
+db-rebuild-misplaced = Mal ubicado (en el DAT, ubicación incorrecta): { $path }
+db-exported = { $roms } ROMs exportadas a { $path }
+db-import-other-dat = { $path } se exportó para el DAT "{ $dat }" ({ $games } juegos, sha1 { $sha1 }), no para "{ $current }" ({ $current_games } juegos, sha1 { $current_sha1 })
+db-import-refused = { $message }; usa --force para importarlo de todos modos
+db-import-forced = Aviso: { $message }
+db-imported = { $roms } ROMs importadas en { $db }
+db-merge-conflict = Conflicto: { $game } / { $rom } es { $ours } aquí pero { $theirs } en { $other } (se conserva { $ours })
+db-merged = { $entries } entradas nuevas fusionadas desde { $other }, { $conflicts } conflictos
+diff-none = No hay diferencias en juegos ni ROMs
+diff-summary = Añadidos: { $added }, eliminados: { $removed }, renombrados: { $renamed }, cambiados: { $changed }
+diff-added = Juegos añadidos ({ $count }):
+diff-removed = Juegos eliminados ({ $count }):
+diff-renamed = Juegos renombrados ({ $count }):
+diff-changed = Juegos con ROMs cambiadas ({ $count }):
+one-g1r-written = { $games } de { $total } juegos escritos, uno por grupo padre/clon, en { $path }
+search-no-match = Ningún juego coincide con "{ $query }"
+search-db-only = solo BD
+search-have = tienes
+search-missing = falta
+search-not-in-dat = { $game } (en { $db } pero no en este DAT)
+search-devices = necesita los sets de dispositivos: { $devices }
+volume-plan = Volumen { $number }: { $games } juegos, { $size } ({ $first } .. { $last })
+volume-too-large = Aviso: { $game } por sí solo ocupa más que el tamaño del volumen
+volumes-written = { $volumes } volúmenes escritos en { $dest }
+volume-manifest-header = Volumen { $number } de { $count }: { $games } juegos, { $size }
+volume-manifest-too-large = Aviso: ocupa más que el tamaño de volumen de { $size }; este juego no cabe en un volumen
+
+## Resumen
+
+summary-complete = ¡Auditoría completa!
+summary-found = Encontrados: { $have } / { $total } ROMs ({ $percent }%)
+summary-duplicates = Duplicados: { $duplicates }, Desconocidos: { $unknown }
+quick-unverified = Auditoría rápida: coincidencias solo por CRC32 y tamaño, así que estos resultados no están verificados; ejecuta de nuevo sin --quick para confirmarlos
+summary-size = Tamaño de la colección: { $size }, faltan: { $missing } (ver { $logs }/stats.txt para más detalles)
+summary-partial = Juegos incompletos: { $count } (ver { $logs }/partial.txt para los archivos que faltan)
+summary-shared = ROMs compartidas: { $count } (ver { $logs }/shared.txt para más detalles)
+summary-folders = Juegos en carpetas: { $count } (ver { $logs }/folders.txt para más detalles)
+summary-logs = Consulta el directorio { $logs }/ para ver los resultados detallados.
+summary-unreadable = Ilegibles: { $count } omitidos (ver { $logs }/unreadable.txt para más detalles)
+summary-collisions = Colisiones de nombres: { $count } (ver { $logs }/collisions.txt para más detalles)
+summary-unknown = Archivos desconocidos por sistema probable: { $count } (ver { $logs }/unknown.txt)
+summary-corrupt-zips = Archivos ZIP dañados: { $count } (ver { $logs }/corrupt_zips.txt y descárgalos de nuevo)
+summary-overdumps = Overdumps: { $count } (ver { $logs }/overdumps.txt para más detalles)
+summary-trimmed = Presentes pero recortados: { $count } (ver { $logs }/trimmed.txt para más detalles)
+summary-size-mismatch = Tamaños que no coinciden: { $count } (ver { $logs }/size_mismatch.txt para más detalles)
+summary-bad-dumps = Dumps defectuosos conocidos: { $count } (ver { $logs }/bad_dumps.txt para más detalles)
+summary-scrub-ok = Verificación: { $checked } de { $total } archivos organizados verificados
+summary-scrub-failed = Verificación: { $failed } de { $checked } archivos verificados FALLARON (ver { $logs }/scrub.txt para más detalles)
+summary-confirm-ok = Auditoría rápida: { $checked } coincidencias anteriores confirmadas con hashes completos
+summary-confirm-failed = Auditoría rápida: { $failed } de { $checked } coincidencias anteriores FALLARON con hashes completos (consulta { $logs }/unconfirmed.txt para más detalles)
+summary-dry-run = Simulación: se harían { $count } cambios; no se cambió nada (ver { $logs }/dry_run.txt para la lista)
+summary-torrent = Torrent: { $good } de { $files } archivos correctos, { $complete } juegos del DAT completos, { $incomplete } incompletos (ver { $logs }/torrent.txt para más detalles)
+summary-remote = Remoto: { $games } de { $total } juegos presentes en { $source } (ver { $logs }/remote.txt para más detalles)
+summary-images = Imágenes de disco: { $count } (ver { $logs }/images.txt para más detalles)
+summary-convertible = Imágenes convertibles: { $count } (ver { $logs }/convertible.txt para más detalles)
+summary-convertible-counted = { $count } de ellas son imágenes del DAT presentes en formato NKit (no cuentan como faltantes)
+summary-convertible-missing = { $count } de ellas son imágenes del DAT presentes en formato NKit (--nkit-as-present las deja fuera de missing.txt)
+summary-probable = Coincidencias probables de entradas sin dump: { $count } (revisa { $logs }/probable.txt)
+summary-retroachievements = Hashes de RetroAchievements: { $count } (ver { $logs }/retroachievements.txt)
+summary-renamed = Renombrados: { $count } (ver { $logs }/renames.txt para el mapa de nombres)
+summary-would-rename = Se renombrarían: { $count } (ver { $logs }/renames.txt para el mapa de nombres)
+summary-renames-declined = Renombrados rechazados: { $count } (ver { $logs }/renames.txt para el mapa de nombres)
+
+## Informes
+
+have-header = ROMs encontradas: { $have } / { $total }
+have-dat-description = { $dat }: los { $have } de { $total } juegos presentes
+have-paths-header = # Juegos encontrados: { $games }, tamaño total: { $size }
+stats-title = Estadísticas de tamaño de la colección:
+stats-organized = Colección organizada: { $size } en { $games } juegos
+stats-average = Tamaño medio por juego: { $size }
+stats-missing = Contenido que falta: { $size } en { $roms } archivos ROM
+stats-missing-unsized = ({ $roms } archivos ROM que faltan no tienen tamaño en el DAT y no se cuentan)
+stats-complete = Set completo: { $size }
+stats-per-system = Por sistema:
+stats-system = { $system }: tienes { $have }, faltan { $missing }
+missing-header = ROMs que faltan: { $missing } / { $total }
+missing-optional = (opcional)
+missing-not-dumped = (sin dumpear; no hace falta)
+missing-by-size-header = Archivos ROM que faltan por tamaño: { $files } archivos, { $size }
+unknown-size = tamaño desconocido
+partial-header = Juegos incompletos (aún faltan archivos ROM): { $count }
+partial-game = { $game } ({ $present }/{ $total } ROMs presentes)
+partial-optional-header = Opcionales que faltan (no necesarios para jugar): { $count }
+shared-title = ROMs compartidas (mismo contenido usado por varios juegos; cada uno tiene su copia):
+shared-hash = Hash: { $hash }
+shared-by = Compartida por { $games } juegos:
+shared-rom-names = Nombre(s) de ROM: { $names }
+shared-summary = Resumen:
+shared-total-files = Total de archivos compartidos: { $count }
+shared-total-games = Total de juegos afectados: { $count }
+dedup-title = Ahorro estimado por deduplicación (enlaces duros o archivos sólidos/fusionados para archivos idénticos):
+dedup-shared-files = Archivos compartidos: { $count }
+dedup-stored-copies = Copias almacenadas: { $count }
+dedup-space-used = Espacio usado por contenido compartido: { $size }
+dedup-savings = Ahorro posible: { $size }
+dedup-offenders = Los que más ocupan:
+dedup-entry = { $savings } ahorrados ({ $copies } copias de { $size })
+dedup-games = Juegos: { $games }
+dedup-more = ... y { $count } archivos compartidos más pequeños
+dat-issues-header = Entradas del DAT omitidas por no poder leerse: { $count }
+dat-issue = Línea { $line }: { $message }
+unreadable-header = Omitidos por no poder leerse: { $count }
+dry-run-header = Cambios que haría la organización: { $count }. No se copió, movió ni borró nada.
+collisions-header = Archivos con el mismo nombre que compartirían juegos sin carpeta: { $count }
+collisions-folder = Cada uno de estos juegos recibió su propia carpeta.
+collisions-suffix = Cada archivo lleva el nombre de su juego, p. ej. rom (Juego).bin.
+unknown-header = Archivos que no están en el DAT: { $count }, agrupados por el sistema al que probablemente pertenecen
+unknown-note = Las rutas son donde se encontraron los archivos; se movieron a las carpetas unknown numeradas.
+unknown-note-read-only = Modo de solo lectura; los archivos se dejaron donde estaban.
+unknown-note-dry-run = Simulación; los archivos se dejaron donde estaban, y se habrían movido a las carpetas unknown numeradas.
+unknown-unclassified = Sin clasificar
+corrupt-zips-header = Archivos ZIP dañados: { $count }
+corrupt-zips-note = Se dejaron en su sitio; descárgalos de nuevo antes de fiarte de su contenido.
+overdumps-header = Overdumps (más grandes que la entrada del DAT, sus primeros bytes coinciden con ella): { $count }
+overdumps-trimmed = Las copias recortadas se verificaron y organizaron; los originales se movieron con los archivos desconocidos.
+overdumps-hint = Se dejaron en su sitio. Recórtalos como se sugiere, o ejecuta con --trim-overdumps para organizar copias recortadas y verificadas.
+overdump-sizes = { $size } bytes, tamaño verificado { $verified } + { $padding } bytes de relleno { $byte }
+overdump-extra = { $size } bytes, tamaño verificado { $verified } + { $extra } bytes de más
+overdump-truncate = recorte sugerido: conservar los primeros { $verified } bytes, quitando { $extra }
+overdump-matches = coincide con { $game } / { $rom }
+trimmed-header = Presentes pero recortados (más pequeños que el cartucho, coinciden al rellenarlos hasta su tamaño): { $count }
+trimmed-repadded = Las copias rellenadas se verificaron y organizaron; los originales se movieron con los archivos desconocidos.
+trimmed-hint = Se dejaron en su sitio. Ejecuta con --repad-trimmed para organizar copias rellenadas y verificadas.
+trimmed-sizes = Volcado de { $system } de { $size } bytes, tamaño del cartucho { $cart } ({ $missing } bytes de relleno 0xFF recortados)
+size-mismatch-header = Archivos que coinciden con el DAT por hash pero no por tamaño: { $count }
+size-mismatch-note = Se organizaron según su hash; así se ve un archivo recortado o sobredumpeado cuyo CRC32 colisiona, o un tamaño erróneo en el DAT.
+size-mismatch-expected = coincide con { $game } / { $rom }, que según el DAT ocupa { $size } bytes
+bad-dumps-header = Archivos que coinciden con entradas del DAT marcadas como dumps defectuosos: { $count }
+bad-dumps-note = No se conoce un dump correcto de estos; se organizaron, pero conviene reemplazarlos cuando exista uno.
+scrub-header = Verificación: { $checked } de { $total } archivos organizados verificados, { $failed } fallidos
+confirm-header = Coincidencias de la auditoría rápida: comprobadas { $checked } de { $total }, { $failed } fallaron
+folders-title = Juegos guardados en subcarpetas:
+folders-multiple = - Juegos con varios archivos ROM
+folders-renamed = - Juegos de una sola ROM cuyo nombre de archivo difiere del nombre del juego
+folders-renamed-note = (la ROM conserva su nombre original del DAT, dentro de una carpeta con el nombre del juego)
+torrent-header = Torrent { $name }: { $good } de { $files } archivos correctos, { $pieces } de { $total } piezas verificadas
+torrent-all-good = Todos los archivos se verifican; puedes seguir compartiéndolo.
+torrent-redownload-note = Vuelve a descargar los archivos de abajo antes de compartirlo.
+torrent-redownload = Volver a descargar: { $count }
+torrent-file-missing = falta
+torrent-file-failed = piezas fallidas: { $pieces }
+torrent-file-complete = completo
+torrent-would-provide = aportaría { $roms }
+torrent-good-in-dat = Archivos correctos en el DAT: { $count }
+torrent-good-not-in-dat = Archivos correctos que no están en el DAT: { $count }
+remote-header = Auditoría remota de { $source }
+remote-files = Archivos: { $files } ({ $matched } coinciden con el DAT, { $remote } comprobados con los hashes del propio remoto)
+remote-games = Juegos presentes: { $games } / { $total }
+remote-game = { $game } ({ $present }/{ $total } ROMs)
+remote-not-in-dat = No están en el DAT: { $count }
+images-header = Imágenes de disco escaneadas sin montarlas: { $count }
+images-note = Los archivos dentro de las imágenes no se organizan ni cuentan como propios.
+images-image = { $path } ({ $matched } de { $files } archivos en el DAT)
+convertible-header = Formato convertible detectado (conviértelo a ISO para que coincida con el DAT): { $count }
+convertible-hint = Usa --convert-command para convertirlos con una herramienta externa.
+convertible-image = Imagen { $format }, ID { $id }, título { $title }
+convertible-unknown = desconocido
+convertible-present = presente (NKit): { $game } / { $rom }
+probable-header = Dumps probables de entradas sin dump (identificados solo por nombre y tamaño, NO verificados): { $count }
+probable-note = Revísalos a mano; no cuentan como propios.
+probable-from = de { $path } ({ $size } bytes)
+probable-placed = colocado en { $path }
+probable-would-place = se colocaría en { $path }
+ra-whole = archivo completo
+ra-header-skipped = cabecera de { $bytes } bytes omitida
+ra-big-endian = convertido a big-endian
+renames-applied = Archivos renombrados a su nombre del DAT: { $count }
+renames-read-only = Modo de solo lectura; estos archivos se renombrarían: { $count }
+renames-dry-run = Simulación; estos archivos se renombrarían: { $count }
+renames-declined = Renombrado rechazado; estos archivos se dejaron en su sitio: { $count }
+pack-summary-header = Resumen del paquete de DATs: { $dats } DATs
+pack-audited = { $name } (duplicados: { $duplicates }, desconocidos: { $unknown })
+pack-no-folder = { $name } (sin carpeta)
+pack-interrupted = { $name } (interrumpido)
+pack-failed = { $name } (falló: { $error })
+pack-total = Total: { $have } / { $total } juegos ({ $percent }%)
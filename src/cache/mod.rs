@@ -1,74 +1,98 @@
 // src/cache/mod.rs - Hash cache for performance optimization
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{File, metadata};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
-use blake3;
 
+use crate::config::MtimeTolerance;
 use crate::error::Result;
+use crate::scanner::incremental::mtime_matches;
+use crate::types::FileDigests;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedFileInfo {
     pub path: PathBuf,
-    pub sha1: String,
-    pub md5: String,
-    pub crc: String,
+    pub digests: FileDigests,
     pub size: u64,
     pub modified: SystemTime,
     pub cache_key: String,
+    /// Size of the decompressed payload of a gzip, xz or bzip2 file
+    pub content_size: Option<u64>,
+}
+
+impl CachedFileInfo {
+    /// Size of the ROM the hashes are of
+    pub fn rom_size(&self) -> u64 {
+        self.content_size.unwrap_or(self.size)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HashCache {
     entries: HashMap<String, CachedFileInfo>,
     version: u32,
+    /// Where the cache was loaded from and is saved to
+    #[serde(skip)]
+    path: PathBuf,
+    /// Cache key of each path's latest entry, for lookups with a tolerance
+    #[serde(skip)]
+    keys_by_path: HashMap<PathBuf, String>,
+    /// Digests and ROM size of content seen before, by file size and
+    /// xxHash64, for the fast pre-pass
+    fingerprints: HashMap<(u64, u64), (FileDigests, u64)>,
+    /// File sizes in `fingerprints`; content of any other size is new
+    #[serde(skip)]
+    fingerprint_sizes: HashSet<u64>,
 }
 
 impl HashCache {
-    const CACHE_VERSION: u32 = 1;
-    const CACHE_FILE: &'static str = ".romaudit_cache.bin";
+    const CACHE_VERSION: u32 = 4;
+    pub const CACHE_FILE: &'static str = ".romaudit_cache.bin";
     
     pub fn new() -> Self {
         HashCache {
             entries: HashMap::new(),
             version: Self::CACHE_VERSION,
+            path: PathBuf::from(Self::CACHE_FILE),
+            keys_by_path: HashMap::new(),
+            fingerprints: HashMap::new(),
+            fingerprint_sizes: HashSet::new(),
         }
     }
     
-    /// Load cache from disk
-    pub fn load() -> Result<Self> {
-        let cache_path = Path::new(Self::CACHE_FILE);
-        if !cache_path.exists() {
-            return Ok(Self::new());
-        }
-        
-        let file = File::open(cache_path)?;
+    /// Load the cache from `cache_path` (see `Config::state_path`), starting
+    /// fresh if it is missing, unreadable or from another version
+    pub fn load(cache_path: &Path) -> Self {
+        let fresh = HashCache { path: cache_path.to_path_buf(), ..Self::new() };
+        let Ok(file) = File::open(cache_path) else {
+            return fresh;
+        };
         let mut reader = BufReader::new(file);
         
-        match bincode::deserialize_from(&mut reader) {
-            Ok(cache) => {
-                let cache: HashCache = cache;
-                if cache.version == Self::CACHE_VERSION {
-                    Ok(cache)
-                } else {
-                    // Version mismatch, start fresh
-                    Ok(Self::new())
+        match bincode::deserialize_from::<_, HashCache>(&mut reader) {
+            Ok(cache) if cache.version == Self::CACHE_VERSION => {
+                let mut latest: HashMap<PathBuf, &CachedFileInfo> = HashMap::new();
+                for info in cache.entries.values() {
+                    let entry = latest.entry(info.path.clone()).or_insert(info);
+                    if info.modified > entry.modified {
+                        *entry = info;
+                    }
                 }
+                let keys_by_path = latest.into_iter().map(|(path, info)| (path, info.cache_key.clone())).collect();
+                let fingerprint_sizes = cache.fingerprints.keys().map(|&(size, _)| size).collect();
+                HashCache { path: fresh.path, keys_by_path, fingerprint_sizes, ..cache }
             }
-            Err(_) => {
-                // Cache corrupted or old format, start fresh
-                Ok(Self::new())
-            }
+            // Corrupted, old format or version mismatch
+            _ => fresh,
         }
     }
     
     /// Save cache to disk
     pub fn save(&self) -> Result<()> {
-        let cache_path = Path::new(Self::CACHE_FILE);
-        let file = File::create(cache_path)?;
+        let file = File::create(&self.path)?;
         let mut writer = BufWriter::new(file);
         bincode::serialize_into(&mut writer, self)?;
         Ok(())
@@ -100,8 +124,23 @@ impl HashCache {
         self.entries.get(&cache_key).cloned()
     }
     
-    /// Store file hashes in cache
-    pub fn insert(&mut self, path: &Path, sha1: String, md5: String, crc: String) -> Result<()> {
+    /// Cached hashes for a file whose modification time may have moved within
+    /// `tolerance` (coarse or shifted timestamps) while its size stayed the same
+    pub fn get_within(&self, path: &Path, tolerance: MtimeTolerance) -> Option<CachedFileInfo> {
+        if let Some(info) = self.get(path) {
+            return Some(info);
+        }
+        if tolerance == MtimeTolerance::default() {
+            return None;
+        }
+        let meta = metadata(path).ok()?;
+        let info = self.entries.get(self.keys_by_path.get(path)?)?;
+        (info.size == meta.len() && mtime_matches(info.modified, meta.modified().ok()?, tolerance)).then(|| info.clone())
+    }
+    
+    /// Store file hashes in cache; `content_size` if they are of a
+    /// compressed file's payload
+    pub fn insert(&mut self, path: &Path, digests: FileDigests, content_size: Option<u64>) -> Result<()> {
         let meta = metadata(path)?;
         let size = meta.len();
         let modified = meta.modified()?;
@@ -110,18 +149,34 @@ impl HashCache {
         
         let info = CachedFileInfo {
             path: path.to_path_buf(),
-            sha1,
-            md5,
-            crc,
+            digests,
             size,
             modified,
             cache_key: cache_key.clone(),
+            content_size,
         };
         
+        self.keys_by_path.insert(path.to_path_buf(), cache_key.clone());
         self.entries.insert(cache_key, info);
         Ok(())
     }
     
+    /// Digests and ROM size of earlier content with this size and xxHash64
+    pub fn get_fingerprint(&self, size: u64, fingerprint: u64) -> Option<(FileDigests, u64)> {
+        self.fingerprints.get(&(size, fingerprint)).cloned()
+    }
+    
+    /// Remember the digests of content by its size and xxHash64
+    pub fn insert_fingerprint(&mut self, size: u64, fingerprint: u64, digests: FileDigests, rom_size: u64) {
+        self.fingerprints.insert((size, fingerprint), (digests, rom_size));
+        self.fingerprint_sizes.insert(size);
+    }
+    
+    /// Whether any content of this size has been fingerprinted
+    pub fn has_fingerprints(&self, size: u64) -> bool {
+        self.fingerprint_sizes.contains(&size)
+    }
+    
     /// Remove stale entries (files that no longer exist)
     #[allow(dead_code)]
     pub fn cleanup(&mut self) {
@@ -151,8 +206,6 @@ impl HashCache {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::io::Write;
     
     #[test]
     fn test_cache_key_generation() {
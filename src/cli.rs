@@ -0,0 +1,360 @@
+// src/cli.rs - Command-line arguments
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::config::{CollisionPolicy, ExtensionPolicy, Layout, LynxForm, OutputMode};
+
+/// Audit a ROM collection against a DAT file and organize it
+#[derive(Debug, Default, Parser)]
+#[command(name = "romaudit_cli", version, about, args_override_self = true)]
+pub struct Cli {
+    /// Config file, instead of config.toml in the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<String>,
+
+    /// Use the settings of [profile.NAME] in the config file
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// DAT file to audit against, instead of the first .dat/.xml in the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub dat: Option<String>,
+
+    /// Directory scanned for ROMs, instead of the current directory
+    #[arg(long, global = true, value_name = "DIR")]
+    pub scan: Option<String>,
+
+    /// Directory organized ROMs go to, instead of roms/
+    #[arg(long, global = true, value_name = "DIR")]
+    pub out: Option<String>,
+
+    /// Fail on DAT anomalies (missing hashes, duplicate games, bad sizes)
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Organize trimmed copies of overdumped files
+    #[arg(long, global = true)]
+    pub trim_overdumps: bool,
+
+    /// Organize copies of trimmed NDS/3DS dumps padded back to cartridge size
+    #[arg(long, global = true)]
+    pub repad_trimmed: bool,
+
+    /// Export RetroAchievements hashes of organized ROMs
+    #[arg(long, global = true)]
+    pub ra_hashes: bool,
+
+    /// Also write logs/have.dat, a DAT of the games you have
+    #[arg(long, global = true)]
+    pub have_dat: bool,
+
+    /// Count only the best release of each parent/clone group toward have and missing
+    #[arg(long, global = true)]
+    pub one_game_one_rom: bool,
+
+    /// Regions to prefer for one game, one ROM, best first, e.g. USA,Europe,Japan
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub region_priority: Vec<String>,
+
+    /// Languages to prefer for one game, one ROM, best first, e.g. En,Fr
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub language_priority: Vec<String>,
+
+    /// Audit only games in these languages, e.g. En,De
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub language_filter: Vec<String>,
+
+    /// Leave out games with these name tags [default: Proto,Beta,Demo,Sample,Aftermarket]
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub exclude_tags: Vec<String>,
+
+    /// Keep games with these name tags after all, e.g. Beta,Proto
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub include_tags: Vec<String>,
+
+    /// MAME catver.ini to read categories from [default: catver.ini]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub catver: Option<String>,
+
+    /// Directory of header skipper XMLs for headered dumps (NES, FDS, Lynx and 7800 are built in)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub header_skippers: Option<String>,
+
+    /// Organize headered dumps without their copier header, as the DAT hashes them
+    #[arg(long, global = true)]
+    pub strip_headers: bool,
+
+    /// Organize .v64/.n64 N64 dumps as .z64 and interleaved .smd dumps as .md/.bin, as the DAT hashes them
+    #[arg(long, global = true)]
+    pub convert_dumps: bool,
+
+    /// Store Atari Lynx ROMs as-found, headered (with an LNX header) or headerless
+    #[arg(long, global = true, value_name = "FORM", value_enum)]
+    pub lynx_form: Option<LynxForm>,
+
+    /// Leave out MAME games whose catver.ini category contains one of these, e.g. Mahjong,Casino,Mature
+    #[arg(long, global = true, value_name = "LIST", value_delimiter = ',')]
+    pub exclude_categories: Vec<String>,
+
+    /// Confirm the rename map before organizing
+    #[arg(long, global = true)]
+    pub review_renames: bool,
+
+    /// Write NDJSON progress to stderr instead of drawing progress bars
+    #[arg(long, global = true)]
+    pub progress_json: bool,
+
+    /// Write NDJSON progress to this inherited file descriptor
+    #[arg(long, global = true, value_name = "FD")]
+    pub progress_fd: Option<i32>,
+
+    /// Keep the cache, scan state, database and logs in DIR
+    #[arg(long, global = true, value_name = "DIR")]
+    pub state_dir: Option<String>,
+
+    /// Leave the scanned directory untouched (needs --state-dir)
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Show what organizing would copy, move or delete, without doing it
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Organize without asking first, as scripts need
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Convert NKit/WBFS/WIA/RVZ images with an external tool ({input}, {id})
+    #[arg(long, global = true, value_name = "CMD")]
+    pub convert_command: Option<String>,
+
+    /// Leave games found as NKit images out of missing.txt
+    #[arg(long, global = true)]
+    pub nkit_as_present: bool,
+
+    /// Re-hash this percentage of the organized files
+    #[arg(long, global = true, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub scrub: Option<u8>,
+
+    /// Also scan duplicatesN/ and unknownN/ for missing ROMs
+    #[arg(long, global = true)]
+    pub use_donors: bool,
+
+    /// Report damaged ZIP archives
+    #[arg(long, global = true)]
+    pub verify_zips: bool,
+
+    /// Fingerprint files with xxHash64 first and only fully hash content not seen before
+    #[arg(long, global = true)]
+    pub fast_prepass: bool,
+
+    /// Quick, unverified audit: match files by CRC32 and size only
+    #[arg(long, global = true)]
+    pub quick: bool,
+
+    /// Compute SHA1, MD5 and CRC32 for every file, even those the DAT doesn't use
+    #[arg(long, global = true)]
+    pub all_hashes: bool,
+
+    /// Never memory-map files; read them through buffers (for network filesystems)
+    #[arg(long, global = true)]
+    pub no_mmap: bool,
+
+    /// Don't hash files whose size is more than BYTES away from every ROM in the DAT (default 512)
+    #[arg(long, global = true, value_name = "BYTES", num_args = 0..=1, default_missing_value = "512")]
+    pub size_prefilter: Option<u64>,
+
+    /// Group games in rom_dir: flat, year, decade or manufacturer
+    #[arg(long, global = true, value_name = "LAYOUT", value_enum)]
+    pub layout: Option<Layout>,
+
+    /// Extension of organized files: dat, source or map
+    #[arg(long, global = true, value_name = "POLICY", value_enum)]
+    pub extensions: Option<ExtensionPolicy>,
+
+    /// DAT extension to extension on disk, e.g. bin:gen,smd:md
+    #[arg(long, global = true, value_name = "MAP")]
+    pub extension_map: Vec<String>,
+
+    /// Keep colliding games apart with a folder, a suffix, or fail (error)
+    #[arg(long, global = true, value_name = "POLICY", value_enum)]
+    pub collisions: Option<CollisionPolicy>,
+
+    /// Loose files, or an archive per game: files, torrentzip or zip
+    #[arg(long, global = true, value_name = "MODE", value_enum)]
+    pub output: Option<OutputMode>,
+
+    /// Sanitize names that Windows, exFAT or FAT32 would refuse
+    #[arg(long, global = true)]
+    pub portable_names: bool,
+
+    /// Timestamp tolerance, once per volume: [PATH=]SECONDS[+dst][+verify]
+    #[arg(long, global = true, value_name = "RULE")]
+    pub mtime_tolerance: Vec<String>,
+
+    /// Plain console output, also when NO_COLOR isn't set
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Language of messages and reports, e.g. es
+    #[arg(long, global = true, value_name = "LOCALE")]
+    pub locale: Option<String>,
+
+    /// What to do; without one, `scan`
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Scan for ROMs, match them against the DAT and organize them (the default)
+    Scan,
+    /// Re-hash the organized ROMs and report any that no longer match the DAT
+    Verify {
+        /// Only the least recently verified PERCENT of them
+        #[arg(long, value_name = "PERCENT", default_value_t = 100, value_parser = clap::value_parser!(u8).range(1..=100))]
+        percent: u8,
+    },
+    /// Fill missing ROMs from a donor collection, hashing only candidates
+    #[command(alias = "rebuild-from")]
+    Rebuild {
+        donor: PathBuf,
+    },
+    /// Print collection statistics from the database, without scanning
+    Stats,
+    /// Delete the numbered duplicate and unknown folders earlier runs created
+    Clean {
+        /// Delete them instead of listing them
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Seed the hash cache and database from a clrmamepro/RomVault "have" DAT.
+    /// Its hashes are trusted unverified until the next verify or --scrub run
+    ImportHave {
+        have_dat: PathBuf,
+        root: PathBuf,
+    },
+    /// Maintain the ROM database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Search the DAT for games
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Write a DAT with only the best release of each parent/clone group
+    #[command(name = "1g1r", alias = "one-game-one-rom")]
+    OneGameOneRom {
+        path: PathBuf,
+    },
+    /// Show the games and ROMs added, removed, renamed or changed between two DATs
+    DatDiff {
+        old: PathBuf,
+        new: PathBuf,
+    },
+    /// Hash a file and show what it is in the DAT
+    Identify {
+        path: PathBuf,
+    },
+    /// Audit an SFTP, SMB or HTTP(S) directory or rclone remote without downloading it
+    Remote {
+        source: String,
+    },
+    /// Check a torrent's files against the DAT
+    Torrent {
+        torrent: PathBuf,
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Audit every DAT in a folder against its own subfolder
+    Pack {
+        dat_dir: PathBuf,
+        #[arg(default_value = ".")]
+        root: PathBuf,
+    },
+    /// Copy the collection into volumes of at most SIZE (e.g. 25G)
+    SplitVolumes {
+        max_size: String,
+        dest: PathBuf,
+    },
+    /// Check that every organized file is where the rules put it
+    CheckLayout {
+        /// Move misplaced files where they belong
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Serve JSON-RPC requests on a Unix socket
+    Daemon {
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// List entries that are not in the DAT
+    Prune {
+        /// Remove them instead of listing them
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Rebuild the database by re-hashing rom_dir
+    Rebuild,
+    /// Write the database to a file
+    Export {
+        path: PathBuf,
+    },
+    /// Replace the database with an exported one
+    Import {
+        path: PathBuf,
+        /// Even if the current database isn't empty
+        #[arg(long)]
+        force: bool,
+    },
+    /// Add the entries of another database
+    Merge {
+        other: PathBuf,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::config::{Config, Layout};
+
+    #[test]
+    fn test_paths_and_flags() {
+        let cli = Cli::try_parse_from([
+            "romaudit_cli", "--dat", "sets/snes.dat", "--scan=/mnt/downloads", "--out", "/mnt/roms",
+            "--mtime-tolerance=2", "--mtime-tolerance=/mnt/nas=1+dst", "identify", "game.sfc", "--layout=year",
+        ]).unwrap();
+        assert!(matches!(&cli.command, Some(Command::Identify { path }) if path == Path::new("game.sfc")));
+
+        let config = Config::from_cli(&cli).unwrap();
+        assert_eq!(config.dat_file.as_deref(), Some("sets/snes.dat"));
+        assert_eq!(config.scan_dir, "/mnt/downloads");
+        assert_eq!(config.rom_dir, "/mnt/roms");
+        assert_eq!(config.layout, Layout::Year);
+        assert_eq!(config.mtime_rules.len(), 2);
+
+        assert!(Cli::try_parse_from(["romaudit_cli", "--no-such-flag"]).is_err());
+        let cli = Cli::try_parse_from(["romaudit_cli", "rebuild-from", "donor"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Rebuild { .. })));
+        assert!(Cli::try_parse_from(["romaudit_cli", "verify", "--percent=0"]).is_err());
+        assert!(Cli::try_parse_from(["romaudit_cli", "--scrub=101"]).is_err());
+        assert!(Cli::try_parse_from(["romaudit_cli", "--layout=yearly"]).is_err());
+        assert!(Cli::try_parse_from(["romaudit_cli", "--collisions=rename"]).is_err());
+        let cli = Cli::try_parse_from(["romaudit_cli", "--output=torrentzip"]).unwrap();
+        assert_eq!(cli.output, Some(OutputMode::TorrentZip));
+        let cli = Cli::try_parse_from(["romaudit_cli", "--lynx-form=as-found"]).unwrap();
+        assert_eq!(cli.lynx_form, Some(LynxForm::AsFound));
+        assert!(Cli::try_parse_from(["romaudit_cli", "--lynx-form=lnx"]).is_err());
+        let cli = Cli::try_parse_from(["romaudit_cli", "1g1r", "best.dat", "--region-priority=Europe,USA"]).unwrap();
+        assert!(matches!(&cli.command, Some(Command::OneGameOneRom { path }) if path == Path::new("best.dat")));
+        assert_eq!(cli.region_priority, ["Europe", "USA"]);
+    }
+}
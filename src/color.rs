@@ -0,0 +1,34 @@
+// src/color.rs - Whether console output is colored
+
+use std::ffi::OsString;
+use std::io::IsTerminal;
+
+use crate::config::Config;
+
+/// Color console output unless it isn't going to a terminal, `--no-color`
+/// was given or NO_COLOR is set (https://no-color.org). Progress bars
+/// follow the same setting.
+pub fn init(config: &Config) {
+    let no_color = std::env::var_os("NO_COLOR");
+    console::set_colors_enabled(enabled(config.color, no_color.as_ref(), std::io::stdout().is_terminal()));
+    console::set_colors_enabled_stderr(enabled(config.color, no_color.as_ref(), std::io::stderr().is_terminal()));
+}
+
+/// NO_COLOR only counts when it isn't empty
+fn enabled(configured: bool, no_color: Option<&OsString>, terminal: bool) -> bool {
+    configured && no_color.is_none_or(|value| value.is_empty()) && terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_switches() {
+        assert!(enabled(true, None, true));
+        assert!(enabled(true, Some(&OsString::new()), true));
+        assert!(!enabled(true, Some(&OsString::from("1")), true));
+        assert!(!enabled(false, None, true));
+        assert!(!enabled(true, None, false));
+    }
+}
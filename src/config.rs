@@ -1,38 +1,620 @@
 // src/config.rs - Configuration module
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::cli::Cli;
+use crate::error::{Result, RomAuditError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
+    /// DAT to audit against, instead of the first one in the current directory
+    pub dat_file: Option<String>,
+    /// Directory scanned for ROMs
+    pub scan_dir: String,
     pub rom_dir: String,
     pub logs_dir: String,
+    /// Where name/size matches for nodump entries go; never verified
+    pub probable_dir: String,
     pub db_file: String,
     pub duplicate_prefix: String,
     pub unknown_prefix: String,
     pub buffer_size: usize,
+    /// Files larger than this many bytes are memory-mapped rather than read
+    pub mmap_threshold: u64,
+    /// Read every file through buffers, for filesystems where mmap misbehaves
+    pub no_mmap: bool,
     pub stop_words: Vec<String>,
+    pub report_encoding: ReportEncoding,
+    pub line_ending: LineEnding,
+    pub recover_dat_errors: bool,
+    pub strict: bool,
+    pub per_dat_db: bool,
+    pub trim_overdumps: bool,
+    pub repad_trimmed: bool,
+    pub ra_hashes: bool,
+    /// Also write logs/have.dat, a DAT of only the games that are present
+    pub have_dat: bool,
+    /// Regions to prefer when picking one release per game, best first
+    pub region_priority: Vec<String>,
+    /// Languages to prefer among releases of the same region rank, best first
+    pub language_priority: Vec<String>,
+    /// Count only the best release of each parent/clone group toward have and missing
+    pub one_game_one_rom: bool,
+    /// Count only games in these languages toward have and missing; empty for all
+    pub language_filter: Vec<String>,
+    /// Leave games with these name tags out of the DAT, e.g. `Proto` for `(Proto 2)`
+    pub excluded_tags: Vec<String>,
+    /// MAME catver.ini with the category of every machine; `catver.ini` when unset
+    pub catver: Option<String>,
+    /// Directory of header skipper XMLs (clrmamepro's `headers`), added to
+    /// and replacing the built-in NES, FDS, Lynx and 7800 ones
+    pub header_skippers: Option<String>,
+    /// Leave the copier header out of organized copies of headered dumps
+    pub strip_headers: bool,
+    /// Write byte-swapped N64 dumps and interleaved SMD dumps in the form DATs hash
+    pub convert_dumps: bool,
+    /// Whether Atari Lynx ROMs are stored with the LNX header or without it
+    pub lynx_form: LynxForm,
+    /// Leave games whose catver.ini category contains one of these out of the DAT
+    pub excluded_categories: Vec<String>,
+    pub review_renames: bool,
+    /// Write NDJSON progress to stderr instead of drawing progress bars
+    pub progress_json: bool,
+    /// Write NDJSON progress to this inherited file descriptor instead
+    pub progress_fd: Option<i32>,
+    /// Writable directory for the cache, scan state, database and logs,
+    /// instead of the scanned directory
+    pub state_dir: Option<String>,
+    /// With per-DAT databases, the DAT whose hash cache, scan state and
+    /// scrub state are used; set once the DAT is loaded
+    #[serde(skip)]
+    pub state_namespace: Option<String>,
+    /// Never write to, move or delete anything in the scanned directory
+    pub read_only: bool,
+    /// Scan and match, but only report what organizing would copy, move or
+    /// delete; the hash cache, scan state and logs are still written
+    pub dry_run: bool,
+    /// Organize without first asking to confirm the moves and deletes
+    pub assume_yes: bool,
+    /// External tool run on each NKit/WBFS/WIA/RVZ image, e.g. `nkit-convert {input}`
+    pub convert_command: Option<String>,
+    /// Leave games whose image is present in NKit form out of the missing list
+    pub nkit_as_present: bool,
+    /// Re-verify this percentage of the organized files on each run
+    pub scrub_percent: Option<u8>,
+    /// Scan earlier duplicate and unknown folders as a last-resort source
+    pub use_donors: bool,
+    /// Check unmatched ZIP archives structurally and report the corrupt ones
+    pub verify_zips: bool,
+    /// Reuse the digests of content seen before, recognized by size and xxHash64
+    pub fast_prepass: bool,
+    /// Match by CRC32 and size only, for a fast first pass whose results are
+    /// confirmed by a later full run
+    pub quick: bool,
+    /// Compute SHA1, MD5 and CRC32 even when the DAT lacks some of them, so
+    /// the cache serves DATs that use the others
+    pub all_hashes: bool,
+    /// Skip hashing files whose size is further than this from every DAT ROM
+    /// size; the slack allows for headered dumps
+    pub size_prefilter: Option<u64>,
+    /// Group games into year, decade or manufacturer folders inside rom_dir
+    pub layout: Layout,
+    /// Which extension organized files get when the source's differs from the DAT's
+    pub extension_policy: ExtensionPolicy,
+    /// DAT extension -> extension on disk, for `ExtensionPolicy::Map`
+    pub extension_map: HashMap<String, String>,
+    /// How games whose files would land on the same path are kept apart
+    pub collision_policy: CollisionPolicy,
+    /// Sanitize names in rom_dir that Windows, exFAT or FAT32 would refuse;
+    /// on by default on Windows
+    pub portable_names: bool,
+    /// Loose files, or an archive per game
+    pub output_mode: OutputMode,
+    /// Timestamp tolerances for incremental scanning, most specific path wins
+    pub mtime_rules: Vec<MtimeRule>,
+    /// Color the console output when it goes to a terminal
+    pub color: bool,
+    /// Language for console messages and report headers; detected from the environment if unset
+    pub locale: Option<String>,
+}
+
+/// Text encoding for the .txt reports in the logs directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportEncoding {
+    #[serde(alias = "utf-8")]
+    Utf8,
+    /// UTF-8 with a byte order mark, for legacy Windows tools
+    #[serde(alias = "utf-8-bom")]
+    Utf8Bom,
+}
+
+/// How games are grouped inside rom_dir, from the DAT's game metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Games directly in rom_dir
+    Flat,
+    /// `roms/1991/`, from the game's `<year>`
+    Year,
+    /// `roms/1990s/`
+    Decade,
+    /// `roms/Capcom/`, from the game's `<manufacturer>`
+    Manufacturer,
+}
+
+
+/// File extension of organized files, e.g. for a `.gen` dump of a `.bin` DAT entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionPolicy {
+    /// Use the DAT name as is
+    Dat,
+    /// Keep the extension of the file that was found
+    Source,
+    /// Replace DAT extensions using `extension_map`
+    Map,
+}
+
+/// What to do when games without folders would put files with the same name
+/// in the same directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Give each of the games a folder of its own
+    Folder,
+    /// Append the game name to each file name, e.g. `rom (Game).bin`
+    Suffix,
+    /// Refuse to organize until the DAT or the policy changes
+    Error,
+}
+
+/// The form Atari Lynx ROMs are organized in. Emulators such as Handy and
+/// Mednafen want the 64-byte LNX header, which No-Intro hashes leave out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LynxForm {
+    /// As the dump was found, unless `strip_headers` is set
+    AsFound,
+    /// With an LNX header, made up from the ROM size and game name for
+    /// headerless dumps
+    Headered,
+    /// Without the LNX header
+    Headerless,
+}
+
+/// What the organizer leaves in rom_dir for each game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// Loose files, in a folder per game where the rules call for one
+    #[default]
+    Files,
+    /// `roms/<game>.zip` in TorrentZip form, byte for byte what other
+    /// TorrentZip tools produce from the same files
+    #[value(name = "torrentzip")]
+    TorrentZip,
+    /// `roms/<game>.zip` for games that would get a folder; single-ROM
+    /// games stay loose files
+    Zip,
+}
+
+impl OutputMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputMode::Files => "files",
+            OutputMode::TorrentZip => "torrentzip",
+            OutputMode::Zip => "zip",
+        }
+    }
+}
+
+/// How modification times are compared where timestamps are coarse or shift,
+/// as on FAT32/exFAT and some NAS mounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MtimeTolerance {
+    /// Differences up to this many seconds don't count (2 on FAT32/exFAT)
+    pub granularity_secs: u64,
+    /// Shifts by whole quarter hours (DST, time zone changes) don't count
+    pub ignore_shifts: bool,
+    /// Files that only match thanks to the tolerance are confirmed by
+    /// hashing samples of their contents
+    pub spot_check: bool,
+}
+
+/// A tolerance for the files below `prefix`, relative to the scanned directory
+/// or absolute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MtimeRule {
+    pub prefix: PathBuf,
+    pub tolerance: MtimeTolerance,
+}
+
+impl MtimeRule {
+    /// `[PATH=]SECONDS[+dst][+verify]`, e.g. `2+dst` or `usb=2+verify`
+    fn parse(spec: &str) -> Option<Self> {
+        let (prefix, spec) = spec.rsplit_once('=').unwrap_or(("", spec));
+        let mut parts = spec.split('+');
+        let mut tolerance = MtimeTolerance { granularity_secs: parts.next()?.trim_end_matches('s').parse().ok()?, ..Default::default() };
+        for part in parts {
+            match part {
+                "dst" => tolerance.ignore_shifts = true,
+                "verify" => tolerance.spot_check = true,
+                _ => return None,
+            }
+        }
+        let prefix = Path::new(prefix);
+        Some(MtimeRule { prefix: prefix.strip_prefix(".").unwrap_or(prefix).to_path_buf(), tolerance })
+    }
+
+    fn applies_to(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(".").unwrap_or(path);
+        if self.prefix.is_absolute() {
+            std::env::current_dir().is_ok_and(|cwd| cwd.join(relative).starts_with(&self.prefix))
+        } else {
+            relative.starts_with(&self.prefix)
+        }
+    }
+}
+
+/// Line endings for the .txt reports in the logs directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
+            dat_file: None,
+            scan_dir: ".".to_string(),
             rom_dir: "roms".to_string(),
             logs_dir: "logs".to_string(),
+            probable_dir: "probable".to_string(),
             db_file: "rom_db.json".to_string(),
             duplicate_prefix: "duplicates".to_string(),
             unknown_prefix: "unknown".to_string(),
             buffer_size: 1024 * 1024, // 1MB
+            mmap_threshold: crate::scanner::hasher_optimized::DEFAULT_MMAP_THRESHOLD,
+            no_mmap: false,
             stop_words: vec![
                 "the", "of", "and", "a", "an", "in", "on", "at", "to", "for"
             ].into_iter().map(String::from).collect(),
+            report_encoding: ReportEncoding::Utf8,
+            line_ending: LineEnding::Lf,
+            recover_dat_errors: true,
+            strict: false,
+            per_dat_db: true,
+            trim_overdumps: false,
+            repad_trimmed: false,
+            ra_hashes: false,
+            have_dat: false,
+            region_priority: ["USA", "World", "Europe", "Japan"].map(String::from).to_vec(),
+            language_priority: vec!["En".to_string()],
+            one_game_one_rom: false,
+            language_filter: Vec::new(),
+            excluded_tags: ["Proto", "Beta", "Demo", "Sample", "Aftermarket"].map(String::from).to_vec(),
+            catver: None,
+            header_skippers: None,
+            strip_headers: false,
+            lynx_form: LynxForm::AsFound,
+            convert_dumps: false,
+            excluded_categories: Vec::new(),
+            review_renames: false,
+            progress_json: false,
+            progress_fd: None,
+            state_dir: None,
+            state_namespace: None,
+            read_only: false,
+            dry_run: false,
+            assume_yes: false,
+            convert_command: None,
+            nkit_as_present: false,
+            scrub_percent: None,
+            use_donors: false,
+            verify_zips: false,
+            fast_prepass: false,
+            quick: false,
+            all_hashes: false,
+            size_prefilter: None,
+            layout: Layout::Flat,
+            extension_policy: ExtensionPolicy::Dat,
+            extension_map: HashMap::new(),
+            collision_policy: CollisionPolicy::Folder,
+            portable_names: cfg!(windows),
+            output_mode: OutputMode::Files,
+            mtime_rules: Vec::new(),
+            color: true,
+            locale: None,
         }
     }
 }
 
 impl Config {
-    pub fn load() -> Self {
-        // For now, just use defaults
-        // Could be enhanced to load from config.toml if it exists
-        Config::default()
+    /// Read from the current directory unless --config names another file
+    pub const CONFIG_FILE: &str = "config.toml";
+    
+    /// Defaults, overridden by the config file (and the profile chosen from
+    /// it), overridden by the command-line options
+    pub fn from_cli(cli: &Cli) -> Result<Self> {
+        let path = cli.config.as_deref().unwrap_or(Self::CONFIG_FILE);
+        let mut config = if cli.config.is_some() || Path::new(path).exists() {
+            Config::from_file(Path::new(path), cli.profile.as_deref())?
+        } else if let Some(profile) = &cli.profile {
+            return Err(RomAuditError::ConfigError(format!("--profile={} needs a {} with a [profile.{}] section", profile, path, profile)));
+        } else {
+            Config::default()
+        };
+        
+        // --dat=PATH, --scan=DIR, --out=DIR: work outside the current directory
+        if let Some(dat) = &cli.dat {
+            config.dat_file = Some(dat.clone());
+        }
+        if let Some(scan) = &cli.scan {
+            config.scan_dir = scan.clone();
+        }
+        if let Some(out) = &cli.out {
+            config.rom_dir = out.clone();
+        }
+        
+        config.strict |= cli.strict;
+        config.trim_overdumps |= cli.trim_overdumps;
+        config.repad_trimmed |= cli.repad_trimmed;
+        config.ra_hashes |= cli.ra_hashes;
+        config.have_dat |= cli.have_dat;
+        config.one_game_one_rom |= cli.one_game_one_rom;
+        if !cli.region_priority.is_empty() {
+            config.region_priority = cli.region_priority.clone();
+        }
+        if !cli.language_priority.is_empty() {
+            config.language_priority = cli.language_priority.clone();
+        }
+        if !cli.language_filter.is_empty() {
+            config.language_filter = cli.language_filter.clone();
+        }
+        if !cli.exclude_tags.is_empty() {
+            config.excluded_tags = cli.exclude_tags.clone();
+        }
+        if cli.catver.is_some() {
+            config.catver = cli.catver.clone();
+        }
+        if cli.header_skippers.is_some() {
+            config.header_skippers = cli.header_skippers.clone();
+        }
+        if !cli.exclude_categories.is_empty() {
+            config.excluded_categories = cli.exclude_categories.clone();
+        }
+        config.excluded_tags.retain(|tag| !cli.include_tags.iter().any(|included| included.eq_ignore_ascii_case(tag)));
+        config.review_renames |= cli.review_renames;
+        config.nkit_as_present |= cli.nkit_as_present;
+        config.use_donors |= cli.use_donors;
+        config.strip_headers |= cli.strip_headers;
+        config.convert_dumps |= cli.convert_dumps;
+        config.verify_zips |= cli.verify_zips;
+        config.fast_prepass |= cli.fast_prepass;
+        config.quick |= cli.quick;
+        config.all_hashes |= cli.all_hashes;
+        config.no_mmap |= cli.no_mmap;
+        if cli.size_prefilter.is_some() {
+            config.size_prefilter = cli.size_prefilter;
+        }
+        config.portable_names |= cli.portable_names;
+        
+        // --progress-json / --progress-fd=N: machine-readable progress for wrappers
+        config.progress_json |= cli.progress_json;
+        if cli.progress_fd.is_some() {
+            config.progress_fd = cli.progress_fd;
+        }
+        
+        // --state-dir=DIR: keep cache, scan state, database and logs in DIR
+        // --read-only: leave the scanned directory untouched (needs --state-dir)
+        if let Some(dir) = &cli.state_dir {
+            config.state_dir = Some(dir.clone());
+        }
+        config.read_only |= cli.read_only;
+        // --dry-run: report what organizing would do without doing it
+        config.dry_run |= cli.dry_run;
+        // --yes: don't ask before moving and deleting, as scripts need
+        config.assume_yes |= cli.yes;
+        if let Some(command) = &cli.convert_command {
+            config.convert_command = Some(command.clone());
+        }
+        
+        // --scrub=PERCENT: re-hash a slice of the organized files to catch bit rot
+        if cli.scrub.is_some() {
+            config.scrub_percent = cli.scrub;
+        }
+        
+        // --layout=year|decade|manufacturer: group games inside rom_dir
+        if let Some(layout) = cli.layout {
+            config.layout = layout;
+        }
+        
+        // --extensions=dat|source|map and --extension-map=bin:gen,smd:md
+        if let Some(policy) = cli.extensions {
+            config.extension_policy = policy;
+        }
+        for pair in cli.extension_map.iter().flat_map(|map| map.split(',')) {
+            match pair.split_once(':') {
+                Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+                    let ext = |e: &str| e.trim().trim_start_matches('.').to_lowercase();
+                    config.extension_map.insert(ext(from), ext(to));
+                }
+                _ => eprintln!("Warning: ignoring invalid extension mapping \"{}\" (expected dat:disk)", pair),
+            }
+        }
+        // A mapping alone is enough to select it
+        if !config.extension_map.is_empty() && cli.extensions.is_none() {
+            config.extension_policy = ExtensionPolicy::Map;
+        }
+        
+        // --collisions=folder|suffix|error
+        if let Some(policy) = cli.collisions {
+            config.collision_policy = policy;
+        }
+        
+        // --lynx-form=as-found|headered|headerless
+        if let Some(form) = cli.lynx_form {
+            config.lynx_form = form;
+        }
+        
+        // --output=files|torrentzip|zip
+        if let Some(mode) = cli.output {
+            config.output_mode = mode;
+        }
+        
+        // --mtime-tolerance=[PATH=]SECONDS[+dst][+verify], once per volume
+        for spec in &cli.mtime_tolerance {
+            match MtimeRule::parse(spec) {
+                Some(rule) => config.mtime_rules.push(rule),
+                None => eprintln!("Warning: ignoring invalid --mtime-tolerance={} (expected [PATH=]SECONDS[+dst][+verify])", spec),
+            }
+        }
+        
+        // --locale=es: language of console messages and reports
+        if let Some(locale) = &cli.locale {
+            config.locale = Some(locale.clone());
+        }
+        // --no-color: plain output even on a terminal
+        config.color &= !cli.no_color;
+        
+        if let Some(dir) = &config.state_dir {
+            config.logs_dir = Path::new(dir).join(&config.logs_dir).to_string_lossy().to_string();
+            config.db_file = Path::new(dir).join(&config.db_file).to_string_lossy().to_string();
+        }
+        
+        Ok(config)
+    }
+    
+    /// Settings from a config file. Any field of Config can be set at the top
+    /// level, and `[profile.NAME]` sections set them again for one collection;
+    /// with a profile, its settings win over the top-level ones.
+    pub fn from_file(path: &Path, profile: Option<&str>) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| RomAuditError::ConfigError(format!("{}: {}", path.display(), e));
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let mut table: toml::Table = text.parse().map_err(|e| invalid(&e))?;
+        
+        let profiles = match table.remove("profile") {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => return Err(invalid(&"`profile` must hold [profile.NAME] sections")),
+            None => toml::Table::new(),
+        };
+        if let Some(name) = profile {
+            match profiles.get(name) {
+                Some(toml::Value::Table(overrides)) => table.extend(overrides.clone()),
+                _ => {
+                    let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                    return Err(invalid(&format!("no [profile.{}] (profiles: {})",
+                        name, if names.is_empty() { "none".to_string() } else { names.join(", ") })));
+                }
+            }
+        }
+        
+        // Written as on the command line rather than as MtimeRule fields
+        let mtime_tolerance = table.remove("mtime_tolerance");
+        let mut config: Config = toml::Value::Table(table).try_into().map_err(|e| invalid(&e))?;
+        if let Some(specs) = mtime_tolerance {
+            let specs: Vec<String> = specs.try_into().map_err(|e| invalid(&e))?;
+            for spec in specs {
+                let rule = MtimeRule::parse(&spec)
+                    .ok_or_else(|| invalid(&format!("invalid mtime_tolerance \"{}\" (expected [PATH=]SECONDS[+dst][+verify])", spec)))?;
+                config.mtime_rules.push(rule);
+            }
+        }
+        Ok(config)
+    }
+    
+    /// Timestamp tolerance for a scanned file, from the most specific rule
+    pub fn mtime_tolerance(&self, path: &Path) -> MtimeTolerance {
+        self.mtime_rules.iter()
+            .filter(|rule| rule.applies_to(path))
+            .max_by_key(|rule| rule.prefix.components().count())
+            .map(|rule| rule.tolerance)
+            .unwrap_or_default()
+    }
+    
+    /// Path of a state file (hash cache, scan state), inside state_dir if set
+    /// and named for the DAT with per-DAT databases
+    pub fn state_path(&self, file_name: &str) -> PathBuf {
+        let file_name = match &self.state_namespace {
+            Some(namespace) => crate::database::namespaced_db_file(file_name, namespace),
+            None => file_name.to_string(),
+        };
+        match &self.state_dir {
+            Some(dir) => Path::new(dir).join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+    
+    /// Whether organizing copies, moves and deletes files, rather than
+    /// leaving them alone for --read-only or --dry-run
+    pub fn changes_files(&self) -> bool {
+        !self.read_only && !self.dry_run
+    }
+    
+    /// Fail for commands and options that read games as loose files in
+    /// rom_dir when games are archived instead
+    pub fn require_loose_files(&self, what: &str) -> Result<()> {
+        match self.output_mode {
+            OutputMode::Files => Ok(()),
+            mode => Err(RomAuditError::ConfigError(format!(
+                "{} works on loose files in {} and can't be used with --output={}", what, self.rom_dir, mode.name()
+            ))),
+        }
+    }
+    
+    /// Check the read-only setup and create state_dir
+    pub fn prepare_state_dir(&self) -> Result<()> {
+        match &self.state_dir {
+            Some(dir) => std::fs::create_dir_all(dir).map_err(|e| {
+                RomAuditError::ConfigError(format!("state directory {} is not writable: {}", dir, e))
+            }),
+            None if self.read_only => Err(RomAuditError::ConfigError(
+                "--read-only needs --state-dir=<writable directory> for the cache, database and logs".to_string()
+            )),
+            None => Ok(()),
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_override_top_level_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, r#"
+            layout = "year"
+            mtime_tolerance = ["2+dst"]
+
+            [profile.mame]
+            dat_file = "dats/mame.xml"
+            rom_dir = "/mnt/mame"
+            collision_policy = "suffix"
+        "#).unwrap();
+
+        let config = Config::from_file(&path, None).unwrap();
+        assert_eq!(config.rom_dir, "roms");
+        assert_eq!(config.layout, Layout::Year);
+        assert_eq!(config.mtime_rules.len(), 1);
+
+        let mame = Config::from_file(&path, Some("mame")).unwrap();
+        assert_eq!(mame.dat_file.as_deref(), Some("dats/mame.xml"));
+        assert_eq!(mame.rom_dir, "/mnt/mame");
+        assert_eq!(mame.layout, Layout::Year);
+        assert_eq!(mame.collision_policy, CollisionPolicy::Suffix);
+
+        assert!(Config::from_file(&path, Some("snes")).is_err());
+        std::fs::write(&path, "rom_dirr = \"typo\"").unwrap();
+        assert!(Config::from_file(&path, None).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// src/daemon.rs - Long-running daemon driven over a local socket
+#![cfg_attr(not(unix), allow(dead_code, unused_imports))]
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::error::{Result, RomAuditError};
+use crate::types::ScanResult;
+
+/// Socket file inside the logs directory, unless another path is given
+pub const SOCKET_FILE: &str = "romaudit.sock";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ScanState {
+    Idle,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// What `status` and `results` report about the latest scan
+struct ScanStatus {
+    state: ScanState,
+    error: Option<String>,
+    results: Option<Value>,
+}
+
+/// Keeps the most recent line of the progress stream for `status`
+#[derive(Clone, Default)]
+struct LatestProgress {
+    line: Arc<Mutex<Option<String>>>,
+    pending: Vec<u8>,
+}
+
+impl Write for LatestProgress {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            *self.line.lock().unwrap() = Some(String::from_utf8_lossy(&line[..end]).into_owned());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32000;
+
+/// Summary of a finished audit, as returned by `results`
+fn results_json(result: &ScanResult) -> Value {
+    fn sorted<'a>(items: impl IntoIterator<Item = &'a String>) -> Vec<&'a String> {
+        let mut items: Vec<_> = items.into_iter().collect();
+        items.sort();
+        items
+    }
+    json!({
+        "have": sorted(&result.have),
+        "missing": sorted(&result.missing),
+        "duplicate": sorted(&result.duplicate),
+        "unknown": sorted(&result.unknown),
+    })
+}
+
+/// Build the JSON-RPC response line for one request line
+fn response(id: Value, outcome: std::result::Result<Value, (i64, String)>) -> Value {
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    }
+}
+
+#[cfg(unix)]
+pub use server::serve;
+
+#[cfg(not(unix))]
+pub fn serve(_config: Config, _socket: &std::path::Path, _interrupted: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Result<()> {
+    Err(RomAuditError::ConfigError("daemon mode needs Unix domain sockets".to_string()))
+}
+
+#[cfg(unix)]
+mod server {
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::progress;
+
+    /// How often the accept loop checks for shutdown
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    struct Daemon {
+        config: Config,
+        status: Mutex<ScanStatus>,
+        scan_thread: Mutex<Option<JoinHandle<()>>>,
+        /// Cancels the running scan; reset when a new one starts
+        cancel: Arc<AtomicBool>,
+        shutdown: AtomicBool,
+        progress: Arc<Mutex<Option<String>>>,
+    }
+
+    /// Listen on `socket` for newline-delimited JSON-RPC 2.0 requests until a
+    /// `shutdown` request or Ctrl+C. Methods: `scan`, `status`, `results`,
+    /// `cancel` and `shutdown`.
+    pub fn serve(config: Config, socket: &Path, interrupted: Arc<AtomicBool>) -> Result<()> {
+        let latest = LatestProgress::default();
+        let progress = latest.line.clone();
+        if !progress::set_stream(Box::new(latest)) {
+            eprintln!("{}", tr!("daemon-progress-elsewhere"));
+        }
+        listen(config, socket, interrupted, progress)
+    }
+
+    /// `serve`, with the latest progress line kept in `progress`
+    fn listen(config: Config, socket: &Path, interrupted: Arc<AtomicBool>, progress: Arc<Mutex<Option<String>>>) -> Result<()> {
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(RomAuditError::ConfigError(format!("a daemon is already listening on {}", socket.display())));
+            }
+            // Left over from a daemon that didn't shut down cleanly
+            std::fs::remove_file(socket)?;
+        }
+        if let Some(parent) = socket.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(socket)?;
+        listener.set_nonblocking(true)?;
+
+        let daemon = Arc::new(Daemon {
+            config,
+            status: Mutex::new(ScanStatus { state: ScanState::Idle, error: None, results: None }),
+            scan_thread: Mutex::new(None),
+            cancel: Arc::new(AtomicBool::new(false)),
+            shutdown: AtomicBool::new(false),
+            progress,
+        });
+        println!("{}", tr!("daemon-listening", socket = socket.display().to_string()));
+
+        let outcome = accept_loop(&listener, &daemon, &interrupted);
+
+        // Let a running scan stop at a safe point before exiting
+        daemon.cancel.store(true, Ordering::Relaxed);
+        if let Some(scan) = daemon.scan_thread.lock().unwrap().take() {
+            let _ = scan.join();
+        }
+        let _ = std::fs::remove_file(socket);
+        outcome
+    }
+
+    fn accept_loop(listener: &UnixListener, daemon: &Arc<Daemon>, interrupted: &AtomicBool) -> Result<()> {
+        while !daemon.shutdown.load(Ordering::Relaxed) && !interrupted.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    let daemon = daemon.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &daemon) {
+                            eprintln!("{}", tr!("daemon-connection-failed", error = e.to_string()));
+                        }
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(stream: UnixStream, daemon: &Arc<Daemon>) -> Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(request) => {
+                    let id = request.get("id").cloned().unwrap_or(Value::Null);
+                    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+                    response(id, handle_request(method, daemon))
+                }
+                Err(e) => response(Value::Null, Err((PARSE_ERROR, e.to_string()))),
+            };
+            serde_json::to_writer(&mut writer, &reply)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn handle_request(method: &str, daemon: &Arc<Daemon>) -> std::result::Result<Value, (i64, String)> {
+        let failed = |message: &str| Err((REQUEST_FAILED, message.to_string()));
+        reap_scan(daemon);
+        match method {
+            "scan" => {
+                let mut status = daemon.status.lock().unwrap();
+                if status.state == ScanState::Running {
+                    return failed("a scan is already running");
+                }
+                *status = ScanStatus { state: ScanState::Running, error: None, results: None };
+                *daemon.progress.lock().unwrap() = None;
+                daemon.cancel.store(false, Ordering::Relaxed);
+
+                let worker = daemon.clone();
+                *daemon.scan_thread.lock().unwrap() = Some(thread::spawn(move || run_scan(&worker)));
+                Ok(json!({ "started": true }))
+            }
+            "status" => {
+                let status = daemon.status.lock().unwrap();
+                let progress = daemon.progress.lock().unwrap().as_deref()
+                    .and_then(|line| serde_json::from_str::<Value>(line).ok());
+                Ok(json!({ "state": status.state, "error": status.error, "progress": progress }))
+            }
+            "results" => match &daemon.status.lock().unwrap().results {
+                Some(results) => Ok(results.clone()),
+                None => failed("no finished scan yet"),
+            },
+            "cancel" => {
+                let running = daemon.status.lock().unwrap().state == ScanState::Running;
+                daemon.cancel.store(running, Ordering::Relaxed);
+                Ok(json!({ "cancelled": running }))
+            }
+            "shutdown" => {
+                daemon.shutdown.store(true, Ordering::Relaxed);
+                Ok(json!({ "shutting_down": true }))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method \"{}\"", method))),
+        }
+    }
+
+    /// Collect a scan thread that has ended, marking the scan failed if it
+    /// panicked instead of leaving it running forever
+    fn reap_scan(daemon: &Daemon) {
+        let ended = {
+            let mut scan_thread = daemon.scan_thread.lock().unwrap();
+            if scan_thread.as_ref().is_some_and(JoinHandle::is_finished) { scan_thread.take() } else { None }
+        };
+        if let Some(scan) = ended
+            && scan.join().is_err()
+        {
+            let mut status = daemon.status.lock().unwrap();
+            *status = ScanStatus { state: ScanState::Failed, error: Some("the scan stopped unexpectedly".to_string()), results: None };
+        }
+    }
+
+    fn run_scan(daemon: &Daemon) {
+        let outcome = crate::RomAuditor::new(daemon.config.clone(), daemon.cancel.clone())
+            .and_then(|mut auditor| auditor.run());
+
+        let mut status = daemon.status.lock().unwrap();
+        match outcome {
+            Ok(result) => {
+                status.state = ScanState::Finished;
+                status.results = Some(results_json(&result));
+            }
+            Err(e) => {
+                status.state = ScanState::Failed;
+                status.error = Some(e.to_string());
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn request(stream: &mut UnixStream, reader: &mut impl BufRead, body: &str) -> Value {
+            writeln!(stream, "{}", body).unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str(&line).unwrap()
+        }
+
+        #[test]
+        fn test_daemon_answers_json_rpc_until_shutdown() {
+            let dir = tempfile::tempdir().unwrap();
+            let socket = dir.path().join("test.sock");
+            let server = {
+                let socket = socket.clone();
+                // Not `serve`, which would send the progress of every other test to this daemon
+                thread::spawn(move || listen(Config::default(), &socket, Arc::new(AtomicBool::new(false)), Arc::default()))
+            };
+
+            let mut stream = loop {
+                match UnixStream::connect(&socket) {
+                    Ok(stream) => break stream,
+                    Err(_) => thread::sleep(Duration::from_millis(10)),
+                }
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let status = request(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":1,"method":"status"}"#);
+            assert_eq!(status["id"], 1);
+            assert_eq!(status["result"]["state"], "idle");
+
+            let results = request(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":2,"method":"results"}"#);
+            assert_eq!(results["error"]["code"], REQUEST_FAILED);
+
+            let unknown = request(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":3,"method":"bogus"}"#);
+            assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+
+            let bye = request(&mut stream, &mut reader, r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#);
+            assert_eq!(bye["result"]["shutting_down"], true);
+
+            server.join().unwrap().unwrap();
+            assert!(!socket.exists());
+        }
+
+        #[test]
+        fn test_panicked_scan_is_reported_failed() {
+            let daemon = Arc::new(Daemon {
+                config: Config::default(),
+                status: Mutex::new(ScanStatus { state: ScanState::Running, error: None, results: None }),
+                scan_thread: Mutex::new(Some(thread::spawn(|| panic!("scan failed")))),
+                cancel: Arc::default(),
+                shutdown: AtomicBool::new(false),
+                progress: Arc::default(),
+            });
+            while !daemon.scan_thread.lock().unwrap().as_ref().unwrap().is_finished() {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            let status = handle_request("status", &daemon).unwrap();
+            assert_eq!(status["state"], "failed");
+            assert_eq!(handle_request("cancel", &daemon).unwrap()["cancelled"], false);
+        }
+    }
+}
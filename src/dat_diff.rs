@@ -0,0 +1,231 @@
+// src/dat_diff.rs - What changed between two releases of a DAT
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::error::Result;
+use crate::logger::sort;
+use crate::types::{ParsedDat, RomEntry};
+
+/// Games and ROMs that differ between an old and a new DAT
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DatDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// (old name, new name) of games whose ROMs are the same under another name
+    pub renamed: Vec<(String, String)>,
+    pub changed: Vec<ChangedGame>,
+}
+
+/// A game in both DATs whose ROMs differ
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChangedGame {
+    pub game: String,
+    pub changes: Vec<RomChange>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RomChange {
+    Added(String),
+    Removed(String),
+    /// ROM name, old hash, new hash
+    Hash(String, String, String),
+}
+
+impl DatDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two DATs game by game. A game only in the old DAT whose ROMs are
+/// exactly those of a game only in the new one was renamed, not replaced,
+/// even if the new DAT gives its ROMs more hashes.
+pub fn diff(old: &ParsedDat, new: &ParsedDat) -> DatDiff {
+    let mut diff = DatDiff::default();
+
+    let mut only_old: Vec<&String> = old.all_games.iter().filter(|g| !new.all_games.contains(*g)).collect();
+    let mut only_new: Vec<&String> = new.all_games.iter().filter(|g| !old.all_games.contains(*g)).collect();
+    sort::sort_natural(&mut only_old);
+    sort::sort_natural(&mut only_new);
+
+    // Compare on a hash every ROM of those games has in both DATs
+    let all_roms = only_old.iter().flat_map(|game| old.game_roms.get(*game))
+        .chain(only_new.iter().flat_map(|game| new.game_roms.get(*game)))
+        .flatten()
+        .collect::<Vec<_>>();
+    let kind = (0..CRC).find(|&kind| all_roms.iter().all(|rom| hashes(rom)[kind].is_some())).unwrap_or(CRC);
+
+    // Games without ROMs can't be told apart by content
+    let mut new_by_content: HashMap<Vec<&str>, Vec<&String>> = HashMap::new();
+    for game in &only_new {
+        let content = content(new, game, kind);
+        if !content.is_empty() {
+            new_by_content.entry(content).or_default().push(game);
+        }
+    }
+    let mut renamed_to = Vec::new();
+    for game in only_old {
+        let content = content(old, game, kind);
+        match new_by_content.get_mut(&content).filter(|_| !content.is_empty()).and_then(|games| games.pop()) {
+            Some(new_name) => {
+                renamed_to.push(new_name);
+                diff.renamed.push((game.clone(), new_name.clone()));
+            }
+            None => diff.removed.push(game.clone()),
+        }
+    }
+    diff.added = only_new.into_iter().filter(|g| !renamed_to.contains(g)).cloned().collect();
+
+    let mut both: Vec<&String> = old.all_games.iter().filter(|g| new.all_games.contains(*g)).collect();
+    sort::sort_natural(&mut both);
+    for game in both {
+        let changes = rom_changes(roms(old, game), roms(new, game));
+        if !changes.is_empty() {
+            diff.changed.push(ChangedGame { game: game.clone(), changes });
+        }
+    }
+    diff
+}
+
+fn roms<'a>(dat: &'a ParsedDat, game: &str) -> BTreeMap<&'a str, &'a RomEntry> {
+    dat.game_roms.get(game).into_iter().flatten().map(|rom| (rom.name.as_str(), rom)).collect()
+}
+
+/// A game's ROM hashes of one kind, sorted, whatever the ROMs are called
+fn content<'a>(dat: &'a ParsedDat, game: &str, kind: usize) -> Vec<&'a str> {
+    let mut content: Vec<&str> = dat.game_roms.get(game).into_iter().flatten().filter_map(|rom| hashes(rom)[kind]).collect();
+    content.sort_unstable();
+    content
+}
+
+/// Index of CRC32 in `hashes`, the one nearly every DAT gives
+const CRC: usize = 3;
+
+/// A ROM's SHA256, SHA1, MD5 and CRC32, where the DAT gives them
+fn hashes(rom: &RomEntry) -> [Option<&str>; 4] {
+    [&rom.hashes.sha256, &rom.hashes.sha1, &rom.hashes.md5, &rom.hashes.crc].map(Option::as_deref)
+}
+
+fn rom_changes(old: BTreeMap<&str, &RomEntry>, new: BTreeMap<&str, &RomEntry>) -> Vec<RomChange> {
+    let mut changes = Vec::new();
+    for (name, old_rom) in &old {
+        match new.get(name) {
+            None => changes.push(RomChange::Removed(name.to_string())),
+            Some(new_rom) => {
+                // Compare the hashes both DATs give, so adding a SHA1 isn't a change
+                let mut pairs = hashes(old_rom).into_iter().zip(hashes(new_rom));
+                if let Some((Some(before), Some(after))) = pairs.find(|(a, b)| a.is_some() && b.is_some())
+                    && before != after
+                {
+                    changes.push(RomChange::Hash(name.to_string(), before.to_string(), after.to_string()));
+                }
+            }
+        }
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        changes.push(RomChange::Added(name.to_string()));
+    }
+    changes
+}
+
+/// Print the diff, grouped as added, removed, renamed and changed games
+pub fn write_diff(diff: &DatDiff, out: &mut impl Write) -> Result<()> {
+    if diff.is_empty() {
+        writeln!(out, "{}", tr!("diff-none"))?;
+        return Ok(());
+    }
+    writeln!(out, "{}", tr!("diff-summary", added = diff.added.len(), removed = diff.removed.len(),
+        renamed = diff.renamed.len(), changed = diff.changed.len()))?;
+    if !diff.added.is_empty() {
+        writeln!(out, "\n{}", tr!("diff-added", count = diff.added.len()))?;
+        for game in &diff.added {
+            writeln!(out, "  + {}", game)?;
+        }
+    }
+    if !diff.removed.is_empty() {
+        writeln!(out, "\n{}", tr!("diff-removed", count = diff.removed.len()))?;
+        for game in &diff.removed {
+            writeln!(out, "  - {}", game)?;
+        }
+    }
+    if !diff.renamed.is_empty() {
+        writeln!(out, "\n{}", tr!("diff-renamed", count = diff.renamed.len()))?;
+        for (from, to) in &diff.renamed {
+            writeln!(out, "  {} -> {}", from, to)?;
+        }
+    }
+    if !diff.changed.is_empty() {
+        writeln!(out, "\n{}", tr!("diff-changed", count = diff.changed.len()))?;
+        for changed in &diff.changed {
+            writeln!(out, "  {}", changed.game)?;
+            for change in &changed.changes {
+                match change {
+                    RomChange::Added(rom) => writeln!(out, "    + {}", rom)?,
+                    RomChange::Removed(rom) => writeln!(out, "    - {}", rom)?,
+                    RomChange::Hash(rom, before, after) => writeln!(out, "    ~ {}: {} -> {}", rom, before, after)?,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    fn parse(dat: &str) -> ParsedDat {
+        parse_dat_bytes(dat.as_bytes(), "test".to_string(), ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_diff_dats() {
+        let old = parse(r#"<datafile>
+            <game name="Kept"><rom name="k.bin" crc="11111111"/></game>
+            <game name="Fixed"><rom name="f.bin" crc="22222222"/><rom name="old.bin" crc="33333333"/></game>
+            <game name="Old Title"><rom name="t.bin" crc="44444444"/></game>
+            <game name="Dropped"><rom name="d.bin" crc="55555555"/></game>
+        </datafile>"#);
+        let new = parse(r#"<datafile>
+            <game name="Kept"><rom name="k.bin" crc="11111111" sha1="aaaa"/></game>
+            <game name="Fixed"><rom name="f.bin" crc="66666666"/><rom name="new.bin" crc="77777777"/></game>
+            <game name="New Title"><rom name="t2.bin" crc="44444444"/></game>
+            <game name="Brand New"><rom name="b.bin" crc="88888888"/></game>
+        </datafile>"#);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.added, ["Brand New"]);
+        assert_eq!(diff.removed, ["Dropped"]);
+        assert_eq!(diff.renamed, [("Old Title".to_string(), "New Title".to_string())]);
+        assert_eq!(diff.changed, [ChangedGame {
+            game: "Fixed".to_string(),
+            changes: vec![
+                RomChange::Hash("f.bin".to_string(), "22222222".to_string(), "66666666".to_string()),
+                RomChange::Removed("old.bin".to_string()),
+                RomChange::Added("new.bin".to_string()),
+            ],
+        }]);
+
+        let mut out = Vec::new();
+        write_diff(&diff, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("Old Title -> New Title"));
+    }
+
+    #[test]
+    fn test_rename_that_adds_a_hash() {
+        let old = parse(r#"<datafile>
+            <game name="Old Title"><rom name="t.bin" crc="44444444"/></game>
+            <game name="Dropped"><rom name="d.bin" crc="55555555" sha1="dddd"/></game>
+        </datafile>"#);
+        let new = parse(r#"<datafile>
+            <game name="New Title"><rom name="t2.bin" crc="44444444" sha1="bbbb"/></game>
+        </datafile>"#);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.renamed, [("Old Title".to_string(), "New Title".to_string())]);
+        assert_eq!(diff.removed, ["Dropped"]);
+        assert!(diff.added.is_empty());
+    }
+}
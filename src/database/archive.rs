@@ -0,0 +1,96 @@
+// src/database/archive.rs - Portable export/import of the known-ROMs DB
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::error::{Result, RomAuditError};
+use crate::types::{KnownRoms, ParsedDat};
+
+/// Which DAT an exported DB was audited against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatIdentity {
+    pub name: String,
+    pub sha1: String,
+    pub games: usize,
+}
+
+impl DatIdentity {
+    pub fn from_dat(dat_path: &Path, parsed_dat: &ParsedDat) -> Result<Self> {
+        let mut hasher = Sha1::new();
+        std::io::copy(&mut File::open(dat_path)?, &mut hasher)?;
+
+        Ok(DatIdentity {
+            name: parsed_dat.name.clone(),
+            sha1: hex::encode(hasher.finalize()),
+            games: parsed_dat.all_games.len(),
+        })
+    }
+}
+
+/// Exported audit state: the DB in rom_db.json layout plus the DAT it belongs to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DbArchive {
+    pub version: u32,
+    pub dat: DatIdentity,
+    pub known_roms: serde_json::Value,
+}
+
+impl DbArchive {
+    const ARCHIVE_VERSION: u32 = 1;
+
+    pub fn new(dat: DatIdentity, known_roms: &KnownRoms) -> Self {
+        DbArchive {
+            version: Self::ARCHIVE_VERSION,
+            dat,
+            known_roms: super::known_roms_to_json(known_roms),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let archive: DbArchive = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if archive.version != Self::ARCHIVE_VERSION {
+            return Err(RomAuditError::ParseError(format!(
+                "{} has unsupported archive version {}", path.display(), archive.version
+            )));
+        }
+        Ok(archive)
+    }
+
+    pub fn known_roms(&self) -> KnownRoms {
+        super::known_roms_from_json(&self.known_roms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.json");
+
+        let mut known_roms = KnownRoms::new();
+        known_roms.insert("abc".to_string(), vec![
+            ("Game A".to_string(), "a.bin".to_string()),
+            ("Game B".to_string(), "a.bin".to_string()),
+        ]);
+        let dat = DatIdentity { name: "Test".to_string(), sha1: "00".to_string(), games: 2 };
+
+        DbArchive::new(dat.clone(), &known_roms).save(&path).unwrap();
+        let archive = DbArchive::load(&path).unwrap();
+
+        assert_eq!(archive.dat, dat);
+        assert_eq!(archive.known_roms(), known_roms);
+    }
+}
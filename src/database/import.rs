@@ -0,0 +1,200 @@
+// src/database/import.rs - Seed the hash cache and DB from another ROM manager
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::HashCache;
+use crate::config::Config;
+use crate::error::Result;
+use crate::organizer::processor::rom_destination;
+use crate::organizer::rules::GameFolders;
+use crate::scanner::find_matching_entries;
+use crate::scanner::scrub::ScrubState;
+use crate::types::{FileDigests, HashAlgorithms, KnownRoms, ParsedDat, RomEntry};
+
+/// Outcome of seeding from a "have" DAT
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Files whose hashes were added to the hash cache
+    pub cached: usize,
+    /// ROMs already in place under rom_dir, added to the known-ROMs DB
+    pub known: usize,
+    /// ROMs listed in the have DAT but not found under the root
+    pub not_found: usize,
+    /// Files found on disk with a different size than the have DAT says
+    pub size_mismatch: usize,
+    /// Places in rom_dir the imported files are or will be organized to,
+    /// marked for the next scrub to verify
+    pub unverified: usize,
+}
+
+/// What an import seeds: the hash cache, the known-ROMs DB, and the scrub
+/// state that marks the imported hashes unverified
+pub struct Seeded<'a> {
+    pub cache: &'a mut HashCache,
+    pub known_roms: &'a mut KnownRoms,
+    pub scrub_state: &'a mut ScrubState,
+}
+
+/// Seed the hash cache (and the known-ROMs DB for files already organized)
+/// from a "have" DAT exported by clrmamepro or RomVault.
+///
+/// Both tools keep their scan state in undocumented binary files, but can
+/// export what they have as a regular Logiqx DAT. Each listed ROM is looked
+/// up under `root` as `game/rom` (split layout) or `rom` (flat layout); if it
+/// exists with the expected size, its hashes are trusted instead of re-hashing.
+/// Nothing checks them until then, so wherever a file with those hashes is
+/// organized to is marked unverified, for the next scrub or verify to re-hash.
+pub fn import_have_dat(
+    have: &ParsedDat,
+    root: &Path,
+    current: &ParsedDat,
+    games_needing_folders: &GameFolders,
+    config: &Config,
+    seeded: Seeded,
+) -> Result<ImportSummary> {
+    let Seeded { cache, known_roms, scrub_state } = seeded;
+    let mut summary = ImportSummary::default();
+    let algorithms = HashAlgorithms::from_rom_db(&current.rom_db);
+
+    let mut games: Vec<_> = have.game_roms.iter().collect();
+    games.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (_, roms) in games {
+        for rom in roms {
+            let Some(path) = locate_rom(root, rom) else {
+                summary.not_found += 1;
+                continue;
+            };
+
+            if let Some(size) = rom.size
+                && fs::metadata(&path)?.len() != size
+            {
+                summary.size_mismatch += 1;
+                continue;
+            }
+
+            // Only keep the digests the current DAT uses, like a fresh scan would
+            let pick = |enabled: bool, hash: &Option<String>| {
+                if enabled { hash.clone().unwrap_or_default() } else { String::new() }
+            };
+            let digests = FileDigests {
+                sha1: pick(algorithms.sha1, &rom.hashes.sha1),
+                md5: pick(algorithms.md5, &rom.hashes.md5),
+                crc: pick(algorithms.crc, &rom.hashes.crc),
+                sha256: pick(algorithms.sha256, &rom.hashes.sha256),
+            };
+            let primary_hash = digests.primary().to_string();
+            if primary_hash.is_empty() {
+                continue;
+            }
+
+            let size = fs::metadata(&path)?.len();
+            for entry in find_matching_entries(&current.rom_db, &digests, size) {
+                if scrub_state.unverified.insert(games_needing_folders.locate(&entry, rom_destination(&entry, games_needing_folders, &config.rom_dir))) {
+                    summary.unverified += 1;
+                }
+            }
+            cache.insert(&path, digests, None)?;
+            summary.cached += 1;
+
+            // Files already at their organized location count as known ROMs
+            if let Some(entry) = current.game_roms.get(&rom.game)
+                .and_then(|entries| entries.iter().find(|e| e.name == rom.name))
+                && is_same_file(&path, &games_needing_folders.locate(entry, rom_destination(entry, games_needing_folders, &config.rom_dir)))
+            {
+                let entries = known_roms.entry(primary_hash).or_default();
+                let known = (entry.game.clone(), entry.name.clone());
+                if !entries.contains(&known) {
+                    entries.push(known);
+                    summary.known += 1;
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Find a ROM from the have DAT under the given root
+fn locate_rom(root: &Path, rom: &RomEntry) -> Option<PathBuf> {
+    // The scanner sees paths relative to the current directory as "./..."
+    let root = if root.is_relative() && !root.starts_with(".") {
+        Path::new(".").join(root)
+    } else {
+        root.to_path_buf()
+    };
+
+    let mut split = root.join(&rom.game);
+    let mut flat = root.clone();
+    for part in rom.name.split(&['\\', '/'][..]) {
+        split = split.join(part);
+        flat = flat.join(part);
+    }
+
+    [split, flat].into_iter().find(|p| p.is_file())
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    #[test]
+    fn test_import_seeds_cache_and_known_roms() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("roms")).unwrap();
+        fs::write(root.join("roms").join("Game A.bin"), b"AAAA").unwrap();
+        fs::create_dir_all(root.join("elsewhere")).unwrap();
+        fs::write(root.join("elsewhere").join("Game B.bin"), b"BB").unwrap();
+
+        let dat = br#"<datafile>
+    <game name="Game A"><rom name="Game A.bin" size="4" crc="0000000a" sha1="aaaa"/></game>
+    <game name="Game B"><rom name="Game B.bin" size="3" crc="0000000b" sha1="bbbb"/></game>
+    <game name="Game C"><rom name="Game C.bin" size="1" crc="0000000c" sha1="cccc"/></game>
+</datafile>"#;
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+
+        let config = Config {
+            rom_dir: root.join("roms").to_string_lossy().to_string(),
+            ..Config::default()
+        };
+        let mut cache = HashCache::new();
+        let mut known_roms = KnownRoms::new();
+        let mut scrub_state = ScrubState::load(&root.join("scrub_state.json"));
+
+        // Game A is organized, Game B has the wrong size, Game C is absent
+        let summary = import_have_dat(
+            &parsed,
+            &root.join("roms"),
+            &parsed,
+            &GameFolders::default(),
+            &config,
+            Seeded { cache: &mut cache, known_roms: &mut known_roms, scrub_state: &mut scrub_state },
+        ).unwrap();
+
+        assert_eq!(summary, ImportSummary { cached: 1, known: 1, not_found: 2, size_mismatch: 0, unverified: 1 });
+        assert_eq!(known_roms["aaaa"], vec![("Game A".to_string(), "Game A.bin".to_string())]);
+        assert!(cache.get(&root.join("roms").join("Game A.bin")).is_some());
+        // The imported hashes are trusted only until the next scrub
+        assert!(scrub_state.unverified.contains(&root.join("roms").join("Game A.bin")));
+
+        let summary = import_have_dat(
+            &parsed,
+            &root.join("elsewhere"),
+            &parsed,
+            &GameFolders::default(),
+            &config,
+            Seeded { cache: &mut cache, known_roms: &mut known_roms, scrub_state: &mut scrub_state },
+        ).unwrap();
+        assert_eq!(summary.size_mismatch, 1);
+    }
+}
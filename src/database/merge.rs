@@ -0,0 +1,92 @@
+// src/database/merge.rs - Merge another machine's known-ROMs DB into ours
+
+use std::collections::HashMap;
+
+use crate::types::KnownRoms;
+
+/// A ROM both databases know, but with different hashes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub game: String,
+    pub rom: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    /// Entries only the other DB had
+    pub added: usize,
+    /// Sorted by game and ROM; our entry is always the one kept
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Union `theirs` into `ours`. A game/ROM pair recorded under different
+/// hashes keeps our hash and is reported as a conflict.
+pub fn merge_known_roms(ours: &mut KnownRoms, theirs: &KnownRoms) -> MergeSummary {
+    let mut our_hashes: HashMap<(String, String), String> = HashMap::new();
+    for (hash, entries) in ours.iter() {
+        for (game, rom) in entries {
+            our_hashes.insert((game.clone(), rom.clone()), hash.clone());
+        }
+    }
+
+    let mut incoming: Vec<(&String, &String, &String)> = theirs.iter()
+        .flat_map(|(hash, entries)| entries.iter().map(move |(game, rom)| (game, rom, hash)))
+        .collect();
+    incoming.sort();
+
+    let mut summary = MergeSummary::default();
+    for (game, rom, hash) in incoming {
+        let key = (game.clone(), rom.clone());
+        match our_hashes.get(&key) {
+            Some(ours_hash) if ours_hash == hash => {}
+            Some(ours_hash) => summary.conflicts.push(MergeConflict {
+                game: game.clone(),
+                rom: rom.clone(),
+                ours: ours_hash.clone(),
+                theirs: hash.clone(),
+            }),
+            None => {
+                ours.entry(hash.clone()).or_default().push(key.clone());
+                our_hashes.insert(key, hash.clone());
+                summary.added += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(game: &str, rom: &str) -> (String, String) {
+        (game.to_string(), rom.to_string())
+    }
+
+    #[test]
+    fn test_merge_unions_and_keeps_ours_on_conflict() {
+        let mut ours = KnownRoms::new();
+        ours.insert("h1".to_string(), vec![entry("A", "a.bin")]);
+        ours.insert("h2".to_string(), vec![entry("B", "b.bin")]);
+
+        let mut theirs = KnownRoms::new();
+        theirs.insert("h1".to_string(), vec![entry("A", "a.bin"), entry("C", "a.bin")]);
+        theirs.insert("h9".to_string(), vec![entry("B", "b.bin")]);
+
+        let summary = merge_known_roms(&mut ours, &theirs);
+
+        assert_eq!(summary.added, 1);
+        assert_eq!(ours["h1"], vec![entry("A", "a.bin"), entry("C", "a.bin")]);
+        assert_eq!(ours["h2"], vec![entry("B", "b.bin")]);
+        assert!(!ours.contains_key("h9"));
+        assert_eq!(summary.conflicts, vec![MergeConflict {
+            game: "B".to_string(),
+            rom: "b.bin".to_string(),
+            ours: "h2".to_string(),
+            theirs: "h9".to_string(),
+        }]);
+    }
+}
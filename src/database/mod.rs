@@ -1,53 +1,161 @@
 // src/database/mod.rs - Database module
 
+pub mod import;
+pub mod prune;
+pub mod rebuild;
+pub mod archive;
+pub mod merge;
+
 use std::fs::{self, File};
-use std::collections::HashMap;
-use serde_json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
+use crate::cache::HashCache;
+use crate::config::Config;
 use crate::error::Result;
-use crate::types::KnownRoms;
+use crate::types::{KnownRoms, ParsedDat};
+
+/// Key of the names sanitized on disk (name on disk -> DAT name) in a
+/// database file; no game name starts with `$`
+pub const PATH_NAMES_KEY: &str = "$path_names";
+
+/// Database file for one DAT: "rom_db.json" becomes "rom_db.<namespace>.json"
+pub fn namespaced_db_file(db_file: &str, namespace: &str) -> String {
+    let path = Path::new(db_file);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(db_file);
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("json");
+    let namespace: String = namespace.chars()
+        .map(|c| if c.is_control() || "<>:\"/\\|?*".contains(c) { '_' } else { c })
+        .collect();
+
+    path.with_file_name(format!("{}.{}.{}", stem, namespace.trim(), extension))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Whether a file name is one of our database files (shared, per-DAT or
+/// backup). `db_file` may itself already be a per-DAT name.
+pub fn is_database_file(file_name: &str, db_file: &str) -> bool {
+    let base = Path::new(db_file).file_name().and_then(|n| n.to_str()).unwrap_or(db_file);
+    let prefix = base.split('.').next().unwrap_or(base);
+    let file_name = file_name.strip_suffix(".bak").unwrap_or(file_name);
+
+    file_name == base || (file_name.starts_with(&format!("{}.", prefix)) && file_name.ends_with(".json"))
+}
+
+/// With per-DAT databases enabled, point the config at this DAT's database
+/// and state files. On first use, this DAT's entries are copied out of the
+/// shared database, and the shared hash cache is copied since it only
+/// records file contents.
+pub fn use_dat_database(config: &mut Config, parsed_dat: &ParsedDat) -> Result<()> {
+    if !config.per_dat_db {
+        return Ok(());
+    }
+
+    let shared_db = config.db_file.clone();
+    let dat_db = namespaced_db_file(&shared_db, parsed_dat.db_namespace());
+
+    if !Path::new(&dat_db).exists() && Path::new(&shared_db).exists() {
+        let mut known_roms = load_known_roms(&shared_db)?;
+        let other_dats = prune::find_stale_entries(&known_roms, &parsed_dat.game_roms);
+        prune::remove_entries(&mut known_roms, &other_dats);
+
+        if !known_roms.is_empty() {
+            save_known_roms(&known_roms, &dat_db)?;
+            let rom_count: usize = known_roms.values().map(Vec::len).sum();
+            println!("Copied {} entries for this DAT from {} to {}", rom_count, shared_db, dat_db);
+        }
+    }
+
+    config.db_file = dat_db;
+
+    let shared_cache = config.state_path(HashCache::CACHE_FILE);
+    config.state_namespace = Some(parsed_dat.db_namespace().to_string());
+    let dat_cache = config.state_path(HashCache::CACHE_FILE);
+    if !dat_cache.exists() && shared_cache.exists() {
+        fs::copy(&shared_cache, &dat_cache)?;
+    }
+    Ok(())
+}
 
 /// Load known ROMs from database file
 pub fn load_known_roms(db_file: &str) -> Result<KnownRoms> {
     match File::open(db_file) {
         Ok(file) => {
             let value: serde_json::Value = serde_json::from_reader(&file)?;
-            let mut known_roms = KnownRoms::new();
-
-            if let Some(obj) = value.as_object() {
-                for (game_name, roms_obj) in obj {
-                    if let Some(roms) = roms_obj.as_object() {
-                        for (hash, rom_name_val) in roms {
-                            if let Some(rom_name) = rom_name_val.as_str() {
-                                known_roms.entry(hash.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push((game_name.clone(), rom_name.to_string()));
-                            }
-                        }
-                    } else if let Some(game_val) = roms_obj.as_str() {
-                        // Old format compatibility
-                        known_roms.entry(game_name.clone())
-                            .or_insert_with(Vec::new)
-                            .push((game_val.to_string(), String::new()));
+            Ok(known_roms_from_json(&value))
+        }
+        Err(_) => Ok(KnownRoms::new()),
+    }
+}
+
+/// Read known ROMs from the rom_db.json structure (game -> hash -> ROM name)
+pub fn known_roms_from_json(value: &serde_json::Value) -> KnownRoms {
+    let mut known_roms = KnownRoms::new();
+
+    if let Some(obj) = value.as_object() {
+        for (game_name, roms_obj) in obj.iter().filter(|(key, _)| *key != PATH_NAMES_KEY) {
+            if let Some(roms) = roms_obj.as_object() {
+                for (hash, rom_name_val) in roms {
+                    if let Some(rom_name) = rom_name_val.as_str() {
+                        known_roms.entry(hash.clone())
+                            .or_default()
+                            .push((game_name.clone(), rom_name.to_string()));
                     }
                 }
+            } else if let Some(game_val) = roms_obj.as_str() {
+                // Old format compatibility
+                known_roms.entry(game_name.clone())
+                    .or_default()
+                    .push((game_val.to_string(), String::new()));
             }
-
-            Ok(known_roms)
         }
-        Err(_) => Ok(KnownRoms::new()),
     }
+
+    known_roms
+}
+
+/// Names sanitized on disk recorded in a database file: name on disk -> DAT name
+pub fn load_path_names(db_file: &str) -> BTreeMap<String, String> {
+    File::open(db_file).ok()
+        .and_then(|file| serde_json::from_reader::<_, serde_json::Value>(file).ok())
+        .and_then(|mut value| serde_json::from_value(value.get_mut(PATH_NAMES_KEY)?.take()).ok())
+        .unwrap_or_default()
 }
 
 /// Save known ROMs to database file
 pub fn save_known_roms(known_roms: &KnownRoms, db_file: &str) -> Result<()> {
+    save_database(known_roms, &BTreeMap::new(), db_file)
+}
+
+/// Save known ROMs along with names sanitized on disk, added to the ones
+/// recorded earlier
+pub fn save_database(known_roms: &KnownRoms, path_names: &BTreeMap<String, String>, db_file: &str) -> Result<()> {
+    let mut result = known_roms_to_json(known_roms);
+    let mut all_names = load_path_names(db_file);
+    all_names.extend(path_names.iter().map(|(on_disk, dat)| (on_disk.clone(), dat.clone())));
+    if !all_names.is_empty() && let Some(obj) = result.as_object_mut() {
+        obj.insert(PATH_NAMES_KEY.to_string(), serde_json::to_value(all_names)?);
+    }
+
+    // Write to temporary file first, then rename atomically
+    let temp_file = format!("{}.tmp", db_file);
+    let file = File::create(&temp_file)?;
+    serde_json::to_writer_pretty(file, &result)?;
+    fs::rename(temp_file, db_file)?;
+
+    Ok(())
+}
+
+/// Convert known ROMs to the rom_db.json structure, sorted for stable output
+pub fn known_roms_to_json(known_roms: &KnownRoms) -> serde_json::Value {
     // Group by game name for better organization
     let mut games_map: HashMap<String, Vec<(String, String)>> = HashMap::new();
 
     for (hash, entries) in known_roms {
         for (game, rom) in entries {
             games_map.entry(game.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push((hash.clone(), rom.clone()));
         }
     }
@@ -59,8 +167,8 @@ pub fn save_known_roms(known_roms: &KnownRoms, db_file: &str) -> Result<()> {
     // Create final structure
     let mut result = serde_json::Map::new();
     for (game, mut roms) in sorted_games {
-        // Sort ROMs within each game
-        roms.sort_by(|a, b| a.1.cmp(&b.1));
+        // Sort ROMs within each game (by name, then hash, so output is stable)
+        roms.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
 
         let rom_entries: serde_json::Map<String, serde_json::Value> = roms
             .into_iter()
@@ -70,11 +178,59 @@ pub fn save_known_roms(known_roms: &KnownRoms, db_file: &str) -> Result<()> {
         result.insert(game, serde_json::Value::Object(rom_entries));
     }
 
-    // Write to temporary file first, then rename atomically
-    let temp_file = format!("{}.tmp", db_file);
-    let file = File::create(&temp_file)?;
-    serde_json::to_writer_pretty(file, &result)?;
-    fs::rename(temp_file, db_file)?;
+    serde_json::Value::Object(result)
+}
 
-    Ok(())
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaced_db_file() {
+        assert_eq!(namespaced_db_file("rom_db.json", "Nintendo - Game Boy"), "rom_db.Nintendo - Game Boy.json");
+        assert_eq!(namespaced_db_file("data/db.json", "A/B: C"), "data/db.A_B_ C.json");
+
+        assert!(is_database_file("rom_db.json", "rom_db.json"));
+        assert!(is_database_file("rom_db.Nintendo - Game Boy.json", "rom_db.json"));
+        assert!(is_database_file("rom_db.json.bak", "rom_db.json"));
+        assert!(is_database_file("rom_db.json", "rom_db.Nintendo - Game Boy.json"));
+        assert!(!is_database_file("rom_db.bin", "rom_db.json"));
+    }
+
+    #[test]
+    fn test_state_files_per_dat() {
+        use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+        use crate::scanner::incremental::IncrementalScanState;
+
+        let dir = tempfile::tempdir().unwrap();
+        let rom = dir.path().join("a.bin");
+        fs::write(&rom, b"data").unwrap();
+        fs::write(dir.path().join(HashCache::CACHE_FILE), b"").unwrap();
+        let base = Config {
+            state_dir: Some(dir.path().to_string_lossy().to_string()),
+            db_file: dir.path().join("rom_db.json").to_string_lossy().to_string(),
+            ..Config::default()
+        };
+        let dat = |name: &str| parse_dat_bytes(b"<datafile></datafile>", name.to_string(), ParseOptions::default()).unwrap();
+        let switch_to = |name: &str| {
+            let mut config = base.clone();
+            use_dat_database(&mut config, &dat(name)).unwrap();
+            config
+        };
+
+        // Files scanned against one DAT are rescanned under another
+        let system_a = switch_to("System A");
+        let mut state = IncrementalScanState::load(&system_a.state_path(IncrementalScanState::STATE_FILE));
+        state.update_file(&rom, "sha1".to_string(), Default::default()).unwrap();
+        state.save().unwrap();
+
+        let system_b = switch_to("System B");
+        assert_ne!(system_a.state_path(HashCache::CACHE_FILE), system_b.state_path(HashCache::CACHE_FILE));
+        assert!(system_b.state_path(HashCache::CACHE_FILE).exists());
+        let state = IncrementalScanState::load(&system_b.state_path(IncrementalScanState::STATE_FILE));
+        assert!(state.needs_rescan(&rom, Default::default()));
+
+        let state = IncrementalScanState::load(&switch_to("System A").state_path(IncrementalScanState::STATE_FILE));
+        assert!(!state.needs_rescan(&rom, Default::default()));
+    }
+}
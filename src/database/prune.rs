@@ -0,0 +1,75 @@
+// src/database/prune.rs - Reconcile the known-ROMs DB with the current DAT
+
+use std::collections::HashSet;
+
+use crate::types::{GameRoms, KnownRoms};
+
+/// A DB entry whose game/ROM pair no longer exists in the DAT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleEntry {
+    pub hash: String,
+    pub game: String,
+    pub rom: String,
+}
+
+/// Find DB entries with no corresponding DAT entry, sorted by game and ROM
+pub fn find_stale_entries(known_roms: &KnownRoms, game_roms: &GameRoms) -> Vec<StaleEntry> {
+    let dat_roms: HashSet<(&str, &str)> = game_roms.iter()
+        .flat_map(|(game, roms)| roms.iter().map(move |rom| (game.as_str(), rom.name.as_str())))
+        .collect();
+
+    let mut stale: Vec<StaleEntry> = known_roms.iter()
+        .flat_map(|(hash, entries)| entries.iter().map(move |entry| (hash, entry)))
+        .filter(|(_, (game, rom))| !dat_roms.contains(&(game.as_str(), rom.as_str())))
+        .map(|(hash, (game, rom))| StaleEntry {
+            hash: hash.clone(),
+            game: game.clone(),
+            rom: rom.clone(),
+        })
+        .collect();
+
+    stale.sort_by(|a, b| (&a.game, &a.rom, &a.hash).cmp(&(&b.game, &b.rom, &b.hash)));
+    stale
+}
+
+/// Remove the given entries from the DB, dropping hashes left without entries
+pub fn remove_entries(known_roms: &mut KnownRoms, stale: &[StaleEntry]) {
+    for entry in stale {
+        if let Some(entries) = known_roms.get_mut(&entry.hash) {
+            entries.retain(|(game, rom)| !(game == &entry.game && rom == &entry.rom));
+            if entries.is_empty() {
+                known_roms.remove(&entry.hash);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RomEntry;
+
+    #[test]
+    fn test_prune_removes_only_entries_missing_from_dat() {
+        let mut game_roms = GameRoms::new();
+        game_roms.insert("Kept".to_string(), vec![RomEntry {
+            name: "kept.bin".to_string(),
+            game: "Kept".to_string(),
+            ..Default::default()
+        }]);
+
+        let mut known_roms = KnownRoms::new();
+        known_roms.insert("h1".to_string(), vec![
+            ("Kept".to_string(), "kept.bin".to_string()),
+            ("Renamed".to_string(), "kept.bin".to_string()),
+        ]);
+        known_roms.insert("h2".to_string(), vec![("Gone".to_string(), "gone.bin".to_string())]);
+
+        let stale = find_stale_entries(&known_roms, &game_roms);
+        assert_eq!(stale.iter().map(|s| s.game.as_str()).collect::<Vec<_>>(), vec!["Gone", "Renamed"]);
+
+        remove_entries(&mut known_roms, &stale);
+        assert_eq!(known_roms.len(), 1);
+        assert_eq!(known_roms["h1"], vec![("Kept".to_string(), "kept.bin".to_string())]);
+    }
+}
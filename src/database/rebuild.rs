@@ -0,0 +1,169 @@
+// src/database/rebuild.rs - Rebuild the known-ROMs DB from the organized tree
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+
+use crate::cache::HashCache;
+use crate::config::Config;
+use crate::error::Result;
+use crate::organizer::processor::rom_destination;
+use crate::organizer::rules::GameFolders;
+use crate::progress::Progress;
+use crate::scanner::{collector, dump_format, find_matching_entries, hasher_optimized, header};
+use crate::types::{FileHash, HashAlgorithms, KnownRoms, RomDb};
+
+/// Files under rom_dir that could not be turned into DB entries
+#[derive(Debug, Default)]
+pub struct RebuildSummary {
+    /// Files matching the DAT by hash, but not at any of their organized paths
+    pub misplaced: Vec<PathBuf>,
+    /// Files not in the DAT at all
+    pub unknown: Vec<PathBuf>,
+}
+
+/// Hash every file under rom_dir and rebuild the DB from the ones sitting
+/// where the organizer would have put them. Returns `None` if interrupted.
+pub fn rebuild_known_roms(
+    config: &Config,
+    rom_db: &RomDb,
+    games_needing_folders: &GameFolders,
+    interrupted: &AtomicBool,
+) -> Result<Option<(KnownRoms, RebuildSummary)>> {
+    let Some(file_hashes) = hash_rom_dir(config, rom_db, interrupted)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(match_organized_files(&file_hashes, games_needing_folders, &config.rom_dir)))
+}
+
+/// Hash every file under rom_dir (using the hash cache) and look each up in
+/// the DAT. Returns `None` if interrupted.
+pub fn hash_rom_dir(
+    config: &Config,
+    rom_db: &RomDb,
+    interrupted: &AtomicBool,
+) -> Result<Option<Vec<FileHash>>> {
+    let files = collector::collect_tree(Path::new(&config.rom_dir))?;
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+    let mut cache = HashCache::load(&config.state_path(HashCache::CACHE_FILE));
+
+    println!("{}", tr!("db-rebuild-hashing", files = files.len(), dir = config.rom_dir.as_str()));
+    let bar = Progress::new(
+        "rebuild",
+        files.len() as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} [{eta_precise}]",
+    );
+
+    let mut file_hashes = Vec::with_capacity(files.len());
+    for path in files {
+        if interrupted.load(Ordering::Relaxed) {
+            bar.finish_with_message(tr!("interrupted-by-user"));
+            cache.save()?;
+            return Ok(None);
+        }
+
+        let (digests, size) = hasher_optimized::calculate_hashes_cached(
+            &path,
+            config.buffer_size,
+            algorithms,
+            &mut cache,
+        )?;
+        let matching_entries = find_matching_entries(rom_db, &digests, size);
+        let find_matches = |digests: &_, size| find_matching_entries(rom_db, digests, size);
+        if matching_entries.is_empty()
+            && let Some(matched) = header::headerless_match(&path, config.buffer_size, algorithms, find_matches)?
+        {
+            file_hashes.push(matched);
+        } else if matching_entries.is_empty()
+            && let Some(matched) = dump_format::converted_match(&path, config.buffer_size, algorithms, find_matches)?
+        {
+            file_hashes.push(matched);
+        } else {
+            file_hashes.push(FileHash { path, digests, size, matching_entries });
+        }
+        bar.inc(1);
+    }
+    bar.finish();
+    cache.save()?;
+
+    Ok(Some(file_hashes))
+}
+
+/// Turn hashed files into DB entries. A file only counts for a ROM entry if
+/// it is at that entry's destination, so shared ROMs are credited per copy.
+pub fn match_organized_files(
+    files: &[FileHash],
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> (KnownRoms, RebuildSummary) {
+    let mut known_roms = KnownRoms::new();
+    let mut summary = RebuildSummary::default();
+
+    for file in files {
+        if file.matching_entries.is_empty() {
+            summary.unknown.push(file.path.clone());
+            continue;
+        }
+
+        let mut placed = false;
+        for entry in &file.matching_entries {
+            if !games_needing_folders.matches_destination(&rom_destination(entry, games_needing_folders, rom_dir), &file.path) {
+                continue;
+            }
+            let entries = known_roms.entry(file.primary_hash().to_string()).or_default();
+            let known = (entry.game.clone(), entry.name.clone());
+            if !entries.contains(&known) {
+                entries.push(known);
+            }
+            placed = true;
+        }
+
+        if !placed {
+            summary.misplaced.push(file.path.clone());
+        }
+    }
+
+    (known_roms, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomEntry, RomHashes};
+    use std::collections::HashSet;
+
+    fn file(path: &str, game: &str, rom: &str) -> FileHash {
+        FileHash {
+            path: PathBuf::from(path),
+            digests: FileDigests { sha1: "abc".to_string(), ..Default::default() },
+            size: 0,
+            matching_entries: vec![RomEntry {
+                name: rom.to_string(),
+                game: game.to_string(),
+                hashes: RomHashes { sha1: Some("abc".to_string()), ..Default::default() },
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_rebuild_only_credits_files_at_their_destination() {
+        let folders = GameFolders::from(HashSet::from(["Multi".to_string()]));
+        let files = vec![
+            file("roms/Single.bin", "Single", "Single.bin"),
+            file("roms/Multi/a.bin", "Multi", "a.bin"),
+            file("roms/Wrong Place/a.bin", "Multi", "a.bin"),
+            FileHash { matching_entries: Vec::new(), ..file("roms/junk.txt", "", "") },
+        ];
+
+        let (known_roms, summary) = match_organized_files(&files, &folders, "roms");
+
+        assert_eq!(known_roms["abc"], vec![
+            ("Single".to_string(), "Single.bin".to_string()),
+            ("Multi".to_string(), "a.bin".to_string()),
+        ]);
+        assert_eq!(summary.misplaced, vec![PathBuf::from("roms/Wrong Place/a.bin")]);
+        assert_eq!(summary.unknown, vec![PathBuf::from("roms/junk.txt")]);
+    }
+}
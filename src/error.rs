@@ -3,8 +3,6 @@
 use std::error::Error;
 use std::fmt;
 use std::io;
-use serde_json;
-use quick_xml;
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -19,6 +17,7 @@ pub enum RomAuditError {
     Custom(String),
     Bincode(bincode::Error),
     Join(tokio::task::JoinError),
+    Zip(zip::result::ZipError),
 }
 
 impl fmt::Display for RomAuditError {
@@ -31,9 +30,10 @@ impl fmt::Display for RomAuditError {
             RomAuditError::InvalidPath(p) => write!(f, "Invalid path: {}", p),
             RomAuditError::ParseError(e) => write!(f, "Parse error: {}", e),
             RomAuditError::ConfigError(e) => write!(f, "Configuration error: {}", e),
-            RomAuditError::Custom(e) => write!(f, "Error: {}", e),
+            RomAuditError::Custom(e) => write!(f, "{}", e),
             RomAuditError::Bincode(e) => write!(f, "Serialization error: {}", e),
             RomAuditError::Join(e) => write!(f, "Task join error: {}", e),
+            RomAuditError::Zip(e) => write!(f, "ZIP error: {}", e),
         }
     }
 }
@@ -70,4 +70,10 @@ impl From<tokio::task::JoinError> for RomAuditError {
     }
 }
 
+impl From<zip::result::ZipError> for RomAuditError {
+    fn from(error: zip::result::ZipError) -> Self {
+        RomAuditError::Zip(error)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, RomAuditError>;
\ No newline at end of file
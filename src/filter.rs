@@ -0,0 +1,160 @@
+// src/filter.rs - Narrowing a DAT down to the games an audit covers
+
+use std::collections::{HashMap, HashSet};
+
+use crate::one_g1r::{self, Release};
+use crate::types::ParsedDat;
+
+/// The language a release for a single country is in when its name has no
+/// language tag, as No-Intro leaves it out then
+const REGION_LANGUAGES: &[(&str, &str)] = &[
+    ("USA", "En"), ("UK", "En"), ("Australia", "En"), ("Canada", "En"), ("Ireland", "En"), ("New Zealand", "En"),
+    ("Japan", "Ja"), ("France", "Fr"), ("Germany", "De"), ("Austria", "De"), ("Spain", "Es"), ("Mexico", "Es"),
+    ("Latin America", "Es"), ("Italy", "It"), ("Netherlands", "Nl"), ("Brazil", "Pt"), ("Portugal", "Pt"),
+    ("Sweden", "Sv"), ("Norway", "No"), ("Denmark", "Da"), ("Finland", "Fi"), ("Poland", "Pl"), ("Russia", "Ru"),
+    ("Greece", "El"), ("Korea", "Ko"), ("China", "Zh"), ("Taiwan", "Zh"), ("Hong Kong", "Zh"),
+];
+
+/// The languages a game is in: those of its `<release>` entries, else the
+/// language tag of its name (or description), else what its regions imply.
+/// Empty when nothing tells.
+pub fn game_languages(parsed_dat: &ParsedDat, game: &str) -> Vec<String> {
+    let info = parsed_dat.game_info.get(game);
+    if let Some(info) = info.filter(|info| !info.languages.is_empty()) {
+        return info.languages.clone();
+    }
+    let release = Release::from_name(info.and_then(|info| info.description.as_deref()).unwrap_or(game));
+    if !release.languages.is_empty() {
+        return release.languages;
+    }
+    let mut languages: Vec<String> = release.regions.iter()
+        .filter_map(|region| REGION_LANGUAGES.iter().find(|(name, _)| name == region))
+        .map(|(_, language)| language.to_string())
+        .collect();
+    languages.dedup();
+    languages
+}
+
+/// Whether a language is one of `wanted`; `Zh-Hant` is `Zh` too
+fn is_wanted(language: &str, wanted: &[String]) -> bool {
+    let primary = language.split('-').next().unwrap_or(language);
+    wanted.iter().any(|w| w.eq_ignore_ascii_case(language) || w.eq_ignore_ascii_case(primary))
+}
+
+/// The games in one of `languages`. Games nothing gives a language for are
+/// kept, so a DAT without language information isn't emptied.
+pub fn games_in_languages(parsed_dat: &ParsedDat, languages: &[String]) -> HashSet<String> {
+    parsed_dat.counted_games().iter()
+        .filter(|game| {
+            let game_languages = game_languages(parsed_dat, game);
+            game_languages.is_empty() || game_languages.iter().any(|language| is_wanted(language, languages))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether a game's name has a tag starting with one of `tags`, like
+/// `(Proto 2)` or `(Beta)` for `Proto` and `Beta`
+pub fn has_excluded_tag(game: &str, tags: &[String]) -> bool {
+    !tags.is_empty() && one_g1r::name_tags(game).any(|tag| {
+        tag.split_whitespace().next().is_some_and(|word| tags.iter().any(|excluded| excluded.eq_ignore_ascii_case(word)))
+    })
+}
+
+/// The games whose catver.ini category contains one of `excluded`, e.g.
+/// `Mahjong` for `Tabletop / Mahjong` or `Mature` for `... * Mature *`
+pub fn games_in_categories(parsed_dat: &ParsedDat, categories: &HashMap<String, String>, excluded: &[String]) -> HashSet<String> {
+    let excluded: Vec<String> = excluded.iter().map(|term| term.to_lowercase()).collect();
+    parsed_dat.all_games.iter()
+        .filter(|game| {
+            categories.get(*game).is_some_and(|category| {
+                let category = category.to_lowercase();
+                excluded.iter().any(|term| category.contains(term.as_str()))
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Take games out of the DAT altogether, so they are neither organized nor missing
+pub fn remove_games(parsed_dat: &mut ParsedDat, games: &HashSet<String>) {
+    parsed_dat.all_games.retain(|game| !games.contains(game));
+    parsed_dat.game_roms.retain(|game, _| !games.contains(game));
+    parsed_dat.game_info.retain(|game, _| !games.contains(game));
+    parsed_dat.device_refs.retain(|game, _| !games.contains(game));
+    parsed_dat.parent_clone_map.retain(|game, _| !games.contains(game));
+    parsed_dat.rom_of.retain(|game, _| !games.contains(game));
+    parsed_dat.rom_db.retain(|_, entries| {
+        entries.retain(|entry| !games.contains(&entry.game));
+        !entries.is_empty()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    #[test]
+    fn test_games_in_languages() {
+        let dat = br#"<datafile>
+            <game name="Tetris (USA)"><rom name="a" crc="00000001"/></game>
+            <game name="Tetris (Japan)"><rom name="b" crc="00000002"/></game>
+            <game name="Tetris (Europe) (Fr,De)"><rom name="c" crc="00000003"/></game>
+            <game name="Tetris (Europe) (En,Fr,De)"><rom name="d" crc="00000004"/></game>
+            <game name="Tetris (Brazil) (Traduzido)">
+                <release name="Tetris" region="BRA" language="pt"/>
+                <rom name="e" crc="00000005"/>
+            </game>
+            <game name="tetris"><rom name="f" crc="00000006"/></game>
+        </datafile>"#;
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+        assert_eq!(game_languages(&parsed, "Tetris (Brazil) (Traduzido)"), ["pt"]);
+
+        let mut english: Vec<String> = games_in_languages(&parsed, &["En".to_string()]).into_iter().collect();
+        english.sort();
+        assert_eq!(english, ["Tetris (Europe) (En,Fr,De)", "Tetris (USA)", "tetris"]);
+        assert_eq!(games_in_languages(&parsed, &["PT".to_string()]).len(), 2);
+    }
+
+    #[test]
+    fn test_remove_games_in_categories() {
+        let dat = br#"<datafile>
+            <game name="sf2"><rom name="a" crc="00000001"/></game>
+            <game name="mjkjidai"><rom name="b" crc="00000002"/></game>
+            <game name="pokernew"><rom name="c" crc="00000003"/></game>
+        </datafile>"#;
+        let mut parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+        let categories = crate::parser::catver::parse_catver(
+            "[Category]\nsf2=Fighter / Versus\nmjkjidai=Tabletop / Mahjong * Mature *\npokernew=Casino / Cards\n",
+        );
+
+        let excluded = games_in_categories(&parsed, &categories, &["mahjong".to_string(), "Casino".to_string()]);
+        assert_eq!(excluded.len(), 2);
+        remove_games(&mut parsed, &excluded);
+        assert_eq!(parsed.all_games.iter().collect::<Vec<_>>(), ["sf2"]);
+        assert_eq!(parsed.rom_db.len(), 1);
+        assert!(!parsed.game_roms.contains_key("pokernew"));
+    }
+
+    #[test]
+    fn test_excluded_tags() {
+        let tags = ["Proto".to_string(), "Beta".to_string(), "Aftermarket".to_string()];
+        assert!(has_excluded_tag("Star Fox 2 (Japan) (Proto 2)", &tags));
+        assert!(has_excluded_tag("Game (USA) (beta)", &tags));
+        assert!(has_excluded_tag("Homebrew (World) (Aftermarket) (Unl)", &tags));
+        assert!(!has_excluded_tag("Protoman (USA)", &tags));
+        assert!(!has_excluded_tag("Game (USA) (Rev 1)", &tags));
+        assert!(!has_excluded_tag("Game (USA) (Proto)", &[]));
+
+        let dat = br#"<datafile>
+            <game name="Game (USA)"><rom name="a" crc="00000001"/></game>
+            <game name="Game (USA) (Proto)"><rom name="b" crc="00000002"/></game>
+        </datafile>"#;
+        let options = ParseOptions { excluded_tags: tags.to_vec(), ..Default::default() };
+        let parsed = parse_dat_bytes(dat, "test".to_string(), options).unwrap();
+        assert_eq!(parsed.all_games.len(), 1);
+        assert!(parsed.rom_db.contains_key("00000001"));
+        assert!(!parsed.rom_db.contains_key("00000002"));
+    }
+}
@@ -0,0 +1,128 @@
+// src/i18n.rs - Translated console messages and report headers
+
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::config::Config;
+
+/// Built-in locales as (language, Fluent source); English comes first and
+/// fills in any message a translation lacks
+pub const LOCALES: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+static TRANSLATIONS: OnceLock<Translations> = OnceLock::new();
+
+/// Messages in one language, with English behind them
+pub struct Translations {
+    bundle: FluentBundle<FluentResource>,
+    fallback: Option<FluentBundle<FluentResource>>,
+}
+
+impl Translations {
+    /// Translations for `language`, or English if it isn't one of LOCALES
+    pub fn new(language: &str) -> Self {
+        let language = LOCALES.iter().find(|(lang, _)| *lang == language).map_or("en", |(lang, _)| lang);
+        Translations {
+            bundle: bundle(language),
+            fallback: (language != "en").then(|| bundle("en")),
+        }
+    }
+
+    /// A message with its arguments filled in; the id itself if no locale has it
+    pub fn message(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        [Some(&self.bundle), self.fallback.as_ref()].into_iter()
+            .flatten()
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(id)?.value()?;
+                let mut errors = Vec::new();
+                Some(bundle.format_pattern(pattern, args, &mut errors).into_owned())
+            })
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+fn bundle(language: &str) -> FluentBundle<FluentResource> {
+    let source = LOCALES.iter().find(|(lang, _)| *lang == language).map_or(LOCALES[0].1, |(_, source)| source);
+    let langid: LanguageIdentifier = language.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Reports are plain text; no Unicode isolation marks around arguments
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(source.to_string()).expect("built-in locale is valid Fluent");
+    bundle.add_resource(resource).expect("built-in locale has no duplicate messages");
+    bundle
+}
+
+/// Language from `--locale`, else from LC_ALL, LC_MESSAGES or LANG
+/// (`es_ES.UTF-8` -> `es`)
+pub fn detect_language(config: &Config) -> String {
+    let from_env = || ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    let locale = config.locale.clone().or_else(from_env).unwrap_or_default();
+    let language = locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+    match language.as_str() {
+        "" | "c" | "posix" => "en".to_string(),
+        _ => language,
+    }
+}
+
+/// Select the language for the rest of the process
+pub fn init(config: &Config) {
+    let language = detect_language(config);
+    if config.locale.is_some() && !LOCALES.iter().any(|(lang, _)| *lang == language) {
+        eprintln!("Warning: no {} translation, using English", language);
+    }
+    let _ = TRANSLATIONS.set(Translations::new(&language));
+}
+
+/// A message in the selected language; English before `init`
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    TRANSLATIONS.get_or_init(|| Translations::new("en")).message(id, args)
+}
+
+/// Translate a message from locales/*.ftl: `tr!("dat-parsed", games = 12)`
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locales_translate_every_message() {
+        // Message lines are `id = value`; comments and continuations aren't
+        let ids = |source: &'static str| -> Vec<&'static str> {
+            source.lines()
+                .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+                .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+                .collect()
+        };
+        let english = ids(LOCALES[0].1);
+        for (language, source) in &LOCALES[1..] {
+            let translated = ids(source);
+            let untranslated: Vec<&&str> = english.iter().filter(|id| !translated.contains(id)).collect();
+            assert!(untranslated.is_empty(), "{} lacks {:?}", language, untranslated);
+        }
+
+        let mut args = FluentArgs::new();
+        args.set("games", 12);
+        assert_eq!(Translations::new("es").message("dat-parsed", Some(&args)), "12 juegos leídos del archivo DAT");
+        assert_eq!(Translations::new("xx").message("dat-parsed", Some(&args)), "Parsed 12 games from DAT file");
+
+        let config = Config { locale: Some("es_AR.UTF-8".to_string()), ..Config::default() };
+        assert_eq!(detect_language(&config), "es");
+    }
+}
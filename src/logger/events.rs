@@ -0,0 +1,202 @@
+// src/logger/events.rs - Append-only machine-readable event log
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::types::FileHash;
+
+/// Something romaudit did to or found about a file
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event {
+    Scanned {
+        path: PathBuf,
+        size: u64,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        sha1: String,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        md5: String,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        crc: String,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        sha256: String,
+    },
+    Matched {
+        path: PathBuf,
+        game: String,
+        rom: String,
+    },
+    Placed {
+        from: PathBuf,
+        to: PathBuf,
+        game: String,
+        rom: String,
+        /// How the copy differs from the file, e.g. `stripped iNES` or `v64 to z64`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        converted: Option<String>,
+    },
+    Skipped {
+        path: PathBuf,
+        reason: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        moved_to: Option<PathBuf>,
+    },
+    Removed {
+        path: PathBuf,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+        message: String,
+    },
+}
+
+/// One line of events.jsonl
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Seconds since the Unix epoch
+    time: u64,
+    /// Start time of the run, in milliseconds, to group the events of one run
+    run: u128,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// events.jsonl in the logs directory. It is only ever appended to, so it
+/// holds the history of every run.
+///
+/// A dry run logs to dry_run.jsonl instead, rewritten on each dry run, and
+/// also keeps the changes it would have made to list them at the end.
+pub struct EventLog {
+    writer: BufWriter<File>,
+    run: u128,
+    planned: Option<Vec<String>>,
+}
+
+impl EventLog {
+    pub const EVENTS_FILE: &'static str = "events.jsonl";
+    pub const DRY_RUN_FILE: &'static str = "dry_run.jsonl";
+
+    pub fn open(logs_dir: &str) -> Result<Self> {
+        fs::create_dir_all(logs_dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Path::new(logs_dir).join(Self::EVENTS_FILE))?;
+        Ok(Self::with_file(file, None))
+    }
+
+    /// A log for a dry run, whose events never reach the history in events.jsonl
+    pub fn open_dry_run(logs_dir: &str) -> Result<Self> {
+        fs::create_dir_all(logs_dir)?;
+        let file = File::create(Path::new(logs_dir).join(Self::DRY_RUN_FILE))?;
+        Ok(Self::with_file(file, Some(Vec::new())))
+    }
+
+    fn with_file(file: File, planned: Option<Vec<String>>) -> Self {
+        let run = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        EventLog { writer: BufWriter::new(file), run, planned }
+    }
+
+    pub fn record(&mut self, event: Event) -> Result<()> {
+        if let Some(planned) = &mut self.planned {
+            match &event {
+                Event::Placed { from, to, converted: None, .. } => planned.push(format!("place {} -> {}", from.display(), to.display())),
+                Event::Placed { from, to, converted: Some(converted), .. } => {
+                    planned.push(format!("place {} -> {} ({})", from.display(), to.display(), converted));
+                }
+                Event::Skipped { path, moved_to: Some(to), .. } => planned.push(format!("move {} -> {}", path.display(), to.display())),
+                Event::Removed { path } => planned.push(format!("delete {}", path.display())),
+                _ => {}
+            }
+        }
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        serde_json::to_writer(&mut self.writer, &Record { time, run: self.run, event: &event })?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// In a dry run, each file change that was recorded, in order
+    pub fn planned(&self) -> &[String] {
+        self.planned.as_deref().unwrap_or_default()
+    }
+
+    /// Record a scanned file and each DAT entry it matched
+    pub fn record_scanned(&mut self, file_hash: &FileHash) -> Result<()> {
+        self.record(Event::Scanned {
+            path: file_hash.path.clone(),
+            size: file_hash.size,
+            sha1: file_hash.digests.sha1.clone(),
+            md5: file_hash.digests.md5.clone(),
+            crc: file_hash.digests.crc.clone(),
+            sha256: file_hash.digests.sha256.clone(),
+        })?;
+        for entry in &file_hash.matching_entries {
+            self.record(Event::Matched {
+                path: file_hash.path.clone(),
+                game: entry.game.clone(),
+                rom: entry.name.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_appended_as_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs_dir = dir.path().to_str().unwrap();
+
+        for run in ["first.bin", "second.bin"] {
+            let mut events = EventLog::open(logs_dir).unwrap();
+            events.record(Event::Skipped { path: PathBuf::from(run), reason: "unknown".to_string(), moved_to: None }).unwrap();
+        }
+
+        let contents = fs::read_to_string(dir.path().join(EventLog::EVENTS_FILE)).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "skipped");
+        assert_eq!(lines[0]["path"], "first.bin");
+        assert_eq!(lines[1]["path"], "second.bin");
+        assert!(lines[1].get("moved_to").is_none());
+    }
+
+    #[test]
+    fn test_dry_run_lists_changes_apart_from_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs_dir = dir.path().to_str().unwrap();
+
+        let mut events = EventLog::open_dry_run(logs_dir).unwrap();
+        events.record(Event::Placed {
+            from: PathBuf::from("a.bin"), to: PathBuf::from("roms/A.bin"), game: "A".to_string(), rom: "A.bin".to_string(), converted: None,
+        }).unwrap();
+        events.record(Event::Removed { path: PathBuf::from("a.bin") }).unwrap();
+        events.record(Event::Skipped { path: PathBuf::from("b.bin"), reason: "not in DAT".to_string(), moved_to: Some(PathBuf::from("unknown1/b.bin")) }).unwrap();
+        events.flush().unwrap();
+
+        assert_eq!(events.planned(), ["place a.bin -> roms/A.bin", "delete a.bin", "move b.bin -> unknown1/b.bin"]);
+        assert_eq!(fs::read_to_string(dir.path().join(EventLog::DRY_RUN_FILE)).unwrap().lines().count(), 3);
+        assert!(!dir.path().join(EventLog::EVENTS_FILE).exists());
+        assert!(EventLog::open(logs_dir).unwrap().planned().is_empty());
+    }
+}
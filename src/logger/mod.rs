@@ -1,13 +1,35 @@
 // src/logger/mod.rs - Logger module
 
-use std::fs::File;
+pub mod sort;
+pub mod stats;
+pub mod writer;
+pub mod events;
+
 use std::io::Write;
-use std::path::Path;
-use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use console::style;
 
 use crate::error::Result;
-use crate::types::{ScanResult, KnownRoms};
-use crate::config::Config;
+use crate::types::{ScanResult, KnownRoms, ParsedDat, GameRoms, RomEntry, RomStatus, DatHeader, DatIssue};
+use crate::config::{CollisionPolicy, Config};
+use crate::scanner::collector::Unreadable;
+use crate::scanner::{BadDumpMatch, SizeMismatch};
+use crate::scanner::overdump::Overdump;
+use crate::scanner::trimmed::Trimmed;
+use crate::scanner::convertible::Convertible;
+use crate::scanner::scrub::ScrubReport;
+use crate::scanner::zip_check::CorruptZip;
+use crate::scanner::classify::UnknownFile;
+use crate::scanner::disc_image::ImageContents;
+use crate::scanner::remote::RemoteMatch;
+use crate::scanner::torrent::{FileCheck, FileState, TorrentCheck};
+use crate::organizer::renames::Rename;
+use crate::organizer::rules::{Collision, GameFolders};
+use crate::scanner::nodump::ProbableMatch;
+use crate::scanner::retroachievements::{RaHash, RaMethod};
+use writer::ReportWriter;
 
 pub struct Logger {
     config: Config,
@@ -18,56 +40,230 @@ impl Logger {
         Logger { config }
     }
     
+    /// Create a report file in the logs directory using the configured encoding
+    fn create_report(&self, name: &str) -> Result<ReportWriter> {
+        let path = Path::new(&self.config.logs_dir).join(name);
+        ReportWriter::create(&path, self.config.report_encoding, self.config.line_ending)
+    }
+    
     pub fn write_logs(
         &self,
         results: &ScanResult,
-        all_games: &HashSet<String>,
+        parsed_dat: &ParsedDat,
         known_roms: &KnownRoms,
-        games_needing_folders: &HashSet<String>,
+        games_needing_folders: &GameFolders,
     ) -> Result<()> {
-        self.write_have_log(&results.have, all_games)?;
-        self.write_missing_log(&results.missing, all_games)?;
+        let all_games = parsed_dat.counted_games();
+        self.write_have_log(&results.have, parsed_dat)?;
+        if self.config.have_dat {
+            self.write_have_dat(&results.have, parsed_dat, known_roms)?;
+        }
+        
+        let usage = stats::game_disk_usage(
+            &results.have,
+            &parsed_dat.game_roms,
+            known_roms,
+            games_needing_folders,
+            &self.config.rom_dir,
+        );
+        self.write_have_paths_log(&usage, parsed_dat)?;
+        
+        let collection_stats = stats::collection_stats(&usage, &parsed_dat.game_roms, known_roms);
+        self.write_stats_log(&parsed_dat.name, &collection_stats)?;
+        self.write_missing_by_size_log(&stats::missing_roms_by_size(&parsed_dat.game_roms, known_roms))?;
+        
+        self.write_missing_log(&results.missing, parsed_dat)?;
+        
+        let partial_games = find_partial_games(&parsed_dat.game_roms, known_roms);
+        self.write_partial_log(&partial_games)?;
         
         if !results.shared_roms.is_empty() {
             self.write_shared_log(&results.shared_roms, known_roms)?;
+            
+            let dedup = stats::estimate_dedup_savings(
+                &results.shared_roms,
+                known_roms,
+                &parsed_dat.rom_db,
+                games_needing_folders,
+                &self.config.rom_dir,
+            );
+            self.write_dedup_log(&dedup)?;
         }
         
         if !games_needing_folders.is_empty() {
             self.write_folders_log(games_needing_folders)?;
         }
         
-        self.print_summary(results, all_games, games_needing_folders);
+        if !parsed_dat.issues.is_empty() {
+            self.write_dat_issues_log(&parsed_dat.issues)?;
+        }
+        
+        self.print_summary(results, all_games, games_needing_folders, partial_games.len(), &collection_stats);
         
         Ok(())
     }
     
-    fn write_have_log(&self, have: &HashSet<String>, all_games: &HashSet<String>) -> Result<()> {
-        let have_log = Path::new(&self.config.logs_dir).join("have.txt");
-        let mut have_file = File::create(&have_log)?;
+    fn write_have_log(&self, have: &HashSet<String>, parsed_dat: &ParsedDat) -> Result<()> {
+        let mut have_file = self.create_report("have.txt")?;
         
-        writeln!(have_file, "ROMs Found: {} / {}", have.len(), all_games.len())?;
+        if let Some(header) = parsed_dat.header.summary() {
+            writeln!(have_file, "{}", tr!("dat-header", header = header))?;
+        }
+        writeln!(have_file, "{}", tr!("have-header", have = have.len(), total = parsed_dat.counted_games().len()))?;
+        if self.config.quick {
+            writeln!(have_file, "{}", tr!("quick-unverified"))?;
+        }
         writeln!(have_file)?;
         
         let mut have_list: Vec<_> = have.iter().collect();
-        have_list.sort();
+        sort::sort_natural(&mut have_list);
         for name in have_list {
-            writeln!(have_file, "{}", name)?;
+            writeln!(have_file, "{}", parsed_dat.display_name(name))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// The games that are present as a DAT of their own, with the ROMs that
+    /// are there, to share as a manifest of the collection
+    fn write_have_dat(&self, have: &HashSet<String>, parsed_dat: &ParsedDat, known_roms: &KnownRoms) -> Result<()> {
+        let mut have_file = self.create_report("have.dat")?;
+        
+        let name = parsed_dat.header.name.as_deref().unwrap_or(&parsed_dat.name);
+        let header = DatHeader {
+            name: Some(format!("{} (have)", name)),
+            description: Some(tr!("have-dat-description", dat = name, have = have.len(), total = parsed_dat.counted_games().len())),
+            ..parsed_dat.header.clone()
+        };
+        let mut games: Vec<&str> = have.iter().map(String::as_str).collect();
+        sort::sort_natural(&mut games);
+        let present = present_roms(known_roms);
+        crate::parser::export::write_dat(&mut have_file, &header, parsed_dat, &games, |rom| {
+            present.contains(&(rom.game.as_str(), rom.name.as_str()))
+        })
+    }
+    
+    /// Manifest of found games with their location and size on disk (tab-separated)
+    fn write_have_paths_log(&self, usage: &[stats::GameDiskUsage], parsed_dat: &ParsedDat) -> Result<()> {
+        let total_size: u64 = usage.iter().map(|u| u.size).sum();
+        
+        let mut paths_file = self.create_report("have_paths.txt")?;
+        writeln!(paths_file, "{}", tr!("have-paths-header", games = usage.len(), size = format_size(total_size)))?;
+        writeln!(paths_file, "# game\tlocation\tsize_bytes\tdescription\tyear\tmanufacturer")?;
+        for entry in usage {
+            let info = parsed_dat.game_info.get(entry.game).cloned().unwrap_or_default();
+            writeln!(paths_file, "{}\t{}\t{}\t{}\t{}\t{}", entry.game, entry.location.display(), entry.size,
+                info.description.unwrap_or_default(), info.year.unwrap_or_default(), info.manufacturer.unwrap_or_default())?;
         }
         
         Ok(())
     }
     
-    fn write_missing_log(&self, missing: &HashSet<String>, all_games: &HashSet<String>) -> Result<()> {
-        let missing_log = Path::new(&self.config.logs_dir).join("missing.txt");
-        let mut missing_file = File::create(&missing_log)?;
+    fn write_stats_log(&self, dat_name: &str, stats: &stats::CollectionStats) -> Result<()> {
+        let mut stats_file = self.create_report("stats.txt")?;
+        for line in stats_lines(dat_name, stats) {
+            writeln!(stats_file, "{}", line)?;
+        }
+        Ok(())
+    }
+    
+    /// Print the size statistics of stats.txt for the games in the database,
+    /// without scanning
+    pub fn print_stats(&self, parsed_dat: &ParsedDat, known_roms: &KnownRoms, games_needing_folders: &GameFolders) {
+        let have: HashSet<String> = present_roms(known_roms).into_iter()
+            .map(|(game, _)| game)
+            .filter(|game| parsed_dat.counted_games().contains(*game))
+            .map(str::to_string)
+            .collect();
+        let usage = stats::game_disk_usage(&have, &parsed_dat.game_roms, known_roms, games_needing_folders, &self.config.rom_dir);
+        let collection_stats = stats::collection_stats(&usage, &parsed_dat.game_roms, known_roms);
         
-        writeln!(missing_file, "Missing ROMs: {} / {}", missing.len(), all_games.len())?;
+        println!("{}", tr!("have-header", have = have.len(), total = parsed_dat.counted_games().len()));
+        println!();
+        for line in stats_lines(&parsed_dat.name, &collection_stats) {
+            println!("{}", line);
+        }
+    }
+    
+    fn write_missing_log(&self, missing: &HashSet<String>, parsed_dat: &ParsedDat) -> Result<()> {
+        let mut missing_file = self.create_report("missing.txt")?;
+        
+        writeln!(missing_file, "{}", tr!("missing-header", missing = missing.len(), total = parsed_dat.counted_games().len()))?;
         writeln!(missing_file)?;
         
+        let optional = format!(" {}", tr!("missing-optional"));
+        let not_dumped = format!(" {}", tr!("missing-not-dumped"));
         let mut missing_list: Vec<_> = missing.iter().collect();
-        missing_list.sort();
+        sort::sort_natural(&mut missing_list);
         for name in missing_list {
-            writeln!(missing_file, "{}", name)?;
+            writeln!(missing_file, "{}", parsed_dat.display_name(name))?;
+            for rom in parsed_dat.game_roms.get(name).into_iter().flatten() {
+                let note = if rom.status == RomStatus::NoDump && rom.optional {
+                    not_dumped.as_str()
+                } else if rom.optional {
+                    optional.as_str()
+                } else {
+                    ""
+                };
+                writeln!(missing_file, "  - {}{}", format_rom_details(rom), note)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn write_missing_by_size_log(&self, missing: &[&RomEntry]) -> Result<()> {
+        let mut size_file = self.create_report("missing_by_size.txt")?;
+        
+        let total: u64 = missing.iter().filter_map(|rom| rom.size).sum();
+        writeln!(size_file, "{}", tr!("missing-by-size-header", files = missing.len(), size = format_size(total)))?;
+        writeln!(size_file)?;
+        
+        let unknown_size = tr!("unknown-size");
+        for rom in missing {
+            let size = rom.size.map(format_size).unwrap_or_else(|| unknown_size.clone());
+            writeln!(size_file, "{:>12}  {} / {}", size, rom.game, rom.name)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn write_partial_log(&self, partial_games: &[PartialGame]) -> Result<()> {
+        let mut partial_file = self.create_report("partial.txt")?;
+        
+        let incomplete: Vec<&PartialGame> = partial_games.iter()
+            .filter(|partial| !partial.missing.is_empty())
+            .collect();
+        
+        writeln!(partial_file, "{}", tr!("partial-header", count = incomplete.len()))?;
+        writeln!(partial_file)?;
+        
+        for partial in incomplete {
+            writeln!(partial_file, "{}", tr!("partial-game",
+                game = partial.game, present = partial.present, total = partial.present + partial.missing.len()))?;
+            
+            for rom in &partial.missing {
+                writeln!(partial_file, "  - {}", format_rom_details(rom))?;
+            }
+            
+            writeln!(partial_file)?;
+        }
+        
+        // Optional ROMs and disks don't make a game incomplete
+        let with_optional_missing: Vec<&PartialGame> = partial_games.iter()
+            .filter(|partial| !partial.optional_missing.is_empty())
+            .collect();
+        if !with_optional_missing.is_empty() {
+            writeln!(partial_file, "{}", tr!("partial-optional-header", count = with_optional_missing.len()))?;
+            writeln!(partial_file)?;
+            
+            for partial in with_optional_missing {
+                writeln!(partial_file, "{}", partial.game)?;
+                for rom in &partial.optional_missing {
+                    writeln!(partial_file, "  - {}", format_rom_details(rom))?;
+                }
+                writeln!(partial_file)?;
+            }
         }
         
         Ok(())
@@ -75,25 +271,24 @@ impl Logger {
     
     fn write_shared_log(
         &self,
-        shared_roms: &std::collections::HashMap<String, Vec<String>>,
+        shared_roms: &BTreeMap<String, Vec<String>>,
         known_roms: &KnownRoms,
     ) -> Result<()> {
-        let shared_log = Path::new(&self.config.logs_dir).join("shared.txt");
-        let mut shared_file = File::create(&shared_log)?;
+        let mut shared_file = self.create_report("shared.txt")?;
         
-        writeln!(shared_file, "Shared ROMs (same file content used by multiple games - each has its own copy):")?;
-        writeln!(shared_file, "===============================================================================")?;
+        let title = tr!("shared-title");
+        writeln!(shared_file, "{}", title)?;
+        writeln!(shared_file, "{}", "=".repeat(title.chars().count()))?;
         writeln!(shared_file)?;
         
-        let mut shared_list: Vec<_> = shared_roms.iter().collect();
-        shared_list.sort_by_key(|(hash, _)| *hash);
+        let shared_list: Vec<_> = shared_roms.iter().collect();
         
         for (hash, games) in &shared_list {
-            writeln!(shared_file, "Hash: {}", hash)?;
-            writeln!(shared_file, "Shared by {} games:", games.len())?;
+            writeln!(shared_file, "{}", tr!("shared-hash", hash = hash.as_str()))?;
+            writeln!(shared_file, "{}", tr!("shared-by", games = games.len()))?;
             
             // Try to find the ROM name(s) for this hash
-            let mut rom_names = HashSet::new();
+            let mut rom_names = BTreeSet::new();
             if let Some(entries) = known_roms.get(*hash) {
                 for (_, rom_name) in entries {
                     rom_names.insert(rom_name.clone());
@@ -101,15 +296,16 @@ impl Logger {
             }
             
             if !rom_names.is_empty() {
-                writeln!(shared_file, "ROM name(s): {}", 
-                    rom_names.iter().cloned().collect::<Vec<_>>().join(", "))?;
+                let mut rom_names: Vec<_> = rom_names.into_iter().collect();
+                sort::sort_natural(&mut rom_names);
+                writeln!(shared_file, "{}", tr!("shared-rom-names", names = rom_names.join(", ")))?;
             }
             
             writeln!(shared_file)?;
             
-            // Sort games alphabetically and display each on its own line
+            // Sort games naturally and display each on its own line
             let mut sorted_games = (*games).clone();
-            sorted_games.sort();
+            sort::sort_natural(&mut sorted_games);
             
             for game in sorted_games {
                 writeln!(shared_file, "  - {}", game)?;
@@ -127,25 +323,574 @@ impl Logger {
             .flat_map(|(_, games)| games.iter())
             .collect();
         
-        writeln!(shared_file, "Summary:")?;
-        writeln!(shared_file, "Total shared files: {}", total_shared_files)?;
-        writeln!(shared_file, "Total games affected: {}", total_affected_games.len())?;
+        writeln!(shared_file, "{}", tr!("shared-summary"))?;
+        writeln!(shared_file, "{}", tr!("shared-total-files", count = total_shared_files))?;
+        writeln!(shared_file, "{}", tr!("shared-total-games", count = total_affected_games.len()))?;
+        
+        Ok(())
+    }
+    
+    fn write_dedup_log(&self, dedup: &[stats::DedupEntry]) -> Result<()> {
+        /// Only the biggest offenders are broken down individually
+        const TOP_OFFENDERS: usize = 50;
+        
+        let mut dedup_file = self.create_report("dedup.txt")?;
+        
+        let total_copies: usize = dedup.iter().map(|e| e.copies).sum();
+        let total_size: u64 = dedup.iter().map(|e| e.file_size * e.copies as u64).sum();
+        let total_savings: u64 = dedup.iter().map(|e| e.savings()).sum();
+        
+        let title = tr!("dedup-title");
+        writeln!(dedup_file, "{}", title)?;
+        writeln!(dedup_file, "{}", "=".repeat(title.chars().count()))?;
+        writeln!(dedup_file)?;
+        writeln!(dedup_file, "{}", tr!("dedup-shared-files", count = dedup.len()))?;
+        writeln!(dedup_file, "{}", tr!("dedup-stored-copies", count = total_copies))?;
+        writeln!(dedup_file, "{}", tr!("dedup-space-used", size = format_size(total_size)))?;
+        writeln!(dedup_file, "{}", tr!("dedup-savings", size = format_size(total_savings)))?;
+        writeln!(dedup_file)?;
+        writeln!(dedup_file, "{}", tr!("dedup-offenders"))?;
+        writeln!(dedup_file)?;
+        
+        for entry in dedup.iter().take(TOP_OFFENDERS) {
+            writeln!(dedup_file, "{}", tr!("dedup-entry",
+                savings = format_size(entry.savings()), copies = entry.copies, size = format_size(entry.file_size)))?;
+            writeln!(dedup_file, "  {}", tr!("shared-hash", hash = entry.hash))?;
+            writeln!(dedup_file, "  {}", tr!("shared-rom-names", names = entry.rom_names.join(", ")))?;
+            writeln!(dedup_file, "  {}", tr!("dedup-games", games = entry.games.join(", ")))?;
+            writeln!(dedup_file)?;
+        }
+        
+        if dedup.len() > TOP_OFFENDERS {
+            writeln!(dedup_file, "{}", tr!("dedup-more", count = dedup.len() - TOP_OFFENDERS))?;
+        }
+        
+        Ok(())
+    }
+    
+    fn write_dat_issues_log(&self, issues: &[DatIssue]) -> Result<()> {
+        let mut issues_file = self.create_report("dat_issues.txt")?;
+        
+        writeln!(issues_file, "{}", tr!("dat-issues-header", count = issues.len()))?;
+        writeln!(issues_file)?;
+        
+        for issue in issues {
+            writeln!(issues_file, "{}", tr!("dat-issue", line = issue.line, message = issue.message.as_str()))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Directories and files the scan skipped because they couldn't be read
+    pub fn write_unreadable_log(&self, unreadable: &[Unreadable]) -> Result<()> {
+        let mut unreadable_file = self.create_report("unreadable.txt")?;
+        
+        writeln!(unreadable_file, "{}", tr!("unreadable-header", count = unreadable.len()))?;
+        writeln!(unreadable_file)?;
+        for skipped in unreadable {
+            writeln!(unreadable_file, "{}: {}", skipped.path.display(), skipped.error)?;
+        }
+        
+        println!("{}", tr!("summary-unreadable", count = unreadable.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// The copies, moves and deletes a dry run would have made, also printed
+    pub fn write_dry_run_log(&self, planned: &[String]) -> Result<()> {
+        let mut dry_run_file = self.create_report("dry_run.txt")?;
+        
+        writeln!(dry_run_file, "{}", tr!("dry-run-header", count = planned.len()))?;
+        writeln!(dry_run_file)?;
+        for change in planned {
+            writeln!(dry_run_file, "{}", change)?;
+            let change = match change.split(' ').next() {
+                Some("place") => style(change).green(),
+                Some("move") => style(change).yellow(),
+                _ => style(change).red(),
+            };
+            println!("  {}", change);
+        }
+        
+        println!("{}", tr!("summary-dry-run", count = planned.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Paths that games without folders would have shared, and how they were kept apart
+    pub fn write_collisions_log(&self, collisions: &[Collision]) -> Result<()> {
+        let mut collisions_file = self.create_report("collisions.txt")?;
+        
+        // With `error` the audit stops before organizing, so this isn't written
+        let policy = match self.config.collision_policy {
+            CollisionPolicy::Suffix => tr!("collisions-suffix"),
+            CollisionPolicy::Folder | CollisionPolicy::Error => tr!("collisions-folder"),
+        };
+        writeln!(collisions_file, "{}", tr!("collisions-header", count = collisions.len()))?;
+        writeln!(collisions_file, "{}", policy)?;
+        writeln!(collisions_file)?;
+        for collision in collisions {
+            writeln!(collisions_file, "{}", collision.path.display())?;
+            for (game, rom) in &collision.games {
+                writeln!(collisions_file, "  {} / {}", game, rom)?;
+            }
+        }
+        
+        println!("{}", tr!("summary-collisions", count = collisions.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Files not in the DAT, grouped by the system they most likely belong to
+    pub fn write_unknown_log(&self, unknown: &[UnknownFile]) -> Result<()> {
+        let mut unknown_file = self.create_report("unknown.txt")?;
+        
+        let unclassified = tr!("unknown-unclassified");
+        let mut groups: BTreeMap<&str, Vec<&UnknownFile>> = BTreeMap::new();
+        for file in unknown {
+            groups.entry(file.guess.as_ref().map_or(unclassified.as_str(), |g| g.system)).or_default().push(file);
+        }
+        let mut groups: Vec<(&str, Vec<&UnknownFile>)> = groups.into_iter().collect();
+        // Largest groups first; unclassified files last
+        groups.sort_by_key(|(system, files)| (*system == unclassified, std::cmp::Reverse(files.len())));
+        
+        writeln!(unknown_file, "{}", tr!("unknown-header", count = unknown.len()))?;
+        let note = if self.config.read_only {
+            tr!("unknown-note-read-only")
+        } else if self.config.dry_run {
+            tr!("unknown-note-dry-run")
+        } else {
+            tr!("unknown-note")
+        };
+        writeln!(unknown_file, "{}", note)?;
+        writeln!(unknown_file)?;
+        for (system, mut files) in groups {
+            files.sort_by(|a, b| sort::natural_cmp(&a.path.to_string_lossy(), &b.path.to_string_lossy()));
+            writeln!(unknown_file, "{} ({})", system, files.len())?;
+            for file in files {
+                let reason = file.guess.as_ref().map(|g| format!(" - {}", g.reason)).unwrap_or_default();
+                writeln!(unknown_file, "  {} ({}){}", file.path.display(), format_size(file.size), reason)?;
+            }
+            writeln!(unknown_file)?;
+        }
+        
+        println!("{}", tr!("summary-unknown", count = unknown.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// ZIP archives that failed the structural check, to download again
+    pub fn write_corrupt_zips_log(&self, corrupt: &[CorruptZip]) -> Result<()> {
+        let mut corrupt_file = self.create_report("corrupt_zips.txt")?;
+        
+        writeln!(corrupt_file, "{}", tr!("corrupt-zips-header", count = corrupt.len()))?;
+        writeln!(corrupt_file, "{}", tr!("corrupt-zips-note"))?;
+        writeln!(corrupt_file)?;
+        for zip in corrupt {
+            writeln!(corrupt_file, "{}", zip.path.display())?;
+            for problem in &zip.problems {
+                writeln!(corrupt_file, "  {}", problem)?;
+            }
+        }
+        
+        println!("{}", tr!("summary-corrupt-zips", count = corrupt.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Files whose leading bytes match a smaller DAT entry
+    pub fn write_overdumps_log(&self, overdumps: &[Overdump], trimmed: bool) -> Result<()> {
+        let mut overdumps_file = self.create_report("overdumps.txt")?;
+        
+        writeln!(overdumps_file, "{}", tr!("overdumps-header", count = overdumps.len()))?;
+        if trimmed {
+            writeln!(overdumps_file, "{}", tr!("overdumps-trimmed"))?;
+        } else {
+            writeln!(overdumps_file, "{}", tr!("overdumps-hint"))?;
+        }
+        writeln!(overdumps_file)?;
+        
+        for overdump in overdumps {
+            writeln!(overdumps_file, "{}", overdump.path.display())?;
+            let extra = overdump.file_size - overdump.verified_size;
+            let sizes = match overdump.padding {
+                Some(byte) => tr!("overdump-sizes",
+                    size = overdump.file_size, verified = overdump.verified_size,
+                    padding = extra, byte = format!("0x{:02X}", byte)),
+                None => tr!("overdump-extra", size = overdump.file_size, verified = overdump.verified_size, extra = extra),
+            };
+            writeln!(overdumps_file, "  {}", sizes)?;
+            if !trimmed {
+                writeln!(overdumps_file, "  {}", tr!("overdump-truncate", verified = overdump.verified_size, extra = extra))?;
+            }
+            for rom in &overdump.matches {
+                writeln!(overdumps_file, "  {}", tr!("overdump-matches", game = rom.game.as_str(), rom = rom.name.as_str()))?;
+            }
+        }
+        
+        println!("{}", tr!("summary-overdumps", count = overdumps.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// NDS/3DS dumps that only match the DAT once padded to cartridge size
+    pub fn write_trimmed_log(&self, trimmed: &[Trimmed], repadded: bool) -> Result<()> {
+        let mut trimmed_file = self.create_report("trimmed.txt")?;
+        
+        writeln!(trimmed_file, "{}", tr!("trimmed-header", count = trimmed.len()))?;
+        if repadded {
+            writeln!(trimmed_file, "{}", tr!("trimmed-repadded"))?;
+        } else {
+            writeln!(trimmed_file, "{}", tr!("trimmed-hint"))?;
+        }
+        writeln!(trimmed_file)?;
+        
+        for dump in trimmed {
+            writeln!(trimmed_file, "{}", dump.path.display())?;
+            writeln!(trimmed_file, "  {}", tr!("trimmed-sizes",
+                system = dump.system, size = dump.file_size, cart = dump.cart_size,
+                missing = dump.cart_size - dump.file_size))?;
+            for rom in &dump.matches {
+                writeln!(trimmed_file, "  {}", tr!("overdump-matches", game = rom.game.as_str(), rom = rom.name.as_str()))?;
+            }
+        }
+        
+        println!("{}", tr!("summary-trimmed", count = trimmed.len(), logs = self.config.logs_dir.as_str()));
         
         Ok(())
     }
     
-    fn write_folders_log(&self, games_needing_folders: &HashSet<String>) -> Result<()> {
-        let folders_log = Path::new(&self.config.logs_dir).join("folders.txt");
-        let mut folders_file = File::create(&folders_log)?;
+    /// Files that match the DAT by hash but not by size
+    pub fn write_size_mismatch_log(&self, mismatches: &[SizeMismatch]) -> Result<()> {
+        let mut mismatch_file = self.create_report("size_mismatch.txt")?;
+        
+        writeln!(mismatch_file, "{}", tr!("size-mismatch-header", count = mismatches.len()))?;
+        writeln!(mismatch_file, "{}", tr!("size-mismatch-note"))?;
+        writeln!(mismatch_file)?;
+        
+        for mismatch in mismatches {
+            writeln!(mismatch_file, "{} ({} bytes)", mismatch.path.display(), mismatch.size)?;
+            for rom in &mismatch.matches {
+                let expected = rom.size.map_or_else(|| "?".to_string(), |size| size.to_string());
+                writeln!(mismatch_file, "  {}", tr!("size-mismatch-expected", game = rom.game.as_str(), rom = rom.name.as_str(), size = expected))?;
+            }
+        }
+        
+        println!("{}", tr!("summary-size-mismatch", count = mismatches.len(), logs = self.config.logs_dir.as_str()));
         
-        writeln!(folders_file, "Games stored in subfolders:")?;
-        writeln!(folders_file, "- Games with multiple ROM files")?;
-        writeln!(folders_file, "- Single ROM games where ROM filename differs from game name")?;
-        writeln!(folders_file, "  (ROM keeps its original name from DAT, placed in game-named folder)")?;
+        Ok(())
+    }
+    
+    /// Files matching entries the DAT marks as bad dumps
+    pub fn write_bad_dumps_log(&self, bad_dumps: &[BadDumpMatch]) -> Result<()> {
+        let mut bad_dumps_file = self.create_report("bad_dumps.txt")?;
+        
+        writeln!(bad_dumps_file, "{}", tr!("bad-dumps-header", count = bad_dumps.len()))?;
+        writeln!(bad_dumps_file, "{}", tr!("bad-dumps-note"))?;
+        writeln!(bad_dumps_file)?;
+        
+        for bad_dump in bad_dumps {
+            writeln!(bad_dumps_file, "{}", bad_dump.path.display())?;
+            for rom in &bad_dump.matches {
+                writeln!(bad_dumps_file, "  {}", tr!("overdump-matches", game = rom.game.as_str(), rom = rom.name.as_str()))?;
+            }
+        }
+        
+        println!("{}", tr!("summary-bad-dumps", count = bad_dumps.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Organized files re-verified by --scrub, and those that no longer match
+    pub fn write_scrub_log(&self, report: &ScrubReport) -> Result<()> {
+        let mut scrub_file = self.create_report("scrub.txt")?;
+        
+        writeln!(scrub_file, "{}", tr!("scrub-header", checked = report.checked, total = report.total, failed = report.failures.len()))?;
+        writeln!(scrub_file)?;
+        
+        for failure in &report.failures {
+            writeln!(scrub_file, "{}", failure.path.display())?;
+            writeln!(scrub_file, "  {} / {}: {}", failure.game, failure.rom, failure.problem)?;
+        }
+        
+        if report.failures.is_empty() {
+            println!("{}", tr!("summary-scrub-ok", checked = style(report.checked).green().to_string(), total = report.total));
+        } else {
+            println!("{}", tr!("summary-scrub-failed",
+                failed = style(report.failures.len()).red().to_string(), checked = report.checked, logs = self.config.logs_dir.as_str()));
+        }
+        
+        Ok(())
+    }
+    
+    /// The quick-audit matches a full run couldn't confirm
+    pub fn write_confirm_log(&self, report: &ScrubReport) -> Result<()> {
+        let mut confirm_file = self.create_report("unconfirmed.txt")?;
+        
+        writeln!(confirm_file, "{}", tr!("confirm-header", checked = report.checked, total = report.total, failed = report.failures.len()))?;
+        writeln!(confirm_file)?;
+        
+        for failure in &report.failures {
+            writeln!(confirm_file, "{}", failure.path.display())?;
+            writeln!(confirm_file, "  {} / {}: {}", failure.game, failure.rom, failure.problem)?;
+        }
+        
+        if report.failures.is_empty() {
+            println!("{}", tr!("summary-confirm-ok", checked = style(report.checked).green().to_string()));
+        } else {
+            println!("{}", tr!("summary-confirm-failed",
+                failed = style(report.failures.len()).red().to_string(), checked = report.checked, logs = self.config.logs_dir.as_str()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Which files of a torrent verify, which to re-download, and what they are in the DAT
+    pub fn write_torrent_log(&self, check: &TorrentCheck) -> Result<()> {
+        let mut torrent_file = self.create_report("torrent.txt")?;
+        let torrent = check.torrent;
+        
+        let (good, bad): (Vec<&FileCheck>, Vec<&FileCheck>) = check.files.iter().partition(|f| f.is_good());
+        let roms = |check: &FileCheck| check.dat_entries.iter()
+            .map(|rom| format!("{} / {}", rom.game, rom.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        
+        writeln!(torrent_file, "{}", tr!("torrent-header", name = torrent.name.as_str(), good = good.len(),
+            files = check.files.len(), pieces = check.good_pieces, total = torrent.pieces_len()))?;
+        if bad.is_empty() {
+            writeln!(torrent_file, "{}", tr!("torrent-all-good"))?;
+        } else {
+            writeln!(torrent_file, "{}", tr!("torrent-redownload-note"))?;
+        }
+        writeln!(torrent_file)?;
+        
+        if !bad.is_empty() {
+            writeln!(torrent_file, "{}", tr!("torrent-redownload", count = bad.len()))?;
+            for file in &bad {
+                let state = match file.state {
+                    FileState::Missing => tr!("torrent-file-missing"),
+                    FileState::Incomplete { bad_pieces } => tr!("torrent-file-failed", pieces = bad_pieces),
+                    FileState::Complete => tr!("torrent-file-complete"),
+                };
+                writeln!(torrent_file, "  {} ({})", file.file.path.display(), state)?;
+                if !file.dat_entries.is_empty() {
+                    writeln!(torrent_file, "    {}", tr!("torrent-would-provide", roms = roms(file)))?;
+                }
+            }
+            writeln!(torrent_file)?;
+        }
+        
+        let (in_dat, not_in_dat): (Vec<&FileCheck>, Vec<&FileCheck>) = good.iter().partition(|f| !f.dat_entries.is_empty());
+        writeln!(torrent_file, "{}", tr!("torrent-good-in-dat", count = in_dat.len()))?;
+        for file in &in_dat {
+            writeln!(torrent_file, "  {} -> {}", file.file.path.display(), roms(file))?;
+        }
+        if !not_in_dat.is_empty() {
+            writeln!(torrent_file)?;
+            writeln!(torrent_file, "{}", tr!("torrent-good-not-in-dat", count = not_in_dat.len()))?;
+            for file in &not_in_dat {
+                writeln!(torrent_file, "  {}", file.file.path.display())?;
+            }
+        }
+        
+        let games = check.games();
+        let complete = games.values().filter(|&&complete| complete).count();
+        println!("{}", tr!("summary-torrent", good = good.len(), files = check.files.len(), complete = complete,
+            incomplete = games.len() - complete, logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Games found on a remote source, and the remote files not in the DAT
+    pub fn write_remote_log(&self, source: &str, results: &[RemoteMatch], parsed_dat: &ParsedDat) -> Result<()> {
+        let mut remote_file = self.create_report("remote.txt")?;
+        
+        let mut games: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for result in results {
+            for rom in &result.matches {
+                games.entry(&rom.game).or_default().insert(&rom.name);
+            }
+        }
+        let matched = results.iter().filter(|r| !r.matches.is_empty()).count();
+        let by_remote_hash = results.iter().filter(|r| r.remote_hashes).count();
+        
+        writeln!(remote_file, "{}", tr!("remote-header", source = source))?;
+        writeln!(remote_file, "{}", tr!("remote-files", files = results.len(), matched = matched, remote = by_remote_hash))?;
+        writeln!(remote_file, "{}", tr!("remote-games", games = games.len(), total = parsed_dat.all_games.len()))?;
+        writeln!(remote_file)?;
+        
+        let mut game_list: Vec<_> = games.iter().collect();
+        game_list.sort_by(|a, b| sort::natural_cmp(a.0, b.0));
+        for (game, roms) in game_list {
+            let total = parsed_dat.game_roms.get(*game).map_or(0, Vec::len);
+            writeln!(remote_file, "{}", tr!("remote-game", game = *game, present = roms.len(), total = total))?;
+        }
+        
+        let unknown: Vec<_> = results.iter().filter(|r| r.matches.is_empty()).collect();
+        if !unknown.is_empty() {
+            writeln!(remote_file)?;
+            writeln!(remote_file, "{}", tr!("remote-not-in-dat", count = unknown.len()))?;
+            for result in unknown {
+                writeln!(remote_file, "  {}", result.file.path)?;
+            }
+        }
+        
+        println!("{}", tr!("summary-remote", games = games.len(), total = parsed_dat.all_games.len(),
+            source = source, logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// What the unmatched ISO9660/UDF images hold, as far as the DAT knows
+    pub fn write_images_log(&self, images: &[ImageContents]) -> Result<()> {
+        let mut images_file = self.create_report("images.txt")?;
+        
+        writeln!(images_file, "{}", tr!("images-header", count = images.len()))?;
+        writeln!(images_file, "{}", tr!("images-note"))?;
+        writeln!(images_file)?;
+        
+        for image in images {
+            writeln!(images_file, "{}", tr!("images-image", path = image.path.display().to_string(),
+                matched = image.matched(), files = image.members.len()))?;
+            let prefix = format!("{}#", image.path.display());
+            for member in image.members.iter().filter(|m| !m.matches.is_empty()) {
+                let name = member.label.strip_prefix(&prefix).unwrap_or(&member.label);
+                for rom in &member.matches {
+                    writeln!(images_file, "  {} -> {} / {}", name, rom.game, rom.name)?;
+                }
+            }
+            writeln!(images_file)?;
+        }
+        
+        println!("{}", tr!("summary-images", count = images.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// NKit/WBFS/WIA/RVZ images left in place, with the outcome of convert_command if it ran
+    pub fn write_convertible_log(&self, convertibles: &[(Convertible, Option<String>)]) -> Result<()> {
+        let mut convertible_file = self.create_report("convertible.txt")?;
+        
+        writeln!(convertible_file, "{}", tr!("convertible-header", count = convertibles.len()))?;
+        if self.config.convert_command.is_none() {
+            writeln!(convertible_file, "{}", tr!("convertible-hint"))?;
+        }
+        writeln!(convertible_file)?;
+        
+        for (convertible, conversion) in convertibles {
+            writeln!(convertible_file, "{}", convertible.path.display())?;
+            let unknown = tr!("convertible-unknown");
+            writeln!(convertible_file, "  {}", tr!("convertible-image",
+                format = convertible.format.name(),
+                id = convertible.game_id.clone().unwrap_or_else(|| unknown.clone()),
+                title = convertible.title.clone().unwrap_or(unknown)))?;
+            for rom in &convertible.matches {
+                writeln!(convertible_file, "  {}", tr!("convertible-present", game = rom.game.as_str(), rom = rom.name.as_str()))?;
+            }
+            if let Some(conversion) = conversion {
+                writeln!(convertible_file, "  {}", conversion)?;
+            }
+        }
+        
+        println!("{}", tr!("summary-convertible", count = convertibles.len(), logs = self.config.logs_dir.as_str()));
+        let present = convertibles.iter().filter(|(convertible, _)| !convertible.matches.is_empty()).count();
+        if present > 0 {
+            if self.config.nkit_as_present {
+                println!("  {}", tr!("summary-convertible-counted", count = present));
+            } else {
+                println!("  {}", tr!("summary-convertible-missing", count = present));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Files placed in probable_dir by name and size, for entries without hashes
+    pub fn write_probable_log(&self, probable: &[(ProbableMatch, PathBuf)]) -> Result<()> {
+        let mut probable_file = self.create_report("probable.txt")?;
+        
+        writeln!(probable_file, "{}", tr!("probable-header", count = probable.len()))?;
+        writeln!(probable_file, "{}", tr!("probable-note"))?;
+        writeln!(probable_file)?;
+        
+        for (probable_match, placed) in probable {
+            writeln!(probable_file, "{} / {}", probable_match.rom.game, probable_match.rom.name)?;
+            writeln!(probable_file, "  {}", tr!("probable-from", path = probable_match.path.display().to_string(), size = probable_match.size))?;
+            let placed = placed.display().to_string();
+            if self.config.changes_files() {
+                writeln!(probable_file, "  {}", tr!("probable-placed", path = placed))?;
+            } else {
+                writeln!(probable_file, "  {}", tr!("probable-would-place", path = placed))?;
+            }
+        }
+        
+        println!("{}", tr!("summary-probable", count = probable.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// RetroAchievements hashes of organized ROMs, tab separated
+    pub fn write_retroachievements_log(&self, hashes: &[RaHash]) -> Result<()> {
+        let mut ra_file = self.create_report("retroachievements.txt")?;
+        
+        writeln!(ra_file, "game\trom\tra_hash\tmethod\tpath")?;
+        for ra in hashes {
+            let method = match ra.method {
+                RaMethod::Whole => tr!("ra-whole"),
+                RaMethod::SkipHeader(bytes) => tr!("ra-header-skipped", bytes = bytes),
+                RaMethod::N64ByteOrder => tr!("ra-big-endian"),
+            };
+            writeln!(ra_file, "{}\t{}\t{}\t{}\t{}", ra.game, ra.rom, ra.hash, method, ra.path.display())?;
+        }
+        
+        println!("{}", tr!("summary-retroachievements", count = hashes.len(), logs = self.config.logs_dir.as_str()));
+        
+        Ok(())
+    }
+    
+    /// Matched files organized under a different name than they had
+    pub fn write_renames_log(&self, renames: &[Rename], applied: bool) -> Result<()> {
+        let mut renames_file = self.create_report("renames.txt")?;
+        
+        let header = if applied {
+            tr!("renames-applied", count = renames.len())
+        } else if self.config.read_only {
+            tr!("renames-read-only", count = renames.len())
+        } else if self.config.dry_run {
+            tr!("renames-dry-run", count = renames.len())
+        } else {
+            tr!("renames-declined", count = renames.len())
+        };
+        writeln!(renames_file, "{}", header)?;
+        writeln!(renames_file)?;
+        
+        for rename in renames {
+            writeln!(renames_file, "{} -> {}", rename.from.display(), rename.to.display())?;
+        }
+        
+        let logs = self.config.logs_dir.as_str();
+        let summary = match (applied, !self.config.changes_files()) {
+            (true, _) => tr!("summary-renamed", count = renames.len(), logs = logs),
+            (false, true) => tr!("summary-would-rename", count = renames.len(), logs = logs),
+            (false, false) => tr!("summary-renames-declined", count = renames.len(), logs = logs),
+        };
+        println!("{}", summary);
+        
+        Ok(())
+    }
+    
+    fn write_folders_log(&self, games_needing_folders: &GameFolders) -> Result<()> {
+        let mut folders_file = self.create_report("folders.txt")?;
+        
+        writeln!(folders_file, "{}", tr!("folders-title"))?;
+        writeln!(folders_file, "{}", tr!("folders-multiple"))?;
+        writeln!(folders_file, "{}", tr!("folders-renamed"))?;
+        writeln!(folders_file, "  {}", tr!("folders-renamed-note"))?;
         writeln!(folders_file)?;
         
         let mut folders_list: Vec<_> = games_needing_folders.iter().collect();
-        folders_list.sort();
+        sort::sort_natural(&mut folders_list);
         
         for game in folders_list {
             writeln!(folders_file, "{}", game)?;
@@ -158,29 +903,167 @@ impl Logger {
         &self,
         results: &ScanResult,
         all_games: &HashSet<String>,
-        games_needing_folders: &HashSet<String>,
+        games_needing_folders: &GameFolders,
+        partial_count: usize,
+        collection_stats: &stats::CollectionStats,
     ) {
-        println!("Audit complete!");
-        println!("Found: {} / {} ROMs ({:.1}%)",
-            results.have.len(),
-            all_games.len(),
-            (results.have.len() as f64 / all_games.len() as f64) * 100.0
-        );
-        println!("Duplicates: {}, Unknown: {}", 
-            results.duplicate.len(), 
-            results.unknown.len()
-        );
+        let logs = self.config.logs_dir.as_str();
+        println!("{}", tr!("summary-complete"));
+        println!("{}", tr!("summary-found",
+            have = style(results.have.len()).green().to_string(),
+            total = all_games.len(),
+            percent = format!("{:.1}", (results.have.len() as f64 / all_games.len() as f64) * 100.0)
+        ));
+        println!("{}", tr!("summary-duplicates",
+            duplicates = style(results.duplicate.len()).yellow().to_string(),
+            unknown = style(results.unknown.len()).yellow().to_string()
+        ));
+        if self.config.quick {
+            println!("{}", style(tr!("quick-unverified")).yellow());
+        }
+        
+        println!("{}", tr!("summary-size",
+            size = format_size(collection_stats.present_bytes),
+            missing = style(format_size(collection_stats.missing_bytes)).red().to_string(),
+            logs = logs));
+        
+        if partial_count > 0 {
+            println!("{}", tr!("summary-partial", count = partial_count, logs = logs));
+        }
         
         if !results.shared_roms.is_empty() {
-            println!("Shared ROMs: {} (check {}/shared.txt for details)",
-                results.shared_roms.len(), self.config.logs_dir);
+            println!("{}", tr!("summary-shared", count = results.shared_roms.len(), logs = logs));
         }
         
         if !games_needing_folders.is_empty() {
-            println!("Games in folders: {} (check {}/folders.txt for details)",
-                games_needing_folders.len(), self.config.logs_dir);
+            println!("{}", tr!("summary-folders", count = games_needing_folders.len(), logs = logs));
         }
         
-        println!("Check the {}/ directory for detailed results.", self.config.logs_dir);
+        println!("{}", tr!("summary-logs", logs = logs));
+    }
+}
+
+/// A game with some, but not all, of its ROM files present. Only required
+/// ROMs count towards `present` and `missing`.
+struct PartialGame<'a> {
+    game: &'a str,
+    present: usize,
+    missing: Vec<&'a RomEntry>,
+    optional_missing: Vec<&'a RomEntry>,
+}
+
+/// Set of (game, ROM name) pairs that are present in the collection
+/// The lines of stats.txt
+fn stats_lines(dat_name: &str, stats: &stats::CollectionStats) -> Vec<String> {
+    let title = tr!("stats-title");
+    let underline = "=".repeat(title.chars().count());
+    let mut lines = vec![
+        title,
+        underline,
+        String::new(),
+        tr!("stats-organized", size = format_size(stats.present_bytes), games = stats.games_present),
+        tr!("stats-average", size = format_size(stats.average_game_size())),
+        tr!("stats-missing", size = format_size(stats.missing_bytes), roms = stats.missing_roms),
+    ];
+    if stats.missing_without_size > 0 {
+        lines.push(format!("  {}", tr!("stats-missing-unsized", roms = stats.missing_without_size)));
     }
-}
\ No newline at end of file
+    lines.push(tr!("stats-complete", size = format_size(stats.present_bytes + stats.missing_bytes)));
+    lines.push(String::new());
+    lines.push(tr!("stats-per-system"));
+    lines.push(format!("  {}", tr!("stats-system",
+        system = dat_name, have = format_size(stats.present_bytes), missing = format_size(stats.missing_bytes))));
+    lines
+}
+
+pub(crate) fn present_roms(known_roms: &KnownRoms) -> HashSet<(&str, &str)> {
+    known_roms.values()
+        .flatten()
+        .map(|(game, rom)| (game.as_str(), rom.as_str()))
+        .collect()
+}
+
+/// Find games where at least one ROM is present and at least one is missing,
+/// in natural sort order
+fn find_partial_games<'a>(game_roms: &'a GameRoms, known_roms: &KnownRoms) -> Vec<PartialGame<'a>> {
+    let present = present_roms(known_roms);
+    
+    let mut partial_games: Vec<PartialGame> = game_roms.iter()
+        .filter_map(|(game, roms)| {
+            let (have, missing): (Vec<&RomEntry>, Vec<&RomEntry>) = roms.iter()
+                .partition(|rom| present.contains(&(game.as_str(), rom.name.as_str())));
+            
+            if have.is_empty() || missing.is_empty() {
+                return None;
+            }
+            
+            let (optional_missing, missing): (Vec<&RomEntry>, Vec<&RomEntry>) = missing.into_iter()
+                .partition(|rom| rom.optional);
+            let present = have.iter().filter(|rom| !rom.optional).count();
+            Some(PartialGame { game, present, missing, optional_missing })
+        })
+        .collect();
+    
+    partial_games.sort_by(|a, b| sort::natural_cmp(a.game, b.game));
+    partial_games
+}
+
+/// One-line description of a DAT ROM entry: name followed by size and hashes
+fn format_rom_details(rom: &RomEntry) -> String {
+    let mut details = vec![rom.name.clone()];
+    if let Some(size) = rom.size {
+        details.push(format!("size:{}", size));
+    }
+    let hashes = [("crc", &rom.hashes.crc), ("md5", &rom.hashes.md5), ("sha1", &rom.hashes.sha1), ("sha256", &rom.hashes.sha256)];
+    for (label, hash) in hashes {
+        if let Some(hash) = hash {
+            details.push(format!("{}:{}", label, hash));
+        }
+    }
+    details.join("  ")
+}
+
+/// Human-readable size using binary units
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} bytes", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optional_roms_do_not_make_a_game_partial() {
+        let rom = |game: &str, name: &str, optional: bool| RomEntry {
+            name: name.to_string(),
+            game: game.to_string(),
+            optional,
+            ..Default::default()
+        };
+        let mut game_roms = GameRoms::new();
+        game_roms.insert("Complete".to_string(), vec![rom("Complete", "main.bin", false), rom("Complete", "extra.bin", true)]);
+        game_roms.insert("Partial".to_string(), vec![rom("Partial", "a.bin", false), rom("Partial", "b.bin", false)]);
+        let mut known_roms = KnownRoms::new();
+        known_roms.insert("h1".to_string(), vec![("Complete".to_string(), "main.bin".to_string())]);
+        known_roms.insert("h2".to_string(), vec![("Partial".to_string(), "a.bin".to_string())]);
+
+        let partial = find_partial_games(&game_roms, &known_roms);
+        assert_eq!(partial.len(), 2);
+        assert_eq!(partial[0].game, "Complete");
+        assert!(partial[0].missing.is_empty());
+        assert_eq!(partial[0].optional_missing[0].name, "extra.bin");
+        assert_eq!((partial[1].present, partial[1].missing.len()), (1, 1));
+        assert_eq!(stats::missing_roms_by_size(&game_roms, &known_roms).len(), 1);
+    }
+}
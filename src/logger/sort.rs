@@ -0,0 +1,81 @@
+// src/logger/sort.rs - Natural sort order for report listings
+
+use std::cmp::Ordering;
+
+/// Compare two names so embedded numbers sort by value ("Game 2" < "Game 10").
+/// Text runs compare case-insensitively; the exact string breaks ties so the
+/// order never depends on locale or input order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let num_a = take_digits(&mut a_chars);
+                let num_b = take_digits(&mut b_chars);
+                let ordering = compare_digit_runs(&num_a, &num_b);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                let ordering = ca.to_lowercase().cmp(cb.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Sort a list of names in natural order
+pub fn sort_natural<S: AsRef<str>>(names: &mut [S]) {
+    names.sort_by(|a, b| natural_cmp(a.as_ref(), b.as_ref()));
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+/// Compare digit runs by numeric value without parsing (no overflow on long runs)
+fn compare_digit_runs(a: &str, b: &str) -> Ordering {
+    let a_trimmed = a.trim_start_matches('0');
+    let b_trimmed = b.trim_start_matches('0');
+    a_trimmed.len().cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+        // "007" after "7" so equal values still have a fixed order
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_sort_by_value() {
+        let mut names = vec!["Game 10", "Game 2", "Game 1", "game 3"];
+        sort_natural(&mut names);
+        assert_eq!(names, vec!["Game 1", "Game 2", "game 3", "Game 10"]);
+    }
+
+    #[test]
+    fn test_ties_are_stable() {
+        assert_eq!(natural_cmp("Disc 7", "Disc 007"), Ordering::Less);
+        assert_eq!(natural_cmp("ABC", "abc"), Ordering::Less);
+        assert_eq!(natural_cmp("Game", "Game (USA)"), Ordering::Less);
+    }
+}
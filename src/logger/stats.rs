@@ -0,0 +1,183 @@
+// src/logger/stats.rs - Size-based statistics for reports
+
+use std::collections::{BTreeMap, HashSet};
+
+use std::path::{Path, PathBuf};
+
+use crate::types::{GameRoms, KnownRoms, RomDb, RomEntry};
+use crate::organizer::processor::rom_destination;
+use crate::organizer::rules::GameFolders;
+
+/// On-disk footprint of a game in the organized collection
+pub struct GameDiskUsage<'a> {
+    pub game: &'a str,
+    pub location: PathBuf,
+    pub size: u64,
+}
+
+/// Location and size of every present game, in natural sort order
+pub fn game_disk_usage<'a>(
+    have: &'a HashSet<String>,
+    game_roms: &GameRoms,
+    known_roms: &KnownRoms,
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> Vec<GameDiskUsage<'a>> {
+    let present = super::present_roms(known_roms);
+    
+    let mut have_list: Vec<_> = have.iter().collect();
+    super::sort::sort_natural(&mut have_list);
+    
+    have_list.into_iter()
+        .map(|game| {
+            let paths: Vec<PathBuf> = game_roms.get(game)
+                .into_iter()
+                .flatten()
+                .filter(|rom| present.contains(&(game.as_str(), rom.name.as_str())))
+                .map(|rom| games_needing_folders.locate(rom, rom_destination(rom, games_needing_folders, rom_dir)))
+                .collect();
+            
+            // Games in their own folder are listed by folder, single files by
+            // file, and packed games by archive (plus any disks beside it)
+            let archive = games_needing_folders.archive_path(game, rom_dir);
+            let archive = (games_needing_folders.is_archived(game) && archive.is_file()).then_some(archive);
+            let location = match (&archive, paths.as_slice()) {
+                (Some(archive), _) => archive.clone(),
+                (None, [single]) if single.parent() == Some(Path::new(rom_dir)) => single.clone(),
+                _ => Path::new(rom_dir).join(game),
+            };
+            
+            let size = paths.iter()
+                .chain(&archive)
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|meta| meta.len())
+                .sum();
+            
+            GameDiskUsage { game, location, size }
+        })
+        .collect()
+}
+
+/// Size totals for the collection as a whole
+pub struct CollectionStats {
+    pub games_present: usize,
+    pub present_bytes: u64,
+    pub missing_roms: usize,
+    pub missing_bytes: u64,
+    pub missing_without_size: usize,
+}
+
+impl CollectionStats {
+    pub fn average_game_size(&self) -> u64 {
+        if self.games_present == 0 {
+            0
+        } else {
+            self.present_bytes / self.games_present as u64
+        }
+    }
+}
+
+/// Combine on-disk sizes of present games with DAT sizes of everything still missing
+pub fn collection_stats(
+    usage: &[GameDiskUsage],
+    game_roms: &GameRoms,
+    known_roms: &KnownRoms,
+) -> CollectionStats {
+    let present = super::present_roms(known_roms);
+    let mut stats = CollectionStats {
+        games_present: usage.len(),
+        present_bytes: usage.iter().map(|u| u.size).sum(),
+        missing_roms: 0,
+        missing_bytes: 0,
+        missing_without_size: 0,
+    };
+    
+    for (game, roms) in game_roms {
+        for rom in roms {
+            if rom.optional || present.contains(&(game.as_str(), rom.name.as_str())) {
+                continue;
+            }
+            stats.missing_roms += 1;
+            match rom.size {
+                Some(size) => stats.missing_bytes += size,
+                None => stats.missing_without_size += 1,
+            }
+        }
+    }
+    
+    stats
+}
+
+/// Every missing ROM file other than optional ones, largest first; ROMs
+/// without a DAT size go last
+pub fn missing_roms_by_size<'a>(game_roms: &'a GameRoms, known_roms: &KnownRoms) -> Vec<&'a RomEntry> {
+    let present = super::present_roms(known_roms);
+    let mut missing: Vec<&RomEntry> = game_roms.iter()
+        .flat_map(|(game, roms)| {
+            let present = &present;
+            roms.iter().filter(move |rom| !rom.optional && !present.contains(&(game.as_str(), rom.name.as_str())))
+        })
+        .collect();
+    
+    missing.sort_by(|a, b| {
+        b.size.cmp(&a.size)
+            .then_with(|| super::sort::natural_cmp(&a.game, &b.game))
+            .then_with(|| super::sort::natural_cmp(&a.name, &b.name))
+    });
+    missing
+}
+
+/// Space that could be reclaimed for one piece of shared content
+pub struct DedupEntry<'a> {
+    pub hash: &'a str,
+    pub rom_names: Vec<&'a str>,
+    pub games: &'a [String],
+    pub copies: usize,
+    pub file_size: u64,
+}
+
+impl DedupEntry<'_> {
+    /// Bytes saved by keeping one copy and hardlinking (or solid-archiving) the rest
+    pub fn savings(&self) -> u64 {
+        self.file_size * (self.copies.saturating_sub(1) as u64)
+    }
+}
+
+/// Estimate dedup savings for every shared ROM, biggest savings first
+pub fn estimate_dedup_savings<'a>(
+    shared_roms: &'a BTreeMap<String, Vec<String>>,
+    known_roms: &'a KnownRoms,
+    rom_db: &RomDb,
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> Vec<DedupEntry<'a>> {
+    let mut entries: Vec<DedupEntry> = shared_roms.iter()
+        .map(|(hash, games)| {
+            let placements = known_roms.get(hash).map(|v| v.as_slice()).unwrap_or(&[]);
+            let mut rom_names: Vec<&str> = placements.iter().map(|(_, rom)| rom.as_str()).collect();
+            rom_names.sort();
+            rom_names.dedup();
+            
+            DedupEntry {
+                hash,
+                rom_names,
+                games,
+                copies: placements.len(),
+                file_size: content_size(rom_db.get(hash).and_then(|e| e.first()), games_needing_folders, rom_dir),
+            }
+        })
+        .collect();
+    
+    entries.sort_by(|a, b| b.savings().cmp(&a.savings()).then_with(|| a.hash.cmp(b.hash)));
+    entries
+}
+
+/// Size of a ROM: the DAT size when known, otherwise the size of the organized file
+fn content_size(entry: Option<&RomEntry>, games_needing_folders: &GameFolders, rom_dir: &str) -> u64 {
+    let Some(entry) = entry else { return 0 };
+    entry.size.unwrap_or_else(|| {
+        std::fs::metadata(games_needing_folders.locate(entry, rom_destination(entry, games_needing_folders, rom_dir)))
+            .map(|meta| meta.len())
+            .unwrap_or(0)
+    })
+}
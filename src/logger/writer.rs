@@ -0,0 +1,76 @@
+// src/logger/writer.rs - Report file writer with configurable encoding and line endings
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::config::{LineEnding, ReportEncoding};
+use crate::error::Result;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Buffered report file that applies the configured encoding and line endings.
+/// Report code keeps using `writeln!`; `\n` is translated on the way out.
+pub struct ReportWriter {
+    inner: BufWriter<File>,
+    line_ending: LineEnding,
+}
+
+impl ReportWriter {
+    pub fn create(path: &Path, encoding: ReportEncoding, line_ending: LineEnding) -> Result<Self> {
+        let mut inner = BufWriter::new(File::create(path)?);
+        if encoding == ReportEncoding::Utf8Bom {
+            inner.write_all(UTF8_BOM)?;
+        }
+        Ok(ReportWriter { inner, line_ending })
+    }
+}
+
+impl Write for ReportWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.line_ending {
+            LineEnding::Lf => self.inner.write_all(buf)?,
+            LineEnding::Crlf => {
+                for (i, line) in buf.split(|&b| b == b'\n').enumerate() {
+                    if i > 0 {
+                        self.inner.write_all(b"\r\n")?;
+                    }
+                    self.inner.write_all(line)?;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Drop for ReportWriter {
+    fn drop(&mut self) {
+        let _ = self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_crlf_with_bom() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("report.txt");
+
+        {
+            let mut writer = ReportWriter::create(&path, ReportEncoding::Utf8Bom, LineEnding::Crlf).unwrap();
+            writeln!(writer, "Line 1").unwrap();
+            writeln!(writer).unwrap();
+            write!(writer, "a\nb").unwrap();
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes, b"\xEF\xBB\xBFLine 1\r\n\r\na\r\nb");
+    }
+}
@@ -6,6 +6,10 @@
 // Commercial use is strictly prohibited.
 // See LICENSE file for full terms.
 
+#[macro_use]
+mod i18n;
+mod color;
+mod cli;
 mod config;
 mod error;
 mod types;
@@ -15,91 +19,1045 @@ mod organizer;
 mod database;
 mod logger;
 mod cache;
+mod search;
+mod progress;
+mod daemon;
+mod pack;
+mod dat_diff;
+mod one_g1r;
+mod filter;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::path::Path;
+use std::collections::HashSet;
+use std::io::IsTerminal;
 
-use crate::error::Result;
+use clap::Parser;
+
+use crate::error::{Result, RomAuditError};
+use crate::cli::{Cli, Command, DbCommand};
 use crate::config::Config;
 
+/// Find and parse the DAT file in the current directory, load the header
+/// skippers it needs, and switch the config over to that DAT's own database
+fn load_dat(config: &mut Config) -> Result<types::ParsedDat> {
+    let dat_path = match &config.dat_file {
+        Some(path) => std::path::PathBuf::from(path),
+        None => parser::find_dat_file()?,
+    };
+    // Whatever was picked, later steps use the same DAT
+    config.dat_file = Some(dat_path.to_string_lossy().to_string());
+    println!("{}", tr!("dat-found", path = dat_path.display().to_string()));
+    for companion in parser::checksum::verify_dat(&dat_path, config.buffer_size)? {
+        println!("{}", tr!("dat-verified", path = companion.display().to_string()));
+    }
+    
+    let (detected_type, is_mame) = parser::detector::detect_dat_type_from_file(&dat_path)?;
+    let mut parsed_dat = parser::parse_dat_file(&dat_path, config)?;
+    if !config.excluded_categories.is_empty() {
+        let catver = std::path::PathBuf::from(config.catver.as_deref().unwrap_or(parser::catver::CATVER_FILE));
+        let categories = parser::catver::read_catver(&catver)?;
+        let excluded = filter::games_in_categories(&parsed_dat, &categories, &config.excluded_categories);
+        filter::remove_games(&mut parsed_dat, &excluded);
+        println!("{}", tr!("dat-excluded-categories", games = excluded.len(), categories = config.excluded_categories.join(", ")));
+    }
+    
+    // The header's forcemerging directive beats guessing from the file name
+    let dat_type = match parsed_dat.header.force_merging {
+        Some(forced) => {
+            println!("{}", tr!("dat-forced-type", kind = parser::detector::dat_type_name(&forced)));
+            forced
+        }
+        None if is_mame => {
+            println!("{}", tr!("dat-mame", kind = parser::detector::dat_type_name(&detected_type)));
+            detected_type
+        }
+        None => {
+            if detected_type != types::DatType::Standard {
+                println!("{}", tr!("dat-type", kind = parser::detector::dat_type_name(&detected_type)));
+            }
+            detected_type
+        }
+    };
+    parser::sets::apply_dat_type(&mut parsed_dat, dat_type);
+    match parsed_dat.header.zipped_sets {
+        Some(true) if config.output_mode == config::OutputMode::Files => println!("{}", tr!("dat-wants-zipped")),
+        Some(false) if config.output_mode != config::OutputMode::Files => println!("{}", tr!("dat-wants-unzipped")),
+        _ => {}
+    }
+    println!("{}", tr!("dat-parsed", games = parsed_dat.all_games.len()));
+    if !config.language_filter.is_empty() {
+        let games = filter::games_in_languages(&parsed_dat, &config.language_filter);
+        println!("{}", tr!("dat-language-filter", games = games.len(), languages = config.language_filter.join(", ")));
+        parsed_dat.counted = Some(games);
+    }
+    if config.one_game_one_rom {
+        let priorities = one_g1r::Priorities { regions: &config.region_priority, languages: &config.language_priority };
+        let best: HashSet<String> = one_g1r::best_releases(&parsed_dat, priorities).into_iter().collect();
+        println!("{}", tr!("dat-one-g1r", games = best.len(), regions = config.region_priority.join(", ")));
+        parsed_dat.counted = Some(best);
+    }
+    if let Some(header) = parsed_dat.header.summary() {
+        println!("{}", tr!("dat-header", header = header));
+    }
+    scanner::header::init(config, &parsed_dat.header)?;
+    
+    database::use_dat_database(config, &parsed_dat)?;
+    Ok(parsed_dat)
+}
+
+/// Matched files the scan may get ahead of organizing by
+const PIPELINE_DEPTH: usize = 64;
+
 struct RomAuditor {
     config: Config,
     parsed_dat: types::ParsedDat,
     known_roms: types::KnownRoms,
     interrupted: Arc<AtomicBool>,
+    /// Organize matched files while later files are still being hashed
+    pipelined: bool,
 }
 
 impl RomAuditor {
-    fn new(config: Config, interrupted: Arc<AtomicBool>) -> Result<Self> {
+    fn new(mut config: Config, interrupted: Arc<AtomicBool>) -> Result<Self> {
         // Find and parse DAT file
-        let dat_path = parser::find_dat_file()?;
-        println!("Found DAT file: {}", dat_path.display());
-        
-        let parsed_dat = parser::parse_dat_file(&dat_path)?;
-        println!("Parsed {} games from DAT file", parsed_dat.all_games.len());
+        let parsed_dat = load_dat(&mut config)?;
         
         // Load known ROMs database
         let known_roms = database::load_known_roms(&config.db_file)?;
+        if config.scrub_percent.is_some() {
+            config.require_loose_files("--scrub")?;
+        }
+        if config.ra_hashes {
+            config.require_loose_files("--ra-hashes")?;
+        }
+        
+        let stale = database::prune::find_stale_entries(&known_roms, &parsed_dat.game_roms);
+        if !stale.is_empty() {
+            println!("{}", tr!("db-stale-notice", entries = stale.len(), db = config.db_file.as_str()));
+        }
+        
+        // Unless the changes are to be reviewed first
+        let pipelined = config.changes_files()
+            && !config.review_renames
+            && (config.assume_yes || !std::io::stdin().is_terminal());
         
         Ok(RomAuditor {
             config,
             parsed_dat,
             known_roms,
             interrupted,
+            pipelined,
         })
     }
     
-    fn run(&mut self) -> Result<()> {
-        // Scan files and calculate hashes
-        let mut scanner = scanner::Scanner::new(self.config.clone(), self.interrupted.clone());
-        let (file_hashes, games_with_files) = scanner.scan_files(
-            Path::new("."),
-            &self.parsed_dat.rom_db,
+    fn run(&mut self) -> Result<types::ScanResult> {
+        let mut events = if self.config.dry_run {
+            logger::events::EventLog::open_dry_run(&self.config.logs_dir)?
+        } else {
+            logger::events::EventLog::open(&self.config.logs_dir)?
+        };
+        
+        let organizer = organizer::Organizer::new(
+            self.config.clone(),
+            &self.parsed_dat,
+            self.interrupted.clone(),
         )?;
+        let mut organizing = organizer.start(&self.known_roms)?;
+        let mut size_mismatches = Vec::new();
+        let mut bad_dumps = Vec::new();
+        let mut renames = Vec::new();
+        
+        // Scan files and calculate hashes. When pipelined, matched files are
+        // organized while later files are still being hashed; the rest wait
+        // for the checks below. The scanner hands files over in collection
+        // order, so which copy lands in rom_dir is the same either way.
+        let mut scanner = scanner::Scanner::new(self.config.clone(), self.interrupted.clone());
+        let scan_dir = Path::new(&self.config.scan_dir);
+        let rom_db = &self.parsed_dat.rom_db;
+        let scanned = if self.pipelined {
+            let (sender, receiver) = std::sync::mpsc::sync_channel::<types::FileHash>(PIPELINE_DEPTH);
+            std::thread::scope(|scope| {
+                let scanning = scope.spawn(|| scanner.scan_files_into(scan_dir, rom_db, move |file_hash| {
+                    if file_hash.matching_entries.is_empty() {
+                        return Some(file_hash);
+                    }
+                    // Only fails once organizing has failed and stopped listening
+                    sender.send(file_hash).err().map(|e| e.0)
+                }));
+                let mut games = HashSet::new();
+                let mut organized = Ok(());
+                for file_hash in receiver {
+                    games.extend(file_hash.matching_entries.iter().map(|entry| entry.game.clone()));
+                    organized = events.record_scanned(&file_hash).and_then(|()| {
+                        let file = std::slice::from_ref(&file_hash);
+                        size_mismatches.extend(scanner::size_mismatches(file));
+                        bad_dumps.extend(scanner::bad_dump_matches(file));
+                        renames.extend(organizer::renames::plan_renames(
+                            file, &games, organizer.games_needing_folders(), &self.config.rom_dir,
+                        ));
+                        organizer.organize_file(&mut organizing, file_hash, &games, &mut self.known_roms, &mut events)
+                    });
+                    if organized.is_err() {
+                        break;
+                    }
+                }
+                let scanned = scanning.join().expect("scan thread panicked");
+                organized.and(scanned)
+            })
+        } else {
+            scanner.scan_files(scan_dir, rom_db)
+        };
+        let (mut file_hashes, mut games_with_files) = match scanned {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                events.record(logger::events::Event::Error { path: None, message: e.to_string() })?;
+                return Err(e);
+            }
+        };
+        for file_hash in &file_hashes {
+            events.record_scanned(file_hash)?;
+        }
+        let unreadable = scanner.unreadable().to_vec();
+        for skipped in &unreadable {
+            events.record(logger::events::Event::Error { path: Some(skipped.path.clone()), message: skipped.error.clone() })?;
+        }
         
         // Check if interrupted during scanning
         if self.interrupted.load(Ordering::Relaxed) {
-            database::save_known_roms(&self.known_roms, &self.config.db_file)?;
-            return Ok(());
+            if self.config.changes_files() {
+                database::save_known_roms(&self.known_roms, &self.config.db_file)?;
+            }
+            return Ok(types::ScanResult::default());
         }
         
-        // Organize files
-        let organizer = organizer::Organizer::new(
-            self.config.clone(),
-            &self.parsed_dat.rom_db,
-            self.interrupted.clone(),
-        );
+        // Split files (.001/.002, .z01) whose parts match the DAT joined
+        let split_files = scanner::multipart::join_split_files(&file_hashes, rom_db, self.config.buffer_size)?;
+        if !split_files.is_empty() {
+            println!("{}", tr!("scan-joined", files = split_files.len()));
+        }
+        for split_file in split_files {
+            file_hashes.retain(|f| !split_file.parts.contains(&f.path));
+            games_with_files.extend(split_file.file_hash.matching_entries.iter().map(|e| e.game.clone()));
+            events.record_scanned(&split_file.file_hash)?;
+            file_hashes.push(split_file.file_hash);
+        }
+        
+        // Unmatched files whose leading bytes match a smaller DAT entry
+        let overdumps = scanner::overdump::detect_overdumps(&file_hashes, rom_db)?;
+        let trim_overdumps = self.config.trim_overdumps && self.config.changes_files();
+        for overdump in &overdumps {
+            if trim_overdumps {
+                // The trimmed copy is organized; the original ends up with the unknown files
+                if let Some(trimmed) = scanner::overdump::trim_overdump(overdump, rom_db, self.config.buffer_size)? {
+                    games_with_files.extend(trimmed.matching_entries.iter().map(|e| e.game.clone()));
+                    events.record_scanned(&trimmed)?;
+                    file_hashes.push(trimmed);
+                }
+            } else {
+                // Left in place with a suggested truncation, rather than moved with the unknown files
+                file_hashes.retain(|f| f.path != overdump.path);
+                events.record(logger::events::Event::Skipped {
+                    path: overdump.path.clone(),
+                    reason: format!("overdump of {} bytes", overdump.verified_size),
+                    moved_to: None,
+                })?;
+            }
+        }
+        
+        // Unmatched NDS/3DS dumps that match once padded back to cartridge size
+        let trimmed_dumps = scanner::trimmed::detect_trimmed(&file_hashes, rom_db, self.config.buffer_size)?;
+        let repad_trimmed = self.config.repad_trimmed && self.config.changes_files();
+        for trimmed in &trimmed_dumps {
+            if repad_trimmed {
+                // The padded copy is organized; the original ends up with the unknown files
+                if let Some(padded) = scanner::trimmed::repad_trimmed(trimmed, rom_db, self.config.buffer_size)? {
+                    games_with_files.extend(padded.matching_entries.iter().map(|e| e.game.clone()));
+                    events.record_scanned(&padded)?;
+                    file_hashes.push(padded);
+                }
+            } else {
+                // Present but trimmed: left in place rather than moved with the unknown files
+                file_hashes.retain(|f| f.path != trimmed.path);
+                events.record(logger::events::Event::Skipped {
+                    path: trimmed.path.clone(),
+                    reason: format!("trimmed {} dump", trimmed.system),
+                    moved_to: None,
+                })?;
+            }
+        }
+        
+        // Files whose hash matches but whose size doesn't are organized, and flagged
+        size_mismatches.extend(scanner::size_mismatches(&file_hashes));
+        bad_dumps.extend(scanner::bad_dump_matches(&file_hashes));
         
-        let mut result = organizer.organize_files(
+        // NKit/WBFS/WIA/RVZ images never match Redump; leave them in place instead of
+        // moving them with the unknown files, converting them if configured
+        let mut convertibles = Vec::new();
+        for convertible in scanner::convertible::detect_convertibles(&file_hashes, rom_db)? {
+            file_hashes.retain(|f| f.path != convertible.path);
+            events.record(logger::events::Event::Skipped {
+                path: convertible.path.clone(),
+                reason: format!("convertible {} image", convertible.format.name()),
+                moved_to: None,
+            })?;
+            let conversion = match &self.config.convert_command {
+                Some(command) if self.config.changes_files() => Some(
+                    match scanner::convertible::run_convert_command(command, &convertible) {
+                        Ok(()) => "converted; run again to audit the result".to_string(),
+                        Err(e) => format!("conversion failed: {}", e),
+                    },
+                ),
+                _ => None,
+            };
+            convertibles.push((convertible, conversion));
+        }
+        
+        // Damaged ZIP archives are left in place and reported for re-download
+        let corrupt_zips = if self.config.verify_zips {
+            scanner::zip_check::find_corrupt_zips(&file_hashes)?
+        } else {
+            Vec::new()
+        };
+        for corrupt in &corrupt_zips {
+            file_hashes.retain(|f| f.path != corrupt.path);
+            events.record(logger::events::Event::Skipped {
+                path: corrupt.path.clone(),
+                reason: "corrupt ZIP archive".to_string(),
+                moved_to: None,
+            })?;
+        }
+        
+        // Unmatched ISO9660/UDF images: report the DAT files inside them, and
+        // leave images that hold any in place
+        let images = scanner::disc_image::scan_images(&file_hashes, rom_db, self.config.buffer_size)?;
+        for image in images.iter().filter(|image| image.matched() > 0) {
+            file_hashes.retain(|f| f.path != image.path);
+            events.record(logger::events::Event::Skipped {
+                path: image.path.clone(),
+                reason: format!("disc image holding {} DAT files", image.matched()),
+                moved_to: None,
+            })?;
+        }
+        
+        // Unmatched files that are probably dumps of nodump entries go to
+        // probable_dir for review instead of with the unknown files
+        let mut probable = Vec::new();
+        let unmatched: Vec<_> = file_hashes.iter()
+            .filter(|f| !overdumps.iter().any(|o| o.path == f.path))
+            .filter(|f| !trimmed_dumps.iter().any(|t| t.path == f.path))
+            .cloned()
+            .collect();
+        for probable_match in scanner::nodump::find_probable_matches(&unmatched, &self.parsed_dat.game_roms)? {
+            let placed = organizer::processor::place_probable(
+                &probable_match.path, &probable_match.rom, &self.config,
+            )?;
+            if let Some(placed) = placed {
+                if !self.config.read_only {
+                    events.record(logger::events::Event::Placed {
+                        from: probable_match.path.clone(),
+                        to: placed.clone(),
+                        game: probable_match.rom.game.clone(),
+                        rom: probable_match.rom.name.clone(),
+                        converted: None,
+                    })?;
+                }
+                file_hashes.retain(|f| f.path != probable_match.path);
+                probable.push((probable_match, placed));
+            }
+        }
+        
+        // Guess what the unknown files are before they are moved
+        let unknown_files = scanner::classify::classify_unknown(&file_hashes, &self.config);
+        
+        // Files whose names differ from the DAT names, optionally confirmed first
+        renames.extend(organizer::renames::plan_renames(
+            &file_hashes,
+            &games_with_files,
+            organizer.games_needing_folders(),
+            &self.config.rom_dir,
+        ));
+        let mut renames_applied = self.config.changes_files();
+        if self.config.review_renames && renames_applied && !renames.is_empty() {
+            renames_applied = organizer::renames::confirm_renames(
+                &renames,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )?;
+            if !renames_applied {
+                file_hashes.retain(|f| !renames.iter().any(|r| r.from == f.path));
+            }
+        }
+        
+        // Ask before moving and deleting, when someone is there to answer
+        if self.config.changes_files() && !self.config.assume_yes && std::io::stdin().is_terminal() {
+            let changes = organizer::confirm::plan_changes(
+                &file_hashes,
+                &games_with_files,
+                organizer.games_needing_folders(),
+                &self.config,
+            );
+            if !changes.is_empty() && !organizer::confirm::confirm_changes(
+                &changes,
+                &self.config,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )? {
+                println!("{}", tr!("organize-confirm-cancelled"));
+                return Ok(types::ScanResult::default());
+            }
+        }
+        
+        organizer.organize_remaining(
+            &mut organizing,
             file_hashes,
             &games_with_files,
             &mut self.known_roms,
+            &mut events,
         )?;
+        let mut result = organizer.finish(organizing, &self.known_roms);
+        
+        // With --output=torrentzip or zip, move each game's loose files into its archive
+        let packed = organizer::game_archives::pack_games(
+            &self.parsed_dat.game_roms,
+            &self.known_roms,
+            organizer.games_needing_folders(),
+            &self.config,
+            &mut events,
+        )?;
+        if packed.archives > 0 {
+            println!("{}", tr!("organize-packed", files = packed.files, archives = packed.archives));
+        }
+        
+        // A full run confirms what earlier --quick runs matched by CRC32 alone;
+        // a dry or read-only run placed nothing in roms/ to confirm
+        let confirm_report = if !self.config.quick && self.config.changes_files() {
+            let report = scanner::scrub::confirm_quick_matches(
+                &self.parsed_dat.game_roms,
+                &mut self.known_roms,
+                organizer.games_needing_folders(),
+                &self.config,
+                &self.interrupted,
+            )?;
+            for failure in &report.failures {
+                result.have.remove(&failure.game);
+                events.record(logger::events::Event::Error {
+                    path: Some(failure.path.clone()),
+                    message: format!("quick-audit match not confirmed: {}", failure.problem),
+                })?;
+            }
+            (report.total > 0).then_some(report)
+        } else {
+            None
+        };
         
-        // Update missing set
-        result.missing = self.parsed_dat.all_games.clone();
+        // Re-verify the least recently checked slice of the collection
+        // (a dry or read-only run placed nothing, so it would find the new
+        // files missing)
+        let scrub_report = match self.config.scrub_percent {
+            Some(_) if self.config.changes_files() => {
+                let mut state = scanner::scrub::ScrubState::load(&self.config.state_path(scanner::scrub::ScrubState::STATE_FILE));
+                let report = scanner::scrub::scrub(
+                    &self.parsed_dat.game_roms,
+                    &self.known_roms,
+                    organizer.games_needing_folders(),
+                    &self.config,
+                    &mut state,
+                    &self.interrupted,
+                )?;
+                state.save()?;
+                for failure in &report.failures {
+                    events.record(logger::events::Event::Error {
+                        path: Some(failure.path.clone()),
+                        message: format!("scrub: {}", failure.problem),
+                    })?;
+                }
+                Some(report)
+            }
+            _ => None,
+        };
+        events.flush()?;
+        
+        // Update missing set; in a 1G1R audit, other releases don't count
+        result.have.retain(|game| self.parsed_dat.counted_games().contains(game));
+        result.missing = self.parsed_dat.counted_games().clone();
         for game in &result.have {
             result.missing.remove(game);
         }
+        // A game whose ROMs no one has dumped can't be had
+        result.missing.retain(|game| {
+            !self.parsed_dat.game_roms.get(game).is_some_and(|roms| {
+                !roms.is_empty() && roms.iter().all(|rom| rom.status == types::RomStatus::NoDump && rom.optional)
+            })
+        });
+        if self.config.nkit_as_present {
+            for (convertible, _) in &convertibles {
+                for entry in &convertible.matches {
+                    result.missing.remove(&entry.game);
+                }
+            }
+        }
         
-        // Save database
-        database::save_known_roms(&self.known_roms, &self.config.db_file)?;
+        // Save database, with the DAT names of any names sanitized on disk;
+        // a dry run leaves it as it was, and so does a read-only one, whose
+        // matches are where they were found rather than in roms/
+        if self.config.changes_files() {
+            let path_names = organizer.games_needing_folders().path_names();
+            database::save_database(&self.known_roms, &path_names, &self.config.db_file)?;
+        }
         
         // Write logs
         let logger = logger::Logger::new(self.config.clone());
         logger.write_logs(
             &result,
-            &self.parsed_dat.all_games,
+            &self.parsed_dat,
             &self.known_roms,
             organizer.games_needing_folders(),
         )?;
+        if !unreadable.is_empty() {
+            logger.write_unreadable_log(&unreadable)?;
+        }
+        if !organizer.games_needing_folders().collisions().is_empty() {
+            logger.write_collisions_log(organizer.games_needing_folders().collisions())?;
+        }
+        if !unknown_files.is_empty() {
+            logger.write_unknown_log(&unknown_files)?;
+        }
+        if !corrupt_zips.is_empty() {
+            logger.write_corrupt_zips_log(&corrupt_zips)?;
+        }
+        if !overdumps.is_empty() {
+            logger.write_overdumps_log(&overdumps, trim_overdumps)?;
+        }
+        if !trimmed_dumps.is_empty() {
+            logger.write_trimmed_log(&trimmed_dumps, repad_trimmed)?;
+        }
+        if !size_mismatches.is_empty() {
+            logger.write_size_mismatch_log(&size_mismatches)?;
+        }
+        if !bad_dumps.is_empty() {
+            logger.write_bad_dumps_log(&bad_dumps)?;
+        }
+        if let Some(report) = &scrub_report {
+            logger.write_scrub_log(report)?;
+        }
+        if let Some(report) = &confirm_report {
+            logger.write_confirm_log(report)?;
+        }
+        if !images.is_empty() {
+            logger.write_images_log(&images)?;
+        }
+        if !convertibles.is_empty() {
+            logger.write_convertible_log(&convertibles)?;
+        }
+        if !probable.is_empty() {
+            logger.write_probable_log(&probable)?;
+        }
+        if !renames.is_empty() {
+            logger.write_renames_log(&renames, renames_applied)?;
+        }
+        if self.config.ra_hashes {
+            let ra_hashes = scanner::retroachievements::collection_ra_hashes(
+                &self.parsed_dat.game_roms,
+                &self.known_roms,
+                organizer.games_needing_folders(),
+                &self.config.rom_dir,
+            )?;
+            logger.write_retroachievements_log(&ra_hashes)?;
+        }
+        
+        if self.config.dry_run {
+            logger.write_dry_run_log(events.planned())?;
+        }
         
         // Clean up empty folders
-        organizer::folders::remove_empty_folders(Path::new("."), &self.config)?;
+        if self.config.changes_files() {
+            organizer::folders::remove_empty_folders(Path::new(&self.config.scan_dir), &self.config)?;
+        }
         
+        Ok(result)
+    }
+}
+
+/// Seed the hash cache and DB from a clrmamepro/RomVault "have" DAT export
+fn import_have(config: &mut Config, have_dat: &Path, root: &Path) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let have = parser::parse_dat_file(have_dat, config)?;
+    println!("Importing {} games from {}", have.all_games.len(), have_dat.display());
+    
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let mut cache = cache::HashCache::load(&config.state_path(cache::HashCache::CACHE_FILE));
+    let mut known_roms = database::load_known_roms(&config.db_file)?;
+    let mut scrub_state = scanner::scrub::ScrubState::load(&config.state_path(scanner::scrub::ScrubState::STATE_FILE));
+    
+    let summary = database::import::import_have_dat(
+        &have,
+        root,
+        &parsed_dat,
+        &games_needing_folders,
+        config,
+        database::import::Seeded { cache: &mut cache, known_roms: &mut known_roms, scrub_state: &mut scrub_state },
+    )?;
+    
+    cache.save()?;
+    database::save_known_roms(&known_roms, &config.db_file)?;
+    scrub_state.save()?;
+    
+    println!("Cached hashes for {} files ({} already organized, added to {})",
+        summary.cached, summary.known, config.db_file);
+    if summary.unverified > 0 {
+        println!("These hashes are trusted without re-hashing; the next `verify` or --scrub run re-hashes all {} ROMs they organize to",
+            summary.unverified);
+    }
+    if summary.not_found > 0 || summary.size_mismatch > 0 {
+        println!("Skipped {} ROMs not found under {} and {} with a different size",
+            summary.not_found, root.display(), summary.size_mismatch);
+    }
+    
+    Ok(())
+}
+
+/// List DB entries missing from the current DAT, and remove them with --apply
+fn prune_db(config: &mut Config, apply: bool) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let mut known_roms = database::load_known_roms(&config.db_file)?;
+    
+    let stale = database::prune::find_stale_entries(&known_roms, &parsed_dat.game_roms);
+    if stale.is_empty() {
+        println!("{}", tr!("db-prune-nothing", db = config.db_file.as_str()));
+        return Ok(());
+    }
+    
+    for entry in &stale {
+        println!("  {} / {} ({})", entry.game, entry.rom, entry.hash);
+    }
+    
+    if apply {
+        database::prune::remove_entries(&mut known_roms, &stale);
+        database::save_known_roms(&known_roms, &config.db_file)?;
+        println!("{}", tr!("db-pruned", entries = stale.len(), db = config.db_file.as_str()));
+    } else {
+        println!("{}", tr!("db-prune-dry-run", entries = stale.len()));
+    }
+    
+    Ok(())
+}
+
+/// Regenerate the DB by hashing the organized tree
+fn rebuild_db(config: &mut Config, interrupted: &AtomicBool) -> Result<()> {
+    config.require_loose_files("db rebuild")?;
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    
+    let Some((known_roms, summary)) = database::rebuild::rebuild_known_roms(
+        config,
+        &parsed_dat.rom_db,
+        &games_needing_folders,
+        interrupted,
+    )? else {
+        println!("{}", tr!("db-rebuild-interrupted", db = config.db_file.as_str()));
+        return Ok(());
+    };
+    
+    // Keep the previous DB around in case the rebuild isn't what was wanted
+    if Path::new(&config.db_file).exists() {
+        std::fs::copy(&config.db_file, format!("{}.bak", config.db_file))?;
+    }
+    database::save_known_roms(&known_roms, &config.db_file)?;
+    
+    let rom_count: usize = known_roms.values().map(Vec::len).sum();
+    println!("{}", tr!("db-rebuilt", db = config.db_file.as_str(), roms = rom_count));
+    for path in &summary.misplaced {
+        println!("  {}", tr!("db-rebuild-misplaced", path = path.display().to_string()));
+    }
+    for path in &summary.unknown {
+        println!("  {}", tr!("layout-unknown", path = path.display().to_string()));
+    }
+    
+    Ok(())
+}
+
+/// Write the DB and the identity of the current DAT to a portable file
+fn export_db(config: &mut Config, path: &Path) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let dat_path = std::path::PathBuf::from(config.dat_file.as_deref().unwrap_or_default());
+    let known_roms = database::load_known_roms(&config.db_file)?;
+    
+    let identity = database::archive::DatIdentity::from_dat(&dat_path, &parsed_dat)?;
+    database::archive::DbArchive::new(identity, &known_roms).save(path)?;
+    
+    let rom_count: usize = known_roms.values().map(Vec::len).sum();
+    println!("{}", tr!("db-exported", roms = rom_count, path = path.display().to_string()));
+    Ok(())
+}
+
+/// Replace the DB with an exported one, refusing a different DAT unless forced
+fn import_db(config: &mut Config, path: &Path, force: bool) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let dat_path = std::path::PathBuf::from(config.dat_file.as_deref().unwrap_or_default());
+    let archive = database::archive::DbArchive::load(path)?;
+    
+    let identity = database::archive::DatIdentity::from_dat(&dat_path, &parsed_dat)?;
+    if archive.dat != identity {
+        let message = tr!("db-import-other-dat", path = path.display().to_string(),
+            dat = archive.dat.name.as_str(), games = archive.dat.games, sha1 = archive.dat.sha1.as_str(),
+            current = identity.name.as_str(), current_games = identity.games, current_sha1 = identity.sha1.as_str());
+        if !force {
+            return Err(RomAuditError::Custom(tr!("db-import-refused", message = message)));
+        }
+        println!("{}", tr!("db-import-forced", message = message));
+    }
+    
+    if Path::new(&config.db_file).exists() {
+        std::fs::copy(&config.db_file, format!("{}.bak", config.db_file))?;
+    }
+    let known_roms = archive.known_roms();
+    database::save_known_roms(&known_roms, &config.db_file)?;
+    
+    let rom_count: usize = known_roms.values().map(Vec::len).sum();
+    println!("{}", tr!("db-imported", roms = rom_count, db = config.db_file.as_str()));
+    Ok(())
+}
+
+/// Union another rom_db.json into ours, reporting ROMs recorded with different hashes
+fn merge_db(config: &mut Config, other: &Path) -> Result<()> {
+    load_dat(config)?;
+    let mut known_roms = database::load_known_roms(&config.db_file)?;
+    if !other.exists() {
+        return Err(RomAuditError::InvalidPath(other.display().to_string()));
+    }
+    let theirs = database::load_known_roms(&other.to_string_lossy())?;
+    
+    let summary = database::merge::merge_known_roms(&mut known_roms, &theirs);
+    
+    for conflict in &summary.conflicts {
+        println!("  {}", tr!("db-merge-conflict", game = conflict.game.as_str(), rom = conflict.rom.as_str(),
+            ours = conflict.ours.as_str(), theirs = conflict.theirs.as_str(), other = other.display().to_string()));
+    }
+    
+    if summary.added > 0 {
+        if Path::new(&config.db_file).exists() {
+            std::fs::copy(&config.db_file, format!("{}.bak", config.db_file))?;
+        }
+        database::save_known_roms(&known_roms, &config.db_file)?;
+    }
+    
+    println!("{}", tr!("db-merged", entries = summary.added, other = other.display().to_string(), conflicts = summary.conflicts.len()));
+    Ok(())
+}
+
+/// Audit a remote source against the DAT without copying it, reporting to logs/remote.txt
+fn remote_audit(config: &mut Config, source: &str, interrupted: &AtomicBool) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let remote = scanner::remote::RemoteSource::parse(source)?;
+    let mut unreadable = Vec::new();
+    let results = scanner::remote::audit_remote(&remote, &parsed_dat.rom_db, config.buffer_size, interrupted, &mut unreadable)?;
+    
+    std::fs::create_dir_all(&config.logs_dir)?;
+    let logger = logger::Logger::new(config.clone());
+    logger.write_remote_log(source, &results, &parsed_dat)?;
+    if !unreadable.is_empty() {
+        logger.write_unreadable_log(&unreadable)?;
+    }
+    Ok(())
+}
+
+/// Verify a local copy of a torrent piece by piece and cross-reference its files with the DAT
+fn torrent_check(config: &mut Config, torrent_file: &Path, dir: &Path) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let torrent = scanner::torrent::Torrent::load(torrent_file)?;
+    println!("Checking {} files of {} in {}", torrent.data_files().count(), torrent.name, dir.display());
+    let check = scanner::torrent::check_torrent(
+        &torrent, dir, &parsed_dat.rom_db, &parsed_dat.game_roms, config.buffer_size,
+    )?;
+    
+    std::fs::create_dir_all(&config.logs_dir)?;
+    logger::Logger::new(config.clone()).write_torrent_log(&check)?;
+    Ok(())
+}
+
+/// Fill missing ROMs with copies from a donor tree, which is never modified
+fn rebuild_from(config: &mut Config, donor: &Path, interrupted: &AtomicBool) -> Result<()> {
+    if config.read_only {
+        return Err(RomAuditError::ConfigError("rebuild copies into roms/ and can't be used with --read-only".to_string()));
+    }
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let mut known_roms = database::load_known_roms(&config.db_file)?;
+    let mut events = logger::events::EventLog::open(&config.logs_dir)?;
+    
+    let summary = organizer::donor::rebuild_from_donor(
+        donor,
+        organizer::donor::Collection {
+            rom_db: &parsed_dat.rom_db,
+            game_roms: &parsed_dat.game_roms,
+            games_needing_folders: &games_needing_folders,
+            known_roms: &mut known_roms,
+        },
+        config,
+        &mut events,
+        interrupted,
+    )?;
+    events.flush()?;
+    if !summary.copied.is_empty() {
+        database::save_known_roms(&known_roms, &config.db_file)?;
+    }
+    
+    for copy in &summary.copied {
+        println!("  {}", console::style(format!("{} / {}: {} -> {}", copy.game, copy.rom, copy.from.display(), copy.to.display())).green());
+    }
+    println!("Copied {} of {} missing ROMs from {} ({} of {} donor files hashed)",
+        summary.copied.len(), summary.missing, donor.display(), summary.hashed, summary.total);
+    Ok(())
+}
+
+/// Re-hash `percent` of the organized files, least recently verified first
+fn verify(config: &mut Config, percent: u8, interrupted: &AtomicBool) -> Result<()> {
+    config.require_loose_files("verify")?;
+    config.scrub_percent = Some(percent);
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let known_roms = database::load_known_roms(&config.db_file)?;
+    
+    let mut state = scanner::scrub::ScrubState::load(&config.state_path(scanner::scrub::ScrubState::STATE_FILE));
+    let report = scanner::scrub::scrub(
+        &parsed_dat.game_roms,
+        &known_roms,
+        &games_needing_folders,
+        config,
+        &mut state,
+        interrupted,
+    )?;
+    state.save()?;
+    logger::Logger::new(config.clone()).write_scrub_log(&report)?;
+    if report.failures.is_empty() {
         Ok(())
+    } else {
+        Err(RomAuditError::Custom(tr!("verify-failed", files = report.failures.len())))
+    }
+}
+
+/// Size statistics of the collection, from the database alone
+fn show_stats(config: &mut Config) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let known_roms = database::load_known_roms(&config.db_file)?;
+    println!();
+    logger::Logger::new(config.clone()).print_stats(&parsed_dat, &known_roms, &games_needing_folders);
+    Ok(())
+}
+
+/// List the numbered duplicate and unknown folders, and delete them with --apply
+fn clean(config: &Config, apply: bool) -> Result<()> {
+    let folders = organizer::folders::spill_folders(config)?;
+    if folders.is_empty() {
+        println!("{}", tr!("clean-nothing"));
+        return Ok(());
+    }
+    if apply && config.read_only {
+        return Err(RomAuditError::ConfigError("clean --apply deletes files and can't be used with --read-only".to_string()));
+    }
+    
+    let mut total = 0;
+    for folder in &folders {
+        let files = scanner::collector::collect_tree(folder)?;
+        let size: u64 = files.iter().filter_map(|file| std::fs::metadata(file).ok()).map(|m| m.len()).sum();
+        total += size;
+        println!("  {}", tr!("clean-folder", folder = folder.display().to_string(), files = files.len(), size = logger::format_size(size)));
+        if apply {
+            std::fs::remove_dir_all(folder)?;
+        }
+    }
+    
+    if apply {
+        println!("{}", tr!("clean-deleted", folders = folders.len(), size = logger::format_size(total)));
+    } else {
+        println!("{}", tr!("clean-dry-run", folders = folders.len(), size = logger::format_size(total)));
     }
+    Ok(())
+}
+
+/// Copy the organized collection into volumes of at most `max_size`, one game never split
+fn split_volumes(config: &mut Config, max_size: &str, dest: &Path, interrupted: &AtomicBool) -> Result<()> {
+    let max_size = organizer::volumes::parse_size(max_size)
+        .ok_or_else(|| RomAuditError::ConfigError(format!("invalid volume size \"{}\" (e.g. 25G, 700MB, 8GiB)", max_size)))?;
+    config.require_loose_files("split-volumes")?;
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let known_roms = database::load_known_roms(&config.db_file)?;
+    
+    let games = organizer::volumes::collect_games(&parsed_dat.game_roms, &known_roms, &games_needing_folders, &config.rom_dir);
+    let volumes = organizer::volumes::plan_volumes(games, max_size);
+    for (index, volume) in volumes.iter().enumerate() {
+        let first = volume.games.first().map_or("", |g| g.game);
+        let last = volume.games.last().map_or("", |g| g.game);
+        println!("{}", tr!("volume-plan", number = index + 1, games = volume.games.len(),
+            size = logger::format_size(volume.size), first = first, last = last));
+        if volume.size > max_size {
+            println!("  {}", tr!("volume-too-large", game = first));
+        }
+    }
+    
+    organizer::volumes::write_volumes(&volumes, dest, &config.rom_dir, max_size, interrupted)
+}
+
+/// Write the DAT collapsed to the best release of each parent/clone group
+fn write_one_g1r_dat(config: &mut Config, output: &Path) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let priorities = one_g1r::Priorities { regions: &config.region_priority, languages: &config.language_priority };
+    let best = one_g1r::best_releases(&parsed_dat, priorities);
+    
+    let name = parsed_dat.header.name.as_deref().unwrap_or(&parsed_dat.name);
+    let header = types::DatHeader {
+        name: Some(format!("{} (1G1R)", name)),
+        description: Some(format!("{} (1G1R: {})", name, config.region_priority.join(", "))),
+        ..parsed_dat.header.clone()
+    };
+    let games: Vec<&str> = best.iter().map(String::as_str).collect();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(output)?);
+    parser::export::write_dat(&mut file, &header, &parsed_dat, &games, |_| true)?;
+    std::io::Write::flush(&mut file)?;
+    
+    println!("{}", tr!("one-g1r-written", games = best.len(), total = parsed_dat.all_games.len(), path = output.display().to_string()));
+    Ok(())
+}
+
+/// Report what changed between two releases of a DAT
+fn diff_dats(config: &Config, old: &Path, new: &Path) -> Result<()> {
+    let old = parser::parse_dat_file(old, config)?;
+    let new = parser::parse_dat_file(new, config)?;
+    dat_diff::write_diff(&dat_diff::diff(&old, &new), &mut std::io::stdout().lock())
+}
+
+/// Fuzzy-search DAT and database game names, showing whether each is present and where
+fn search_games(config: &mut Config, query: &str) -> Result<()> {
+    /// Number of hits shown
+    const MAX_RESULTS: usize = 25;
+    
+    let parsed_dat = load_dat(config)?;
+    let known_roms = database::load_known_roms(&config.db_file)?;
+    
+    let have: HashSet<String> = known_roms.values()
+        .flatten()
+        .map(|(game, _)| game.clone())
+        .collect();
+    let candidates = parsed_dat.all_games.iter().chain(have.difference(&parsed_dat.all_games));
+    let hits = search::fuzzy_search(query, candidates.map(String::as_str), MAX_RESULTS);
+    
+    if hits.is_empty() {
+        println!("{}", tr!("search-no-match", query = query));
+        return Ok(());
+    }
+    
+    let have_hits: HashSet<String> = hits.iter()
+        .filter(|game| have.contains(**game))
+        .map(|game| game.to_string())
+        .collect();
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    let usage = logger::stats::game_disk_usage(
+        &have_hits,
+        &parsed_dat.game_roms,
+        &known_roms,
+        &games_needing_folders,
+        &config.rom_dir,
+    );
+    
+    let (db_only, present, missing) = (tr!("search-db-only"), tr!("search-have"), tr!("search-missing"));
+    for game in hits {
+        if !parsed_dat.all_games.contains(game) {
+            println!("{:<8} {}", db_only, tr!("search-not-in-dat", game = game, db = config.db_file.as_str()));
+        } else if let Some(entry) = usage.iter().find(|u| u.game == game) {
+            println!("{:<8} {} -> {}", present, parsed_dat.display_name(game), entry.location.display());
+        } else {
+            println!("{:<8} {}", missing, parsed_dat.display_name(game));
+        }
+        if let Some(devices) = parsed_dat.device_refs.get(game) {
+            let devices: Vec<String> = devices.iter()
+                .map(|device| format!("{} ({})", device, if have.contains(device) { &present } else { &missing }))
+                .collect();
+            println!("{:<8} {}", "", tr!("search-devices", devices = devices.join(", ")));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Show which DAT entries a file (or each member of a ZIP) matches, and where it would go
+fn identify(config: &mut Config, path: &Path) -> Result<()> {
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    
+    // Member hashes of a damaged archive can't be trusted
+    if config.verify_zips && scanner::identify::is_zip(path)? {
+        let problems = scanner::zip_check::check_zip(path)?;
+        if !problems.is_empty() {
+            println!("{} is a corrupt ZIP archive; download it again:", path.display());
+            for problem in &problems {
+                println!("  {}", problem);
+            }
+            return Ok(());
+        }
+    }
+    
+    for item in scanner::identify::identify_file(path, &parsed_dat.rom_db, config.buffer_size)? {
+        println!("{}", item.label);
+        let digests = &item.digests;
+        println!("  size:{}  crc:{}  md5:{}  sha1:{}  sha256:{}", item.size, digests.crc, digests.md5, digests.sha1, digests.sha256);
+        
+        if item.matches.is_empty() {
+            println!("  No match in the DAT");
+        }
+        for rom in &item.matches {
+            let size_note = match rom.size {
+                Some(size) if size != item.size => format!(" (DAT size {} differs)", size),
+                _ => String::new(),
+            };
+            println!("  {} / {}{}", rom.game, rom.name, size_note);
+            println!("    -> {}", organizer::processor::rom_destination(rom, &games_needing_folders, &config.rom_dir).display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Report files in rom_dir that are not where the rules would put them, and move them with --fix
+fn check_layout(config: &mut Config, fix: bool, interrupted: &AtomicBool) -> Result<()> {
+    config.require_loose_files("check-layout")?;
+    let parsed_dat = load_dat(config)?;
+    let games_needing_folders = organizer::rules::identify_games_needing_folders(&parsed_dat, config);
+    
+    let Some(files) = database::rebuild::hash_rom_dir(config, &parsed_dat.rom_db, interrupted)? else {
+        println!("{}", tr!("layout-interrupted"));
+        return Ok(());
+    };
+    let report = organizer::layout::check_layout(&files, &games_needing_folders, &config.rom_dir);
+    
+    for relocation in &report.relocations {
+        println!("  {}", tr!("layout-misplaced", from = relocation.from.display().to_string(), to = relocation.to.display().to_string()));
+    }
+    for path in &report.redundant {
+        println!("  {}", tr!("layout-extra-copy", path = path.display().to_string()));
+    }
+    for path in &report.unknown {
+        println!("  {}", tr!("layout-unknown", path = path.display().to_string()));
+    }
+    
+    if report.relocations.is_empty() {
+        println!("{}", tr!("layout-nothing"));
+    } else if fix && config.read_only {
+        return Err(RomAuditError::ConfigError("--fix moves files and can't be used with --read-only".to_string()));
+    } else if fix {
+        let mut known_roms = database::load_known_roms(&config.db_file)?;
+        organizer::layout::apply_relocations(&report.relocations, &mut known_roms)?;
+        database::save_database(&known_roms, &games_needing_folders.path_names(), &config.db_file)?;
+        organizer::folders::remove_empty_folders(Path::new(&config.rom_dir), config)?;
+        println!("{}", tr!("layout-moved", files = report.relocations.len()));
+    } else {
+        println!("{}", tr!("layout-dry-run", files = report.relocations.len()));
+    }
+    
+    Ok(())
 }
 
 fn main() {
@@ -108,19 +1066,114 @@ fn main() {
     let interrupted_clone = interrupted.clone();
     
     ctrlc::set_handler(move || {
-        println!("\nReceived interrupt signal. Cleaning up...");
+        println!("\n{}", tr!("interrupt-received"));
         interrupted_clone.store(true, Ordering::Relaxed);
     }).expect("Error setting Ctrl-C handler");
     
     // Load configuration
-    let config = Config::load();
+    let cli = Cli::parse();
+    let mut config = match Config::from_cli(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    i18n::init(&config);
+    color::init(&config);
+    scanner::hasher_optimized::init(&config);
+    
+    let result = progress::init(&config)
+        .and_then(|()| config.prepare_state_dir())
+        .and_then(|()| match cli.command.unwrap_or(Command::Scan) {
+            Command::Scan => RomAuditor::new(config, interrupted).and_then(|mut auditor| auditor.run()).map(|_| ()),
+            Command::Verify { percent } => verify(&mut config, percent, &interrupted),
+            Command::Rebuild { donor } => rebuild_from(&mut config, &donor, &interrupted),
+            Command::Stats => show_stats(&mut config),
+            Command::Clean { apply } => clean(&config, apply),
+            Command::ImportHave { have_dat, root } => import_have(&mut config, &have_dat, &root),
+            Command::Db { command } => match command {
+                DbCommand::Prune { apply } => prune_db(&mut config, apply),
+                DbCommand::Rebuild => rebuild_db(&mut config, &interrupted),
+                DbCommand::Export { path } => export_db(&mut config, &path),
+                DbCommand::Import { path, force } => import_db(&mut config, &path, force),
+                DbCommand::Merge { other } => merge_db(&mut config, &other),
+            },
+            Command::Pack { dat_dir, root } => pack::audit_pack(&config, &dat_dir, &root, interrupted),
+            Command::SplitVolumes { max_size, dest } => split_volumes(&mut config, &max_size, &dest, &interrupted),
+            Command::CheckLayout { fix } => check_layout(&mut config, fix, &interrupted),
+            Command::Identify { path } => identify(&mut config, &path),
+            Command::Remote { source } => remote_audit(&mut config, &source, &interrupted),
+            Command::Torrent { torrent, dir } => torrent_check(&mut config, &torrent, &dir),
+            Command::Daemon { socket } => {
+                // Nobody is there to answer a prompt
+                config.assume_yes = true;
+                let socket = socket.unwrap_or_else(|| Path::new(&config.logs_dir).join(daemon::SOCKET_FILE));
+                daemon::serve(config, &socket, interrupted)
+            }
+            Command::Search { query } => search_games(&mut config, &query.join(" ")),
+            Command::DatDiff { old, new } => diff_dats(&config, &old, &new),
+            Command::OneGameOneRom { path } => write_one_g1r_dat(&mut config, &path),
+        });
     
-    // Run the auditor
-    match RomAuditor::new(config, interrupted).and_then(|mut auditor| auditor.run()) {
+    match result {
         Ok(()) => {}
         Err(e) => {
-            eprintln!("Error: {}", e);
+            eprintln!("{}", console::style(tr!("error", message = e.to_string())).red().for_stderr());
             std::process::exit(1);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Organize the same files pipelined or not, and list where they ended up
+    fn organized_layout(pipelined: bool) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        let (a, b) = (b"first rom", b"second rom");
+        let rom = |name: &str, data: &[u8]| format!(
+            r#"<rom name="{}" size="{}" crc="{:08x}"/>"#, name, data.len(), crc32fast::hash(data),
+        );
+        std::fs::write(path("test.dat"), format!(
+            r#"<datafile><header><name>Test</name></header><game name="A">{}</game><game name="B">{}</game></datafile>"#,
+            rom("a.bin", a), rom("b.bin", b),
+        )).unwrap();
+        for (name, data) in [("in/a.bin", &a[..]), ("in/x/a.bin", a), ("in/a copy.bin", a), ("in/y/b.bin", b), ("in/b.bin", b), ("in/notes.txt", b"?")] {
+            std::fs::create_dir_all(dir.path().join(name).parent().unwrap()).unwrap();
+            std::fs::write(dir.path().join(name), data).unwrap();
+        }
+
+        let config = Config {
+            dat_file: Some(path("test.dat")),
+            scan_dir: path("in"),
+            rom_dir: path("in/roms"),
+            probable_dir: path("in/probable"),
+            logs_dir: path("state/logs"),
+            db_file: path("state/rom_db.json"),
+            state_dir: Some(path("state")),
+            assume_yes: true,
+            ..Config::default()
+        };
+        std::fs::create_dir_all(path("state")).unwrap();
+        let mut auditor = RomAuditor::new(config, Arc::new(AtomicBool::new(false))).unwrap();
+        auditor.pipelined = pipelined;
+        auditor.run().unwrap();
+
+        let mut layout: Vec<String> = scanner::collector::collect_tree(&dir.path().join("in")).unwrap()
+            .iter()
+            .map(|file| file.strip_prefix(dir.path()).unwrap().to_string_lossy().to_string())
+            .collect();
+        layout.sort();
+        layout
+    }
+
+    #[test]
+    fn test_pipelined_organizing_matches_sequential() {
+        let pipelined = organized_layout(true);
+        assert!(pipelined.contains(&"in/roms/A/a.bin".to_string()));
+        assert_eq!(pipelined, organized_layout(false));
+    }
+}
@@ -0,0 +1,187 @@
+// src/one_g1r.rs - One game, one ROM: the best release of each parent/clone group
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::logger::sort;
+use crate::types::ParsedDat;
+
+/// Region names No-Intro and Redump put in the first tag of a name, e.g.
+/// `(USA, Europe)`
+const REGIONS: &[&str] = &[
+    "World", "USA", "Europe", "Japan", "Asia", "Australia", "Austria", "Belgium", "Brazil", "Canada",
+    "China", "Denmark", "Finland", "France", "Germany", "Greece", "Hong Kong", "India", "Ireland",
+    "Israel", "Italy", "Korea", "Latin America", "Mexico", "Netherlands", "New Zealand", "Norway",
+    "Poland", "Portugal", "Russia", "Scandinavia", "South Africa", "Spain", "Sweden", "Switzerland",
+    "Taiwan", "UK", "Unknown",
+];
+
+/// Tags of releases that aren't the finished game
+const PRERELEASE_TAGS: &[&str] = &["beta", "proto", "demo", "sample", "preview", "promo", "kiosk", "debug"];
+
+/// What a No-Intro/Redump style name says about a release
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Release {
+    pub regions: Vec<String>,
+    pub languages: Vec<String>,
+    /// `(Rev 2)` or `(v1.1)`, as numbers to compare; empty for the first release
+    pub revision: Vec<u32>,
+    pub prerelease: bool,
+}
+
+impl Release {
+    pub fn from_name(name: &str) -> Self {
+        let mut release = Release::default();
+        for tag in name_tags(name) {
+            let items: Vec<&str> = tag.split(',').map(str::trim).collect();
+            let lower = tag.to_lowercase();
+            if release.regions.is_empty() && items.iter().all(|item| REGIONS.contains(item)) {
+                release.regions = items.iter().map(|s| s.to_string()).collect();
+            } else if release.languages.is_empty() && items.iter().all(|item| is_language(item)) {
+                release.languages = items.iter().map(|s| s.to_string()).collect();
+            } else if let Some(revision) = lower.strip_prefix("rev ")
+                .or_else(|| lower.strip_prefix('v').filter(|v| v.starts_with(|c: char| c.is_ascii_digit())))
+            {
+                release.revision = revision_numbers(revision);
+            } else if PRERELEASE_TAGS.iter().any(|prerelease| lower.split_whitespace().next() == Some(prerelease)) {
+                release.prerelease = true;
+            }
+        }
+        release
+    }
+}
+
+/// The parenthesized tags of a name, in order
+pub fn name_tags(name: &str) -> impl Iterator<Item = &str> {
+    name.split('(').skip(1).filter_map(|part| part.split_once(')').map(|(tag, _)| tag.trim()))
+}
+
+/// `En`, `Fr`, `Zh-Hant`, ...
+fn is_language(item: &str) -> bool {
+    item.split('-').enumerate().all(|(index, part)| {
+        let capitalized = part.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+            && part.chars().skip(1).all(|c| c.is_ascii_lowercase());
+        let length = if index == 0 { 2..=2 } else { 2..=4 };
+        capitalized && length.contains(&part.len())
+    })
+}
+
+/// `2` -> [2], `1.1` -> [1, 1], `A` -> [1]
+fn revision_numbers(revision: &str) -> Vec<u32> {
+    revision.split('.')
+        .map(|part| match part.trim().parse() {
+            Ok(number) => number,
+            Err(_) => part.trim().chars().next().map_or(0, |c| (c.to_ascii_uppercase() as u32).saturating_sub('A' as u32) + 1),
+        })
+        .collect()
+}
+
+/// The region and language priorities releases are ranked by
+#[derive(Debug, Clone, Copy)]
+pub struct Priorities<'a> {
+    pub regions: &'a [String],
+    pub languages: &'a [String],
+}
+
+impl Priorities<'_> {
+    /// Lower is better: finished before prerelease, then the best region and
+    /// language in the lists, then the latest revision
+    fn rank(&self, release: &Release) -> (bool, usize, usize, Reverse<Vec<u32>>) {
+        let position = |list: &[String], items: &[String]| {
+            items.iter()
+                .filter_map(|item| list.iter().position(|wanted| wanted.eq_ignore_ascii_case(item)))
+                .min()
+                .unwrap_or(list.len())
+        };
+        (
+            release.prerelease,
+            position(self.regions, &release.regions),
+            position(self.languages, &release.languages),
+            Reverse(release.revision.clone()),
+        )
+    }
+}
+
+/// Each parent with those of its clones that are counted, parents in natural order
+pub fn clone_groups(parsed_dat: &ParsedDat) -> Vec<(String, Vec<String>)> {
+    let mut groups: HashMap<&str, Vec<String>> = HashMap::new();
+    for game in parsed_dat.counted_games() {
+        let parent = parsed_dat.parent_clone_map.get(game)
+            .filter(|parent| parsed_dat.all_games.contains(*parent))
+            .unwrap_or(game);
+        groups.entry(parent.as_str()).or_default().push(game.clone());
+    }
+    let mut groups: Vec<(String, Vec<String>)> = groups.into_iter()
+        .map(|(parent, mut games)| {
+            sort::sort_natural(&mut games);
+            (parent.to_string(), games)
+        })
+        .collect();
+    groups.sort_by(|a, b| sort::natural_cmp(&a.0, &b.0));
+    groups
+}
+
+/// The best release of every parent/clone group, in natural order. A game's
+/// description is ranked when the DAT gives one, since MAME short names
+/// carry no regions.
+pub fn best_releases(parsed_dat: &ParsedDat, priorities: Priorities) -> Vec<String> {
+    let mut best: Vec<String> = clone_groups(parsed_dat).into_iter()
+        .filter_map(|(_, games)| {
+            games.into_iter().min_by_key(|game| {
+                let name = parsed_dat.game_info.get(game).and_then(|info| info.description.as_deref()).unwrap_or(game);
+                priorities.rank(&Release::from_name(name))
+            })
+        })
+        .collect();
+    sort::sort_natural(&mut best);
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    #[test]
+    fn test_release_from_name() {
+        let release = Release::from_name("Pokemon - Red Version (USA, Europe) (En,Fr) (Rev 2) (SGB Enhanced)");
+        assert_eq!(release.regions, ["USA", "Europe"]);
+        assert_eq!(release.languages, ["En", "Fr"]);
+        assert_eq!(release.revision, [2]);
+        assert!(!release.prerelease);
+        assert!(Release::from_name("Star Fox 2 (Japan) (Beta 1)").prerelease);
+        assert_eq!(Release::from_name("Game (Europe) (v1.1)").revision, [1, 1]);
+    }
+
+    #[test]
+    fn test_best_release_per_group() {
+        let dat = br#"<datafile>
+            <game name="Tetris (Japan)"><rom name="a" crc="00000001"/></game>
+            <game name="Tetris (USA, Europe)" cloneof="Tetris (Japan)"><rom name="b" crc="00000002"/></game>
+            <game name="Tetris (USA, Europe) (Rev 1)" cloneof="Tetris (Japan)"><rom name="c" crc="00000003"/></game>
+            <game name="Tetris (USA) (Proto)" cloneof="Tetris (Japan)"><rom name="d" crc="00000004"/></game>
+            <game name="Zelda (Japan)"><rom name="e" crc="00000005"/></game>
+            <game name="Zelda (France)" cloneof="Zelda (Japan)"><rom name="f" crc="00000006"/></game>
+        </datafile>"#;
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+        let regions = ["USA".to_string(), "Europe".to_string(), "Japan".to_string()];
+        let priorities = Priorities { regions: &regions, languages: &[] };
+
+        assert_eq!(best_releases(&parsed, priorities), ["Tetris (USA, Europe) (Rev 1)", "Zelda (Japan)"]);
+    }
+
+    #[test]
+    fn test_counted_games_in_1g1r_audit() {
+        let dat = br#"<datafile>
+            <game name="Tetris (Japan)"><rom name="a" crc="00000001"/></game>
+            <game name="Tetris (USA)" cloneof="Tetris (Japan)"><rom name="b" crc="00000002"/></game>
+        </datafile>"#;
+        let mut parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+        assert_eq!(parsed.counted_games().len(), 2);
+
+        let regions = ["Japan".to_string()];
+        let best = best_releases(&parsed, Priorities { regions: &regions, languages: &[] });
+        parsed.counted = Some(best.into_iter().collect());
+        assert_eq!(parsed.counted_games().iter().collect::<Vec<_>>(), ["Tetris (Japan)"]);
+    }
+}
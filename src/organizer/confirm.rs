@@ -0,0 +1,139 @@
+// src/organizer/confirm.rs - Asking before scanned files are moved and deleted
+
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::scanner::collector::is_donor_path;
+use crate::types::FileHash;
+use super::processor::{placement_entries, rom_destination};
+use super::rules::GameFolders;
+
+/// What organizing is about to do to the scanned files
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Files copied into rom_dir, whose originals are then deleted
+    pub organized: usize,
+    /// Files moved to a numbered duplicates folder
+    pub duplicates: usize,
+    /// Files moved to a numbered unknown folder
+    pub unknown: usize,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.organized + self.duplicates + self.unknown == 0
+    }
+}
+
+/// Count what `process_file` will do with each file, without doing it
+pub fn plan_changes(
+    file_hashes: &[FileHash],
+    games_with_files: &HashSet<String>,
+    games_needing_folders: &GameFolders,
+    config: &Config,
+) -> Changes {
+    let mut changes = Changes::default();
+    // Two copies of a ROM: the first is organized, the second is a duplicate
+    let mut placed: HashSet<PathBuf> = HashSet::new();
+    for file_hash in file_hashes {
+        let donor = is_donor_path(&file_hash.path, config);
+        let entries = placement_entries(file_hash, games_with_files);
+        if entries.is_empty() {
+            if !donor {
+                changes.unknown += 1;
+            }
+            continue;
+        }
+        let mut placements = 0;
+        for entry in &entries {
+            let to = games_needing_folders.placement_path(
+                entry,
+                rom_destination(entry, games_needing_folders, &config.rom_dir),
+                &file_hash.path,
+            );
+            if !to.exists() && !games_needing_folders.in_archive(entry, &config.rom_dir) && placed.insert(to) {
+                placements += 1;
+            }
+        }
+        if placements > 0 {
+            changes.organized += 1;
+        } else if !donor {
+            changes.duplicates += 1;
+        }
+    }
+    changes
+}
+
+/// Show what is about to change and ask whether to go ahead. Anything but
+/// a yes in the current language ("y" or "yes") declines.
+pub fn confirm_changes(changes: &Changes, config: &Config, input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    writeln!(output, "{}", tr!("organize-confirm-header", dir = config.scan_dir.as_str()))?;
+    if changes.organized > 0 {
+        writeln!(output, "  {}", tr!("organize-confirm-organized", count = changes.organized, rom_dir = config.rom_dir.as_str()))?;
+    }
+    if changes.duplicates > 0 {
+        writeln!(output, "  {}", tr!("organize-confirm-duplicates", count = changes.duplicates, prefix = config.duplicate_prefix.as_str()))?;
+    }
+    if changes.unknown > 0 {
+        writeln!(output, "  {}", tr!("organize-confirm-unknown", count = changes.unknown, prefix = config.unknown_prefix.as_str()))?;
+    }
+    write!(output, "{} ", tr!("organize-confirm-continue"))?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+/// Whether an answer to a [y/N] prompt is one of the current language's
+/// yeses, listed in the `answer-yes` message
+pub fn is_yes(answer: &str) -> bool {
+    accepts(&tr!("answer-yes"), answer)
+}
+
+fn accepts(yeses: &str, answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    yeses.split(',').any(|yes| yes.trim() == answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomEntry};
+
+    #[test]
+    fn test_plan_and_confirm_changes() {
+        let file = |path: &str, roms: &[&str]| FileHash {
+            path: PathBuf::from(path),
+            digests: FileDigests { sha1: "abc".to_string(), ..Default::default() },
+            size: 0,
+            matching_entries: roms.iter().map(|rom| RomEntry {
+                name: rom.to_string(),
+                game: rom.trim_end_matches(".nes").to_string(),
+                ..Default::default()
+            }).collect(),
+        };
+        let files = vec![
+            file("./a.nes", &["Game.nes"]),
+            file("./copy of a.nes", &["Game.nes"]),
+            file("./other.nes", &["Not Collected.nes"]),
+            file("./junk.txt", &[]),
+        ];
+        let present: HashSet<String> = ["Game".to_string()].into();
+        let config = Config { rom_dir: "missing-roms-dir".to_string(), ..Config::default() };
+
+        let changes = plan_changes(&files, &present, &GameFolders::default(), &config);
+        assert_eq!(changes, Changes { organized: 1, duplicates: 1, unknown: 2 });
+
+        let mut shown = Vec::new();
+        assert!(confirm_changes(&changes, &config, &mut &b"y\n"[..], &mut shown).unwrap());
+        assert!(String::from_utf8(shown).unwrap().contains("1 copied into missing-roms-dir and the originals deleted"));
+        assert!(!confirm_changes(&changes, &config, &mut &b"no\n"[..], &mut Vec::new()).unwrap());
+        let spanish = crate::i18n::Translations::new("es").message("answer-yes", None);
+        assert!(accepts(&spanish, "Sí\n") && accepts(&spanish, "s") && !accepts(&spanish, "n"));
+        assert!(Changes::default().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+// src/organizer/donor.rs - Fill missing ROMs from a separate donor tree
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cache::HashCache;
+use crate::config::Config;
+use crate::error::{Result, RomAuditError};
+use crate::logger::events::{Event, EventLog};
+use crate::logger::present_roms;
+use crate::progress::Progress;
+use crate::scanner::{collector, find_matching_entries, hasher_optimized};
+use crate::scanner::compressed::{copy_payload, Compression};
+use crate::types::{GameRoms, HashAlgorithms, KnownRoms, RomDb, RomEntry, RomStatus};
+use super::processor::rom_destination;
+use super::rules::GameFolders;
+
+/// A donor file copied into the collection
+#[derive(Debug)]
+pub struct DonorCopy {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub game: String,
+    pub rom: String,
+}
+
+#[derive(Debug, Default)]
+pub struct DonorSummary {
+    pub copied: Vec<DonorCopy>,
+    /// Donor files hashed; files no missing ROM could have been are skipped
+    pub hashed: usize,
+    pub total: usize,
+    /// Missing ROMs before the rebuild
+    pub missing: usize,
+}
+
+/// The DAT, organized layout and database a rebuild fills in
+pub struct Collection<'a> {
+    pub rom_db: &'a RomDb,
+    pub game_roms: &'a GameRoms,
+    pub games_needing_folders: &'a GameFolders,
+    pub known_roms: &'a mut KnownRoms,
+}
+
+/// Copy every missing ROM found in `donor` to where it belongs. The donor
+/// tree is only read: nothing in it is moved, renamed or deleted.
+pub fn rebuild_from_donor(
+    donor: &Path,
+    collection: Collection,
+    config: &Config,
+    events: &mut EventLog,
+    interrupted: &AtomicBool,
+) -> Result<DonorSummary> {
+    if !donor.is_dir() {
+        return Err(RomAuditError::InvalidPath(format!("{} is not a directory", donor.display())));
+    }
+
+    let present = present_roms(collection.known_roms);
+    let mut missing: HashSet<(String, String)> = collection.game_roms.iter()
+        .flat_map(|(game, roms)| roms.iter().map(move |rom| (game, rom)))
+        .filter(|(game, rom)| rom.status != RomStatus::NoDump && !present.contains(&(game.as_str(), rom.name.as_str())))
+        .map(|(game, rom)| (game.clone(), rom.name.clone()))
+        .collect();
+
+    // Only files the size of some missing ROM can fill it
+    let missing_sizes: Option<HashSet<u64>> = collection.game_roms.values()
+        .flatten()
+        .filter(|rom| missing.contains(&(rom.game.clone(), rom.name.clone())))
+        .map(|rom| if rom.is_disk { None } else { rom.size })
+        .collect();
+
+    let files = collector::collect_tree(donor)?;
+    let mut summary = DonorSummary { total: files.len(), missing: missing.len(), ..Default::default() };
+    let algorithms = HashAlgorithms::from_rom_db(collection.rom_db);
+    let mut cache = HashCache::load(&config.state_path(HashCache::CACHE_FILE));
+
+    let bar = Progress::new(
+        "donor",
+        files.len() as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg} [{eta_precise}]",
+    );
+    let hashing = tr!("progress-hashing");
+    for file in files {
+        if interrupted.load(Ordering::Relaxed) || missing.is_empty() {
+            break;
+        }
+        bar.inc(1);
+        let Ok(file_size) = fs::metadata(&file).map(|m| m.len()) else { continue };
+        // A compressed file's size says nothing about its payload
+        if missing_sizes.as_ref().is_some_and(|sizes| !sizes.contains(&file_size)) && Compression::from_extension(&file).is_none() {
+            continue;
+        }
+
+        let filename = file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string();
+        bar.set_current_file(&hashing, &filename);
+        let Ok((digests, size)) = hasher_optimized::calculate_hashes_cached(&file, config.buffer_size, algorithms, &mut cache) else {
+            continue;
+        };
+        summary.hashed += 1;
+        let hash = digests.primary().to_string();
+
+        let entries: Vec<RomEntry> = find_matching_entries(collection.rom_db, &digests, size)
+            .into_iter()
+            .filter(|rom| rom.size_matches(size) && missing.contains(&(rom.game.clone(), rom.name.clone())))
+            .collect();
+        for rom in entries {
+            let folders = collection.games_needing_folders;
+            let to = folders.placement_path(&rom, rom_destination(&rom, folders, &config.rom_dir), &file);
+            if to.exists() {
+                continue;
+            }
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            copy_payload(&file, &to)?;
+
+            missing.remove(&(rom.game.clone(), rom.name.clone()));
+            collection.known_roms.entry(hash.clone()).or_default().push((rom.game.clone(), rom.name.clone()));
+            events.record(Event::Placed { from: file.clone(), to: to.clone(), game: rom.game.clone(), rom: rom.name.clone(), converted: None })?;
+            summary.copied.push(DonorCopy { from: file.clone(), to, game: rom.game, rom: rom.name });
+        }
+    }
+    bar.finish_with_message(tr!("donor-copied", roms = summary.copied.len()));
+    cache.save()?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RomHashes;
+
+    #[test]
+    fn test_rebuild_copies_only_missing_roms() {
+        let root = tempfile::tempdir().unwrap();
+        let donor = root.path().join("donor");
+        fs::create_dir_all(donor.join("sub")).unwrap();
+        fs::write(donor.join("sub/x.bin"), b"abc").unwrap();
+        fs::write(donor.join("other.bin"), b"zzz").unwrap();
+        let config = Config {
+            rom_dir: root.path().join("roms").to_string_lossy().to_string(),
+            state_dir: Some(root.path().to_string_lossy().to_string()),
+            logs_dir: root.path().join("logs").to_string_lossy().to_string(),
+            ..Config::default()
+        };
+
+        let rom = |game: &str| RomEntry {
+            name: format!("{}.bin", game),
+            game: game.to_string(),
+            hashes: RomHashes { crc: Some("352441c2".to_string()), ..Default::default() },
+            size: Some(3),
+            ..Default::default()
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert("352441c2".to_string(), vec![rom("A"), rom("B")]);
+        let mut game_roms = GameRoms::new();
+        game_roms.insert("A".to_string(), vec![rom("A")]);
+        game_roms.insert("B".to_string(), vec![rom("B")]);
+        let mut known_roms = KnownRoms::new();
+        known_roms.insert("352441c2".to_string(), vec![("A".to_string(), "A.bin".to_string())]);
+
+        let mut events = EventLog::open(&config.logs_dir).unwrap();
+        let summary = rebuild_from_donor(
+            &donor,
+            Collection { rom_db: &rom_db, game_roms: &game_roms, games_needing_folders: &GameFolders::default(), known_roms: &mut known_roms },
+            &config,
+            &mut events,
+            &AtomicBool::new(false),
+        ).unwrap();
+
+        assert_eq!(summary.missing, 1);
+        assert_eq!(summary.hashed, 2);
+        assert_eq!(summary.copied.len(), 1);
+        assert_eq!(summary.copied[0].game, "B");
+        assert_eq!(fs::read(root.path().join("roms/B.bin")).unwrap(), b"abc");
+        assert!(!root.path().join("roms/A.bin").exists());
+        assert!(donor.join("sub/x.bin").exists());
+        assert_eq!(known_roms["352441c2"].len(), 2);
+    }
+}
@@ -4,26 +4,35 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use crate::error::{Result, RomAuditError};
 
-/// Create the next numbered folder with the given prefix
-pub fn create_next_folder(prefix: &str) -> Result<PathBuf> {
+/// Create the next numbered folder with the given prefix inside `parent`
+pub fn create_next_folder(parent: &Path, prefix: &str) -> Result<PathBuf> {
     for i in 1..1000 {
-        let candidate = PathBuf::from(format!("{}{}", prefix, i));
+        let candidate = parent.join(format!("{}{}", prefix, i));
         match fs::create_dir(&candidate) {
             Ok(_) => return Ok(candidate),
             Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
             Err(e) => return Err(e.into()),
         }
     }
-    Err(RomAuditError::Io(std::io::Error::new(
-        std::io::ErrorKind::Other,
+    Err(RomAuditError::Io(std::io::Error::other(
         "Could not create numbered folder after 1000 attempts"
     )))
 }
 
-/// Remove empty folders recursively
+/// The folder `create_next_folder` would create, without creating it
+pub fn next_free_folder(parent: &Path, prefix: &str) -> Result<PathBuf> {
+    (1..1000)
+        .map(|i| parent.join(format!("{}{}", prefix, i)))
+        .find(|candidate| !candidate.exists())
+        .ok_or_else(|| RomAuditError::Io(std::io::Error::other("No free numbered folder after 1000 attempts")))
+}
+
+/// Remove empty folders recursively. Generated folders (rom_dir, logs, ...)
+/// are left alone, unless `dir` is itself inside one, as with rom_dir.
 pub fn remove_empty_folders(dir: &Path, config: &crate::config::Config) -> Result<()> {
+    let skip_generated = !crate::scanner::collector::is_generated_directory(dir, config);
     let mut folders_to_check = Vec::new();
-    collect_folders_recursively(dir, &mut folders_to_check, config)?;
+    collect_folders_recursively(dir, &mut folders_to_check, config, skip_generated)?;
     
     // Sort by depth (deepest first)
     folders_to_check.sort_by(|a, b| {
@@ -31,11 +40,12 @@ pub fn remove_empty_folders(dir: &Path, config: &crate::config::Config) -> Resul
     });
     
     for folder in folders_to_check {
-        if crate::scanner::collector::is_generated_directory(&folder, config) {
+        if skip_generated && crate::scanner::collector::is_generated_directory(&folder, config) {
             continue;
         }
         
-        if is_folder_empty(&folder)? {
+        // Unreadable folders were already reported by the scan
+        if is_folder_empty(&folder).unwrap_or(false) {
             let _ = fs::remove_dir(&folder);
         }
     }
@@ -47,14 +57,16 @@ fn collect_folders_recursively(
     dir: &Path,
     folders: &mut Vec<PathBuf>,
     config: &crate::config::Config,
+    skip_generated: bool,
 ) -> Result<()> {
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
         
-        if path.is_dir() && !crate::scanner::collector::is_generated_directory(&path, config) {
+        if path.is_dir() && !(skip_generated && crate::scanner::collector::is_generated_directory(&path, config)) {
             folders.push(path.clone());
-            collect_folders_recursively(&path, folders, config)?;
+            // Unreadable folders were already reported by the scan
+            let _ = collect_folders_recursively(&path, folders, config, skip_generated);
         }
     }
     Ok(())
@@ -63,4 +75,18 @@ fn collect_folders_recursively(
 fn is_folder_empty(dir: &Path) -> Result<bool> {
     let mut entries = fs::read_dir(dir)?;
     Ok(entries.next().is_none())
-}
\ No newline at end of file
+}
+
+/// The numbered duplicate and unknown folders earlier runs created in the
+/// scanned directory, in name order
+pub fn spill_folders(config: &crate::config::Config) -> Result<Vec<PathBuf>> {
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(&config.scan_dir)? {
+        let path = entry?.path();
+        if path.is_dir() && crate::scanner::collector::is_donor_path(&path, config) {
+            folders.push(path);
+        }
+    }
+    folders.sort_by_key(|path| crate::scanner::collector::path_sort_key(path));
+    Ok(folders)
+}
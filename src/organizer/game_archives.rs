@@ -0,0 +1,155 @@
+// src/organizer/game_archives.rs - Packing organized games into one archive each
+
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::{Config, OutputMode};
+use crate::error::Result;
+use crate::logger::events::{Event, EventLog};
+use crate::logger::present_roms;
+use crate::logger::sort::sort_natural;
+use crate::types::{GameRoms, KnownRoms, RomEntry};
+use super::processor::rom_destination;
+use super::rules::GameFolders;
+use super::torrentzip::{member_order, TorrentZipWriter};
+
+/// What packing changed
+#[derive(Debug, Default)]
+pub struct PackSummary {
+    /// Archives written or rewritten
+    pub archives: usize,
+    /// Loose files moved into them
+    pub files: usize,
+}
+
+/// A game archive being written, in the form the output mode asks for
+enum ArchiveWriter {
+    TorrentZip(TorrentZipWriter),
+    Zip(Box<ZipWriter<File>>),
+}
+
+impl ArchiveWriter {
+    fn create(path: &Path, mode: OutputMode, buffer_size: usize) -> Result<Self> {
+        Ok(match mode {
+            OutputMode::TorrentZip => ArchiveWriter::TorrentZip(TorrentZipWriter::create(path, buffer_size)?),
+            _ => ArchiveWriter::Zip(Box::new(ZipWriter::new(File::create(path)?))),
+        })
+    }
+
+    fn add(&mut self, name: &str, contents: &mut impl Read) -> Result<()> {
+        match self {
+            ArchiveWriter::TorrentZip(writer) => writer.add(name, contents),
+            ArchiveWriter::Zip(writer) => {
+                // Large ROMs need ZIP64 records, which have to be asked for up front
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .large_file(true);
+                writer.start_file(name, options)?;
+                std::io::copy(contents, &mut **writer)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            ArchiveWriter::TorrentZip(writer) => writer.finish(),
+            ArchiveWriter::Zip(writer) => {
+                writer.finish()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Name of a ROM inside its game's archive: the DAT name, with `/` between folders
+pub fn member_name(rom: &RomEntry) -> String {
+    rom.name.replace('\\', "/")
+}
+
+/// Move the loose files of every archived game into the game's archive. An
+/// archive that already exists is rewritten with its members plus the new
+/// files, so a TorrentZip stays in canonical form. Disks (CHDs) are never
+/// packed.
+pub fn pack_games(
+    game_roms: &GameRoms,
+    known_roms: &KnownRoms,
+    folders: &GameFolders,
+    config: &Config,
+    events: &mut EventLog,
+) -> Result<PackSummary> {
+    let mut summary = PackSummary::default();
+    if config.output_mode == OutputMode::Files || !config.changes_files() {
+        return Ok(summary);
+    }
+    let present = present_roms(known_roms);
+
+    let mut games: Vec<&String> = game_roms.keys().collect();
+    sort_natural(&mut games);
+    for game in games.into_iter().filter(|game| folders.is_archived(game)) {
+        let mut roms: Vec<&RomEntry> = game_roms[game].iter()
+            .filter(|rom| !rom.is_disk && present.contains(&(game.as_str(), rom.name.as_str())))
+            .collect();
+        let loose: Vec<(&RomEntry, PathBuf)> = roms.iter()
+            .map(|rom| (*rom, folders.locate(rom, rom_destination(rom, folders, &config.rom_dir))))
+            .filter(|(_, path)| path.is_file())
+            .collect();
+        if loose.is_empty() {
+            continue;
+        }
+
+        let archive_path = folders.archive_path(game, &config.rom_dir);
+        let mut existing = match File::open(&archive_path) {
+            Ok(file) => Some(ZipArchive::new(BufReader::new(file))?),
+            Err(_) => None,
+        };
+
+        // Loose files take precedence over members of the old archive
+        roms.sort_by(|a, b| member_order(&member_name(a), &member_name(b)));
+        roms.dedup_by(|a, b| member_name(a).eq_ignore_ascii_case(&member_name(b)));
+        let temp_path = archive_path.with_extension("zip.tmp");
+        let mut writer = ArchiveWriter::create(&temp_path, config.output_mode, config.buffer_size)?;
+        for rom in roms {
+            let name = member_name(rom);
+            if let Some((_, path)) = loose.iter().find(|(entry, _)| entry.name == rom.name) {
+                writer.add(&name, &mut BufReader::new(File::open(path)?))?;
+            } else if let Some(archive) = existing.as_mut()
+                && let Ok(mut member) = archive.by_name(&name)
+            {
+                writer.add(&name, &mut member)?;
+            }
+        }
+        writer.finish()?;
+        fs::rename(&temp_path, &archive_path)?;
+
+        for (rom, path) in &loose {
+            fs::remove_file(path)?;
+            remove_empty_parents(path, Path::new(&config.rom_dir));
+            events.record(Event::Placed {
+                from: path.clone(),
+                to: archive_path.clone(),
+                game: game.clone(),
+                rom: rom.name.clone(),
+                converted: None,
+            })?;
+        }
+        summary.archives += 1;
+        summary.files += loose.len();
+    }
+    Ok(summary)
+}
+
+/// Remove the folders a packed file leaves empty, up to rom_dir
+fn remove_empty_parents(path: &Path, rom_dir: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir.filter(|d| *d != rom_dir && d.starts_with(rom_dir)) {
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
@@ -0,0 +1,134 @@
+// src/organizer/layout.rs - Consistency check of the organized tree
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::types::{FileHash, KnownRoms};
+use super::processor::rom_destination;
+use super::rules::GameFolders;
+
+/// A known ROM sitting under the wrong folder or name, and where it belongs
+#[derive(Debug, PartialEq, Eq)]
+pub struct Relocation {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub hash: String,
+    pub game: String,
+    pub rom: String,
+}
+
+#[derive(Debug, Default)]
+pub struct LayoutReport {
+    /// Misplaced or misnamed files with a free expected path
+    pub relocations: Vec<Relocation>,
+    /// Misplaced files whose expected paths are all taken already (extra copies)
+    pub redundant: Vec<PathBuf>,
+    /// Files that are not in the DAT at all
+    pub unknown: Vec<PathBuf>,
+}
+
+/// Compare where each file under rom_dir is with where the rules would put it
+pub fn check_layout(
+    files: &[FileHash],
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> LayoutReport {
+    let mut report = LayoutReport::default();
+    let mut taken: HashSet<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+
+    for file in files {
+        if file.matching_entries.is_empty() {
+            report.unknown.push(file.path.clone());
+            continue;
+        }
+
+        let destinations: Vec<_> = file.matching_entries.iter()
+            .map(|entry| (rom_destination(entry, games_needing_folders, rom_dir), entry))
+            .collect();
+        if destinations.iter().any(|(path, _)| games_needing_folders.matches_destination(path, &file.path)) {
+            continue;
+        }
+        let mut destinations: Vec<_> = destinations.into_iter()
+            .map(|(path, entry)| (games_needing_folders.placement_path(entry, path, &file.path), entry))
+            .collect();
+        destinations.sort_by(|a, b| a.0.cmp(&b.0));
+
+        match destinations.into_iter().find(|(path, _)| !taken.contains(path)) {
+            Some((to, entry)) => {
+                taken.insert(to.clone());
+                report.relocations.push(Relocation {
+                    from: file.path.clone(),
+                    to,
+                    hash: file.primary_hash().to_string(),
+                    game: entry.game.clone(),
+                    rom: entry.name.clone(),
+                });
+            }
+            None => report.redundant.push(file.path.clone()),
+        }
+    }
+
+    report
+}
+
+/// Move misplaced files to their expected paths and record them as known
+pub fn apply_relocations(relocations: &[Relocation], known_roms: &mut KnownRoms) -> Result<()> {
+    for relocation in relocations {
+        if let Some(parent) = relocation.to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&relocation.from, &relocation.to)?;
+
+        let entries = known_roms.entry(relocation.hash.clone()).or_default();
+        let known = (relocation.game.clone(), relocation.rom.clone());
+        if !entries.contains(&known) {
+            entries.push(known);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomEntry, RomHashes};
+
+    fn file(path: &str, hash: &str, entries: &[(&str, &str)]) -> FileHash {
+        FileHash {
+            path: PathBuf::from(path),
+            digests: FileDigests { sha1: hash.to_string(), ..Default::default() },
+            size: 0,
+            matching_entries: entries.iter().map(|(game, rom)| RomEntry {
+                name: rom.to_string(),
+                game: game.to_string(),
+                hashes: RomHashes { sha1: Some(hash.to_string()), ..Default::default() },
+                ..Default::default()
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_check_layout_finds_misplaced_and_redundant_files() {
+        let folders = GameFolders::from(HashSet::from(["Multi".to_string()]));
+        let files = vec![
+            file("roms/Multi/a.bin", "h1", &[("Multi", "a.bin")]),
+            file("roms/a.bin", "h1", &[("Multi", "a.bin")]),
+            file("roms/b (renamed).bin", "h2", &[("Multi", "b.bin")]),
+            file("roms/notes.txt", "h3", &[]),
+        ];
+
+        let report = check_layout(&files, &folders, "roms");
+
+        assert_eq!(report.relocations, vec![Relocation {
+            from: PathBuf::from("roms/b (renamed).bin"),
+            to: PathBuf::from("roms/Multi/b.bin"),
+            hash: "h2".to_string(),
+            game: "Multi".to_string(),
+            rom: "b.bin".to_string(),
+        }]);
+        assert_eq!(report.redundant, vec![PathBuf::from("roms/a.bin")]);
+        assert_eq!(report.unknown, vec![PathBuf::from("roms/notes.txt")]);
+    }
+}
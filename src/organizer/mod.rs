@@ -3,90 +3,154 @@
 pub mod rules;
 pub mod folders;
 pub mod processor;
+pub mod layout;
+pub mod renames;
+pub mod confirm;
+pub mod volumes;
+pub mod donor;
+pub mod sanitize;
+pub mod torrentzip;
+pub mod game_archives;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use indicatif::{ProgressBar, ProgressStyle};
-
-use crate::error::Result;
-use crate::types::{FileHash, ScanResult, KnownRoms, RomDb};
-use crate::config::Config;
+use crate::error::{Result, RomAuditError};
+use crate::types::{FileHash, ScanResult, KnownRoms, ParsedDat};
+use rules::GameFolders;
+use crate::config::{CollisionPolicy, Config};
+use crate::progress::Progress;
+use crate::logger::events::{Event, EventLog};
 
 pub struct Organizer {
     config: Config,
-    games_needing_folders: HashSet<String>,
+    games_needing_folders: GameFolders,
     interrupted: Arc<AtomicBool>,
 }
 
 impl Organizer {
     pub fn new(
         config: Config,
-        rom_db: &RomDb,
+        parsed_dat: &ParsedDat,
         interrupted: Arc<AtomicBool>,
-    ) -> Self {
-        let games_needing_folders = rules::identify_games_needing_folders(rom_db, &config);
+    ) -> Result<Self> {
+        let games_needing_folders = rules::identify_games_needing_folders(parsed_dat, &config);
+        
+        let collisions = games_needing_folders.collisions();
+        if config.collision_policy == CollisionPolicy::Error && !collisions.is_empty() {
+            let list: Vec<String> = collisions.iter()
+                .map(|c| format!("  {}: {}", c.path.display(), c.games.iter().map(|(game, _)| game.as_str()).collect::<Vec<_>>().join(", ")))
+                .collect();
+            return Err(RomAuditError::ConfigError(format!(
+                "{} files would be shared by games without folders (use --collisions=folder or --collisions=suffix):\n{}",
+                collisions.len(), list.join("\n"),
+            )));
+        }
         
-        Organizer {
+        Ok(Organizer {
             config,
             games_needing_folders,
             interrupted,
-        }
+        })
     }
     
     /// Get the set of games needing folders
-    pub fn games_needing_folders(&self) -> &HashSet<String> {
+    pub fn games_needing_folders(&self) -> &GameFolders {
         &self.games_needing_folders
     }
     
-    /// Organize files based on DAT information
-    pub fn organize_files(
-        &self,
-        file_hashes: Vec<FileHash>,
-        games_with_files: &HashSet<String>,
-        known_roms: &mut KnownRoms,
-    ) -> Result<ScanResult> {
-        let mut result = ScanResult {
-            have: HashSet::new(),
-            missing: HashSet::new(),
-            duplicate: Vec::new(),
-            unknown: Vec::new(),
-            shared_roms: HashMap::new(),
-        };
-        
-        // Build initial have set from known_roms
+    /// Begin organizing files based on DAT information: `start`, then
+    /// `organize_file` or `organize_remaining`, then `finish`. The games in
+    /// the database are had already.
+    pub fn start(&self, known_roms: &KnownRoms) -> Result<Organizing> {
+        let mut organizing = Organizing::default();
         for entries in known_roms.values() {
             for (game, _) in entries {
-                result.have.insert(game.clone());
+                organizing.result.have.insert(game.clone());
             }
         }
         
         // Create necessary directories
-        std::fs::create_dir_all(&self.config.rom_dir)?;
+        if self.config.changes_files() {
+            std::fs::create_dir_all(&self.config.rom_dir)?;
+        }
         std::fs::create_dir_all(&self.config.logs_dir)?;
+        Ok(organizing)
+    }
+    
+    /// Organize one file, as the scan pipeline hands them over
+    pub fn organize_file(
+        &self,
+        organizing: &mut Organizing,
+        file_hash: FileHash,
+        games_with_files: &HashSet<String>,
+        known_roms: &mut KnownRoms,
+        events: &mut EventLog,
+    ) -> Result<()> {
+        let path = file_hash.path.clone();
+        let processed = match processor::process_file(
+            file_hash,
+            games_with_files,
+            &self.games_needing_folders,
+            &self.config,
+            &mut organizing.spill_dirs,
+            known_roms,
+            events,
+        ) {
+            Ok(processed) => processed,
+            Err(e) => {
+                events.record(Event::Error { path: Some(path), message: e.to_string() })?;
+                return Err(e);
+            }
+        };
         
-        println!("Organizing ROMs for {} games...", games_with_files.len());
+        // Update result
+        let result = &mut organizing.result;
+        match processed {
+            processor::ProcessResult::Organized(game) => {
+                result.have.insert(game);
+            }
+            processor::ProcessResult::Duplicate(file) => {
+                result.duplicate.push(file);
+            }
+            processor::ProcessResult::Unknown(file) => {
+                result.unknown.push(file);
+            }
+            processor::ProcessResult::Donor => {}
+        }
+        Ok(())
+    }
+    
+    /// Organize the files still to be organized after scanning, with a
+    /// progress bar; stops early when interrupted
+    pub fn organize_remaining(
+        &self,
+        organizing: &mut Organizing,
+        file_hashes: Vec<FileHash>,
+        games_with_files: &HashSet<String>,
+        known_roms: &mut KnownRoms,
+        events: &mut EventLog,
+    ) -> Result<()> {
+        println!("{}", tr!("organize-start", games = games_with_files.len()));
         
         // Set up progress bar
-        let bar = ProgressBar::new(file_hashes.len() as u64);
-        bar.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}"
-            ).unwrap(),
+        let bar = Progress::new(
+            "organize",
+            file_hashes.len() as u64,
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
         );
-        bar.set_message("Organizing files...");
-        
-        let mut duplicate_dir = None;
-        let mut unknown_dir = None;
+        bar.set_message(tr!("organize-progress"));
+        let processing = tr!("progress-processing");
         
         // Process files
         for file_hash in file_hashes {
             // Check for interruption
             if self.interrupted.load(Ordering::Relaxed) {
-                bar.finish_with_message("Interrupted by user!");
-                println!("\nProcess interrupted. Partial results may have been saved.");
-                return Ok(result);
+                bar.finish_with_message(tr!("interrupted-by-user"));
+                println!("\n{}", tr!("organize-interrupted"));
+                organizing.interrupted = true;
+                return Ok(());
             }
             
             let filename = file_hash.path.file_name()
@@ -94,49 +158,28 @@ impl Organizer {
                 .unwrap_or("unknown")
                 .to_string();
             
-            bar.set_message(format!("Processing: {}", 
-                if filename.len() > 40 { 
-                    format!("...{}", &filename[filename.len()-37..]) 
-                } else { 
-                    filename.clone() 
-                }
-            ));
-            
-            // Process the file
-            let processed = processor::process_file(
-                file_hash,
-                games_with_files,
-                &self.games_needing_folders,
-                &self.config,
-                &mut duplicate_dir,
-                &mut unknown_dir,
-                known_roms,
-            )?;
-            
-            // Update result
-            match processed {
-                processor::ProcessResult::Organized(game) => {
-                    result.have.insert(game);
-                }
-                processor::ProcessResult::Duplicate(file) => {
-                    result.duplicate.push(file);
-                }
-                processor::ProcessResult::Unknown(file) => {
-                    result.unknown.push(file);
-                }
-            }
-            
+            bar.set_current_file(&processing, &filename);
+            self.organize_file(organizing, file_hash, games_with_files, known_roms, events)?;
             bar.inc(1);
         }
         
-        bar.finish_with_message("Organization complete!");
+        bar.finish_with_message(tr!("organize-complete"));
+        Ok(())
+    }
+    
+    /// The result of organizing, with the ROMs games share
+    pub fn finish(&self, organizing: Organizing, known_roms: &KnownRoms) -> ScanResult {
+        let mut result = organizing.result;
+        if organizing.interrupted {
+            return result;
+        }
         
         // Track shared ROMs
         for (hash, entries) in known_roms.iter() {
             if entries.len() > 1 {
                 let games: Vec<String> = entries.iter()
                     .map(|(game, _)| game.clone())
-                    .collect::<HashSet<_>>()
+                    .collect::<BTreeSet<_>>()
                     .into_iter()
                     .collect();
                 
@@ -146,6 +189,14 @@ impl Organizer {
             }
         }
         
-        Ok(result)
+        result
     }
-}
\ No newline at end of file
+}
+
+/// Organizing in progress: the result so far and the spill folders in use
+#[derive(Default)]
+pub struct Organizing {
+    result: ScanResult,
+    spill_dirs: processor::SpillDirs,
+    interrupted: bool,
+}
@@ -5,38 +5,52 @@ use std::path::{Path, PathBuf};
 use std::collections::HashSet;
 
 use crate::error::Result;
-use crate::types::{FileHash, KnownRoms};
-use crate::config::Config;
+use crate::types::{FileHash, KnownRoms, RomEntry};
+use crate::config::{Config, LynxForm};
+use crate::logger::events::{Event, EventLog};
+use crate::scanner::collector::is_donor_path;
+use crate::scanner::compressed::copy_payload;
+use crate::scanner::dump_format::{copy_converted, DumpFormat};
+use crate::scanner::header::{self, copy_with_change, HeaderChange};
+use crate::scanner::multipart::{self, copy_joined};
 use super::folders;
+use super::rules::GameFolders;
+
+/// Numbered duplicate and unknown folders, created on first use
+#[derive(Default)]
+pub struct SpillDirs {
+    pub duplicate: Option<PathBuf>,
+    pub unknown: Option<PathBuf>,
+    /// Where a dry run placed files, which unlike real placements don't exist
+    pub dry_run_placed: HashSet<PathBuf>,
+}
 
 pub enum ProcessResult {
     Organized(String),  // Game name
     Duplicate(String),  // Filename
     Unknown(String),    // Filename
+    Donor,              // Unused donor file, left in place
 }
 
 /// Process a single file based on its hash matches
 pub fn process_file(
     file_hash: FileHash,
     games_with_files: &HashSet<String>,
-    games_needing_folders: &HashSet<String>,
+    games_needing_folders: &GameFolders,
     config: &Config,
-    duplicate_dir: &mut Option<PathBuf>,
-    unknown_dir: &mut Option<PathBuf>,
+    spill_dirs: &mut SpillDirs,
     known_roms: &mut KnownRoms,
+    events: &mut EventLog,
 ) -> Result<ProcessResult> {
-    let filename = file_hash.path.file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let filename = file_name(&file_hash.path);
+    // Files set aside by an earlier run are only taken when needed; the
+    // rest stay in their folder rather than being moved again
+    let donor = is_donor_path(&file_hash.path, config);
     
     if !file_hash.matching_entries.is_empty() {
-        // Filter to only process games that are present in our collection
-        let entries_for_present_games = file_hash.matching_entries
-            .iter()
-            .filter(|entry| games_with_files.contains(&entry.game))
-            .cloned()
-            .collect::<Vec<_>>();
+        // A split file was matched joined, so all its parts go together
+        let sources = multipart::joined_parts(&file_hash)?.unwrap_or_else(|| vec![file_hash.path.clone()]);
+        let entries_for_present_games = placement_entries(&file_hash, games_with_files);
         
         if !entries_for_present_games.is_empty() {
             // Process placements
@@ -46,104 +60,256 @@ pub fn process_file(
             for rom_entry in &entries_for_present_games {
                 let game_name = &rom_entry.game;
                 
-                let needs_folder = games_needing_folders.contains(game_name) ||
-                                   rom_entry.name.contains('\\') || 
-                                   rom_entry.name.contains('/');
-                
-                let new_path = calculate_rom_path(
-                    &rom_entry.name,
-                    game_name,
-                    needs_folder,
-                    &config.rom_dir,
-                    rom_entry.is_disk,
-                )?;
-                
-                if new_path.exists() {
+                // A converted copy is in the DAT's form, so it keeps the
+                // DAT's extension whatever the extension policy
+                let conversion = conversion(&file_hash, &sources, rom_entry, config)?;
+                let destination = rom_destination(rom_entry, games_needing_folders, &config.rom_dir);
+                let new_path = match conversion {
+                    Some(Conversion::Format(_)) => games_needing_folders.locate(rom_entry, destination),
+                    _ => games_needing_folders.placement_path(rom_entry, destination, &file_hash.path),
+                };
+                if new_path.exists()
+                    || spill_dirs.dry_run_placed.contains(&new_path)
+                    || games_needing_folders.in_archive(rom_entry, &config.rom_dir)
+                {
                     // File already exists at destination
                     continue;
                 }
                 
-                // Copy the file to all games that need it
-                if fs::copy(&file_hash.path, &new_path).is_ok() {
+                // Copy the file to all games that need it; in read-only
+                // mode it only counts for this run's reports, and a dry run
+                // only logs the copy
+                if !config.changes_files() || copy_to(&file_hash.path, &new_path, conversion.as_ref())? {
                     placements += 1;
+                    if config.dry_run {
+                        spill_dirs.dry_run_placed.insert(new_path.clone());
+                    }
                     if organized_game.is_empty() {
                         organized_game = game_name.clone();
                     }
 
                     // Add to known ROMs
-                    known_roms.entry(file_hash.sha1.clone())
-                        .or_insert_with(Vec::new)
+                    known_roms.entry(file_hash.primary_hash().to_string())
+                        .or_default()
                         .push((game_name.clone(), rom_entry.name.clone()));
+                    
+                    if !config.read_only {
+                        events.record(Event::Placed {
+                            from: file_hash.path.clone(),
+                            to: new_path,
+                            game: game_name.clone(),
+                            rom: rom_entry.name.clone(),
+                            converted: conversion.as_ref().map(Conversion::describe),
+                        })?;
+                    }
                 }
             }
 
             // After all potential placements, handle the original file
-            if placements > 0 {
+            if placements == 0 && donor {
+                Ok(ProcessResult::Donor)
+            } else if placements > 0 {
                 // Remove the original file after copying
-                let _ = fs::remove_file(&file_hash.path);
-                return Ok(ProcessResult::Organized(organized_game));
+                for source in sources {
+                    if config.dry_run || (!config.read_only && fs::remove_file(&source).is_ok()) {
+                        events.record(Event::Removed { path: source })?;
+                    }
+                }
+                Ok(ProcessResult::Organized(organized_game))
             } else {
                 // All destinations existed, treat as duplicate
-                if duplicate_dir.is_none() {
-                    *duplicate_dir = Some(folders::create_next_folder(&config.duplicate_prefix)?);
+                for source in sources {
+                    let moved_to = spill(&source, &file_name(&source), &mut spill_dirs.duplicate, &config.duplicate_prefix, config)?;
+                    events.record(Event::Skipped {
+                        path: source,
+                        reason: "duplicate".to_string(),
+                        moved_to,
+                    })?;
                 }
-                let dup_path = duplicate_dir.as_ref().unwrap().join(&filename);
-                fs::rename(&file_hash.path, &dup_path)?;
-                return Ok(ProcessResult::Duplicate(filename));
+                Ok(ProcessResult::Duplicate(filename))
             }
+        } else if donor {
+            Ok(ProcessResult::Donor)
         } else {
             // ROM is in DAT but not for any games in our collection
-            if unknown_dir.is_none() {
-                *unknown_dir = Some(folders::create_next_folder(&config.unknown_prefix)?);
+            for source in sources {
+                let moved_to = spill(&source, &file_name(&source), &mut spill_dirs.unknown, &config.unknown_prefix, config)?;
+                events.record(Event::Skipped {
+                    path: source,
+                    reason: "game not in collection".to_string(),
+                    moved_to,
+                })?;
             }
-            let unk_path = unknown_dir.as_ref().unwrap().join(&filename);
-            fs::rename(&file_hash.path, &unk_path)?;
-            return Ok(ProcessResult::Unknown(filename));
+            Ok(ProcessResult::Unknown(filename))
         }
+    } else if donor {
+        Ok(ProcessResult::Donor)
     } else {
         // Unknown ROM - not in DAT at all
-        if unknown_dir.is_none() {
-            *unknown_dir = Some(folders::create_next_folder(&config.unknown_prefix)?);
+        let moved_to = spill(&file_hash.path, &filename, &mut spill_dirs.unknown, &config.unknown_prefix, config)?;
+        events.record(Event::Skipped {
+            path: file_hash.path,
+            reason: "not in DAT".to_string(),
+            moved_to,
+        })?;
+        Ok(ProcessResult::Unknown(filename))
+    }
+}
+
+/// How an organized copy differs from the file it is made from
+enum Conversion {
+    Header(HeaderChange),
+    /// Rewritten in the byte order the DAT hashes
+    Format(DumpFormat),
+    /// The parts of a split file, written as one
+    Join(Vec<PathBuf>),
+}
+
+impl Conversion {
+    /// `stripped iNES`, `added LNX`, `v64 to z64`, `joined 3 parts`
+    fn describe(&self) -> String {
+        match self {
+            Conversion::Header(change) => change.describe(),
+            Conversion::Format(format) => format!("{} to {}", format.name(), format.converted_name()),
+            Conversion::Join(parts) => format!("joined {} parts", parts.len()),
         }
-        let unk_path = unknown_dir.as_ref().unwrap().join(&filename);
-        fs::rename(&file_hash.path, &unk_path)?;
-        return Ok(ProcessResult::Unknown(filename));
     }
 }
 
-/// Calculate the destination path for a ROM
-fn calculate_rom_path(
-    rom_name: &str,
-    game_name: &str,
-    needs_folder: bool,
+/// What the copy of a file placed as `rom` changes, as the config asks;
+/// a split file is always joined
+fn conversion(file_hash: &FileHash, sources: &[PathBuf], rom: &RomEntry, config: &Config) -> Result<Option<Conversion>> {
+    if sources.len() > 1 {
+        return Ok(Some(Conversion::Join(sources.to_vec())));
+    }
+    if config.strip_headers || config.lynx_form != LynxForm::AsFound {
+        let change = header::header_change(file_hash, rom, config.strip_headers, config.lynx_form, header::skippers())?;
+        if let Some(change) = change {
+            return Ok(Some(Conversion::Header(change)));
+        }
+    }
+    if config.convert_dumps
+        && let Some(format) = DumpFormat::of_file(&file_hash.path)?
+        && format.converts_to(rom)
+    {
+        return Ok(Some(Conversion::Format(format)));
+    }
+    Ok(None)
+}
+
+/// Copy a file into the organized tree, decompressed if it is a gzip, xz
+/// or bzip2 file, or converted as asked. Returns false if the copy failed.
+fn copy_to(from: &Path, to: &Path, conversion: Option<&Conversion>) -> Result<bool> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let copied = match conversion {
+        Some(Conversion::Header(change)) => copy_with_change(from, to, change),
+        Some(Conversion::Format(format)) => copy_converted(from, to, *format),
+        Some(Conversion::Join(parts)) => copy_joined(parts, to),
+        None => copy_payload(from, to),
+    };
+    if copied.is_err() {
+        let _ = fs::remove_file(to);
+    }
+    Ok(copied.is_ok())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string()
+}
+
+/// Move a file into a numbered duplicate or unknown folder, creating the
+/// folder on first use. In read-only mode the file stays where it is; a dry
+/// run returns where it would go without creating the folder.
+fn spill(
+    path: &Path,
+    filename: &str,
+    dir: &mut Option<PathBuf>,
+    prefix: &str,
+    config: &Config,
+) -> Result<Option<PathBuf>> {
+    if config.read_only {
+        return Ok(None);
+    }
+    if dir.is_none() {
+        let parent = Path::new(&config.scan_dir);
+        *dir = Some(if config.dry_run {
+            folders::next_free_folder(parent, prefix)?
+        } else {
+            folders::create_next_folder(parent, prefix)?
+        });
+    }
+    let dest = dir.as_ref().unwrap().join(filename);
+    if !config.dry_run {
+        fs::rename(path, &dest)?;
+    }
+    Ok(Some(dest))
+}
+
+/// Entries a file will be placed as: those of games present in our
+/// collection, preferring entries whose size agrees with the file
+pub fn placement_entries(file_hash: &FileHash, games_with_files: &HashSet<String>) -> Vec<RomEntry> {
+    let entries = file_hash.matching_entries
+        .iter()
+        .filter(|entry| games_with_files.contains(&entry.game))
+        .cloned()
+        .collect::<Vec<_>>();
+    crate::scanner::prefer_size_matches(entries, file_hash.size)
+}
+
+/// Destination path for a ROM inside the organized tree
+pub fn rom_destination(
+    rom_entry: &RomEntry,
+    games_needing_folders: &GameFolders,
     rom_dir: &str,
-    is_disk: bool,
-) -> Result<PathBuf> {
-    let new_path = if is_disk {
+) -> PathBuf {
+    let rom_name = rom_entry.name.as_str();
+    let game_name = rom_entry.game.as_str();
+    let has_internal_path = rom_name.contains('\\') || rom_name.contains('/');
+    let needs_folder = games_needing_folders.contains(game_name) || has_internal_path;
+    let rom_dir = match games_needing_folders.group(game_name) {
+        Some(group) => Path::new(rom_dir).join(&*games_needing_folders.on_disk(group)),
+        None => PathBuf::from(rom_dir),
+    };
+    let rom_dir = rom_dir.as_path();
+    let game_dir = || rom_dir.join(&*games_needing_folders.folder_name(game_name));
+    
+    let path = if rom_entry.is_disk {
         // CHDs go in a subdirectory named after the disk
-        let disk_dir = Path::new(rom_dir).join(game_name).join(rom_name);
-        fs::create_dir_all(&disk_dir)?;
-        disk_dir.join(format!("{}.chd", rom_name))
+        let disk_name = games_needing_folders.on_disk(rom_name);
+        return game_dir().join(&*disk_name).join(format!("{}.chd", disk_name));
     } else if needs_folder {
-        if rom_name.contains('\\') || rom_name.contains('/') {
+        if has_internal_path {
             // Preserve internal folder structure
-            let mut path_parts = Path::new(rom_dir).join(game_name);
+            let mut path_parts = game_dir();
             for part in rom_name.split(&['\\', '/'][..]) {
-                path_parts = path_parts.join(part);
-            }
-            if let Some(parent) = path_parts.parent() {
-                fs::create_dir_all(parent)?;
+                path_parts = path_parts.join(&*games_needing_folders.on_disk(part));
             }
             path_parts
         } else {
-            let game_dir = Path::new(rom_dir).join(game_name);
-            fs::create_dir_all(&game_dir)?;
-            game_dir.join(rom_name)
+            game_dir().join(&*games_needing_folders.on_disk(rom_name))
         }
     } else {
-        fs::create_dir_all(rom_dir)?;
-        Path::new(rom_dir).join(rom_name)
+        rom_dir.join(&*games_needing_folders.on_disk(games_needing_folders.flat_name(game_name, rom_name)))
     };
-    
-    Ok(new_path)
-}
\ No newline at end of file
+    games_needing_folders.with_extension_policy(path)
+}
+
+/// Move a probable nodump match to probable_dir/<game>/<rom>, leaving any
+/// existing file in place. Returns the new path (in read-only mode or a dry
+/// run, the path it would have been moved to).
+pub fn place_probable(path: &Path, rom_entry: &RomEntry, config: &Config) -> Result<Option<PathBuf>> {
+    let dest = rom_destination(rom_entry, &GameFolders::from(HashSet::from([rom_entry.game.clone()])), &config.probable_dir);
+    if dest.exists() {
+        return Ok(None);
+    }
+    if !config.changes_files() {
+        return Ok(Some(dest));
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(path, &dest)?;
+    Ok(Some(dest))
+}
@@ -0,0 +1,94 @@
+// src/organizer/renames.rs - Rename map for files stored under non-DAT names
+
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::types::FileHash;
+use super::processor::{placement_entries, rom_destination};
+use super::rules::GameFolders;
+
+/// A matched file that will be organized under a different file name
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub game: String,
+}
+
+/// Every placement that changes a file's name, e.g. GoodTools names like
+/// "Game (U) [!].nes" becoming the DAT's "Game (USA).nes"
+pub fn plan_renames(
+    file_hashes: &[FileHash],
+    games_with_files: &HashSet<String>,
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> Vec<Rename> {
+    let mut renames = Vec::new();
+    for file_hash in file_hashes {
+        for entry in placement_entries(file_hash, games_with_files) {
+            let to = games_needing_folders.placement_path(
+                &entry,
+                rom_destination(&entry, games_needing_folders, rom_dir),
+                &file_hash.path,
+            );
+            if to.file_name() != file_hash.path.file_name() && !to.exists() {
+                renames.push(Rename { from: file_hash.path.clone(), to, game: entry.game });
+            }
+        }
+    }
+    renames
+}
+
+/// Show the rename map and ask whether to apply it. Anything but a yes in
+/// the current language declines.
+pub fn confirm_renames(renames: &[Rename], input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    writeln!(output, "{}", tr!("renames-prompt-header", count = renames.len()))?;
+    for rename in renames {
+        writeln!(output, "  {} -> {}", rename.from.display(), rename.to.display())?;
+    }
+    write!(output, "{} ", tr!("renames-prompt"))?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(super::confirm::is_yes(&answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomEntry};
+
+    #[test]
+    fn test_plan_and_confirm_renames() {
+        let file = |path: &str, rom: &str| FileHash {
+            path: PathBuf::from(path),
+            digests: FileDigests { sha1: "abc".to_string(), ..Default::default() },
+            size: 0,
+            matching_entries: vec![RomEntry {
+                name: rom.to_string(),
+                game: rom.trim_end_matches(".nes").to_string(),
+                ..Default::default()
+            }],
+        };
+        let files = vec![
+            file("./Game (U) [!].nes", "Game (USA).nes"),
+            file("./Other (Europe).nes", "Other (Europe).nes"),
+        ];
+        let present: HashSet<String> = ["Game (USA)".to_string(), "Other (Europe)".to_string()].into();
+
+        let renames = plan_renames(&files, &present, &GameFolders::default(), "missing-roms-dir");
+        assert_eq!(renames, vec![Rename {
+            from: PathBuf::from("./Game (U) [!].nes"),
+            to: PathBuf::from("missing-roms-dir/Game (USA).nes"),
+            game: "Game (USA)".to_string(),
+        }]);
+
+        let mut shown = Vec::new();
+        assert!(confirm_renames(&renames, &mut &b"yes\n"[..], &mut shown).unwrap());
+        assert!(String::from_utf8(shown).unwrap().contains("Game (U) [!].nes -> missing-roms-dir/Game (USA).nes"));
+        assert!(!confirm_renames(&renames, &mut &b"\n"[..], &mut Vec::new()).unwrap());
+    }
+}
@@ -1,15 +1,248 @@
 // src/organizer/rules.rs - Organization rules
 
-use std::collections::HashSet;
-use std::path::Path;
-use crate::config::Config;
-use crate::types::RomDb;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::BufReader;
+use crate::config::{CollisionPolicy, Config, ExtensionPolicy, Layout, OutputMode};
+use crate::types::{GameInfo, ParsedDat, RomEntry};
+use crate::scanner::compressed::payload_name;
+use super::game_archives::member_name;
+use super::sanitize::sanitize_component;
 
-/// Identify games that need folders based on various rules
+/// Where games go inside rom_dir: which get a folder of their own, and the
+/// year, decade or manufacturer folder each game is grouped under
+#[derive(Debug, Clone, Default)]
+pub struct GameFolders {
+    folders: HashSet<String>,
+    groups: HashMap<String, String>,
+    extensions: Extensions,
+    /// File name a game's ROM gets instead of the DAT name, to avoid a collision
+    renamed: HashMap<String, String>,
+    /// Folder name a game gets instead of its name, to avoid a collision
+    folder_names: HashMap<String, String>,
+    collisions: Vec<Collision>,
+    /// Whether names are made safe for Windows, exFAT and FAT32
+    portable: bool,
+    /// Name on disk -> DAT name, for every name that had to be changed
+    path_names: RefCell<BTreeMap<String, String>>,
+    /// Which games are packed into an archive each
+    output_mode: OutputMode,
+    /// Member names of each game archive, read on first use
+    archive_members: RefCell<HashMap<PathBuf, HashSet<String>>>,
+}
+
+/// A path in rom_dir that several games without folders would all use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// Relative to rom_dir, as the first game in natural order names it
+    pub path: PathBuf,
+    /// (game, ROM name) in natural order; names can differ in case
+    pub games: Vec<(String, String)>,
+}
+
+/// File extensions in rom_dir when they differ from the DAT names
+#[derive(Debug, Clone, Default)]
+enum Extensions {
+    /// Files are named exactly as in the DAT
+    #[default]
+    Dat,
+    /// Files keep the extension they were found with
+    Source,
+    /// DAT extension (lowercase) -> extension on disk
+    Map(HashMap<String, String>),
+}
+
+impl GameFolders {
+    /// Whether a game's ROMs go in a folder named after the game
+    pub fn contains(&self, game: &str) -> bool {
+        self.folders.contains(game)
+    }
+
+    /// The group folder a game is placed under, if the layout groups games
+    pub fn group(&self, game: &str) -> Option<&str> {
+        self.groups.get(game).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.folders.len()
+    }
+
+    /// Games that get a folder of their own
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.folders.iter()
+    }
+
+    /// Name of a ROM placed directly in rom_dir (or its group folder)
+    pub fn flat_name<'a>(&'a self, game: &str, rom: &'a str) -> &'a str {
+        self.renamed.get(game).map_or(rom, String::as_str)
+    }
+
+    /// Name of a game's folder (or archive) on disk: the game name, numbered
+    /// when it would only differ in case from another game's folder
+    pub fn folder_name<'a>(&self, game: &'a str) -> Cow<'a, str> {
+        match self.folder_names.get(game) {
+            Some(name) => Cow::Owned(self.on_disk(name).into_owned()),
+            None => self.on_disk(game),
+        }
+    }
+
+    /// A DAT name (game, ROM, group or a part of a ROM's internal path) as
+    /// it appears on disk. With portable names an unsafe name is sanitized,
+    /// and noted so `path_names` can map it back to the DAT name.
+    pub fn on_disk<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if !self.portable {
+            return Cow::Borrowed(name);
+        }
+        let safe = sanitize_component(name);
+        if let Cow::Owned(safe) = &safe {
+            self.path_names.borrow_mut().entry(safe.clone()).or_insert_with(|| name.to_string());
+        }
+        safe
+    }
+
+    /// Names changed by `on_disk` so far: name on disk -> DAT name
+    pub fn path_names(&self) -> BTreeMap<String, String> {
+        self.path_names.borrow().clone()
+    }
+
+    /// The archive a game is packed into with archived output:
+    /// `rom_dir/<game>.zip`, inside the game's group folder if it has one
+    pub fn archive_path(&self, game: &str, rom_dir: &str) -> PathBuf {
+        let dir = match self.group(game) {
+            Some(group) => Path::new(rom_dir).join(&*self.on_disk(group)),
+            None => PathBuf::from(rom_dir),
+        };
+        dir.join(format!("{}.zip", self.folder_name(game)))
+    }
+
+    /// Whether a game is packed into an archive: every game with
+    /// TorrentZip output, only games with a folder with zip output
+    pub fn is_archived(&self, game: &str) -> bool {
+        match self.output_mode {
+            OutputMode::Files => false,
+            OutputMode::TorrentZip => true,
+            OutputMode::Zip => self.contains(game),
+        }
+    }
+
+    /// Whether a ROM is already packed in its game's archive
+    pub fn in_archive(&self, rom: &RomEntry, rom_dir: &str) -> bool {
+        if !self.is_archived(&rom.game) {
+            return false;
+        }
+        let path = self.archive_path(&rom.game, rom_dir);
+        let mut members = self.archive_members.borrow_mut();
+        let names = members.entry(path).or_insert_with_key(|path| {
+            File::open(path).ok()
+                .and_then(|file| zip::ZipArchive::new(BufReader::new(file)).ok())
+                .map(|archive| archive.file_names().map(String::from).collect())
+                .unwrap_or_default()
+        });
+        names.contains(&member_name(rom))
+    }
+
+    /// Paths games without folders would have shared, resolved by the
+    /// collision policy unless it is `error`
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// Where a file the DAT names `path` goes under the extension policy.
+    /// With `source` the extension depends on the file found, so this is
+    /// the DAT name; see `placement_path` and `locate`.
+    pub fn with_extension_policy(&self, path: PathBuf) -> PathBuf {
+        match &self.extensions {
+            Extensions::Dat | Extensions::Source => path,
+            Extensions::Map(map) => {
+                let mapped = path.extension()
+                    .and_then(|ext| map.get(&ext.to_string_lossy().to_lowercase()));
+                match mapped {
+                    Some(ext) => path.with_extension(ext),
+                    None => path,
+                }
+            }
+        }
+    }
+
+    /// Destination for placing `source` as `rom` at `destination`: with
+    /// `source`, the organized file if there already is one, otherwise the
+    /// path with the extension of the file found
+    pub fn placement_path(&self, rom: &RomEntry, destination: PathBuf, source: &Path) -> PathBuf {
+        let existing = self.locate(rom, destination);
+        match (&self.extensions, payload_name(source).extension()) {
+            (Extensions::Source, Some(ext)) if has_file_extension(&existing) && !existing.exists() => {
+                existing.with_extension(ext)
+            }
+            _ => existing,
+        }
+    }
+
+    /// The organized file for `rom`, whose DAT name is at `path`. With
+    /// `source` it may have another extension: the only file in its
+    /// directory with the same name is taken, or the only one of those with
+    /// the ROM's size. Reads the directory on every call.
+    pub fn locate(&self, rom: &RomEntry, path: PathBuf) -> PathBuf {
+        if !matches!(self.extensions, Extensions::Source) || !has_file_extension(&path) || path.exists() {
+            return path;
+        }
+        let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+            return path;
+        };
+        let candidates: Vec<_> = std::fs::read_dir(dir).into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| Path::new(&entry.file_name()).file_stem() == Some(stem))
+            .collect();
+        let sized: Vec<_> = candidates.iter()
+            .filter(|entry| rom.size.is_some_and(|size| entry.metadata().is_ok_and(|meta| meta.len() == size)))
+            .collect();
+        match (candidates.as_slice(), sized.as_slice()) {
+            ([found], _) => found.path(),
+            (_, [found]) => found.path(),
+            _ => path,
+        }
+    }
+
+    /// Whether `path` is the organized file the DAT names `destination`:
+    /// the same path, or with `source` the same name with another extension
+    pub fn matches_destination(&self, destination: &Path, path: &Path) -> bool {
+        destination == path || (
+            matches!(self.extensions, Extensions::Source)
+                && has_file_extension(destination)
+                && destination.parent() == path.parent()
+                && destination.file_stem() == path.file_stem()
+        )
+    }
+}
+
+impl From<HashSet<String>> for GameFolders {
+    fn from(folders: HashSet<String>) -> Self {
+        GameFolders { folders, ..Default::default() }
+    }
+}
+
+/// Whether a name ends in something that looks like a file extension, unlike
+/// "Dr. Mario" or "Game v1.0"
+fn has_file_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| (1..=5).contains(&ext.len()) && ext.bytes().all(|b| b.is_ascii_alphanumeric()) && !ext.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Identify games that need folders based on various rules, and the group
+/// of every game when the layout groups them
 pub fn identify_games_needing_folders(
-    rom_db: &RomDb,
+    parsed_dat: &ParsedDat,
     config: &Config,
-) -> HashSet<String> {
+) -> GameFolders {
+    let rom_db = &parsed_dat.rom_db;
     let mut games_needing_folders = HashSet::new();
     
     // Count ROMs per game
@@ -19,7 +252,7 @@ pub fn identify_games_needing_folders(
         for rom_entry in rom_entries {
             game_rom_counts
                 .entry(rom_entry.game.clone())
-                .or_insert_with(HashSet::new)
+                .or_default()
                 .insert(rom_entry.name.clone());
         }
     }
@@ -35,15 +268,189 @@ pub fn identify_games_needing_folders(
             games_needing_folders.insert(game_name);
         } else if rom_count == 1 {
             // For single ROM games, check if the ROM name matches the game name
-            if let Some(rom_name) = rom_names.iter().next() {
-                if !is_rom_name_similar_to_game(&game_name, rom_name, config) {
-                    games_needing_folders.insert(game_name);
-                }
+            if let Some(rom_name) = rom_names.iter().next()
+                && !is_rom_name_similar_to_game(&game_name, rom_name, config)
+            {
+                games_needing_folders.insert(game_name);
+            }
+        }
+    }
+    
+    let groups = match config.layout {
+        Layout::Flat => HashMap::new(),
+        layout => parsed_dat.all_games.iter()
+            .map(|game| (game.clone(), group_folder(parsed_dat.game_info.get(game), layout)))
+            .collect(),
+    };
+    
+    let collisions = find_collisions(parsed_dat, &games_needing_folders, &groups, config.portable_names);
+    let renamed = match config.collision_policy {
+        CollisionPolicy::Folder => {
+            let colliding = collisions.iter().flat_map(|collision| &collision.games);
+            games_needing_folders.extend(colliding.map(|(game, _)| game.clone()));
+            HashMap::new()
+        }
+        CollisionPolicy::Suffix => suffixed_names(parsed_dat, &collisions, &games_needing_folders, &groups, config.portable_names),
+        CollisionPolicy::Error => HashMap::new(),
+    };
+    let folder_names = folder_names(parsed_dat, &games_needing_folders, &groups, config);
+    
+    let extensions = match config.extension_policy {
+        ExtensionPolicy::Dat => Extensions::Dat,
+        ExtensionPolicy::Source => Extensions::Source,
+        ExtensionPolicy::Map => Extensions::Map(config.extension_map.clone()),
+    };
+    
+    GameFolders {
+        folders: games_needing_folders,
+        groups,
+        extensions,
+        renamed,
+        folder_names,
+        collisions,
+        portable: config.portable_names,
+        path_names: RefCell::default(),
+        output_mode: config.output_mode,
+        archive_members: RefCell::default(),
+    }
+}
+
+/// Games without folders whose ROMs have the same name (ignoring case, as
+/// on Windows and macOS, and once sanitized with portable names) in the same
+/// directory of rom_dir
+fn find_collisions(parsed_dat: &ParsedDat, folders: &HashSet<String>, groups: &HashMap<String, String>, portable: bool) -> Vec<Collision> {
+    let mut flat: BTreeMap<(&str, String), BTreeMap<&str, &str>> = BTreeMap::new();
+    for rom in parsed_dat.rom_db.values().flatten() {
+        if rom.is_disk || rom.name.contains(['/', '\\']) || folders.contains(&rom.game) {
+            continue;
+        }
+        let group = groups.get(&rom.game).map_or("", String::as_str);
+        let name = if portable { sanitize_component(&rom.name) } else { Cow::Borrowed(rom.name.as_str()) };
+        flat.entry((group, name.to_lowercase())).or_default().insert(&rom.game, &rom.name);
+    }
+    
+    flat.into_iter()
+        .filter(|(_, games)| games.len() > 1)
+        .map(|((group, _), games)| {
+            let mut games: Vec<(&str, &str)> = games.into_iter().collect();
+            games.sort_by(|a, b| crate::logger::sort::natural_cmp(a.0, b.0));
+            Collision {
+                path: Path::new(group).join(games[0].1),
+                games: games.into_iter().map(|(game, rom)| (game.to_string(), rom.to_string())).collect(),
             }
+        })
+        .collect()
+}
+
+/// Key two names in the same directory clash under: ignoring case, as on
+/// Windows and macOS, and once sanitized with portable names
+fn path_key(name: &str, portable: bool) -> String {
+    if portable { sanitize_component(name).to_lowercase() } else { name.to_lowercase() }
+}
+
+/// `rom (Game).bin` for every game in a collision. A suffixed name that is
+/// still taken gets a number after the game: `rom (Game 2).bin`
+fn suffixed_names(
+    parsed_dat: &ParsedDat,
+    collisions: &[Collision],
+    folders: &HashSet<String>,
+    groups: &HashMap<String, String>,
+    portable: bool,
+) -> HashMap<String, String> {
+    let group = |game: &str| groups.get(game).map_or("", String::as_str);
+    let colliding: HashSet<&str> = collisions.iter()
+        .flat_map(|collision| collision.games.iter().map(|(game, _)| game.as_str()))
+        .collect();
+    let mut taken: HashSet<(&str, String)> = parsed_dat.rom_db.values().flatten()
+        .filter(|rom| !rom.is_disk && !rom.name.contains(['/', '\\']) && !folders.contains(&rom.game))
+        .filter(|rom| !colliding.contains(rom.game.as_str()))
+        .map(|rom| (group(&rom.game), path_key(&rom.name, portable)))
+        .collect();
+    
+    let mut renamed = HashMap::new();
+    for (game, rom) in collisions.iter().flat_map(|collision| &collision.games) {
+        let mut name = suffixed_name(rom, game);
+        let mut n = 2;
+        while !taken.insert((group(game), path_key(&name, portable))) {
+            name = suffixed_name(rom, &format!("{} {}", game, n));
+            n += 1;
         }
+        renamed.insert(game.clone(), name);
     }
+    renamed
+}
+
+/// Folder names for games whose folder (or archive, with TorrentZip output)
+/// would only differ in case, or once sanitized, from another game's in the
+/// same directory: `Game (2)` for the second game in natural order
+fn folder_names(
+    parsed_dat: &ParsedDat,
+    folders: &HashSet<String>,
+    groups: &HashMap<String, String>,
+    config: &Config,
+) -> HashMap<String, String> {
+    let has_folder = |rom: &RomEntry| {
+        config.output_mode == OutputMode::TorrentZip
+            || rom.is_disk
+            || rom.name.contains(['/', '\\'])
+            || folders.contains(&rom.game)
+    };
+    let games: HashSet<&str> = parsed_dat.rom_db.values().flatten()
+        .filter(|rom| has_folder(rom))
+        .map(|rom| rom.game.as_str())
+        .collect();
+    let mut games: Vec<&str> = games.into_iter().collect();
+    games.sort_by(|a, b| crate::logger::sort::natural_cmp(a, b));
     
-    games_needing_folders
+    let mut taken = HashSet::new();
+    let mut names = HashMap::new();
+    for game in games {
+        let group = groups.get(game).map_or("", String::as_str);
+        let mut name = game.to_string();
+        let mut n = 2;
+        while !taken.insert((group, path_key(&name, config.portable_names))) {
+            name = format!("{} ({})", game, n);
+            n += 1;
+        }
+        if name != game {
+            names.insert(game.to_string(), name);
+        }
+    }
+    names
+}
+
+/// `rom (Game).bin`: the game name before the extension
+fn suffixed_name(rom: &str, game: &str) -> String {
+    let path = Path::new(rom);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) if has_file_extension(path) => {
+            format!("{} ({}).{}", stem.to_string_lossy(), game, ext.to_string_lossy())
+        }
+        _ => format!("{} ({})", rom, game),
+    }
+}
+
+/// Group folder name for a game; games without the metadata go to `Unknown`
+fn group_folder(info: Option<&GameInfo>, layout: Layout) -> String {
+    const UNKNOWN: &str = "Unknown";
+    let year = info.and_then(|info| info.year.as_deref());
+    let name = match layout {
+        Layout::Flat => return String::new(),
+        // MAME writes uncertain digits as '?', e.g. 199?
+        Layout::Year => year.map(|year| year.replace('?', "x")),
+        Layout::Decade => year
+            .filter(|year| year.len() >= 3 && year.bytes().take(3).all(|b| b.is_ascii_digit()))
+            .map(|year| format!("{}0s", &year[..3])),
+        Layout::Manufacturer => info.and_then(|info| info.manufacturer.clone()),
+    };
+    let name: String = name.unwrap_or_default()
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    match name.trim().trim_end_matches('.') {
+        "" => UNKNOWN.to_string(),
+        name => name.to_string(),
+    }
 }
 
 /// Check if a ROM name is similar enough to the game name
@@ -180,4 +587,128 @@ fn extract_significant_words(text: &str, stop_words: &[String]) -> HashSet<Strin
         .filter(|s| s.len() > 2 && !stop_words.contains(&s.to_string()))
         .map(|s| s.to_string())
         .collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_folders_from_game_metadata() {
+        let info = |year: Option<&str>, manufacturer: Option<&str>| GameInfo {
+            year: year.map(String::from),
+            manufacturer: manufacturer.map(String::from),
+            ..Default::default()
+        };
+        let sf2 = info(Some("1991"), Some("Capcom"));
+        let proto = info(Some("199?"), Some("Sega / Namco"));
+
+        assert_eq!(group_folder(Some(&sf2), Layout::Year), "1991");
+        assert_eq!(group_folder(Some(&sf2), Layout::Decade), "1990s");
+        assert_eq!(group_folder(Some(&proto), Layout::Year), "199x");
+        assert_eq!(group_folder(Some(&proto), Layout::Decade), "1990s");
+        assert_eq!(group_folder(Some(&proto), Layout::Manufacturer), "Sega _ Namco");
+        assert_eq!(group_folder(Some(&info(Some("19??"), None)), Layout::Decade), "Unknown");
+        assert_eq!(group_folder(None, Layout::Manufacturer), "Unknown");
+    }
+
+    #[test]
+    fn test_extension_policies() {
+        let map = GameFolders {
+            extensions: Extensions::Map(HashMap::from([("bin".to_string(), "gen".to_string())])),
+            ..Default::default()
+        };
+        assert_eq!(map.with_extension_policy(PathBuf::from("roms/Sonic.BIN")), PathBuf::from("roms/Sonic.gen"));
+        assert_eq!(map.with_extension_policy(PathBuf::from("roms/Sonic.md")), PathBuf::from("roms/Sonic.md"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = GameFolders { extensions: Extensions::Source, ..Default::default() };
+        std::fs::write(dir.path().join("Sonic.smd"), b"").unwrap();
+        let dat_path = |name: &str| dir.path().join(name);
+        let rom = |name: &str, size: u64| RomEntry { name: name.to_string(), size: Some(size), ..Default::default() };
+
+        // An existing file with another extension is found, a new one keeps its own
+        assert_eq!(source.with_extension_policy(dat_path("Sonic.bin")), dat_path("Sonic.bin"));
+        assert_eq!(source.locate(&rom("Sonic.bin", 0), dat_path("Sonic.bin")), dat_path("Sonic.smd"));
+        let tails = rom("Tails.bin", 4);
+        let placed = source.placement_path(&tails, dat_path("Tails.bin"), Path::new("in/Tails.gen"));
+        assert_eq!(placed, dat_path("Tails.gen"));
+        std::fs::write(&placed, b"tail").unwrap();
+        assert_eq!(source.placement_path(&tails, dat_path("Tails.bin"), Path::new("in/Tails.md")), dat_path("Tails.gen"));
+        assert!(source.matches_destination(&dat_path("Tails.bin"), &dat_path("Tails.gen")));
+
+        // Several files with the name: the one with the ROM's size
+        std::fs::write(dat_path("Tails.txt"), b"notes").unwrap();
+        assert_eq!(source.locate(&tails, dat_path("Tails.bin")), dat_path("Tails.gen"));
+
+        // Names without a real extension are left alone
+        assert_eq!(source.placement_path(&rom("Dr. Mario", 0), dat_path("Dr. Mario"), Path::new("in/Dr. Mario.nes")), dat_path("Dr. Mario"));
+    }
+
+    #[test]
+    fn test_destination_collisions() {
+        use crate::organizer::processor::rom_destination;
+        use crate::types::RomEntry;
+
+        let rom = |game: &str, name: &str| RomEntry { name: name.to_string(), game: game.to_string(), ..Default::default() };
+        let roms = vec![rom("Pac", "Pac.bin"), rom("pac", "pac.bin"), rom("Dig", "Dig.bin")];
+        let parsed_dat = ParsedDat {
+            name: "set".to_string(),
+            header: Default::default(),
+            rom_db: HashMap::from([("hash".to_string(), roms.clone())]),
+            all_games: roms.iter().map(|rom| rom.game.clone()).collect(),
+            game_roms: roms.iter().map(|rom| (rom.game.clone(), vec![rom.clone()])).collect(),
+            game_info: HashMap::new(),
+            device_refs: HashMap::new(),
+            parent_clone_map: HashMap::new(),
+            rom_of: HashMap::new(),
+            counted: None,
+            issues: Vec::new(),
+        };
+
+        let folders = identify_games_needing_folders(&parsed_dat, &Config::default());
+        assert_eq!(folders.collisions().len(), 1);
+        assert_eq!(folders.collisions()[0].path, PathBuf::from("Pac.bin"));
+        assert_eq!(rom_destination(&roms[0], &folders, "roms"), PathBuf::from("roms/Pac/Pac.bin"));
+        assert_eq!(rom_destination(&roms[1], &folders, "roms"), PathBuf::from("roms/pac (2)/pac.bin"));
+        assert_eq!(rom_destination(&roms[2], &folders, "roms"), PathBuf::from("roms/Dig.bin"));
+        assert_distinct(&roms, &folders);
+        let config = Config { output_mode: OutputMode::TorrentZip, ..Config::default() };
+        let folders = identify_games_needing_folders(&parsed_dat, &config);
+        assert_eq!(folders.archive_path("pac", "roms"), PathBuf::from("roms/pac (2).zip"));
+
+        let config = Config { collision_policy: CollisionPolicy::Suffix, ..Config::default() };
+        let folders = identify_games_needing_folders(&parsed_dat, &config);
+        assert_eq!(rom_destination(&roms[0], &folders, "roms"), PathBuf::from("roms/Pac (Pac).bin"));
+        assert_eq!(rom_destination(&roms[1], &folders, "roms"), PathBuf::from("roms/pac (pac 2).bin"));
+        assert_eq!(rom_destination(&roms[2], &folders, "roms"), PathBuf::from("roms/Dig.bin"));
+        assert_distinct(&roms, &folders);
+
+        // With portable names, "Dig?.bin" would land on "Dig_.bin" as well
+        let roms = vec![rom("Dig?", "Dig?.bin"), rom("Dig_", "Dig_.bin"), rom("Q*bert", "Q*bert (AUX).bin")];
+        let parsed_dat = ParsedDat {
+            rom_db: HashMap::from([("hash".to_string(), roms.clone())]),
+            game_roms: roms.iter().map(|rom| (rom.game.clone(), vec![rom.clone()])).collect(),
+            ..parsed_dat
+        };
+        let config = Config { portable_names: true, ..Config::default() };
+        let folders = identify_games_needing_folders(&parsed_dat, &config);
+        assert_eq!(folders.collisions().len(), 1);
+        assert_eq!(rom_destination(&roms[0], &folders, "roms"), PathBuf::from("roms/Dig_/Dig_.bin"));
+        assert_eq!(rom_destination(&roms[1], &folders, "roms"), PathBuf::from("roms/Dig_ (2)/Dig_.bin"));
+        assert_eq!(rom_destination(&roms[2], &folders, "roms"), PathBuf::from("roms/Q_bert (AUX).bin"));
+        assert_eq!(folders.path_names().get("Q_bert (AUX).bin").map(String::as_str), Some("Q*bert (AUX).bin"));
+        assert_distinct(&roms, &folders);
+        let config = Config { portable_names: true, collision_policy: CollisionPolicy::Suffix, ..Config::default() };
+        let folders = identify_games_needing_folders(&parsed_dat, &config);
+        assert_distinct(&roms, &folders);
+    }
+
+    /// Every ROM lands on its own path, even on a case-insensitive filesystem
+    fn assert_distinct(roms: &[crate::types::RomEntry], folders: &GameFolders) {
+        let paths: HashSet<String> = roms.iter()
+            .map(|rom| crate::organizer::processor::rom_destination(rom, folders, "roms").to_string_lossy().to_lowercase())
+            .collect();
+        assert_eq!(paths.len(), roms.len(), "{:?}", paths);
+    }
+}
@@ -0,0 +1,54 @@
+// src/organizer/sanitize.rs - Names that every filesystem accepts
+
+use std::borrow::Cow;
+
+/// Characters Windows, exFAT and FAT32 refuse in a file or folder name
+const INVALID_CHARS: &str = "<>:\"/\\|?*";
+
+/// Device names Windows reserves, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A DAT name made safe as one path component: invalid and control
+/// characters and trailing dots or spaces become `_`, and a reserved device
+/// name gets a `_` after it (`CON.nes` -> `CON_.nes`). Names that are
+/// already safe are returned as they are.
+pub fn sanitize_component(name: &str) -> Cow<'_, str> {
+    let kept = name.trim_end_matches(['.', ' ']).len();
+    let stem = name.split('.').next().unwrap_or(name);
+    let reserved = RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem.trim_end()));
+    let invalid = |c: char| c.is_control() || INVALID_CHARS.contains(c);
+    if !name.is_empty() && kept == name.len() && !reserved && !name.contains(invalid) {
+        return Cow::Borrowed(name);
+    }
+
+    let mut safe: String = name[..kept].chars().map(|c| if invalid(c) { '_' } else { c }).collect();
+    safe.extend(std::iter::repeat_n('_', name.len() - kept));
+    if reserved {
+        safe.insert(stem.len(), '_');
+    }
+    if safe.is_empty() {
+        safe.push('_');
+    }
+    Cow::Owned(safe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_component() {
+        assert!(matches!(sanitize_component("Super Mario Bros. (World).nes"), Cow::Borrowed(_)));
+        assert_eq!(sanitize_component("Zelda: Link's Awakening?"), "Zelda_ Link's Awakening_");
+        assert_eq!(sanitize_component("Vol. 2..."), "Vol. 2___");
+        assert_eq!(sanitize_component("con.nes"), "con_.nes");
+        assert_eq!(sanitize_component("LPT1"), "LPT1_");
+        assert_eq!(sanitize_component("Console.nes"), "Console.nes");
+        assert_eq!(sanitize_component("a\tb*"), "a_b_");
+        assert_eq!(sanitize_component(""), "_");
+    }
+}
@@ -0,0 +1,235 @@
+// src/organizer/torrentzip.rs - Writing ZIP archives in TorrentZip form
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crc32fast::Hasher as Crc32Hasher;
+
+use crate::error::{Result, RomAuditError};
+
+/// Every TorrentZip member is dated 1996-12-24 23:32:00 (DOS time, date)
+const DOS_TIME: u16 = 0xBC00;
+const DOS_DATE: u16 = 0x2198;
+/// Deflate with maximum compression
+const METHOD_DEFLATE: u16 = 8;
+const FLAG_MAX_COMPRESSION: u16 = 1 << 1;
+const VERSION_NEEDED: u16 = 20;
+const LOCAL_HEADER_LEN: u64 = 30;
+
+/// TorrentZip member order: names compared byte by byte, ignoring ASCII case
+pub fn member_order(a: &str, b: &str) -> Ordering {
+    a.bytes().map(|b| b.to_ascii_lowercase()).cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+}
+
+/// A member already written: what its central directory entry needs
+struct Written {
+    name: String,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes an archive member by member, in `member_order`, then its central
+/// directory with the `TORRENTZIPPED-<CRC>` comment
+pub struct TorrentZipWriter {
+    file: BufWriter<File>,
+    written: Vec<Written>,
+    buffer: Vec<u8>,
+}
+
+impl TorrentZipWriter {
+    pub fn create(path: &Path, buffer_size: usize) -> Result<Self> {
+        Ok(TorrentZipWriter {
+            file: BufWriter::new(File::create(path)?),
+            written: Vec::new(),
+            buffer: vec![0; buffer_size],
+        })
+    }
+
+    /// Add a file; `name` uses `/` between folders
+    pub fn add(&mut self, name: &str, contents: &mut impl Read) -> Result<()> {
+        if self.written.last().is_some_and(|last| member_order(&last.name, name) != Ordering::Less) {
+            return Err(RomAuditError::Io(io::Error::other(format!("{} is out of TorrentZip order", name))));
+        }
+        let offset = self.file.stream_position()?;
+
+        // Header first with CRC and sizes left blank, filled in once known
+        self.write_local_header(name, 0, 0, 0)?;
+        let mut deflate = zlib::RawDeflate::new();
+        let mut crc = Crc32Hasher::new();
+        let mut size = 0u64;
+        loop {
+            let n = contents.read(&mut self.buffer)?;
+            crc.update(&self.buffer[..n]);
+            size += n as u64;
+            deflate.compress(&self.buffer[..n], n == 0, &mut self.file)?;
+            if n == 0 {
+                break;
+            }
+        }
+        let end = self.file.stream_position()?;
+        let compressed = end - offset - LOCAL_HEADER_LEN - name.len() as u64;
+
+        let written = Written {
+            name: name.to_string(),
+            crc: crc.finalize(),
+            compressed: zip32(compressed, name)?,
+            size: zip32(size, name)?,
+            offset: zip32(offset, name)?,
+        };
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.write_local_header(name, written.crc, written.compressed, written.size)?;
+        self.file.seek(SeekFrom::Start(end))?;
+        self.written.push(written);
+        Ok(())
+    }
+
+    fn write_local_header(&mut self, name: &str, crc: u32, compressed: u32, size: u32) -> io::Result<()> {
+        let mut header = Vec::with_capacity(LOCAL_HEADER_LEN as usize + name.len());
+        header.extend_from_slice(b"PK\x03\x04");
+        for field in [VERSION_NEEDED, FLAG_MAX_COMPRESSION, METHOD_DEFLATE, DOS_TIME, DOS_DATE] {
+            header.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, compressed, size] {
+            header.extend_from_slice(&field.to_le_bytes());
+        }
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.file.write_all(&header)
+    }
+
+    /// Write the central directory and close the archive
+    pub fn finish(mut self) -> Result<()> {
+        let start = self.file.stream_position()?;
+        let mut directory = Vec::new();
+        for member in &self.written {
+            directory.extend_from_slice(b"PK\x01\x02");
+            // Version made by 0 (MS-DOS), like every TorrentZip tool
+            for field in [0, VERSION_NEEDED, FLAG_MAX_COMPRESSION, METHOD_DEFLATE, DOS_TIME, DOS_DATE] {
+                directory.extend_from_slice(&field.to_le_bytes());
+            }
+            for field in [member.crc, member.compressed, member.size] {
+                directory.extend_from_slice(&field.to_le_bytes());
+            }
+            // Name length, then no extra field, comment, disk or attributes
+            directory.extend_from_slice(&(member.name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&member.offset.to_le_bytes());
+            directory.extend_from_slice(member.name.as_bytes());
+        }
+        let comment = format!("TORRENTZIPPED-{:08X}", crc32fast::hash(&directory));
+
+        let count = u16::try_from(self.written.len())
+            .map_err(|_| io::Error::other("more than 65535 files in one archive needs ZIP64"))?;
+        let mut end = Vec::with_capacity(22 + comment.len());
+        end.extend_from_slice(b"PK\x05\x06");
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&zip32(start, "the central directory")?.to_le_bytes());
+        end.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+        end.extend_from_slice(comment.as_bytes());
+
+        self.file.write_all(&directory)?;
+        self.file.write_all(&end)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Sizes and offsets past 4 GiB need ZIP64, which isn't written
+fn zip32(value: u64, what: &str) -> Result<u32> {
+    u32::try_from(value).map_err(|_| RomAuditError::Io(io::Error::other(format!(
+        "{} is past 4 GiB; ZIP64 TorrentZip archives aren't supported", what
+    ))))
+}
+
+/// Raw deflate at level 9 through flate2's zlib backend, built from the
+/// bundled zlib rather than the system's: TorrentZip checksums depend on
+/// zlib's exact output, which other deflate implementations (or zlib-ng)
+/// don't match
+mod zlib {
+    use std::io::{self, Write};
+
+    use flate2::{Compress, Compression, FlushCompress, Status};
+
+    pub struct RawDeflate {
+        stream: Compress,
+    }
+
+    impl RawDeflate {
+        /// Raw (no zlib header) 32 KiB window, memLevel 8, default strategy
+        pub fn new() -> Self {
+            RawDeflate { stream: Compress::new(Compression::best(), false) }
+        }
+
+        /// Compress `input`; with `finish`, also flush the end of the stream
+        pub fn compress(&mut self, mut input: &[u8], finish: bool, output: &mut impl Write) -> io::Result<()> {
+            let mut out = [0u8; 64 * 1024];
+            let flush = if finish { FlushCompress::Finish } else { FlushCompress::None };
+            loop {
+                let (total_in, total_out) = (self.stream.total_in(), self.stream.total_out());
+                let status = self.stream.compress(input, &mut out, flush).map_err(io::Error::other)?;
+                let produced = (self.stream.total_out() - total_out) as usize;
+                input = &input[(self.stream.total_in() - total_in) as usize..];
+                output.write_all(&out[..produced])?;
+                let done = if finish { status == Status::StreamEnd } else { input.is_empty() && produced < out.len() };
+                if done {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_torrentzip_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Game.zip");
+        let mut writer = TorrentZipWriter::create(&path, 4096).unwrap();
+        writer.add("a.bin", &mut &b"first rom"[..]).unwrap();
+        writer.add("B.bin", &mut &b""[..]).unwrap();
+        assert!(writer.add("b.bin", &mut &b"x"[..]).is_err());
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&bytes)).unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>(), ["a.bin", "B.bin"]);
+        let mut contents = String::new();
+        archive.by_name("a.bin").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "first rom");
+
+        // The comment is the CRC of the central directory, which ends where the comment's record starts
+        let comment = String::from_utf8(archive.comment().to_vec()).unwrap();
+        let end = bytes.len() - 22 - comment.len();
+        let start = u32::from_le_bytes(bytes[end + 16..end + 20].try_into().unwrap()) as usize;
+        assert_eq!(comment, format!("TORRENTZIPPED-{:08X}", crc32fast::hash(&bytes[start..end])));
+        assert_eq!(&bytes[10..14], &[0x00, 0xBC, 0x98, 0x21]);
+    }
+
+    #[test]
+    fn test_torrentzip_matches_reference() {
+        // SHA1 of the same members packed by a reference TorrentZip writer on zlib
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Game.zip");
+        let mut writer = TorrentZipWriter::create(&path, 4096).unwrap();
+        writer.add("a.bin", &mut &b"first rom"[..]).unwrap();
+        writer.add("B.bin", &mut &b""[..]).unwrap();
+        let rom: Vec<u8> = (0..100_000u64).map(|i| ((i * i) >> 7) as u8).collect();
+        writer.add("c/d.bin", &mut &rom[..]).unwrap();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.ends_with(b"TORRENTZIPPED-685C2819"));
+        assert_eq!(hex::encode(<sha1::Sha1 as digest::Digest>::digest(&bytes)), "a696a6b513d1403ef3c8de49ee87510edb519617");
+    }
+}
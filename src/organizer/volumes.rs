@@ -0,0 +1,180 @@
+// src/organizer/volumes.rs - Split the organized collection across fixed-size volumes
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::{Result, RomAuditError};
+use crate::logger::{format_size, present_roms, sort};
+use crate::progress::Progress;
+use crate::types::{GameRoms, KnownRoms, RomEntry};
+use super::processor::rom_destination;
+use super::rules::GameFolders;
+
+/// The organized files of one game; a game is never split across volumes
+pub struct GameFiles<'a> {
+    pub game: &'a str,
+    pub files: Vec<(PathBuf, &'a RomEntry, u64)>,
+    pub size: u64,
+}
+
+/// One target directory's worth of games
+#[derive(Default)]
+pub struct Volume<'a> {
+    pub games: Vec<GameFiles<'a>>,
+    pub size: u64,
+}
+
+/// Parse a size such as `25G`, `700MB`, `4.7GB` or `8GiB`. K, M, G and T
+/// are decimal, as disc capacities are; KiB, MiB, GiB and TiB are binary.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1_000,
+        "M" | "MB" => 1_000_000,
+        "G" | "GB" => 1_000_000_000,
+        "T" | "TB" => 1_000_000_000_000,
+        "KIB" => 1 << 10,
+        "MIB" => 1 << 20,
+        "GIB" => 1 << 30,
+        "TIB" => 1 << 40,
+        _ => return None,
+    };
+    let size = (number * multiplier as f64) as u64;
+    (size > 0).then_some(size)
+}
+
+/// Every present game with its organized files and their sizes on disk, in
+/// natural sort order
+pub fn collect_games<'a>(
+    game_roms: &'a GameRoms,
+    known_roms: &KnownRoms,
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> Vec<GameFiles<'a>> {
+    let present = present_roms(known_roms);
+    let mut games: Vec<GameFiles> = game_roms.iter()
+        .filter_map(|(game, roms)| {
+            let files: Vec<(PathBuf, &RomEntry, u64)> = roms.iter()
+                .filter(|rom| present.contains(&(game.as_str(), rom.name.as_str())))
+                .filter_map(|rom| {
+                    let path = games_needing_folders.locate(rom, rom_destination(rom, games_needing_folders, rom_dir));
+                    let size = fs::metadata(&path).ok()?.len();
+                    Some((path, rom, size))
+                })
+                .collect();
+            let size = files.iter().map(|(_, _, size)| size).sum();
+            (!files.is_empty()).then_some(GameFiles { game, files, size })
+        })
+        .collect();
+    games.sort_by(|a, b| sort::natural_cmp(a.game, b.game));
+    games
+}
+
+/// Fill volumes in order, so each one holds a contiguous alphabetical range.
+/// A game larger than `max_size` gets a volume of its own.
+pub fn plan_volumes(games: Vec<GameFiles>, max_size: u64) -> Vec<Volume> {
+    let mut volumes: Vec<Volume> = Vec::new();
+    for game in games {
+        match volumes.last_mut() {
+            Some(volume) if volume.size + game.size <= max_size => {
+                volume.size += game.size;
+                volume.games.push(game);
+            }
+            _ => volumes.push(Volume { size: game.size, games: vec![game] }),
+        }
+    }
+    volumes
+}
+
+/// Copy each volume to `dest/volumeNN/`, laid out as in rom_dir, and write
+/// a manifest into each
+pub fn write_volumes(volumes: &[Volume], dest: &Path, rom_dir: &str, max_size: u64, interrupted: &AtomicBool) -> Result<()> {
+    let total_files: usize = volumes.iter().flat_map(|v| &v.games).map(|g| g.files.len()).sum();
+    let bar = Progress::new(
+        "split",
+        total_files as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg} [{eta_precise}]",
+    );
+
+    let width = volumes.len().to_string().len().max(2);
+    let copying = tr!("progress-copying");
+    for (index, volume) in volumes.iter().enumerate() {
+        let dir = dest.join(format!("volume{:0width$}", index + 1, width = width));
+        if dir.exists() && fs::read_dir(&dir)?.next().is_some() {
+            return Err(RomAuditError::InvalidPath(format!("{} already exists and is not empty", dir.display())));
+        }
+        fs::create_dir_all(&dir)?;
+
+        for game in &volume.games {
+            for (path, _, _) in &game.files {
+                if interrupted.load(Ordering::Relaxed) {
+                    bar.finish_with_message(tr!("interrupted-by-user"));
+                    return Ok(());
+                }
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+                bar.set_current_file(&copying, name);
+                let target = dir.join(path.strip_prefix(rom_dir).unwrap_or(path));
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(path, &target)?;
+                bar.inc(1);
+            }
+        }
+
+        write_manifest(&dir, volume, (index + 1, volumes.len()), rom_dir, max_size)?;
+    }
+    bar.finish_with_message(tr!("volumes-written", volumes = volumes.len(), dest = dest.display().to_string()));
+    Ok(())
+}
+
+/// manifest.txt: every game on the volume with its files, sizes and DAT hashes
+fn write_manifest(dir: &Path, volume: &Volume, (number, count): (usize, usize), rom_dir: &str, max_size: u64) -> Result<()> {
+    let mut manifest = fs::File::create(dir.join("manifest.txt"))?;
+    writeln!(manifest, "{}", tr!("volume-manifest-header", number = number, count = count,
+        games = volume.games.len(), size = format_size(volume.size)))?;
+    if volume.size > max_size {
+        writeln!(manifest, "{}", tr!("volume-manifest-too-large", size = format_size(max_size)))?;
+    }
+    writeln!(manifest)?;
+
+    for game in &volume.games {
+        writeln!(manifest, "{} ({})", game.game, format_size(game.size))?;
+        for (path, rom, size) in &game.files {
+            let hash = rom.hashes.iter().next().map(String::as_str).unwrap_or("");
+            writeln!(manifest, "  {}  {}  {}", path.strip_prefix(rom_dir).unwrap_or(path).display(), size, hash)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_volumes_keeps_games_together() {
+        assert_eq!(parse_size("25G"), Some(25_000_000_000));
+        assert_eq!(parse_size("4.7GB"), Some(4_700_000_000));
+        assert_eq!(parse_size("1 MiB"), Some(1 << 20));
+        assert_eq!(parse_size("lots"), None);
+
+        let entry = RomEntry::default();
+        let game = |name: &'static str, size: u64| GameFiles {
+            game: name,
+            files: vec![(PathBuf::from(name), &entry, size)],
+            size,
+        };
+        let volumes = plan_volumes(vec![game("A", 6), game("B", 4), game("C", 3), game("D", 12), game("E", 1)], 10);
+
+        let names: Vec<Vec<&str>> = volumes.iter().map(|v| v.games.iter().map(|g| g.game).collect()).collect();
+        assert_eq!(names, [vec!["A", "B"], vec!["C"], vec!["D"], vec!["E"]]);
+        assert_eq!(volumes[2].size, 12);
+    }
+}
@@ -0,0 +1,224 @@
+// src/pack.rs - Audit every DAT of a DAT pack against its own system folder
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::error::{Result, RomAuditError};
+use crate::logger::writer::ReportWriter;
+
+/// File with the combined summary, in the logs directory of the directory
+/// the pack audit was started from
+pub const SUMMARY_FILE: &str = "pack_summary.txt";
+
+/// Outcome of one system in the pack
+#[derive(Debug)]
+pub struct SystemResult {
+    pub dat: PathBuf,
+    pub outcome: SystemOutcome,
+}
+
+#[derive(Debug)]
+pub enum SystemOutcome {
+    Audited { have: usize, total: usize, duplicates: usize, unknown: usize },
+    /// No folder for this system below the root
+    NoFolder,
+    /// Stopped by Ctrl+C before the audit finished
+    Interrupted,
+    Failed(String),
+}
+
+/// Every DAT file in a directory, in natural order
+pub fn find_dats(dat_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dats: Vec<PathBuf> = std::fs::read_dir(dat_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| crate::parser::compressed::is_dat_file(p))
+        .collect();
+    dats.sort_by(|a, b| crate::logger::sort::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(dats)
+}
+
+/// Folder names a DAT's system may be stored under: the DAT name as is, then
+/// without the date and `(Parent-Clone)` suffixes daily packs add, e.g.
+/// `Nintendo - Game Boy (Parent-Clone) (20240315-082452)` -> `Nintendo - Game Boy`
+pub fn folder_candidates(dat: &Path) -> Vec<String> {
+    let stem = crate::parser::compressed::dat_name(dat);
+    let mut name = stem.as_str();
+    while let Some((rest, suffix)) = name.trim_end().strip_suffix(')').and_then(|n| n.rsplit_once(" (")) {
+        if !(suffix.starts_with(|c: char| c.is_ascii_digit()) || suffix == "Parent-Clone") {
+            break;
+        }
+        name = rest;
+    }
+    let mut candidates = vec![stem.clone()];
+    if name != stem {
+        candidates.push(name.to_string());
+    }
+    candidates
+}
+
+/// Audit each DAT in `dat_dir` in its folder below `root`, as if romaudit_cli
+/// had been run there, then write the combined completion summary
+pub fn audit_pack(config: &Config, dat_dir: &Path, root: &Path, interrupted: Arc<AtomicBool>) -> Result<()> {
+    let dats: Vec<PathBuf> = find_dats(dat_dir)?
+        .into_iter()
+        .map(|dat| dat.canonicalize())
+        .collect::<std::io::Result<_>>()?;
+    if dats.is_empty() {
+        return Err(RomAuditError::NoDatFile);
+    }
+    // Per-system state goes to its own subdirectory of state_dir
+    let state_dir = config.state_dir.as_ref().map(|dir| -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Path::new(dir).canonicalize()?)
+    }).transpose()?;
+
+    let mut results = Vec::new();
+    for dat in dats {
+        if interrupted.load(Ordering::Relaxed) {
+            break;
+        }
+        let folder = folder_candidates(&dat).into_iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_dir());
+        let Some(folder) = folder else {
+            results.push(SystemResult { dat, outcome: SystemOutcome::NoFolder });
+            continue;
+        };
+        println!();
+        println!("=== {} ===", folder.display());
+
+        let system_config = system_config(config, &dat, &folder, state_dir.as_deref());
+        let outcome = system_config.prepare_state_dir()
+            .and_then(|()| crate::RomAuditor::new(system_config, interrupted.clone()))
+            .and_then(|mut auditor| auditor.run());
+
+        let outcome = match outcome {
+            // An interrupted audit returns an empty result
+            Ok(_) if interrupted.load(Ordering::Relaxed) => SystemOutcome::Interrupted,
+            Ok(result) => SystemOutcome::Audited {
+                have: result.have.len(),
+                total: result.have.len() + result.missing.len(),
+                duplicates: result.duplicate.len(),
+                unknown: result.unknown.len(),
+            },
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                SystemOutcome::Failed(e.to_string())
+            }
+        };
+        results.push(SystemResult { dat, outcome });
+    }
+
+    std::fs::create_dir_all(&config.logs_dir)?;
+    let mut summary = ReportWriter::create(&Path::new(&config.logs_dir).join(SUMMARY_FILE), config.report_encoding, config.line_ending)?;
+    write_summary(&mut summary, &results)?;
+    write_summary(&mut std::io::stdout(), &results)?;
+    Ok(())
+}
+
+/// The settings of one system's audit: everything it reads and writes is in
+/// its folder, as if romaudit_cli had been run there, except that its state
+/// goes to its own subdirectory of state_dir if there is one
+fn system_config(config: &Config, dat: &Path, folder: &Path, state_dir: Option<&Path>) -> Config {
+    let name = |path: &str| Path::new(path).file_name().unwrap_or_default().to_owned();
+    let in_folder = |dir: &Path, path: &str| dir.join(name(path)).to_string_lossy().to_string();
+    let system_state = match state_dir {
+        Some(state_dir) => state_dir.join(folder.file_name().unwrap_or_default()),
+        None => folder.to_path_buf(),
+    };
+
+    let mut system_config = config.clone();
+    system_config.dat_file = Some(dat.to_string_lossy().to_string());
+    system_config.scan_dir = folder.to_string_lossy().to_string();
+    system_config.rom_dir = in_folder(folder, &config.rom_dir);
+    system_config.probable_dir = in_folder(folder, &config.probable_dir);
+    system_config.logs_dir = in_folder(&system_state, &config.logs_dir);
+    system_config.db_file = in_folder(&system_state, &config.db_file);
+    system_config.state_dir = Some(system_state.to_string_lossy().to_string());
+    system_config
+}
+
+/// Completion per system and over the whole pack
+fn write_summary(out: &mut impl Write, results: &[SystemResult]) -> Result<()> {
+    let percent = |have: usize, total: usize| if total == 0 { 0.0 } else { have as f64 * 100.0 / total as f64 };
+    let (mut have_all, mut total_all) = (0, 0);
+
+    writeln!(out)?;
+    writeln!(out, "{}", tr!("pack-summary-header", dats = results.len()))?;
+    writeln!(out)?;
+    for result in results {
+        let name = crate::parser::compressed::dat_name(&result.dat);
+        match &result.outcome {
+            SystemOutcome::Audited { have, total, duplicates, unknown } => {
+                have_all += have;
+                total_all += total;
+                writeln!(out, "{:>6.1}%  {} / {}  {}", percent(*have, *total), have, total,
+                    tr!("pack-audited", name = name.as_str(), duplicates = *duplicates, unknown = *unknown))?;
+            }
+            SystemOutcome::NoFolder => writeln!(out, "{:>7}  {}", "-", tr!("pack-no-folder", name = name.as_str()))?,
+            SystemOutcome::Interrupted => writeln!(out, "{:>7}  {}", "-", tr!("pack-interrupted", name = name.as_str()))?,
+            SystemOutcome::Failed(error) => writeln!(out, "{:>7}  {}", "-", tr!("pack-failed", name = name.as_str(), error = error.as_str()))?,
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", tr!("pack-total", have = have_all, total = total_all,
+        percent = format!("{:.1}", percent(have_all, total_all))))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_candidates_strip_pack_suffixes() {
+        assert_eq!(
+            folder_candidates(Path::new("dats/Nintendo - Game Boy (Parent-Clone) (20240315-082452).dat")),
+            ["Nintendo - Game Boy (Parent-Clone) (20240315-082452)", "Nintendo - Game Boy"],
+        );
+        assert_eq!(
+            folder_candidates(Path::new("Sony - PlayStation Portable (PSN) (Decrypted).dat")),
+            ["Sony - PlayStation Portable (PSN) (Decrypted)"],
+        );
+
+        let results = vec![
+            SystemResult {
+                dat: PathBuf::from("A.dat"),
+                outcome: SystemOutcome::Audited { have: 1, total: 4, duplicates: 0, unknown: 2 },
+            },
+            SystemResult { dat: PathBuf::from("B.dat"), outcome: SystemOutcome::NoFolder },
+            SystemResult { dat: PathBuf::from("C.dat"), outcome: SystemOutcome::Interrupted },
+        ];
+        let mut out = Vec::new();
+        write_summary(&mut out, &results).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("  25.0%  1 / 4  A (duplicates: 0, unknown: 2)"));
+        assert!(out.contains("B (no folder)"));
+        assert!(out.contains("C (interrupted)"));
+        assert!(out.contains("Total: 1 / 4 games (25.0%)"));
+    }
+
+    #[test]
+    fn test_system_config_stays_in_its_folder() {
+        let config = Config {
+            scan_dir: "/mnt/downloads".to_string(),
+            rom_dir: "/mnt/sorted".to_string(),
+            ..Default::default()
+        };
+        let folder = Path::new("/mnt/roms/Nintendo - Game Boy");
+        let system = system_config(&config, Path::new("/dats/gb.dat"), folder, None);
+        assert_eq!(system.scan_dir, "/mnt/roms/Nintendo - Game Boy");
+        assert_eq!(system.rom_dir, "/mnt/roms/Nintendo - Game Boy/sorted");
+        assert_eq!(system.logs_dir, "/mnt/roms/Nintendo - Game Boy/logs");
+        assert_eq!(system.state_path("cache"), folder.join("cache"));
+
+        let system = system_config(&config, Path::new("/dats/gb.dat"), folder, Some(Path::new("/state")));
+        assert_eq!(system.rom_dir, "/mnt/roms/Nintendo - Game Boy/sorted");
+        assert_eq!(system.db_file, "/state/Nintendo - Game Boy/rom_db.json");
+    }
+}
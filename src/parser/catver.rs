@@ -0,0 +1,49 @@
+// src/parser/catver.rs - MAME's catver.ini, the category of every machine
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Result, RomAuditError};
+
+/// Where catver.ini is looked for when no path is configured
+pub const CATVER_FILE: &str = "catver.ini";
+
+/// Machine -> category from the `[Category]` section of a catver.ini
+pub fn read_catver(path: &Path) -> Result<HashMap<String, String>> {
+    let data = std::fs::read(path)
+        .map_err(|e| RomAuditError::ConfigError(format!("cannot read {}: {}", path.display(), e)))?;
+    Ok(parse_catver(&String::from_utf8_lossy(&data)))
+}
+
+/// `name=Category / Subcategory` lines of the `[Category]` section; the
+/// other sections (`[VerAdded]`, ...) and `;` comments are skipped
+pub fn parse_catver(text: &str) -> HashMap<String, String> {
+    let mut categories = HashMap::new();
+    let mut in_category = false;
+    for line in text.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            in_category = section.eq_ignore_ascii_case("Category");
+        } else if in_category
+            && !line.starts_with(';')
+            && let Some((name, category)) = line.split_once('=')
+        {
+            categories.insert(name.trim().to_string(), category.trim().to_string());
+        }
+    }
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_catver() {
+        let text = ";; CatVer 0.262 ;;\r\n\r\n[FOLDER_SETTINGS]\r\nRootFolderIcon mame\r\n\r\n[Category]\r\n\
+            sf2=Fighter / Versus\r\nmjkjidai=Tabletop / Mahjong * Mature *\r\n\r\n[VerAdded]\r\nsf2=0.35\r\n";
+        let categories = parse_catver(text);
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories["sf2"], "Fighter / Versus");
+        assert_eq!(categories["mjkjidai"], "Tabletop / Mahjong * Mature *");
+    }
+}
@@ -0,0 +1,105 @@
+// src/parser/checksum.rs - Verify a DAT against its companion checksum file
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, RomAuditError};
+use crate::scanner::hasher_optimized;
+use crate::types::HashAlgorithms;
+
+/// Checksum files that may accompany a DAT, as extensions
+const COMPANIONS: [(&str, HashAlgorithms); 3] = [
+    ("sha256", HashAlgorithms { sha1: false, md5: false, crc: false, sha256: true }),
+    ("sha1", HashAlgorithms { sha1: true, md5: false, crc: false, sha256: false }),
+    ("md5", HashAlgorithms { sha1: false, md5: true, crc: false, sha256: false }),
+];
+
+/// Companion files of a DAT: `set.dat.sha1` or `set.sha1`, and the same for
+/// sha256 and md5
+fn companion_paths(dat_path: &Path) -> Vec<(PathBuf, &'static str, HashAlgorithms)> {
+    COMPANIONS.iter()
+        .flat_map(|&(ext, algorithms)| {
+            let mut appended = dat_path.as_os_str().to_owned();
+            appended.push(format!(".{}", ext));
+            [(PathBuf::from(appended), ext, algorithms), (dat_path.with_extension(ext), ext, algorithms)]
+        })
+        .collect()
+}
+
+/// Whether a file is the checksum companion of a DAT next to it, so it isn't
+/// scanned as a ROM
+pub fn is_companion_file(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else { return false };
+    if !COMPANIONS.iter().any(|(companion, _)| ext.eq_ignore_ascii_case(companion)) {
+        return false;
+    }
+    let dat = path.with_extension("");
+    let is_dat = |p: &Path| p.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("dat"));
+    is_dat(&dat) || dat.with_extension("dat").is_file()
+}
+
+/// The hash for `file_name` in a checksum file: either a bare hash, or
+/// `hash  name` lines as written by sha1sum/md5sum
+fn expected_hash(content: &str, file_name: &str) -> Option<String> {
+    let mut lines = content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with(['#', ';']));
+    let first = lines.clone().next()?;
+    if !first.contains(char::is_whitespace) {
+        return Some(first.to_lowercase());
+    }
+    lines.find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        // sha1sum marks binary mode with '*'
+        let name = name.trim_start().trim_start_matches('*');
+        (name == file_name || Path::new(name).file_name().is_some_and(|n| n == file_name))
+            .then(|| hash.to_lowercase())
+    })
+}
+
+/// Check the DAT against each companion checksum file found. Returns the
+/// companions that matched; any mismatch is an error, as the DAT is most
+/// likely truncated.
+pub fn verify_dat(dat_path: &Path, buffer_size: usize) -> Result<Vec<PathBuf>> {
+    let file_name = dat_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut verified = Vec::new();
+    for (companion, name, algorithms) in companion_paths(dat_path) {
+        let Ok(content) = std::fs::read_to_string(&companion) else { continue };
+        let Some(expected) = expected_hash(&content, &file_name) else {
+            eprintln!("Warning: {} has no checksum for {}", companion.display(), file_name);
+            continue;
+        };
+
+        let digests = hasher_optimized::calculate_hashes_optimized(dat_path, buffer_size, algorithms)?;
+        let actual = digests.computed().next().unwrap_or_default().to_string();
+        if actual != expected {
+            return Err(RomAuditError::ParseError(format!(
+                "{} does not match {} ({} is {}, expected {}); the DAT is probably truncated or corrupted, download it again",
+                dat_path.display(), companion.display(), name, actual, expected,
+            )));
+        }
+        verified.push(companion);
+    }
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_dat_against_companions() {
+        let dir = tempfile::tempdir().unwrap();
+        let dat = dir.path().join("set.dat");
+        std::fs::write(&dat, b"abc").unwrap();
+        assert!(verify_dat(&dat, 1024).unwrap().is_empty());
+
+        // sha1sum style with the name, and a bare md5
+        std::fs::write(dir.path().join("set.dat.sha1"), "a9993e364706816aba3e25717850c26c9cd0d89d *set.dat\n").unwrap();
+        std::fs::write(dir.path().join("set.md5"), "900150983CD24FB0D6963F7D28E17F72\n").unwrap();
+        assert_eq!(verify_dat(&dat, 1024).unwrap().len(), 2);
+        assert!(is_companion_file(&dir.path().join("set.dat.sha1")));
+        assert!(is_companion_file(&dir.path().join("set.md5")));
+        assert!(!is_companion_file(&dir.path().join("other.md5")));
+
+        std::fs::write(&dat, b"ab").unwrap();
+        assert!(verify_dat(&dat, 1024).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// src/parser/compressed.rs - DATs downloaded gzipped or in a ZIP archive
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::error::Result;
+
+/// Whether `path` is a DAT: `.dat`, a gzipped `.dat.gz` or `.xml.gz`, or a
+/// ZIP archive holding a single `.dat` or `.xml`
+pub fn is_dat_file(path: &Path) -> bool {
+    has_extension(&file_name(path), "dat") || is_wrapped_dat(path)
+}
+
+/// Whether `path` is a gzipped or zipped DAT, which has to be unwrapped to be read
+pub fn is_wrapped_dat(path: &Path) -> bool {
+    is_gzipped_dat(path) || zipped_dat_index(path).is_some()
+}
+
+/// The DAT's name: its file name without `.gz`/`.zip` and `.dat`/`.xml`
+pub fn dat_name(path: &Path) -> String {
+    let name = file_name(path);
+    let lower = name.to_lowercase();
+    let mut end = name.len();
+    for extension in [".gz", ".zip", ".dat", ".xml"] {
+        if lower[..end].ends_with(extension) {
+            end -= extension.len();
+        }
+    }
+    name[..end].to_string()
+}
+
+/// Open a DAT for reading, unwrapped if it's gzipped or zipped
+pub fn open_dat(path: &Path) -> Result<Box<dyn Read>> {
+    if is_gzipped_dat(path) {
+        return Ok(Box::new(GzDecoder::new(BufReader::new(File::open(path)?))));
+    }
+    if let Some(index) = zipped_dat_index(path) {
+        let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        let mut data = Vec::new();
+        archive.by_index(index)?.read_to_end(&mut data)?;
+        return Ok(Box::new(std::io::Cursor::new(data)));
+    }
+    Ok(Box::new(File::open(path)?))
+}
+
+/// The content of a gzipped or zipped DAT; None for a plain one, which is
+/// better memory-mapped than read
+pub fn read_wrapped_dat(path: &Path) -> Result<Option<Vec<u8>>> {
+    if !is_wrapped_dat(path) {
+        return Ok(None);
+    }
+    let mut data = Vec::new();
+    open_dat(path)?.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+fn is_gzipped_dat(path: &Path) -> bool {
+    let name = file_name(path).to_lowercase();
+    name.ends_with(".dat.gz") || name.ends_with(".xml.gz")
+}
+
+/// Index of the DAT in a ZIP archive that holds one DAT and nothing else,
+/// as No-Intro and Redump downloads do; ROM archives never match
+fn zipped_dat_index(path: &Path) -> Option<usize> {
+    if !has_extension(&file_name(path), "zip") {
+        return None;
+    }
+    let archive = ZipArchive::new(BufReader::new(File::open(path).ok()?)).ok()?;
+    let files: Vec<(usize, &str)> = archive.file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(|name| (archive.index_for_name(name), name))
+        .filter_map(|(index, name)| Some((index?, name)))
+        .collect();
+    match files.as_slice() {
+        [(index, name)] if has_extension(name, "dat") || has_extension(name, "xml") => Some(*index),
+        _ => None,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    Path::new(name).extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_gzipped_and_zipped_dats() {
+        let dir = tempfile::tempdir().unwrap();
+        let dat = b"<datafile><game name=\"g\"><rom name=\"g.bin\" crc=\"12345678\"/></game></datafile>";
+
+        let gz = dir.path().join("Nintendo - Game Boy (20240315).dat.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gz).unwrap(), flate2::Compression::default());
+        encoder.write_all(dat).unwrap();
+        encoder.finish().unwrap();
+
+        let zipped = dir.path().join("Nintendo - Game Boy (20240315).zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zipped).unwrap());
+        writer.start_file("Nintendo - Game Boy (20240315).dat", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(dat).unwrap();
+        writer.finish().unwrap();
+
+        let roms = dir.path().join("game.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&roms).unwrap());
+        writer.start_file("game.gb", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"rom").unwrap();
+        writer.finish().unwrap();
+
+        for path in [&gz, &zipped] {
+            assert!(is_dat_file(path));
+            assert_eq!(read_wrapped_dat(path).unwrap().as_deref(), Some(&dat[..]));
+            assert_eq!(dat_name(path), "Nintendo - Game Boy (20240315)");
+        }
+        assert!(!is_dat_file(&roms));
+        assert!(is_dat_file(Path::new("plain.DAT")));
+        assert_eq!(read_wrapped_dat(Path::new("plain.dat")).unwrap(), None);
+    }
+}
@@ -1,26 +1,49 @@
 // src/parser/detector.rs - DAT type detector
 
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Result;
 use crate::types::DatType;
 
+/// How much of a DAT is inspected for detection; the header and first
+/// entries always fit, so huge MAME XMLs are never read in full (64 KB)
+const HEAD_SIZE: u64 = 64 * 1024;
+
+/// Read the beginning of a DAT file without loading the rest of it
+pub fn read_dat_head(path: &Path) -> Result<String> {
+    let mut head = Vec::new();
+    super::compressed::open_dat(path)?.take(HEAD_SIZE).read_to_end(&mut head)?;
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Detect the DAT type of a file from its name and header
+pub fn detect_dat_type_from_file(path: &Path) -> Result<(DatType, bool)> {
+    let head = read_dat_head(path)?;
+    let filename = super::compressed::dat_name(path);
+    
+    Ok((detect_dat_type(&filename, Some(&head)), is_mame_xml(&head)))
+}
+
 /// Detect if this is a MAME XML file based on specific identifiers
-pub fn is_mame_xml(content: &str) -> bool {
+pub fn is_mame_xml(head: &str) -> bool {
     // Check for MAME-specific XML identifiers
-    content.contains("<mame build=") || 
-    content.contains("<!DOCTYPE mame [") ||
-    content.contains("MAME ROM database") ||
+    head.contains("<mame build=") || 
+    head.contains("<!DOCTYPE mame [") ||
+    head.contains("<softwarelist name=") ||
+    head.contains("MAME ROM database") ||
     // Modern MAME uses <machine> instead of <game>
-    (content.contains("<machine name=") && content.contains("romof=")) ||
+    (head.contains("<machine name=") && head.contains("romof=")) ||
     // Older MAME uses <game>
-    (content.contains("<game name=") && content.contains("romof=")) ||
+    (head.contains("<game name=") && head.contains("romof=")) ||
     // Check header comments for MAME
-    content.lines()
+    head.lines()
         .take(10)  // Check first 10 lines
         .any(|line| line.contains("MAME") && (line.contains("XML") || line.contains("xml")))
 }
 
-/// Detect DAT type from filename or content
-#[allow(dead_code)]
-pub fn detect_dat_type(filename: &str, content: Option<&str>) -> DatType {
+/// Detect DAT type from filename or the beginning of its content
+pub fn detect_dat_type(filename: &str, head: Option<&str>) -> DatType {
     // Check filename first
     let lower = filename.to_lowercase();
     if lower.contains("non-merged") || lower.contains("nonmerged") {
@@ -33,9 +56,9 @@ pub fn detect_dat_type(filename: &str, content: Option<&str>) -> DatType {
         return DatType::Merged;
     }
     
-    // Check content if provided
-    if let Some(content) = content {
-        let lower = content.to_lowercase();
+    // Check header content if provided
+    if let Some(head) = head {
+        let lower = head.to_lowercase();
         if lower.contains("non-merged") || lower.contains("nonmerged") {
             return DatType::NonMerged;
         }
@@ -52,7 +75,6 @@ pub fn detect_dat_type(filename: &str, content: Option<&str>) -> DatType {
 }
 
 /// Display name for DAT type
-#[allow(dead_code)]
 pub fn dat_type_name(dat_type: &DatType) -> &'static str {
     match dat_type {
         DatType::NonMerged => "Non-merged (self-contained games)",
@@ -61,3 +83,27 @@ pub fn dat_type_name(dat_type: &DatType) -> &'static str {
         DatType::Standard => "Standard",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detection_reads_only_the_head() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("arcade.dat");
+
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "<?xml version=\"1.0\"?>\n<!DOCTYPE mame [\n]>\n<mame build=\"0.250\">").unwrap();
+        // Past the inspected head: must not influence detection
+        file.write_all(&vec![b' '; HEAD_SIZE as usize]).unwrap();
+        writeln!(file, "<!-- split -->").unwrap();
+
+        let (dat_type, is_mame) = detect_dat_type_from_file(&path).unwrap();
+        assert_eq!(dat_type, DatType::Standard);
+        assert!(is_mame);
+    }
+}
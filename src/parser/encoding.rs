@@ -0,0 +1,125 @@
+// src/parser/encoding.rs - DAT text encoding detection and transcoding
+
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+/// Only the XML declaration at the very start is inspected for an encoding
+const DECLARATION_SCAN_SIZE: usize = 256;
+
+/// Return the DAT content as UTF-8, transcoding legacy encodings.
+///
+/// The encoding comes from a byte order mark or the XML declaration. Undeclared
+/// content that isn't valid UTF-8 is read as Shift-JIS if it decodes cleanly
+/// and has no Latin-1 accented letters, otherwise as Latin-1.
+pub fn decode_to_utf8(data: &[u8]) -> Cow<'_, [u8]> {
+    let encoding = Encoding::for_bom(data)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| declared_encoding(data));
+
+    match encoding {
+        Some(encoding) if encoding != UTF_8 => transcode(data, encoding),
+        Some(_) => Cow::Borrowed(strip_utf8_bom(data)),
+        None if std::str::from_utf8(data).is_ok() => Cow::Borrowed(data),
+        None => {
+            // Undeclared legacy DAT. Much Latin-1 text is also valid Shift-JIS
+            // ("Pok\xe9mon" reads as kanji), so Latin-1 wins when it looks like it.
+            let (decoded, had_errors) = SHIFT_JIS.decode_without_bom_handling(data);
+            if had_errors || has_lone_latin1_letter(data) {
+                transcode(data, WINDOWS_1252)
+            } else {
+                Cow::Owned(decoded.into_owned().into_bytes())
+            }
+        }
+    }
+}
+
+/// Whether a byte in Latin-1's letter range (À to ÿ) stands alone between
+/// ASCII bytes, as accented letters do in "Pokémon" or "Señor". Shift-JIS
+/// text has its non-ASCII bytes in pairs and runs instead.
+fn has_lone_latin1_letter(data: &[u8]) -> bool {
+    data.iter().enumerate().any(|(i, &byte)| {
+        byte >= 0xC0
+            && (i == 0 || data[i - 1].is_ascii())
+            && data.get(i + 1).is_none_or(|next| next.is_ascii())
+    })
+}
+
+/// Name of the encoding a DAT will be read as, for display
+pub fn detected_encoding_name(data: &[u8]) -> Option<&'static str> {
+    let encoding = Encoding::for_bom(data)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| declared_encoding(data))?;
+    (encoding != UTF_8).then(|| encoding.name())
+}
+
+/// Encoding named in the `<?xml ... encoding="..."?>` declaration, if any
+fn declared_encoding(data: &[u8]) -> Option<&'static Encoding> {
+    let head = &data[..data.len().min(DECLARATION_SCAN_SIZE)];
+    let head = String::from_utf8_lossy(head);
+    let declaration = &head[head.find("<?xml")?..];
+    let declaration = &declaration[..declaration.find("?>")?];
+
+    let start = declaration.find("encoding=")? + "encoding=".len();
+    let rest = &declaration[start..];
+    let quote = rest.chars().next()?;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    let label = &rest[1..1 + rest[1..].find(quote)?];
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+fn transcode<'a>(data: &[u8], encoding: &'static Encoding) -> Cow<'a, [u8]> {
+    let (decoded, _, _) = encoding.decode(data);
+    Cow::Owned(decoded.into_owned().into_bytes())
+}
+
+fn strip_utf8_bom(data: &[u8]) -> &[u8] {
+    data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_latin1() {
+        let data = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><game name=\"Pok\xe9mon\"/>";
+        let decoded = decode_to_utf8(data);
+        assert!(std::str::from_utf8(&decoded).unwrap().contains("Pok\u{e9}mon"));
+    }
+
+    #[test]
+    fn test_declared_shift_jis() {
+        // "テスト" in Shift-JIS
+        let data = b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?><game name=\"\x83\x65\x83\x58\x83\x67\"/>";
+        let decoded = decode_to_utf8(data);
+        assert!(std::str::from_utf8(&decoded).unwrap().contains("\u{30c6}\u{30b9}\u{30c8}"));
+    }
+
+    #[test]
+    fn test_undeclared_cp1252() {
+        // Both names are also valid Shift-JIS
+        let data = b"<?xml version=\"1.0\"?><game name=\"Pok\xe9mon\"/><game name=\"Fran\xe7ais\"/>";
+        assert!(!SHIFT_JIS.decode_without_bom_handling(data).1);
+        let decoded = decode_to_utf8(data);
+        let text = std::str::from_utf8(&decoded).unwrap();
+        assert!(text.contains("Pok\u{e9}mon"));
+        assert!(text.contains("Fran\u{e7}ais"));
+    }
+
+    #[test]
+    fn test_undeclared_shift_jis() {
+        // "ゼルダの伝説" in Shift-JIS
+        let data = b"<?xml version=\"1.0\"?><game name=\"\x83\x5b\x83\x8b\x83\x5f\x82\xcc\x93\x60\x90\xe0\"/>";
+        let decoded = decode_to_utf8(data);
+        assert!(std::str::from_utf8(&decoded).unwrap().contains("\u{30bc}\u{30eb}\u{30c0}\u{306e}\u{4f1d}\u{8aac}"));
+    }
+
+    #[test]
+    fn test_utf8_is_borrowed() {
+        let data = "<?xml version=\"1.0\"?><game name=\"Pok\u{e9}mon\"/>".as_bytes();
+        assert!(matches!(decode_to_utf8(data), Cow::Borrowed(_)));
+    }
+}
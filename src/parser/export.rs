@@ -0,0 +1,124 @@
+// src/parser/export.rs - Writing games back out as a Logiqx DAT
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use quick_xml::escape::escape;
+
+use crate::error::Result;
+use crate::types::{DatHeader, ParsedDat, RomEntry, RomStatus};
+
+/// Write `games` of `parsed_dat` as a Logiqx XML DAT, in the given order,
+/// with the ROMs `keep` accepts. Metadata is kept, and so are parent/clone
+/// links to games that are written too.
+pub fn write_dat(
+    out: &mut impl Write,
+    header: &DatHeader,
+    parsed_dat: &ParsedDat,
+    games: &[&str],
+    keep: impl Fn(&RomEntry) -> bool,
+) -> Result<()> {
+    writeln!(out, "<?xml version=\"1.0\"?>")?;
+    writeln!(out, "<!DOCTYPE datafile PUBLIC \"-//Logiqx//DTD ROM Management Datafile//EN\" \"http://www.logiqx.com/Dats/datafile.dtd\">")?;
+    writeln!(out, "<datafile>")?;
+    writeln!(out, "\t<header>")?;
+    let fields = [
+        ("name", &header.name),
+        ("description", &header.description),
+        ("version", &header.version),
+        ("date", &header.date),
+        ("author", &header.author),
+    ];
+    for (tag, value) in fields {
+        if let Some(value) = value {
+            writeln!(out, "\t\t<{tag}>{}</{tag}>", escape(value.as_str()))?;
+        }
+    }
+    writeln!(out, "\t</header>")?;
+
+    let written: HashSet<&str> = games.iter().copied().collect();
+    for &game in games {
+        write!(out, "\t<game name=\"{}\"", escape(game))?;
+        if let Some(parent) = parsed_dat.parent_clone_map.get(game).filter(|parent| written.contains(parent.as_str())) {
+            write!(out, " cloneof=\"{}\"", escape(parent.as_str()))?;
+        }
+        if let Some(set) = parsed_dat.rom_of.get(game).filter(|set| written.contains(set.as_str())) {
+            write!(out, " romof=\"{}\"", escape(set.as_str()))?;
+        }
+        writeln!(out, ">")?;
+        if let Some(info) = parsed_dat.game_info.get(game) {
+            let fields = [("description", &info.description), ("year", &info.year), ("manufacturer", &info.manufacturer)];
+            for (tag, value) in fields {
+                if let Some(value) = value {
+                    writeln!(out, "\t\t<{tag}>{}</{tag}>", escape(value.as_str()))?;
+                }
+            }
+        }
+        for rom in parsed_dat.game_roms.get(game).into_iter().flatten().filter(|rom| keep(rom)) {
+            writeln!(out, "\t\t{}", rom_element(rom))?;
+        }
+        writeln!(out, "\t</game>")?;
+    }
+    writeln!(out, "</datafile>")?;
+    Ok(())
+}
+
+/// `<rom .../>` or `<disk .../>` with the attributes the entry has
+fn rom_element(rom: &RomEntry) -> String {
+    let mut element = format!("<{} name=\"{}\"", if rom.is_disk { "disk" } else { "rom" }, escape(rom.name.as_str()));
+    if let Some(merge) = &rom.merge {
+        element.push_str(&format!(" merge=\"{}\"", escape(merge.as_str())));
+    }
+    if let Some(size) = rom.size.filter(|_| !rom.is_disk) {
+        element.push_str(&format!(" size=\"{}\"", size));
+    }
+    let hashes = [("crc", &rom.hashes.crc), ("md5", &rom.hashes.md5), ("sha1", &rom.hashes.sha1), ("sha256", &rom.hashes.sha256)];
+    for (name, hash) in hashes {
+        if let Some(hash) = hash {
+            element.push_str(&format!(" {}=\"{}\"", name, hash));
+        }
+    }
+    match rom.status {
+        RomStatus::Good => {}
+        RomStatus::BadDump => element.push_str(" status=\"baddump\""),
+        RomStatus::NoDump => element.push_str(" status=\"nodump\""),
+    }
+    if rom.optional {
+        element.push_str(" optional=\"yes\"");
+    }
+    element.push_str("/>");
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    #[test]
+    fn test_written_dat_parses_back() {
+        let dat = br#"<datafile>
+    <header><name>Arcade &amp; more</name><version>1</version></header>
+    <game name="parent"><description>Parent &lt;World&gt;</description><rom name="p.bin" size="4" crc="11111111" sha1="aaaa"/></game>
+    <game name="clone" cloneof="parent" romof="parent">
+        <rom name="p.bin" merge="p.bin" size="4" crc="11111111"/>
+        <rom name="c.bin" size="4" crc="22222222" status="baddump"/>
+        <disk name="cd" sha1="bbbb"/>
+    </game>
+</datafile>"#;
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+
+        let mut out = Vec::new();
+        write_dat(&mut out, &parsed.header, &parsed, &["parent", "clone"], |rom| rom.name != "p.bin" || rom.merge.is_none()).unwrap();
+        let written = parse_dat_bytes(&out, "test".to_string(), ParseOptions::default()).unwrap();
+
+        assert_eq!(written.header, parsed.header);
+        assert_eq!(written.game_info, parsed.game_info);
+        assert_eq!(written.parent_clone_map, parsed.parent_clone_map);
+        let names = |game: &str| written.game_roms[game].iter().map(|rom| rom.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names("parent"), ["p.bin"]);
+        assert_eq!(names("clone"), ["c.bin", "cd"]);
+        assert_eq!(written.game_roms["clone"][0].status, RomStatus::BadDump);
+        assert!(written.game_roms["clone"][1].is_disk);
+    }
+}
@@ -1,31 +1,50 @@
 // src/parser/mod.rs - Parser module root
 
 pub mod xml;
+pub mod detector;
+pub mod encoding;
+pub mod checksum;
+pub mod sets;
+pub mod compressed;
+pub mod select;
+pub mod export;
+pub mod catver;
 
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use crate::error::Result;
+use crate::config::Config;
 use crate::types::ParsedDat;
 
 pub trait DatParser {
     fn parse(&self, path: &Path) -> Result<ParsedDat>;
 }
 
-/// Find the first .dat file in the current directory
+/// Find the DAT in the current directory. With several, ask which one
+/// when there is someone to ask, else take the first and say so.
 pub fn find_dat_file() -> Result<PathBuf> {
-    std::fs::read_dir(".")?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .find(|p| {
-            p.extension()
-                .and_then(|ext| ext.to_str())
-                .map(|ext| ext.eq_ignore_ascii_case("dat"))
-                .unwrap_or(false)
-        })
-        .ok_or(crate::error::RomAuditError::NoDatFile)
+    let mut dats = select::find_dat_files(Path::new("."))?;
+    match dats.len() {
+        0 => Err(crate::error::RomAuditError::NoDatFile),
+        1 => Ok(dats.remove(0)),
+        _ if std::io::stdin().is_terminal() => {
+            let stdin = std::io::stdin();
+            select::choose_dat(&dats, &mut stdin.lock(), &mut std::io::stdout())?
+                .ok_or(crate::error::RomAuditError::NoDatFile)
+        }
+        _ => {
+            println!("{}", tr!("dat-several", count = dats.len(), path = dats[0].display().to_string()));
+            Ok(dats.remove(0))
+        }
+    }
 }
 
 /// Parse DAT file
-pub fn parse_dat_file(path: &Path) -> Result<ParsedDat> {
-    let parser = xml::XmlParser::new();
+pub fn parse_dat_file(path: &Path, config: &Config) -> Result<ParsedDat> {
+    let parser = xml::XmlParser::new(xml::ParseOptions {
+        recover: config.recover_dat_errors,
+        strict: config.strict,
+        excluded_tags: config.excluded_tags.clone(),
+    });
     parser.parse(path)
 }
\ No newline at end of file
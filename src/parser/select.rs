@@ -0,0 +1,97 @@
+// src/parser/select.rs - Picking the DAT when a directory holds several
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use super::{compressed, detector};
+
+/// Every DAT in `dir`, in natural order: `.dat` files, gzipped or zipped
+/// DATs, and `.xml` files that are DATs rather than some other XML
+pub fn find_dat_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dats: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| is_dat(p))
+        .collect();
+    dats.sort_by(|a, b| crate::logger::sort::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(dats)
+}
+
+/// Whether `path` is a DAT of any kind romaudit_cli reads
+pub fn is_dat(path: &Path) -> bool {
+    compressed::is_dat_file(path) || is_xml_dat(path)
+}
+
+fn is_xml_dat(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+        && detector::read_dat_head(path).is_ok_and(|head| head.contains("<datafile") || head.contains("<mame") || head.contains("<softwarelist"))
+}
+
+/// The file name, with the name the DAT's header gives itself when it has one
+pub fn describe(path: &Path) -> String {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    match detector::read_dat_head(path).ok().as_deref().and_then(header_name) {
+        Some(name) => format!("{} ({})", file_name, name),
+        None => file_name,
+    }
+}
+
+/// `<name>` of the `<header>`, or its `<description>`, from the start of a DAT
+fn header_name(head: &str) -> Option<String> {
+    let header = &head[head.find("<header>")?..];
+    let header = &header[..header.find("</header>").unwrap_or(header.len())];
+    ["name", "description"].into_iter().find_map(|tag| {
+        let start = header.find(&format!("<{}>", tag))? + tag.len() + 2;
+        let end = start + header[start..].find(&format!("</{}>", tag))?;
+        let text = header[start..end].trim();
+        (!text.is_empty()).then(|| text.to_string())
+    })
+}
+
+/// List the DATs and ask which one to audit against; anything but a number
+/// from the list asks again, and the end of input picks none
+pub fn choose_dat(dats: &[PathBuf], input: &mut impl BufRead, output: &mut impl Write) -> Result<Option<PathBuf>> {
+    writeln!(output, "{}", tr!("dat-choose-header", count = dats.len()))?;
+    for (number, dat) in dats.iter().enumerate() {
+        writeln!(output, "  {}) {}", number + 1, describe(dat))?;
+    }
+    loop {
+        write!(output, "{} ", tr!("dat-choose-prompt", count = dats.len()))?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        if let Ok(number) = answer.trim().parse::<usize>()
+            && (1..=dats.len()).contains(&number)
+        {
+            return Ok(Some(dats[number - 1].clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_choose_dat() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("snes.dat"), "<datafile><header>\n<name>Nintendo - SNES</name>\n</header></datafile>").unwrap();
+        std::fs::write(dir.path().join("arcade.xml"), "<?xml version=\"1.0\"?>\n<mame build=\"0.262\"></mame>").unwrap();
+        std::fs::write(dir.path().join("layout.xml"), "<layout/>").unwrap();
+        std::fs::write(dir.path().join("readme.txt"), "").unwrap();
+
+        let dats = find_dat_files(dir.path()).unwrap();
+        let names: Vec<String> = dats.iter().map(|dat| describe(dat)).collect();
+        assert_eq!(names, ["arcade.xml", "snes.dat (Nintendo - SNES)"]);
+
+        let mut shown = Vec::new();
+        let chosen = choose_dat(&dats, &mut &b"3\nsnes\n2\n"[..], &mut shown).unwrap();
+        assert_eq!(chosen.as_ref(), Some(&dats[1]));
+        assert!(String::from_utf8(shown).unwrap().contains("2) snes.dat (Nintendo - SNES)"));
+        assert_eq!(choose_dat(&dats, &mut &b""[..], &mut Vec::new()).unwrap(), None);
+    }
+}
@@ -0,0 +1,100 @@
+// src/parser/sets.rs - Where ROMs live in split and merged MAME sets
+
+use crate::types::{DatType, GameRoms, ParsedDat, RomDb, RomEntry};
+
+/// Move the DAT's ROMs to the sets that hold them for `dat_type`. A
+/// non-merged set has every ROM it needs, as the DAT lists them. In a split
+/// set, ROMs marked `merge=` are only in the set named by `romof` (the
+/// parent, or a BIOS). A merged set also holds its clones' own ROMs, and
+/// clones are not sets of their own.
+pub fn apply_dat_type(parsed: &mut ParsedDat, dat_type: DatType) {
+    if !matches!(dat_type, DatType::Split | DatType::Merged) || parsed.rom_of.is_empty() {
+        return;
+    }
+
+    let mut game_roms = std::mem::take(&mut parsed.game_roms);
+    for (game, roms) in game_roms.iter_mut() {
+        if parsed.rom_of.get(game).is_some_and(|set| parsed.all_games.contains(set)) {
+            roms.retain(|rom| rom.merge.is_none());
+        }
+    }
+
+    if dat_type == DatType::Merged {
+        for (clone, parent) in &parsed.parent_clone_map {
+            if !parsed.all_games.contains(parent) {
+                continue;
+            }
+            let roms = game_roms.remove(clone).unwrap_or_default();
+            let parent_roms = game_roms.entry(parent.clone()).or_default();
+            for rom in roms {
+                if !parent_roms.iter().any(|existing| existing.name == rom.name) {
+                    parent_roms.push(RomEntry { game: parent.clone(), ..rom });
+                }
+            }
+            parsed.all_games.remove(clone);
+            parsed.game_info.remove(clone);
+            parsed.device_refs.remove(clone);
+        }
+    }
+
+    // A game left without ROMs (a clone identical to its parent) still
+    // counts as a set, as in the DAT
+    parsed.rom_db = rom_db(&game_roms);
+    parsed.game_roms = game_roms;
+}
+
+/// Index every ROM under each hash it carries
+fn rom_db(game_roms: &GameRoms) -> RomDb {
+    let mut rom_db = RomDb::new();
+    for rom in game_roms.values().flatten() {
+        for hash in rom.hashes.iter() {
+            rom_db.entry(hash.clone()).or_default().push(rom.clone());
+        }
+    }
+    rom_db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xml::{parse_dat_bytes, ParseOptions};
+
+    const DAT: &[u8] = br#"<datafile>
+    <game name="neogeo"><rom name="sp-s2.sp1" size="4" crc="9036d879"/></game>
+    <game name="mslug" romof="neogeo">
+        <rom name="sp-s2.sp1" merge="sp-s2.sp1" size="4" crc="9036d879"/>
+        <rom name="201-p1.p1" size="4" crc="08d8daa5"/>
+    </game>
+    <game name="mslugj" cloneof="mslug" romof="mslug">
+        <rom name="sp-s2.sp1" merge="sp-s2.sp1" size="4" crc="9036d879"/>
+        <rom name="201-p1.p1" merge="201-p1.p1" size="4" crc="08d8daa5"/>
+        <rom name="201-p2j.p2" size="4" crc="12345678"/>
+    </game>
+</datafile>"#;
+
+    fn names(parsed: &ParsedDat, game: &str) -> Vec<String> {
+        parsed.game_roms.get(game).into_iter().flatten().map(|rom| rom.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_split_and_merged_sets() {
+        let mut parsed = parse_dat_bytes(DAT, "mame".to_string(), ParseOptions::default()).unwrap();
+        assert_eq!(parsed.parent_clone_map["mslugj"], "mslug");
+        assert_eq!(parsed.rom_of["mslug"], "neogeo");
+
+        apply_dat_type(&mut parsed, DatType::NonMerged);
+        assert_eq!(names(&parsed, "mslugj").len(), 3);
+
+        let mut split = parse_dat_bytes(DAT, "mame".to_string(), ParseOptions::default()).unwrap();
+        apply_dat_type(&mut split, DatType::Split);
+        assert_eq!(names(&split, "mslug"), ["201-p1.p1"]);
+        assert_eq!(names(&split, "mslugj"), ["201-p2j.p2"]);
+        assert_eq!(split.rom_db["9036d879"].len(), 1);
+
+        let mut merged = parse_dat_bytes(DAT, "mame".to_string(), ParseOptions::default()).unwrap();
+        apply_dat_type(&mut merged, DatType::Merged);
+        assert_eq!(names(&merged, "mslug"), ["201-p1.p1", "201-p2j.p2"]);
+        assert!(!merged.all_games.contains("mslugj"));
+        assert_eq!(merged.rom_db["12345678"][0].game, "mslug");
+    }
+}
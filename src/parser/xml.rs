@@ -1,209 +1,813 @@
 // src/parser/xml.rs - XML/DAT parser for standard DAT files only
 
 use std::fs::File;
-use std::io::BufReader;
 use std::path::Path;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use memmap2::Mmap;
 use quick_xml::Reader;
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 
-use crate::error::Result;
-use crate::types::{RomEntry, RomHashes, RomDb, ParsedDat};
-use super::DatParser;
+use crate::error::{Result, RomAuditError};
+use crate::filter;
+use crate::progress::Progress;
+use crate::types::{RomEntry, RomHashes, RomDb, GameRoms, GameInfo, DatHeader, DatType, ParsedDat, DatIssue, RomStatus};
+use super::{compressed, encoding, DatParser};
 
-pub struct XmlParser;
+/// DATs larger than this get a parsing progress bar (5 MB)
+const PROGRESS_THRESHOLD: usize = 5_000_000;
+
+/// Number of XML events between progress bar updates
+const PROGRESS_INTERVAL: usize = 4096;
+
+/// Maximum number of anomalies listed in a strict mode error
+const MAX_REPORTED_ANOMALIES: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Skip malformed game entries instead of failing the whole parse
+    pub recover: bool,
+    /// Treat DAT anomalies (missing hashes, duplicate games, bad sizes) as errors
+    pub strict: bool,
+    /// Leave out games with one of these name tags, e.g. `Proto` for `(Proto 2)`
+    pub excluded_tags: Vec<String>,
+}
+
+pub struct XmlParser {
+    options: ParseOptions,
+}
 
 impl XmlParser {
-    pub fn new() -> Self {
-        XmlParser
+    pub fn new(options: ParseOptions) -> Self {
+        XmlParser { options }
     }
 }
 
 impl DatParser for XmlParser {
     fn parse(&self, dat_path: &Path) -> Result<ParsedDat> {
+        let name = compressed::dat_name(dat_path);
+        if let Some(data) = compressed::read_wrapped_dat(dat_path)? {
+            return parse_dat_bytes(&data, name, self.options.clone());
+        }
+        
         let file = File::open(dat_path)?;
         let file_size = file.metadata()?.len();
-        
-        // For large files, use a larger buffer
-        let buffer_size = if file_size > 10_000_000 {
-            8192 * 1024  // 8MB buffer for files over 10MB
+        // Parse straight from memory so quick-xml can borrow tag and attribute
+        // data instead of copying every event into an intermediate buffer
+        if crate::scanner::hasher_optimized::use_mmap(file_size) {
+            let mmap = unsafe { Mmap::map(&file)? };
+            parse_dat_bytes(&mmap, name, self.options.clone())
         } else {
-            8192  // 8KB default
-        };
-        
-        let mut reader = Reader::from_reader(BufReader::with_capacity(buffer_size, file));
-
-        let mut buf = Vec::new();
-        let mut current_game = String::new();
-        let mut rom_db = RomDb::new();
-        let mut all_games = HashSet::new();
-        let mut in_game_tag = false;
-
-        // For handling non-self-closing ROM tags
-        let mut current_rom_name = String::new();
-        let mut current_rom_hashes = RomHashes {
-            sha1: None,
-            md5: None,
-            crc: None,
+            let data = std::fs::read(dat_path)?;
+            parse_dat_bytes(&data, name, self.options.clone())
+        }
+    }
+}
+
+/// Parse DAT content that is already in memory.
+///
+/// With `recover`, errors inside a game entry drop that entry, are recorded
+/// as issues with their line number, and parsing continues with the next one.
+/// With `strict`, any error or anomaly fails the parse with every location listed.
+pub fn parse_dat_bytes(data: &[u8], name: String, options: ParseOptions) -> Result<ParsedDat> {
+    let recover = options.recover && !options.strict;
+
+    // Legacy DATs (Latin-1, Shift-JIS, ...) are transcoded to UTF-8 first
+    if let Some(encoding) = encoding::detected_encoding_name(data) {
+        println!("Converting DAT from {} to UTF-8", encoding);
+    }
+    let data = encoding::decode_to_utf8(data);
+    let data: &[u8] = &data;
+    
+    let mut reader = Reader::from_reader(data);
+    let mut builder = DatBuilder { strict: options.strict, excluded_tags: options.excluded_tags, ..Default::default() };
+    let mut issues = Vec::new();
+
+    // Byte-based progress bar for large files
+    let bar = if data.len() > PROGRESS_THRESHOLD {
+        let bar = Progress::new(
+            "parse",
+            data.len() as u64,
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg} [{eta_precise}]",
+        );
+        bar.set_message("Parsing DAT file...");
+        Some(bar)
+    } else {
+        None
+    };
+    let mut events = 0usize;
+    let mut last_error_position = None;
+
+    loop {
+        events += 1;
+        if let Some(bar) = &bar
+            && events.is_multiple_of(PROGRESS_INTERVAL)
+        {
+            bar.set_position(reader.buffer_position());
+        }
+
+        let event_start = reader.buffer_position();
+        let result = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => {
+                builder.position = event_start;
+                builder.handle_event(event).map_err(|e| (e, event_start))
+            }
+            Err(e) => Err((e.into(), reader.error_position())),
         };
-        let mut in_rom_tag = false;
-
-        // Progress indicator for large files
-        let show_progress = file_size > 5_000_000;
-        if show_progress {
-            println!("Parsing DAT file ({:.1} MB)...", file_size as f64 / 1_048_576.0);
-        }
-
-        loop {
-            match reader.read_event_into(&mut buf)? {
-                // Handle <game> tags (standard DAT format)
-                Event::Start(e) if e.name().as_ref() == b"game" => {
-                    current_game = String::new();
-                    
-                    for attr in e.attributes() {
-                        if let Ok(attr) = attr {
-                            if attr.key.as_ref() == b"name" {
-                                current_game = attr.unescape_value()?.to_string();
-                            }
-                        }
+
+        if let Err((error, position)) = result {
+            if !recover {
+                return Err(error);
+            }
+            
+            let game = builder.current_game.as_deref().unwrap_or("<outside of any game>");
+            issues.push(DatIssue {
+                line: line_number(data, position),
+                message: format!("{} (in game \"{}\")", error, game),
+            });
+            builder.game_broken = true;
+            
+            // The reader must move past the bad markup, otherwise give up
+            if last_error_position == Some(reader.buffer_position()) {
+                break;
+            }
+            last_error_position = Some(reader.buffer_position());
+        }
+    }
+    builder.finish_game();
+    builder.resolve_device_refs();
+
+    if let Some(bar) = bar {
+        bar.finish_with_message(format!(
+            "Parsed {} games with {} unique ROM hashes", builder.all_games.len(), builder.rom_db.len()
+        ));
+    }
+
+    if builder.excluded > 0 {
+        println!("{}", tr!("dat-excluded-tags", games = builder.excluded, tags = builder.excluded_tags.join(", ")));
+    }
+
+    for issue in &issues {
+        eprintln!("Warning: skipped broken DAT entry at line {}: {}", issue.line, issue.message);
+    }
+
+    if !builder.anomalies.is_empty() {
+        let mut message = format!("strict mode found {} DAT anomalies:", builder.anomalies.len());
+        for (position, anomaly) in builder.anomalies.iter().take(MAX_REPORTED_ANOMALIES) {
+            message.push_str(&format!("\n  line {}: {}", line_number(data, *position), anomaly));
+        }
+        if builder.anomalies.len() > MAX_REPORTED_ANOMALIES {
+            message.push_str(&format!("\n  ... and {} more", builder.anomalies.len() - MAX_REPORTED_ANOMALIES));
+        }
+        return Err(RomAuditError::ParseError(message));
+    }
+
+    Ok(ParsedDat {
+        name,
+        header: builder.header,
+        rom_db: builder.rom_db,
+        all_games: builder.all_games,
+        game_roms: builder.game_roms,
+        game_info: builder.game_info,
+        device_refs: builder.device_refs,
+        parent_clone_map: builder.parent_clone_map,
+        rom_of: builder.rom_of,
+        counted: None,
+        issues,
+    })
+}
+
+/// Accumulates parsed entries. A game's ROMs are only committed when the game
+/// ends, so an entry that turns out to be broken can be dropped as a whole.
+#[derive(Default)]
+struct DatBuilder {
+    rom_db: RomDb,
+    game_roms: GameRoms,
+    all_games: HashSet<String>,
+    current_game: Option<String>,
+    // The current entry is a MAME -listxml <machine> rather than a <game>
+    current_is_machine: bool,
+    pending_roms: Vec<RomEntry>,
+    pending_devices: Vec<String>,
+    device_refs: HashMap<String, Vec<String>>,
+    // cloneof and romof of the current game
+    pending_parents: (Option<String>, Option<String>),
+    parent_clone_map: HashMap<String, String>,
+    rom_of: HashMap<String, String>,
+    // For handling non-self-closing ROM tags
+    open_rom: Option<RomEntry>,
+    game_broken: bool,
+    // Strict mode: anomalies with the byte position of the event they were found in
+    strict: bool,
+    position: u64,
+    anomalies: Vec<(u64, String)>,
+    seen_games: HashSet<String>,
+    // Games with these name tags are left out, and counted
+    excluded_tags: Vec<String>,
+    excluded: usize,
+    // What the DAT's <header> says about it
+    in_header: bool,
+    header: DatHeader,
+    // <description>, <year> and <manufacturer> of the current game
+    game_info: HashMap<String, GameInfo>,
+    pending_info: GameInfo,
+    info_field: Option<InfoField>,
+    info_text: String,
+}
+
+/// A metadata element of the current game or of the header whose text is being read
+#[derive(Clone, Copy)]
+enum InfoField {
+    Description,
+    Year,
+    Manufacturer,
+    HeaderName,
+    HeaderDescription,
+    HeaderVersion,
+    HeaderAuthor,
+    HeaderDate,
+}
+
+impl InfoField {
+    fn from_tag(tag: &[u8]) -> Option<Self> {
+        match tag {
+            b"description" => Some(InfoField::Description),
+            b"year" => Some(InfoField::Year),
+            // Software lists name the publisher instead
+            b"manufacturer" | b"publisher" => Some(InfoField::Manufacturer),
+            _ => None,
+        }
+    }
+
+    fn from_header_tag(tag: &[u8]) -> Option<Self> {
+        match tag {
+            b"name" => Some(InfoField::HeaderName),
+            b"description" => Some(InfoField::HeaderDescription),
+            b"version" => Some(InfoField::HeaderVersion),
+            b"author" => Some(InfoField::HeaderAuthor),
+            b"date" => Some(InfoField::HeaderDate),
+            _ => None,
+        }
+    }
+}
+
+/// `<game>` in Logiqx DATs, `<machine>` in MAME -listxml output, `<software>`
+/// in MAME software lists
+fn is_game_tag(tag: &[u8]) -> bool {
+    matches!(tag, b"game" | b"machine" | b"software")
+}
+
+impl DatBuilder {
+    fn handle_event(&mut self, event: Event) -> Result<()> {
+        match event {
+            // A software list has no <header>; its name and description stand in
+            Event::Start(e) if e.name().as_ref() == b"softwarelist" => {
+                for attr in e.attributes().flatten() {
+                    match attr.key.as_ref() {
+                        b"name" => self.header.name = Some(attr.unescape_value()?.to_string()),
+                        b"description" => self.header.description = Some(attr.unescape_value()?.to_string()),
+                        _ => {}
                     }
+                }
+            }
 
-                    if !current_game.is_empty() {
-                        all_games.insert(current_game.clone());
-                        in_game_tag = true;
+            // Handle <game>, <machine> and <software> tags
+            Event::Start(e) if is_game_tag(e.name().as_ref()) => {
+                // A game that was never closed ends where the next one starts
+                self.finish_game();
+                
+                let mut game_name = String::new();
+                for attr in e.attributes().flatten() {
+                    match attr.key.as_ref() {
+                        b"name" => game_name = attr.unescape_value()?.to_string(),
+                        b"cloneof" => self.pending_parents.0 = Some(attr.unescape_value()?.to_string()),
+                        b"romof" => self.pending_parents.1 = Some(attr.unescape_value()?.to_string()),
+                        _ => {}
                     }
                 }
 
-                Event::End(e) if e.name().as_ref() == b"game" => {
-                    in_game_tag = false;
+                if self.strict && !self.seen_games.insert(game_name.clone()) {
+                    self.anomaly(format!("duplicate game name \"{}\"", game_name));
                 }
 
-                // Handle self-closing ROM tags (No-Intro style)
-                Event::Empty(e) if e.name().as_ref() == b"rom" && in_game_tag => {
-                    let mut name = String::new();
-                    let mut hashes = RomHashes {
-                        sha1: None,
-                        md5: None,
-                        crc: None,
-                    };
-
-                    for attr in e.attributes() {
-                        if let Ok(attr) = attr {
-                            match attr.key.as_ref() {
-                                b"name" => name = attr.unescape_value()?.to_string(),
-                                b"crc" => hashes.crc = Some(attr.unescape_value()?.to_lowercase()),
-                                b"md5" => hashes.md5 = Some(attr.unescape_value()?.to_lowercase()),
-                                b"sha1" => hashes.sha1 = Some(attr.unescape_value()?.to_lowercase()),
-                                _ => {}
-                            }
-                        }
-                    }
+                if !game_name.is_empty() {
+                    self.current_game = Some(game_name);
+                    self.current_is_machine = e.name().as_ref() == b"machine";
+                }
+            }
 
-                    let rom_entry = RomEntry {
-                        name: name.clone(),
-                        game: current_game.clone(),
-                        hashes: hashes.clone(),
-                        is_disk: false,
-                    };
+            Event::End(e) if is_game_tag(e.name().as_ref()) => {
+                self.finish_game();
+            }
 
-                    // Store by all available hash types
-                    if let Some(ref sha1) = hashes.sha1 {
-                        rom_db.entry(sha1.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
-                    }
-                    if let Some(ref md5) = hashes.md5 {
-                        rom_db.entry(md5.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
+            // DAT header
+            Event::Start(e) if e.name().as_ref() == b"header" => self.in_header = true,
+            Event::End(e) if e.name().as_ref() == b"header" => self.in_header = false,
+            Event::Start(e) | Event::Empty(e) if self.in_header && e.name().as_ref() == b"clrmamepro" => {
+                self.read_clrmamepro_directives(&e)?;
+            }
+
+            // Header and game metadata; the text may be split around entity references
+            Event::Start(e) if self.field_for(e.name().as_ref()).is_some() => {
+                self.info_field = self.field_for(e.name().as_ref());
+                self.info_text.clear();
+            }
+            Event::Text(e) if self.info_field.is_some() => {
+                self.info_text.push_str(&e.decode().map_err(quick_xml::Error::from)?);
+            }
+            Event::GeneralRef(e) if self.info_field.is_some() => {
+                let name = e.decode().map_err(quick_xml::Error::from)?;
+                if let Some(c) = e.resolve_char_ref()? {
+                    self.info_text.push(c);
+                } else if let Some(text) = quick_xml::escape::resolve_predefined_entity(&name) {
+                    self.info_text.push_str(text);
+                }
+            }
+            Event::End(e) if self.field_for(e.name().as_ref()).is_some() => {
+                if let Some(field) = self.info_field.take() {
+                    let text = self.info_text.trim();
+                    let value = (!text.is_empty()).then(|| text.to_string());
+                    match field {
+                        InfoField::Description => self.pending_info.description = value,
+                        InfoField::Year => self.pending_info.year = value,
+                        InfoField::Manufacturer => self.pending_info.manufacturer = value,
+                        InfoField::HeaderName => self.header.name = value,
+                        InfoField::HeaderDescription => self.header.description = value,
+                        InfoField::HeaderVersion => self.header.version = value,
+                        InfoField::HeaderAuthor => self.header.author = value,
+                        InfoField::HeaderDate => self.header.date = value,
                     }
-                    if let Some(ref crc) = hashes.crc {
-                        rom_db.entry(crc.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
+                }
+            }
+
+            // Handle self-closing ROM tags (No-Intro style)
+            Event::Empty(e) if e.name().as_ref() == b"rom" && self.current_game.is_some() => {
+                if let Some(rom_entry) = self.read_rom_entry(&e)? {
+                    self.pending_roms.push(rom_entry);
+                }
+            }
+
+            // Handle self-closing DISK tags
+            Event::Empty(e) if e.name().as_ref() == b"disk" => {
+                let Some(game) = &self.current_game else { return Ok(()) };
+                let mut name = String::new();
+                let mut sha1 = None;
+                let mut optional = false;
+                let mut merge = None;
+                let mut status = RomStatus::Good;
+
+                for attr in e.attributes().flatten() {
+                    match attr.key.as_ref() {
+                        b"name" => name = attr.unescape_value()?.to_string(),
+                        b"sha1" => sha1 = Some(attr.unescape_value()?.to_lowercase()),
+                        b"optional" => optional = attr.unescape_value()? == "yes",
+                        b"merge" => merge = Some(attr.unescape_value()?.to_string()),
+                        b"status" => status = rom_status(&attr.unescape_value()?),
+                        _ => {}
                     }
                 }
 
-                // Handle self-closing DISK tags
-                Event::Empty(e) if e.name().as_ref() == b"disk" && in_game_tag => {
-                    let mut name = String::new();
-                    let mut sha1 = None;
-
-                    for attr in e.attributes() {
-                        if let Ok(attr) = attr {
-                            match attr.key.as_ref() {
-                                b"name" => name = attr.unescape_value()?.to_string(),
-                                b"sha1" => sha1 = Some(attr.unescape_value()?.to_lowercase()),
-                                _ => {}
+                if let Some(sha1_hash) = sha1 {
+                    self.pending_roms.push(RomEntry {
+                        name,
+                        game: game.clone(),
+                        hashes: RomHashes { sha1: Some(sha1_hash), ..Default::default() },
+                        size: None,
+                        is_disk: true,
+                        optional,
+                        merge,
+                        status,
+                    });
+                } else if self.strict {
+                    let message = format!("disk \"{}\" in game \"{}\" has no sha1", name, game);
+                    self.anomaly(message);
+                }
+            }
+
+            // Releases of the game, e.g. <release name="..." region="EUR" language="en,fr"/>
+            Event::Empty(e) if e.name().as_ref() == b"release" && self.current_game.is_some() => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"language" {
+                        for language in attr.unescape_value()?.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+                            if !self.pending_info.languages.iter().any(|known| known.eq_ignore_ascii_case(language)) {
+                                self.pending_info.languages.push(language.to_string());
                             }
                         }
                     }
+                }
+            }
 
-                    if let Some(sha1_hash) = sha1 {
-                        let rom_entry = RomEntry {
-                            name,
-                            game: current_game.clone(),
-                            hashes: RomHashes { sha1: Some(sha1_hash.clone()), ..Default::default() },
-                            is_disk: true,
-                        };
-                        rom_db.entry(sha1_hash).or_insert_with(Vec::new).push(rom_entry);
+            // Devices (CPUs, sound chips, ...) the machine is built from; some have ROMs of their own
+            Event::Empty(e) if e.name().as_ref() == b"device_ref" && self.current_game.is_some() => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"name" {
+                        self.pending_devices.push(attr.unescape_value()?.to_string());
                     }
                 }
+            }
 
-                // Handle opening ROM tags (for non-self-closing format)
-                Event::Start(e) if e.name().as_ref() == b"rom" && in_game_tag => {
-                    in_rom_tag = true;
-                    current_rom_name.clear();
-                    current_rom_hashes = RomHashes {
-                        sha1: None,
-                        md5: None,
-                        crc: None,
-                    };
-
-                    for attr in e.attributes() {
-                        if let Ok(attr) = attr {
-                            match attr.key.as_ref() {
-                                b"name" => current_rom_name = attr.unescape_value()?.to_string(),
-                                b"crc" => current_rom_hashes.crc = Some(attr.unescape_value()?.to_lowercase()),
-                                b"md5" => current_rom_hashes.md5 = Some(attr.unescape_value()?.to_lowercase()),
-                                b"sha1" => current_rom_hashes.sha1 = Some(attr.unescape_value()?.to_lowercase()),
-                                _ => {}
-                            }
-                        }
-                    }
+            // Handle opening ROM tags (for non-self-closing format)
+            Event::Start(e) if e.name().as_ref() == b"rom" && self.current_game.is_some() => {
+                self.open_rom = self.read_rom_entry(&e)?;
+            }
+
+            // Handle closing ROM tags
+            Event::End(e) if e.name().as_ref() == b"rom" => {
+                if let Some(rom_entry) = self.open_rom.take() {
+                    self.pending_roms.push(rom_entry);
                 }
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
 
-                // Handle closing ROM tags
-                Event::End(e) if e.name().as_ref() == b"rom" && in_rom_tag => {
-                    in_rom_tag = false;
+    /// The metadata field `tag` holds where the parser is: in the header or in a game
+    fn field_for(&self, tag: &[u8]) -> Option<InfoField> {
+        if self.in_header {
+            InfoField::from_header_tag(tag)
+        } else if self.current_game.is_some() {
+            InfoField::from_tag(tag)
+        } else {
+            None
+        }
+    }
 
-                    let rom_entry = RomEntry {
-                        name: current_rom_name.clone(),
-                        game: current_game.clone(),
-                        hashes: current_rom_hashes.clone(),
-                        is_disk: false,
-                    };
+    /// The forcemerging, forcenodump, forcepacking and header directives of
+    /// the header's <clrmamepro> element; values clrmamepro doesn't know are ignored
+    fn read_clrmamepro_directives(&mut self, e: &BytesStart) -> Result<()> {
+        for attr in e.attributes().flatten() {
+            let value = attr.unescape_value()?;
+            match attr.key.as_ref() {
+                b"forcemerging" => self.header.force_merging = match value.as_ref() {
+                    "none" => Some(DatType::NonMerged),
+                    "split" => Some(DatType::Split),
+                    "full" | "merged" => Some(DatType::Merged),
+                    _ => None,
+                },
+                b"forcenodump" => self.header.nodumps_required = match value.as_ref() {
+                    "required" => Some(true),
+                    "obsolete" | "ignore" => Some(false),
+                    _ => None,
+                },
+                b"forcepacking" => self.header.zipped_sets = match value.as_ref() {
+                    "zip" => Some(true),
+                    "unzip" => Some(false),
+                    _ => None,
+                },
+                b"header" if !value.is_empty() => self.header.skipper = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 
-                    // Store by all available hash types
-                    if let Some(ref sha1) = current_rom_hashes.sha1 {
-                        rom_db.entry(sha1.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
-                    }
-                    if let Some(ref md5) = current_rom_hashes.md5 {
-                        rom_db.entry(md5.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
-                    }
-                    if let Some(ref crc) = current_rom_hashes.crc {
-                        rom_db.entry(crc.clone()).or_insert_with(Vec::new).push(rom_entry.clone());
+    /// Build a ROM entry for the current game from the attributes of a <rom>
+    /// element. None for the nameless ones software lists use to load more
+    /// of the previous ROM (`loadflag="continue"`, ...), which aren't files.
+    fn read_rom_entry(&mut self, e: &BytesStart) -> Result<Option<RomEntry>> {
+        let mut name = String::new();
+        let mut size = None;
+        let mut bad_size = None;
+        let mut status = RomStatus::Good;
+        let mut continues = false;
+        let mut optional = false;
+        let mut merge = None;
+        let mut hashes = RomHashes::default();
+
+        for attr in e.attributes().flatten() {
+            match attr.key.as_ref() {
+                b"name" => name = attr.unescape_value()?.to_string(),
+                b"size" => {
+                    let value = attr.unescape_value()?;
+                    size = value.parse().ok();
+                    if size.is_none() {
+                        bad_size = Some(value.to_string());
                     }
                 }
-
-                Event::Eof => break,
+                b"crc" => hashes.crc = Some(attr.unescape_value()?.to_lowercase()),
+                b"md5" => hashes.md5 = Some(attr.unescape_value()?.to_lowercase()),
+                b"sha1" => hashes.sha1 = Some(attr.unescape_value()?.to_lowercase()),
+                b"sha256" => hashes.sha256 = Some(attr.unescape_value()?.to_lowercase()),
+                b"status" => status = rom_status(&attr.unescape_value()?),
+                b"loadflag" => continues = true,
+                b"optional" => optional = attr.unescape_value()? == "yes",
+                b"merge" => merge = Some(attr.unescape_value()?.to_string()),
                 _ => {}
             }
-            buf.clear();
         }
 
-        if show_progress {
-            println!("Parsed {} games with {} unique ROM hashes", all_games.len(), rom_db.len());
+        if name.is_empty() && continues {
+            return Ok(None);
+        }
+
+        let game = self.current_game.clone().unwrap_or_default();
+        if self.strict {
+            if let Some(value) = bad_size {
+                self.anomaly(format!("rom \"{}\" in game \"{}\" has unparsable size \"{}\"", name, game, value));
+            }
+            if status != RomStatus::NoDump && hashes.is_empty() {
+                self.anomaly(format!("rom \"{}\" in game \"{}\" has no hashes", name, game));
+            }
+        }
+
+        Ok(Some(RomEntry {
+            name,
+            game,
+            hashes,
+            size,
+            is_disk: false,
+            // A ROM no one has dumped can't be had, unless the DAT says it's still needed
+            optional: optional || (status == RomStatus::NoDump && self.header.nodumps_required != Some(true)),
+            merge,
+            status,
+        }))
+    }
+
+    fn anomaly(&mut self, message: String) {
+        self.anomalies.push((self.position, message));
+    }
+
+    /// Commit the current game, or drop it if an error occurred inside it
+    fn finish_game(&mut self) {
+        self.open_rom = None;
+        self.info_field = None;
+        let info = std::mem::take(&mut self.pending_info);
+        let roms = std::mem::take(&mut self.pending_roms);
+        let mut devices = std::mem::take(&mut self.pending_devices);
+        let broken = std::mem::take(&mut self.game_broken);
+        let is_machine = std::mem::take(&mut self.current_is_machine);
+        let (clone_of, rom_of) = std::mem::take(&mut self.pending_parents);
+        let Some(game) = self.current_game.take() else { return };
+        // Most of a -listxml dump is devices and systems with nothing to dump
+        if broken || (is_machine && roms.is_empty()) {
+            return;
+        }
+        if filter::has_excluded_tag(&game, &self.excluded_tags) {
+            self.excluded += 1;
+            return;
+        }
+
+        for rom_entry in roms {
+            add_rom(&mut self.rom_db, &mut self.game_roms, rom_entry);
         }
+        if !info.is_empty() {
+            self.game_info.insert(game.clone(), info);
+        }
+        // A device is referenced once per instance, e.g. for each of two CPUs
+        devices.sort();
+        devices.dedup();
+        if !devices.is_empty() {
+            self.device_refs.insert(game.clone(), devices);
+        }
+        if let Some(parent) = clone_of {
+            self.parent_clone_map.insert(game.clone(), parent);
+        }
+        if let Some(set) = rom_of {
+            self.rom_of.insert(game.clone(), set);
+        }
+        self.all_games.insert(game);
+    }
 
-        Ok(ParsedDat {
-            rom_db,
-            all_games,
-        })
+    /// Keep only the device references to sets with ROMs, which are the
+    /// ones that have to be present; the rest weren't kept as games
+    fn resolve_device_refs(&mut self) {
+        let all_games = &self.all_games;
+        self.device_refs.retain(|_, devices| {
+            devices.retain(|device| all_games.contains(device));
+            !devices.is_empty()
+        });
     }
-}
\ No newline at end of file
+}
+
+/// Record a ROM under its game and under every hash it carries
+fn add_rom(rom_db: &mut RomDb, game_roms: &mut GameRoms, rom_entry: RomEntry) {
+    for hash in rom_entry.hashes.iter() {
+        rom_db.entry(hash.clone()).or_default().push(rom_entry.clone());
+    }
+    game_roms.entry(rom_entry.game.clone()).or_default().push(rom_entry);
+}
+
+/// 1-based line number of a byte offset
+fn line_number(data: &[u8], position: u64) -> usize {
+    let end = (position as usize).min(data.len());
+    data[..end].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+/// The `status=` of a <rom> or <disk>; anything else is a good dump
+fn rom_status(value: &str) -> RomStatus {
+    match value {
+        "baddump" => RomStatus::BadDump,
+        "nodump" => RomStatus::NoDump,
+        _ => RomStatus::Good,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_self_closing_and_open_rom_tags() {
+        let dat = br#"<?xml version="1.0"?>
+<datafile>
+    <game name="Game A">
+        <rom name="a.bin" size="4" crc="ABCD1234" sha1="0123456789abcdef0123456789abcdef01234567"/>
+    </game>
+    <game name="Game B">
+        <rom name="b.bin" size="8" crc="11112222" sha256="B5BB9D8014A0F9B1D61E21E796D78DCCDF1352F23CD32812F4850B878AE4944C"></rom>
+        <disk name="b-disk" sha1="89abcdef0123456789abcdef0123456789abcdef" optional="yes"/>
+    </game>
+</datafile>"#;
+
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+
+        assert_eq!(parsed.all_games.len(), 2);
+        assert_eq!(parsed.game_roms["Game A"][0].size, Some(4));
+        assert_eq!(parsed.rom_db["abcd1234"][0].name, "a.bin");
+        assert_eq!(parsed.rom_db["b5bb9d8014a0f9b1d61e21e796d78dccdf1352f23cd32812f4850b878ae4944c"][0].name, "b.bin");
+        assert_eq!(parsed.game_roms["Game B"].len(), 2);
+        assert!(parsed.game_roms["Game B"][1].is_disk);
+        assert!(parsed.game_roms["Game B"][1].optional);
+        assert!(!parsed.game_roms["Game B"][0].optional);
+    }
+
+    #[test]
+    fn test_parse_mame_listxml_machines() {
+        let dat = br#"<?xml version="1.0"?>
+<mame build="0.262">
+    <machine name="pacman" sourcefile="pacman/pacman.cpp">
+        <description>Pac-Man (Midway)</description>
+        <rom name="pacman.6e" size="4096" crc="C1E6AB10" sha1="e87e059c5be45753f7e9f33dff851f16d6751181"/>
+        <device_ref name="z80"/>
+        <device_ref name="namco"/>
+        <device_ref name="namco"/>
+    </machine>
+    <machine name="z80" isdevice="yes" runnable="no">
+        <description>Zilog Z80</description>
+    </machine>
+    <machine name="namco" isdevice="yes" runnable="no">
+        <rom name="namco.prom" size="256" crc="A9CC86BF"/>
+    </machine>
+    <machine name="gdrom" sourcefile="naomi.cpp">
+        <disk name="gdl-0001" sha1="89abcdef0123456789abcdef0123456789abcdef"/>
+    </machine>
+</mame>"#;
+
+        let parsed = parse_dat_bytes(dat, "mame".to_string(), ParseOptions::default()).unwrap();
+
+        assert_eq!(parsed.all_games, HashSet::from(["pacman".to_string(), "namco".to_string(), "gdrom".to_string()]));
+        assert_eq!(parsed.rom_db["c1e6ab10"][0].game, "pacman");
+        assert!(parsed.game_roms["gdrom"][0].is_disk);
+        assert_eq!(parsed.display_name("pacman"), "pacman - Pac-Man (Midway)");
+        assert_eq!(parsed.device_refs["pacman"], ["namco"]);
+        assert_eq!(parsed.device_refs.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_software_list() {
+        let dat = br#"<?xml version="1.0"?>
+<!DOCTYPE softwarelist SYSTEM "softwarelist.dtd">
+<softwarelist name="nes" description="Nintendo Entertainment System cartridges">
+    <software name="smb">
+        <description>Super Mario Bros. (World)</description>
+        <year>1985</year>
+        <publisher>Nintendo</publisher>
+        <part name="cart" interface="nes_cart">
+            <dataarea name="prg" size="32768">
+                <rom name="smb.prg" size="32768" crc="5CF548D3" offset="00000"/>
+            </dataarea>
+            <dataarea name="chr" size="8192">
+                <rom name="smb.chr" size="4096" crc="867B51AD" offset="00000"/>
+                <rom size="4096" offset="0x1000" loadflag="reload"/>
+            </dataarea>
+        </part>
+    </software>
+    <software name="smbj" cloneof="smb">
+        <part name="flop" interface="fds_flop">
+            <diskarea name="flop">
+                <disk name="smbj" sha1="0123456789abcdef0123456789abcdef01234567"/>
+            </diskarea>
+        </part>
+    </software>
+</softwarelist>"#;
+
+        let options = ParseOptions { strict: true, ..Default::default() };
+        let parsed = parse_dat_bytes(dat, "nes".to_string(), options).unwrap();
+        assert_eq!(parsed.db_namespace(), "nes");
+        assert_eq!(parsed.header.description.as_deref(), Some("Nintendo Entertainment System cartridges"));
+        assert_eq!(parsed.game_roms["smb"].len(), 2);
+        assert!(parsed.game_roms["smbj"][0].is_disk);
+        assert_eq!(parsed.parent_clone_map["smbj"], "smb");
+        assert_eq!(parsed.display_name("smb"), "smb - Super Mario Bros. (World) (1985, Nintendo)");
+    }
+
+    #[test]
+    fn test_clrmamepro_header_directives() {
+        let dat = br#"<datafile>
+    <header>
+        <name>Arcade</name>
+        <clrmamepro forcemerging="full" forcenodump="obsolete" forcepacking="zip" header="No-Intro_NES.xml"/>
+    </header>
+    <game name="g">
+        <rom name="dumped.bin" size="4" crc="ABCD1234"/>
+        <rom name="lost.bin" size="4" status="nodump"/>
+    </game>
+</datafile>"#;
+
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+        assert_eq!(parsed.header.force_merging, Some(DatType::Merged));
+        assert_eq!(parsed.header.nodumps_required, Some(false));
+        assert_eq!(parsed.header.zipped_sets, Some(true));
+        assert_eq!(parsed.header.skipper.as_deref(), Some("No-Intro_NES.xml"));
+        assert!(!parsed.game_roms["g"][0].optional);
+        assert!(parsed.game_roms["g"][1].optional);
+
+        let required = String::from_utf8_lossy(dat).replace("obsolete", "required");
+        let parsed = parse_dat_bytes(required.as_bytes(), "test".to_string(), ParseOptions::default()).unwrap();
+        assert!(!parsed.game_roms["g"][1].optional);
+
+        let plain = parse_dat_bytes(b"<datafile><header><name>N</name></header></datafile>", "test".to_string(), ParseOptions::default()).unwrap();
+        assert_eq!(plain.header.force_merging, None);
+    }
+
+    #[test]
+    fn test_parse_game_metadata() {
+        let dat = br#"<?xml version="1.0"?>
+<datafile>
+    <header>
+        <name>Arcade</name><description>Arcade set</description>
+        <version>0.262</version><date>2024-01-31</date><author>Team &amp; friends</author>
+    </header>
+    <game name="sf2">
+        <description>Street Fighter II: The World Warrior (World 910522)</description>
+        <year>1991</year>
+        <manufacturer>Capcom</manufacturer>
+        <rom name="sf2.bin" size="4" crc="ABCD1234"/>
+    </game>
+    <game name="tomjerry">
+        <description>Tom &amp; Jerry</description>
+        <manufacturer>Hi Tech Expressions</manufacturer>
+        <release name="Tom &amp; Jerry" region="USA" language="en"/>
+        <release name="Tom &amp; Jerry" region="EUR" language="en,fr"/>
+        <rom name="tj.bin" size="4" crc="11112222"/>
+    </game>
+    <game name="Plain Game">
+        <description>Plain Game</description>
+        <rom name="p.bin" size="4" crc="33334444"/>
+    </game>
+</datafile>"#;
+
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).unwrap();
+
+        assert_eq!(parsed.header.name.as_deref(), Some("Arcade"));
+        assert_eq!(parsed.header.description.as_deref(), Some("Arcade set"));
+        assert_eq!(parsed.header.summary().as_deref(), Some("Arcade 0.262 (2024-01-31) [Team & friends]"));
+        assert!(parsed.game_info.values().all(|info| info.description.as_deref() != Some("Arcade set")));
+        assert_eq!(parsed.game_info["sf2"].year.as_deref(), Some("1991"));
+        assert_eq!(parsed.display_name("sf2"), "sf2 - Street Fighter II: The World Warrior (World 910522) (1991, Capcom)");
+        assert_eq!(parsed.display_name("tomjerry"), "tomjerry - Tom & Jerry (Hi Tech Expressions)");
+        assert_eq!(parsed.game_info["tomjerry"].languages, ["en", "fr"]);
+        assert_eq!(parsed.display_name("Plain Game"), "Plain Game");
+    }
+
+    #[test]
+    fn test_recovery_skips_broken_game() {
+        let dat = br#"<datafile>
+    <game name="Good 1"><rom name="1.bin" crc="00000001"/></game>
+    <game name="Broken"><rom name="&bogus;.bin" crc="00000002"/></game>
+    <game name="Good 2"><rom name="2.bin" crc="00000003"/></game>
+</datafile>"#;
+
+        assert!(parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).is_err());
+
+        let parsed = parse_dat_bytes(dat, "test".to_string(), ParseOptions { recover: true, ..Default::default() }).unwrap();
+        assert_eq!(parsed.all_games.len(), 2);
+        assert!(!parsed.all_games.contains("Broken"));
+        assert!(!parsed.rom_db.contains_key("00000002"));
+        assert_eq!(parsed.issues.len(), 1);
+        assert_eq!(parsed.issues[0].line, 3);
+    }
+
+    #[test]
+    fn test_strict_mode_reports_anomalies() {
+        let dat = br#"<datafile>
+    <game name="Dup"><rom name="1.bin" size="4" crc="00000001"/></game>
+    <game name="Dup"><rom name="2.bin" size="abc" crc="00000002"/></game>
+    <game name="No Hash"><rom name="3.bin" size="4"/></game>
+</datafile>"#;
+
+        assert!(parse_dat_bytes(dat, "test".to_string(), ParseOptions::default()).is_ok());
+
+        let strict = ParseOptions { recover: true, strict: true, ..Default::default() };
+        let Err(RomAuditError::ParseError(message)) = parse_dat_bytes(dat, "test".to_string(), strict) else {
+            panic!("strict mode should fail");
+        };
+        assert!(message.contains("3 DAT anomalies"));
+        assert!(message.contains("line 3: duplicate game name \"Dup\""));
+        assert!(message.contains("line 3: rom \"2.bin\""));
+        assert!(message.contains("line 4: rom \"3.bin\" in game \"No Hash\" has no hashes"));
+    }
+}
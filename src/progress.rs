@@ -0,0 +1,167 @@
+// src/progress.rs - Progress bars, or an NDJSON progress stream for wrappers
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::{Result, RomAuditError};
+
+/// Minimum time between two progress events of the same phase
+const EVENT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where NDJSON progress goes when bars are replaced by a stream
+static STREAM: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+
+/// Route progress to the stream selected in the config: `--progress-json`
+/// writes to stderr, `--progress-fd=N` to an inherited file descriptor
+pub fn init(config: &Config) -> Result<()> {
+    let stream: Box<dyn Write + Send> = match config.progress_fd {
+        Some(fd) => Box::new(open_fd(fd)?),
+        None if config.progress_json => Box::new(std::io::stderr()),
+        None => return Ok(()),
+    };
+    set_stream(stream);
+    Ok(())
+}
+
+/// Send progress to `stream` for the rest of the process. Only the first
+/// stream set is used.
+pub fn set_stream(stream: Box<dyn Write + Send>) -> bool {
+    STREAM.set(Mutex::new(stream)).is_ok()
+}
+
+#[cfg(unix)]
+fn open_fd(fd: i32) -> Result<File> {
+    use std::os::fd::FromRawFd;
+    if fd < 3 {
+        return Err(RomAuditError::ConfigError(format!("--progress-fd={} would take over stdin/stdout/stderr", fd)));
+    }
+    // SAFETY: the descriptor was handed to us by the parent process and
+    // nothing else in this process owns it
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+fn open_fd(_fd: i32) -> Result<File> {
+    Err(RomAuditError::ConfigError("--progress-fd is only supported on Unix; use --progress-json".to_string()))
+}
+
+/// One line of the progress stream
+#[derive(Serialize)]
+struct ProgressEvent<'a> {
+    phase: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_file: Option<&'a str>,
+    position: u64,
+    total: u64,
+    /// "bytes" when position and total count bytes rather than items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<&'static str>,
+    percent: f64,
+    done: bool,
+}
+
+/// An indicatif bar, or a hidden one that reports to the progress stream
+pub struct Progress {
+    bar: ProgressBar,
+    phase: &'static str,
+    unit: Option<&'static str>,
+    streaming: bool,
+    current_file: Mutex<Option<String>>,
+    last_event: Mutex<Option<Instant>>,
+}
+
+impl Progress {
+    pub fn new(phase: &'static str, total: u64, template: &str) -> Self {
+        let streaming = STREAM.get().is_some();
+        let bar = if streaming { ProgressBar::hidden() } else { ProgressBar::new(total) };
+        bar.set_length(total);
+        bar.set_style(ProgressStyle::with_template(template).unwrap());
+        Progress { bar, phase, unit: None, streaming, current_file: Mutex::new(None), last_event: Mutex::new(None) }
+    }
+    
+    /// A bar that counts bytes, for templates showing `{bytes}` and throughput
+    pub fn bytes(phase: &'static str, total: u64, template: &str) -> Self {
+        Progress { unit: Some("bytes"), ..Self::new(phase, total, template) }
+    }
+    
+    /// Show "<action>: <file name>", shortening long names on the bar only
+    pub fn set_current_file(&self, action: &str, filename: &str) {
+        *self.current_file.lock().unwrap() = Some(filename.to_string());
+        let shown = match filename.char_indices().rev().nth(36) {
+            Some((start, _)) if filename.chars().count() > 40 => format!("...{}", &filename[start..]),
+            _ => filename.to_string(),
+        };
+        self.set_message(format!("{}: {}", action, shown));
+    }
+
+    pub fn set_message(&self, message: impl Into<Cow<'static, str>>) {
+        self.bar.set_message(message);
+        self.emit(false, false);
+    }
+
+    pub fn set_position(&self, position: u64) {
+        self.bar.set_position(position);
+        self.emit(false, false);
+    }
+
+    pub fn inc(&self, delta: u64) {
+        self.bar.inc(delta);
+        self.emit(false, false);
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish();
+        *self.current_file.lock().unwrap() = None;
+        self.emit(true, true);
+    }
+
+    pub fn finish_with_message(&self, message: impl Into<Cow<'static, str>>) {
+        self.bar.finish_with_message(message);
+        *self.current_file.lock().unwrap() = None;
+        self.emit(true, true);
+    }
+
+    fn emit(&self, done: bool, force: bool) {
+        let Some(stream) = self.streaming.then(|| STREAM.get()).flatten() else {
+            return;
+        };
+        {
+            let mut last_event = self.last_event.lock().unwrap();
+            if !force && last_event.is_some_and(|last| last.elapsed() < EVENT_INTERVAL) {
+                return;
+            }
+            *last_event = Some(Instant::now());
+        }
+
+        let position = self.bar.position();
+        let total = self.bar.length().unwrap_or(0);
+        let message = self.bar.message();
+        let current_file = self.current_file.lock().unwrap().clone();
+        let event = ProgressEvent {
+            phase: self.phase,
+            message: &message,
+            current_file: current_file.as_deref(),
+            position,
+            total,
+            unit: self.unit,
+            percent: if total == 0 { 100.0 } else { position as f64 * 100.0 / total as f64 },
+            done,
+        };
+
+        // Progress is best-effort: a closed pipe must not stop the audit
+        let mut stream = stream.lock().unwrap();
+        if serde_json::to_writer(&mut *stream, &event).is_ok() {
+            let _ = stream.write_all(b"\n");
+            let _ = stream.flush();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// src/scanner/chd.rs - SHA1 recorded in the header of MAME CHD files
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Result;
+
+const CHD_MAGIC: &[u8] = b"MComprHD";
+
+/// Offset of the SHA1 DATs list for a disk (data and metadata together) in
+/// each header version: (version, header length, offset)
+const SHA1_OFFSETS: &[(u32, usize, usize)] = &[
+    (3, 120, 80),
+    (4, 108, 48),
+    (5, 124, 84),
+];
+
+/// The SHA1 a CHD's header records for its contents, which is what `<disk
+/// sha1>` in a DAT refers to. None if the file isn't a CHD, or is from a
+/// version without a SHA1 (v1 and v2 only have MD5).
+pub fn chd_sha1(path: &Path) -> Result<Option<String>> {
+    let mut header = Vec::with_capacity(124);
+    File::open(path)?.take(124).read_to_end(&mut header)?;
+    Ok(header_sha1(&header))
+}
+
+fn header_sha1(header: &[u8]) -> Option<String> {
+    if !header.starts_with(CHD_MAGIC) {
+        return None;
+    }
+    let length = u32::from_be_bytes(header.get(8..12)?.try_into().ok()?) as usize;
+    let version = u32::from_be_bytes(header.get(12..16)?.try_into().ok()?);
+    let &(_, expected_length, offset) = SHA1_OFFSETS.iter().find(|(v, _, _)| *v == version)?;
+    if length != expected_length {
+        return None;
+    }
+    header.get(offset..offset + 20).map(hex::encode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_sha1_by_version() {
+        let sha1: Vec<u8> = (1..=20).collect();
+        let header = |version: u32, length: u32, offset: usize| {
+            let mut header = vec![0u8; length as usize];
+            header[..8].copy_from_slice(CHD_MAGIC);
+            header[8..12].copy_from_slice(&length.to_be_bytes());
+            header[12..16].copy_from_slice(&version.to_be_bytes());
+            header[offset..offset + 20].copy_from_slice(&sha1);
+            header
+        };
+        let expected = Some(hex::encode(&sha1));
+        assert_eq!(header_sha1(&header(5, 124, 84)), expected);
+        assert_eq!(header_sha1(&header(4, 108, 48)), expected);
+        assert_eq!(header_sha1(&header(3, 120, 80)), expected);
+        assert_eq!(header_sha1(&header(2, 80, 44)), None);
+        assert_eq!(header_sha1(b"not a chd"), None);
+    }
+}
@@ -0,0 +1,165 @@
+// src/scanner/classify.rs - Guess what system an unknown file is for
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::types::FileHash;
+use super::collector::is_donor_path;
+
+/// How much of a file the header checks look at: enough for a HiROM SNES
+/// header behind a 512-byte copier header
+const PROBE_LEN: usize = 0x10000 + 0x200;
+
+/// The system a file most likely belongs to, and what gave it away
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guess {
+    pub system: &'static str,
+    pub reason: String,
+}
+
+/// A file that matched nothing in the DAT, with its guessed system
+#[derive(Debug)]
+pub struct UnknownFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub guess: Option<Guess>,
+}
+
+/// Nintendo logo as stored in Game Boy cartridge headers at 0x104 (first bytes)
+const GB_LOGO: [u8; 8] = [0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B];
+
+/// Checks on the file's contents, strongest first: (offset, magic, system)
+const MAGIC: &[(usize, &[u8], &str)] = &[
+    (0, b"NES\x1a", "Nintendo Entertainment System"),
+    (0, b"FDS\x1a", "Famicom Disk System"),
+    (0, b"LYNX", "Atari Lynx"),
+    (1, b"ATARI7800", "Atari 7800"),
+    (0, &[0x80, 0x37, 0x12, 0x40], "Nintendo 64"),
+    (0, &[0x37, 0x80, 0x40, 0x12], "Nintendo 64"),
+    (0, &[0x40, 0x12, 0x37, 0x80], "Nintendo 64"),
+    (0x104, &GB_LOGO, "Nintendo Game Boy"),
+    (0x100, b"SEGA", "Sega Mega Drive / Genesis"),
+    (0x7FF0, b"TMR SEGA", "Sega Master System / Game Gear"),
+    (0x3FF0, b"TMR SEGA", "Sega Master System / Game Gear"),
+    (0x1FF0, b"TMR SEGA", "Sega Master System / Game Gear"),
+    (0x100, b"NCSD", "Nintendo 3DS"),
+    (0x1C, &[0xC2, 0x33, 0x9F, 0x3D], "Nintendo GameCube"),
+    (0x18, &[0x5D, 0x1C, 0x9E, 0xA3], "Nintendo Wii"),
+    (0, b"MComprHD", "CHD disc or hard disk image"),
+    (0x8001, b"CD001", "CD/DVD image (ISO9660)"),
+    (0x9319, b"CD001", "CD image (raw 2352-byte sectors)"),
+    (0, b"PK\x03\x04", "Archive"),
+    (0, b"7z\xBC\xAF\x27\x1C", "Archive"),
+    (0, b"Rar!", "Archive"),
+    (0, b"\x89PNG", "Not a ROM (image, document or text)"),
+    (0, b"\xFF\xD8\xFF", "Not a ROM (image, document or text)"),
+    (0, b"%PDF", "Not a ROM (image, document or text)"),
+];
+
+/// Systems by file extension, when the contents give nothing away
+const EXTENSIONS: &[(&[&str], &str)] = &[
+    (&["nes", "unf", "unif"], "Nintendo Entertainment System"),
+    (&["fds"], "Famicom Disk System"),
+    (&["sfc", "smc", "swc", "fig"], "Super Nintendo"),
+    (&["gb", "gbc", "sgb"], "Nintendo Game Boy"),
+    (&["gba"], "Nintendo Game Boy Advance"),
+    (&["nds", "dsi"], "Nintendo DS"),
+    (&["3ds", "cia"], "Nintendo 3DS"),
+    (&["n64", "z64", "v64"], "Nintendo 64"),
+    (&["md", "gen", "smd", "bin68k"], "Sega Mega Drive / Genesis"),
+    (&["32x"], "Sega 32X"),
+    (&["sms", "gg", "sg"], "Sega Master System / Game Gear"),
+    (&["pce", "sgx"], "NEC PC Engine / TurboGrafx-16"),
+    (&["a26"], "Atari 2600"),
+    (&["a78"], "Atari 7800"),
+    (&["lnx"], "Atari Lynx"),
+    (&["ws", "wsc"], "Bandai WonderSwan"),
+    (&["ngp", "ngc"], "SNK Neo Geo Pocket"),
+    (&["iso", "cue", "bin", "img", "chd", "cso", "rvz", "wbfs"], "CD/DVD image"),
+    (&["zip", "7z", "rar", "gz", "xz"], "Archive"),
+    (&["txt", "nfo", "diz", "jpg", "jpeg", "png", "pdf", "htm", "html", "url", "sfv", "md5", "sha1"], "Not a ROM (image, document or text)"),
+];
+
+/// Classify the files about to be set aside as unknown, while they are
+/// still where they were found
+pub fn classify_unknown(file_hashes: &[FileHash], config: &Config) -> Vec<UnknownFile> {
+    file_hashes.iter()
+        .filter(|f| f.matching_entries.is_empty() && !is_donor_path(&f.path, config))
+        .map(|f| UnknownFile {
+            path: f.path.clone(),
+            size: f.size,
+            // An unreadable file is simply left unclassified
+            guess: classify(&f.path, f.size).ok().flatten(),
+        })
+        .collect()
+}
+
+/// Guess the system of a file that matched nothing in the DAT
+pub fn classify(path: &Path, size: u64) -> Result<Option<Guess>> {
+    let mut head = Vec::with_capacity(PROBE_LEN.min(size as usize));
+    File::open(path)?.take(PROBE_LEN as u64).read_to_end(&mut head)?;
+    Ok(classify_bytes(&head, size, path.extension().and_then(|e| e.to_str())))
+}
+
+fn classify_bytes(head: &[u8], size: u64, extension: Option<&str>) -> Option<Guess> {
+    if let Some((offset, _, system)) = MAGIC.iter().find(|(offset, magic, _)| head.get(*offset..offset + magic.len()) == Some(magic)) {
+        return Some(Guess { system, reason: format!("header at 0x{:X}", offset) });
+    }
+    if let Some(offset) = snes_header(head, size) {
+        return Some(Guess { system: "Super Nintendo", reason: format!("header checksum at 0x{:X}", offset) });
+    }
+    // GBA and DS carts repeat the Nintendo logo after a branch instruction
+    if head.get(0xB2) == Some(&0x96) && head.get(3) == Some(&0xEA) {
+        return Some(Guess { system: "Nintendo Game Boy Advance", reason: "header at 0xB2".to_string() });
+    }
+
+    if let Some(extension) = extension.map(str::to_lowercase)
+        && let Some((_, system)) = EXTENSIONS.iter().find(|(extensions, _)| extensions.contains(&extension.as_str()))
+    {
+        return Some(Guess { system, reason: format!(".{} extension", extension) });
+    }
+
+    // Cartridge dumps are almost always a power of two between 8 KiB and 64 MiB
+    if size.is_power_of_two() && (8 << 10..=64 << 20).contains(&size) {
+        return Some(Guess { system: "Cartridge ROM (system unknown)", reason: format!("{} bytes, a power of two", size) });
+    }
+    None
+}
+
+/// Offset of a LoROM or HiROM header whose checksum and complement add up,
+/// allowing for a 512-byte copier header
+fn snes_header(head: &[u8], size: u64) -> Option<usize> {
+    let copier = if size % 1024 == 512 { 0x200 } else { 0 };
+    [0x7FC0, 0xFFC0].into_iter()
+        .map(|base| base + copier)
+        .find(|&base| {
+            let Some(header) = head.get(base..base + 0x20) else { return false };
+            let complement = u16::from_le_bytes([header[0x1C], header[0x1D]]);
+            let checksum = u16::from_le_bytes([header[0x1E], header[0x1F]]);
+            complement ^ checksum == 0xFFFF && checksum != 0 && checksum != 0xFFFF
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_header_extension_and_size() {
+        let mut genesis = vec![0u8; 0x200];
+        genesis[0x100..0x104].copy_from_slice(b"SEGA");
+        assert_eq!(classify_bytes(&genesis, 0x200, Some("bin")).unwrap().system, "Sega Mega Drive / Genesis");
+
+        let mut snes = vec![0u8; 0x8000 + 0x200];
+        snes[0x7FC0 + 0x200 + 0x1C..0x7FC0 + 0x200 + 0x20].copy_from_slice(&[0x34, 0x12, 0xCB, 0xED]);
+        let guess = classify_bytes(&snes, 0x8200, None).unwrap();
+        assert_eq!((guess.system, guess.reason.as_str()), ("Super Nintendo", "header checksum at 0x81C0"));
+
+        assert_eq!(classify_bytes(&[0; 16], 16, Some("GBA")).unwrap().system, "Nintendo Game Boy Advance");
+        assert_eq!(classify_bytes(&[0; 16], 1 << 20, None).unwrap().system, "Cartridge ROM (system unknown)");
+        assert_eq!(classify_bytes(&[0; 16], 1000, Some("xyz")), None);
+    }
+}
@@ -5,27 +5,79 @@ use std::path::{Path, PathBuf};
 use crate::config::Config;
 use crate::error::{Result, RomAuditError};
 
-/// Recursively collect all files to be processed
-pub fn collect_files_recursively(dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+/// A directory or file that couldn't be read and was skipped
+#[derive(Debug, Clone)]
+pub struct Unreadable {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Recursively collect all files to be processed. Subdirectories that can't
+/// be read are skipped and added to `unreadable`; only an unreadable `dir`
+/// itself is an error.
+pub fn collect_files_recursively(dir: &Path, config: &Config, unreadable: &mut Vec<Unreadable>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files_recursive_impl(dir, config, &mut files, unreadable)?;
+    files.sort_by_key(|p| scan_order_key(p, config));
+    Ok(files)
+}
+
+/// Scan order: donor files from earlier runs come last, so a copy found
+/// anywhere else always wins
+pub fn scan_order_key(path: &Path, config: &Config) -> (bool, String, PathBuf) {
+    let (name, path) = path_sort_key(path);
+    (is_donor_path(&path, config), name, path)
+}
+
+/// Case-insensitive path ordering, with the exact path as a tie-breaker so
+/// files differing only in case always come out in the same order
+pub fn path_sort_key(path: &Path) -> (String, PathBuf) {
+    (path.to_string_lossy().to_lowercase(), path.to_path_buf())
+}
+
+/// Collect every file below a directory, without skipping generated directories
+pub fn collect_tree(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
-    collect_files_recursive_impl(dir, config, &mut files)?;
-    files.sort_by_key(|p| p.to_string_lossy().to_lowercase());
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort_by_key(|p| path_sort_key(p));
     Ok(files)
 }
 
-fn collect_files_recursive_impl(dir: &Path, config: &Config, files: &mut Vec<PathBuf>) -> Result<()> {
+fn collect_files_recursive_impl(
+    dir: &Path,
+    config: &Config,
+    files: &mut Vec<PathBuf>,
+    unreadable: &mut Vec<Unreadable>,
+) -> std::io::Result<()> {
     for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                unreadable.push(Unreadable { path: dir.to_path_buf(), error: e.to_string() });
+                continue;
+            }
+        };
 
         if path.is_file() {
-            if should_process_file(&path, config)? {
+            if should_process_file(&path, config).unwrap_or(false) {
                 files.push(path);
             }
-        } else if path.is_dir() {
-            if !is_generated_directory(&path, config) {
-                collect_files_recursive_impl(&path, config, files)?;
-            }
+        } else if path.is_dir()
+            && !is_generated_directory(&path, config)
+            && (config.use_donors || !is_donor_path(&path, config))
+            && let Err(e) = collect_files_recursive_impl(&path, config, files, unreadable)
+        {
+            unreadable.push(Unreadable { path, error: e.to_string() });
         }
     }
     Ok(())
@@ -39,19 +91,31 @@ fn should_process_file(path: &Path, config: &Config) -> Result<bool> {
 
     // Skip DAT files ONLY in the root directory (not in ROM folders)
     // Some ROMs have .dat extension!
-    if let Some(parent) = path.parent() {
-        if parent == Path::new(".") {
-            // Only skip DAT files in root directory
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                if ext.eq_ignore_ascii_case("dat") {
-                    return Ok(false);
-                }
-            }
+    if let Some(parent) = path.parent()
+        && parent == Path::new(&config.scan_dir)
+    {
+        // Only skip DAT files in root directory, compressed and XML ones included
+        if crate::parser::select::is_dat(path) {
+            return Ok(false);
+        }
+        // Nor the DAT's checksum companions
+        if crate::parser::checksum::is_companion_file(path) {
+            return Ok(false);
+        }
+        // Nor MAME's catver.ini
+        let catver = config.catver.as_deref().unwrap_or(crate::parser::catver::CATVER_FILE);
+        if path.file_name() == Path::new(catver).file_name() {
+            return Ok(false);
         }
     }
 
-    // Skip database and temp files
-    if file_name == config.db_file || file_name.ends_with(".tmp") {
+    // Skip database, tool state and temp files
+    if crate::database::is_database_file(&file_name, &config.db_file)
+        || is_state_file(&file_name, crate::cache::HashCache::CACHE_FILE)
+        || is_state_file(&file_name, super::incremental::IncrementalScanState::STATE_FILE)
+        || is_state_file(&file_name, super::scrub::ScrubState::STATE_FILE)
+        || file_name.ends_with(".tmp")
+    {
         return Ok(false);
     }
 
@@ -63,16 +127,33 @@ fn should_process_file(path: &Path, config: &Config) -> Result<bool> {
     Ok(true)
 }
 
+/// Whether a file name is one of our state files, shared or per-DAT:
+/// ".romaudit_cache.bin" or ".romaudit_cache.<DAT>.bin"
+fn is_state_file(file_name: &str, state_file: &str) -> bool {
+    let Some((stem, extension)) = state_file.rsplit_once('.') else {
+        return file_name == state_file;
+    };
+    file_name == state_file
+        || (file_name.starts_with(&format!("{}.", stem)) && file_name.ends_with(&format!(".{}", extension)))
+}
+
 /// Check if a path is within a generated directory
 pub fn is_generated_directory(path: &Path, config: &Config) -> bool {
     let Ok(current_dir) = std::env::current_dir() else { return false };
     
-    let generated_dirs = [
+    let mut generated_dirs = vec![
         current_dir.join(&config.rom_dir),
         current_dir.join(&config.logs_dir),
-        // Note: duplicate and unknown dirs are handled at a higher level now
-        // and created inside the execution path, so we don't need to check them here.
+        current_dir.join(&config.probable_dir),
+        // Duplicate and unknown dirs are numbered; see is_donor_path
     ];
+    // A state directory that holds the scanned files, as a pack audit uses
+    // when there is no --state-dir, is just where the state files are
+    if let Some(state_dir) = &config.state_dir
+        && !current_dir.join(&config.scan_dir).starts_with(current_dir.join(state_dir))
+    {
+        generated_dirs.push(current_dir.join(state_dir));
+    }
 
     // Get the absolute path of the file/directory being checked
     let Ok(abs_path) = path.canonicalize() else { return false };
@@ -82,4 +163,36 @@ pub fn is_generated_directory(path: &Path, config: &Config) -> bool {
         let Ok(abs_gen_dir) = gen_dir.canonicalize() else { return false };
         abs_path.starts_with(abs_gen_dir)
     })
-}
\ No newline at end of file
+}
+
+/// Check if a path is in one of the numbered duplicate or unknown folders
+/// that earlier runs created next to the scanned files
+pub fn is_donor_path(path: &Path, config: &Config) -> bool {
+    let Some(std::path::Component::Normal(top)) = path.strip_prefix(&config.scan_dir).unwrap_or(path).components().next() else {
+        return false;
+    };
+    let top = top.to_string_lossy();
+    [&config.duplicate_prefix, &config.unknown_prefix].iter().any(|prefix| {
+        top.strip_prefix(prefix.as_str())
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_donor_files_sort_after_other_sources() {
+        let config = Config::default();
+        assert!(is_donor_path(Path::new("./duplicates1/a.bin"), &config));
+        assert!(is_donor_path(Path::new("unknown12/sub/b.bin"), &config));
+        assert!(!is_donor_path(Path::new("./unknown/c.bin"), &config));
+        assert!(!is_donor_path(Path::new("./unknown1b/c.bin"), &config));
+        assert!(!is_donor_path(Path::new("./new/duplicates1/d.bin"), &config));
+
+        let mut files = vec![PathBuf::from("./duplicates1/a.bin"), PathBuf::from("./z.bin"), PathBuf::from("./b.bin")];
+        files.sort_by_key(|p| scan_order_key(p, &config));
+        assert_eq!(files, [PathBuf::from("./b.bin"), PathBuf::from("./z.bin"), PathBuf::from("./duplicates1/a.bin")]);
+    }
+}
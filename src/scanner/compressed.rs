@@ -0,0 +1,322 @@
+// src/scanner/compressed.rs - Single files compressed with gzip, xz or bzip2,
+// and disc images compressed as CSO, ZSO, WIA or RVZ
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+
+/// A compression format that wraps exactly one file, so the ROM is the
+/// decompressed payload (`game.bin.gz` holds `game.bin`, `game.cso` holds
+/// `game.iso`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Bzip2,
+    /// CISO: an ISO split into blocks, each deflated or stored
+    Cso,
+    /// ZISO: the same layout with LZ4 blocks
+    Zso,
+    /// A GameCube disc in WIA format
+    Wia,
+    /// A GameCube disc in RVZ format, WIA with zstd and junk data left out
+    Rvz,
+}
+
+impl Compression {
+    /// The format a file name says, checked against the file's magic bytes
+    /// so a misnamed file is still hashed as it is
+    pub fn detect(path: &Path) -> Result<Option<Self>> {
+        let Some(compression) = Self::from_extension(path) else {
+            return Ok(None);
+        };
+        let mut magic = Vec::with_capacity(super::wia::DETECT_LEN);
+        File::open(path)?.take(super::wia::DETECT_LEN as u64).read_to_end(&mut magic)?;
+        let expected: &[u8] = match compression {
+            Compression::Gzip => &[0x1F, 0x8B],
+            Compression::Xz => &[0xFD, b'7', b'z', b'X', b'Z', 0x00],
+            Compression::Bzip2 => b"BZh",
+            Compression::Cso => b"CISO",
+            Compression::Zso => b"ZISO",
+            // Wii images stay as they are, and are reported as convertible
+            Compression::Wia if super::wia::is_gamecube(&magic) => b"WIA\x01",
+            Compression::Rvz if super::wia::is_gamecube(&magic) => b"RVZ\x01",
+            Compression::Wia | Compression::Rvz => return Ok(None),
+        };
+        Ok(magic.starts_with(expected).then_some(compression))
+    }
+
+    /// The format named by a file's extension (.gz, .xz, .bz2, .cso, .zso,
+    /// .wia or .rvz)
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gz" => Some(Compression::Gzip),
+            "xz" => Some(Compression::Xz),
+            "bz2" => Some(Compression::Bzip2),
+            "cso" | "ciso" => Some(Compression::Cso),
+            "zso" => Some(Compression::Zso),
+            "wia" => Some(Compression::Wia),
+            "rvz" => Some(Compression::Rvz),
+            _ => None,
+        }
+    }
+
+    /// Write the payload of `path` to `output`; returns its size
+    pub fn decompress(self, path: &Path, output: &mut impl Write) -> Result<u64> {
+        let mut output = CountingWriter { inner: output, written: 0 };
+        let input = BufReader::new(File::open(path)?);
+        match self {
+            // The multi-member decoders also read files made by `cat a.gz b.gz`
+            Compression::Gzip => {
+                io::copy(&mut flate2::bufread::MultiGzDecoder::new(input), &mut output)?;
+            }
+            Compression::Bzip2 => {
+                io::copy(&mut bzip2::bufread::MultiBzDecoder::new(input), &mut output)?;
+            }
+            Compression::Xz => {
+                lzma_rs::xz_decompress(&mut { input }, &mut output)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            }
+            Compression::Cso | Compression::Zso => {
+                decompress_blocks(input, self == Compression::Zso, &mut output)?;
+            }
+            Compression::Wia | Compression::Rvz => {
+                super::wia::decompress(input, self == Compression::Rvz, &mut output)?;
+            }
+        }
+        Ok(output.written)
+    }
+}
+
+/// Name a file is organized under: its payload's name for a compressed file
+/// (`game.md.gz` -> `game.md`, `game.rvz` -> `game.iso`), the joined file's
+/// for the first part of a split one (`game.iso.001` -> `game.iso`),
+/// otherwise its own
+pub fn payload_name(path: &Path) -> Cow<'_, Path> {
+    if let Some(joined) = super::multipart::joined_name(path) {
+        return Cow::Owned(joined);
+    }
+    match (Compression::from_extension(path), path.file_stem()) {
+        (Some(Compression::Cso | Compression::Zso | Compression::Wia | Compression::Rvz), Some(stem)) => Cow::Owned(PathBuf::from(stem).with_extension("iso")),
+        (Some(_), Some(stem)) => Cow::Borrowed(Path::new(stem)),
+        _ => Cow::Borrowed(path.file_name().map_or(path, Path::new)),
+    }
+}
+
+/// Size of the CSO/ZSO header, which the block index follows
+const BLOCK_HEADER_LEN: usize = 24;
+/// Set in an index entry when the block is stored rather than compressed
+const BLOCK_STORED: u32 = 0x8000_0000;
+
+/// Write the image held by a CSO (`lz4` false) or ZSO file. The header gives
+/// the image size, block size and index alignment; index entry `i` is where
+/// block `i` starts, shifted right by the alignment, and entry `i + 1` where
+/// it ends. The image size comes from the file, so the index it implies must
+/// fit in the file before anything is allocated for it.
+fn decompress_blocks(mut input: impl Read + Seek, lz4: bool, output: &mut impl Write) -> io::Result<()> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("not a valid compressed image: {}", what));
+
+    let input_len = input.seek(SeekFrom::End(0))?;
+    input.seek(SeekFrom::Start(0))?;
+    let mut header = [0u8; BLOCK_HEADER_LEN];
+    input.read_exact(&mut header)?;
+    let total = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let block_size = u32::from_le_bytes(header[16..20].try_into().unwrap()) as u64;
+    let (version, align) = (header[20], header[21]);
+    // Version 2 CSOs mix LZ4 and deflate blocks, which nothing we read writes
+    if block_size == 0 || block_size > 1 << 24 || align > 16 || version > 1 {
+        return Err(invalid("unsupported header"));
+    }
+    let index_len = total.div_ceil(block_size).checked_add(1).and_then(|entries| entries.checked_mul(4))
+        .filter(|&len| len <= input_len.saturating_sub(BLOCK_HEADER_LEN as u64))
+        .ok_or_else(|| invalid("block index larger than the file"))?;
+    let blocks = (index_len / 4 - 1) as usize;
+
+    let mut index = vec![0u8; index_len as usize];
+    input.read_exact(&mut index)?;
+    let index: Vec<u32> = index.chunks_exact(4).map(|entry| u32::from_le_bytes(entry.try_into().unwrap())).collect();
+
+    let mut compressed = Vec::new();
+    let mut block = vec![0u8; block_size as usize];
+    for i in 0..blocks {
+        let start = ((index[i] & !BLOCK_STORED) as u64) << align;
+        let end = ((index[i + 1] & !BLOCK_STORED) as u64) << align;
+        if end < start || end - start > block_size + (1 << align) {
+            return Err(invalid("bad block index"));
+        }
+        // The last block is short when the image isn't a whole number of blocks
+        let len = block_size.min(total - i as u64 * block_size) as usize;
+        input.seek(SeekFrom::Start(start))?;
+        compressed.resize((end - start) as usize, 0);
+        input.read_exact(&mut compressed)?;
+
+        if index[i] & BLOCK_STORED != 0 {
+            output.write_all(compressed.get(..len).ok_or_else(|| invalid("short stored block"))?)?;
+            continue;
+        }
+        if lz4 {
+            lz4_block(&compressed, &mut block[..len])?;
+        } else {
+            flate2::read::DeflateDecoder::new(&compressed[..]).read_exact(&mut block[..len])?;
+        }
+        output.write_all(&block[..len])?;
+    }
+    Ok(())
+}
+
+/// Decode one raw LZ4 block into `out`, stopping once it is full: a ZSO block
+/// may be followed by alignment padding, which isn't part of the stream
+fn lz4_block(input: &[u8], out: &mut [u8]) -> io::Result<()> {
+    let corrupt = || io::Error::new(io::ErrorKind::InvalidData, "corrupt LZ4 block");
+    let mut bytes = input.iter().copied();
+    // Lengths of 15 continue in following bytes, until one isn't 255
+    let length = |nibble: u8, bytes: &mut dyn Iterator<Item = u8>| -> io::Result<usize> {
+        let mut length = nibble as usize;
+        if nibble == 15 {
+            loop {
+                let byte = bytes.next().ok_or_else(corrupt)?;
+                length += byte as usize;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(length)
+    };
+
+    let mut o = 0;
+    while o < out.len() {
+        let token = bytes.next().ok_or_else(corrupt)?;
+        let literals = length(token >> 4, &mut bytes)?;
+        let target = out.get_mut(o..o + literals).ok_or_else(corrupt)?;
+        for byte in target {
+            *byte = bytes.next().ok_or_else(corrupt)?;
+        }
+        o += literals;
+        if o == out.len() {
+            break;
+        }
+
+        let offset = u16::from_le_bytes([bytes.next().ok_or_else(corrupt)?, bytes.next().ok_or_else(corrupt)?]) as usize;
+        let matched = length(token & 15, &mut bytes)? + 4;
+        if offset == 0 || offset > o || o + matched > out.len() {
+            return Err(corrupt());
+        }
+        // A match may overlap what it copies, so go byte by byte
+        for k in o..o + matched {
+            out[k] = out[k - offset];
+        }
+        o += matched;
+    }
+    Ok(())
+}
+
+/// Copy a file into place, decompressing it if it is a compressed single file
+pub fn copy_payload(from: &Path, to: &Path) -> Result<u64> {
+    match Compression::detect(from)? {
+        Some(compression) => {
+            let mut file = io::BufWriter::new(File::create(to)?);
+            let size = compression.decompress(from, &mut file)?;
+            file.flush()?;
+            Ok(size)
+        }
+        None => Ok(std::fs::copy(from, to)?),
+    }
+}
+
+struct CountingWriter<'a, W> {
+    inner: &'a mut W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gzip_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.md.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+        encoder.write_all(b"SEGA GENESIS").unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(Compression::detect(&path).unwrap(), Some(Compression::Gzip));
+        assert_eq!(payload_name(&path), Path::new("game.md"));
+        let mut payload = Vec::new();
+        assert_eq!(Compression::Gzip.decompress(&path, &mut payload).unwrap(), 12);
+        assert_eq!(payload, b"SEGA GENESIS");
+
+        // A file only named like one is left alone
+        let fake = dir.path().join("notes.xz");
+        std::fs::write(&fake, b"plain text").unwrap();
+        assert_eq!(Compression::detect(&fake).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cso_and_zso_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let image: Vec<u8> = (0..5000u32).map(|i| (i % 7) as u8).collect();
+        let header = |magic: &[u8]| {
+            let mut header = magic.to_vec();
+            header.extend_from_slice(&(BLOCK_HEADER_LEN as u32).to_le_bytes());
+            header.extend_from_slice(&(image.len() as u64).to_le_bytes());
+            header.extend_from_slice(&2048u32.to_le_bytes());
+            header.extend_from_slice(&[1, 0, 0, 0]);
+            header
+        };
+
+        // Three blocks: deflated, stored, and a short deflated one
+        let deflate = |data: &[u8]| {
+            let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let blocks = [deflate(&image[..2048]), image[2048..4096].to_vec(), deflate(&image[4096..])];
+        let mut cso = header(b"CISO");
+        let mut offset = (BLOCK_HEADER_LEN + 4 * 4) as u32;
+        for (i, block) in blocks.iter().enumerate() {
+            let stored = if i == 1 { BLOCK_STORED } else { 0 };
+            cso.extend_from_slice(&(offset | stored).to_le_bytes());
+            offset += block.len() as u32;
+        }
+        cso.extend_from_slice(&offset.to_le_bytes());
+        cso.extend_from_slice(&blocks.concat());
+        let path = dir.path().join("game.cso");
+        std::fs::write(&path, &cso).unwrap();
+
+        assert_eq!(Compression::detect(&path).unwrap(), Some(Compression::Cso));
+        assert_eq!(payload_name(&path), Path::new("game.iso"));
+        let mut payload = Vec::new();
+        assert_eq!(Compression::Cso.decompress(&path, &mut payload).unwrap(), 5000);
+        assert_eq!(payload, image);
+
+        // "abcd", then 8 bytes copied from 4 back (overlapping), then padding
+        let mut out = [0u8; 12];
+        lz4_block(&[0x44, b'a', b'b', b'c', b'd', 4, 0, 0, 0], &mut out).unwrap();
+        assert_eq!(&out, b"abcdabcdabcd");
+        assert!(lz4_block(&[0x44, b'a', b'b', b'c', b'd', 9, 0], &mut out).is_err());
+
+        // A header claiming a huge image is rejected before its index is allocated
+        let mut huge = header(b"CISO");
+        huge[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &huge).unwrap();
+        assert!(Compression::Cso.decompress(&path, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+// src/scanner/convertible.rs - GameCube/Wii images in formats Redump doesn't hash
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Result, RomAuditError};
+use crate::types::{FileDigests, FileHash, RomDb, RomEntry};
+use super::find_matching_entries;
+
+/// Image formats that never match a Redump DAT until converted back to ISO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertibleFormat {
+    Nkit,
+    Wbfs,
+    Wia,
+    Rvz,
+}
+
+impl ConvertibleFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConvertibleFormat::Nkit => "NKit",
+            ConvertibleFormat::Wbfs => "WBFS",
+            ConvertibleFormat::Wia => "WIA",
+            ConvertibleFormat::Rvz => "RVZ",
+        }
+    }
+}
+
+/// An unmatched file recognized as a convertible image, with the game ID and
+/// title from its disc header when they can be read
+#[derive(Debug, Clone)]
+pub struct Convertible {
+    pub path: PathBuf,
+    pub format: ConvertibleFormat,
+    pub game_id: Option<String>,
+    pub title: Option<String>,
+    /// CRC32 and size of the original ISO, recorded by NKit in its header
+    pub original: Option<(String, u64)>,
+    /// DAT entries that original matches: the image is present, just in NKit form
+    pub matches: Vec<RomEntry>,
+}
+
+/// Length of the disc header fields we read: ID at 0x00, title at 0x20,
+/// NKit header at 0x200
+const DISC_HEADER_LEN: usize = 0x214;
+const NKIT_MAGIC: &[u8] = b"NKIT";
+const WBFS_MAGIC: &[u8] = b"WBFS";
+const WIA_MAGIC: &[u8] = b"WIA\x01";
+const RVZ_MAGIC: &[u8] = b"RVZ\x01";
+/// WIA and RVZ keep the first 0x80 bytes of the disc header uncompressed, in
+/// their second header
+const WIA_DISC_HEADER: usize = 0x58;
+
+/// Check every file that matched nothing for NKit, WBFS, WIA and RVZ images,
+/// and look up the original CRC of NKit images in the DAT
+pub fn detect_convertibles(file_hashes: &[FileHash], rom_db: &RomDb) -> Result<Vec<Convertible>> {
+    let mut convertibles = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        if let Some(mut convertible) = detect(&file.path)? {
+            if let Some((crc, size)) = &convertible.original {
+                let digests = FileDigests { crc: crc.clone(), ..Default::default() };
+                convertible.matches = find_matching_entries(rom_db, &digests, *size)
+                    .into_iter()
+                    .filter(|entry| entry.size_matches(*size))
+                    .collect();
+            }
+            convertibles.push(convertible);
+        }
+    }
+    Ok(convertibles)
+}
+
+/// Recognize an NKit, WBFS, WIA or RVZ image by its magic bytes; compressed
+/// NKit images (.nkit.gcz) only by name, as their header can't be read directly
+pub fn detect(path: &Path) -> Result<Option<Convertible>> {
+    let mut file = File::open(path)?;
+    let mut header = Vec::with_capacity(DISC_HEADER_LEN);
+    (&mut file).take(DISC_HEADER_LEN as u64).read_to_end(&mut header)?;
+
+    let convertible = |format, disc_header: Option<&[u8]>| Convertible {
+        path: path.to_path_buf(),
+        format,
+        game_id: disc_header.and_then(|h| header_text(h, 0, 6)),
+        title: disc_header.and_then(|h| header_text(h, 0x20, 0x40)),
+        original: None,
+        matches: Vec::new(),
+    };
+
+    if header.starts_with(WBFS_MAGIC) && header.len() > 8 {
+        // The first disc's header follows the WBFS header, one HD sector in
+        let disc_offset = 1u64.checked_shl(header[8] as u32).unwrap_or(0);
+        let mut disc_header = Vec::with_capacity(0x60);
+        file.seek(SeekFrom::Start(disc_offset))?;
+        file.take(0x60).read_to_end(&mut disc_header)?;
+        return Ok(Some(convertible(ConvertibleFormat::Wbfs, Some(&disc_header))));
+    }
+
+    for (magic, format) in [(WIA_MAGIC, ConvertibleFormat::Wia), (RVZ_MAGIC, ConvertibleFormat::Rvz)] {
+        // GameCube images are hashed by the ISO they hold, like CSO
+        if header.starts_with(magic) && !super::wia::is_gamecube(&header) {
+            return Ok(Some(convertible(format, header.get(WIA_DISC_HEADER..WIA_DISC_HEADER + 0x80))));
+        }
+    }
+
+    if header.get(0x200..0x204) == Some(NKIT_MAGIC) {
+        return Ok(Some(Convertible {
+            original: nkit_original(&header),
+            ..convertible(ConvertibleFormat::Nkit, Some(&header))
+        }));
+    }
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+    if name.ends_with(".nkit.gcz") {
+        return Ok(Some(convertible(ConvertibleFormat::Nkit, None)));
+    }
+
+    Ok(None)
+}
+
+/// The original image's CRC32 (0x208) and size (0x210) from an NKit header,
+/// both big-endian; a size of 0 means it wasn't recorded
+fn nkit_original(header: &[u8]) -> Option<(String, u64)> {
+    let crc = u32::from_be_bytes(header.get(0x208..0x20C)?.try_into().ok()?);
+    let size = u32::from_be_bytes(header.get(0x210..0x214)?.try_into().ok()?);
+    (size != 0).then(|| (format!("{:08x}", crc), size as u64))
+}
+
+/// NUL-terminated text field of a disc header, if it holds anything printable
+fn header_text(header: &[u8], offset: usize, len: usize) -> Option<String> {
+    let field = header.get(offset..)?;
+    let field = &field[..len.min(field.len())];
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    let text = String::from_utf8_lossy(&field[..end]).trim().to_string();
+    (!text.is_empty() && !text.chars().any(char::is_control)).then_some(text)
+}
+
+/// Run the configured conversion command for one image. Each whitespace
+/// separated word is an argument; `{input}` is replaced by the image path and
+/// `{id}` by its game ID.
+pub fn run_convert_command(command: &str, convertible: &Convertible) -> Result<()> {
+    let input = convertible.path.to_string_lossy();
+    let id = convertible.game_id.as_deref().unwrap_or("");
+    let mut words = command.split_whitespace()
+        .map(|word| word.replace("{input}", &input).replace("{id}", id));
+    let Some(program) = words.next() else {
+        return Err(RomAuditError::ConfigError("convert_command is empty".to_string()));
+    };
+
+    let status = Command::new(&program).args(words).status()?;
+    if !status.success() {
+        return Err(RomAuditError::Custom(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_nkit_and_wbfs_headers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut disc_header = vec![0u8; DISC_HEADER_LEN];
+        disc_header[..6].copy_from_slice(b"GALE01");
+        disc_header[0x20..0x20 + 19].copy_from_slice(b"Super Smash Bros. M");
+        let mut nkit = disc_header.clone();
+        nkit[0x200..0x204].copy_from_slice(NKIT_MAGIC);
+        nkit[0x208..0x20C].copy_from_slice(&0x1234ABCDu32.to_be_bytes());
+        nkit[0x210..0x214].copy_from_slice(&1_459_978_240u32.to_be_bytes());
+        let nkit_path = dir.path().join("game.nkit.iso");
+        std::fs::write(&nkit_path, &nkit).unwrap();
+
+        // WBFS header with 512-byte HD sectors, then the disc header
+        let mut wbfs = vec![0u8; 512];
+        wbfs[..4].copy_from_slice(WBFS_MAGIC);
+        wbfs[8] = 9;
+        wbfs.extend_from_slice(&disc_header);
+        let wbfs_path = dir.path().join("game.wbfs");
+        std::fs::write(&wbfs_path, &wbfs).unwrap();
+
+        let plain_path = dir.path().join("game.iso");
+        std::fs::write(&plain_path, &disc_header).unwrap();
+
+        let nkit = detect(&nkit_path).unwrap().unwrap();
+        assert_eq!(nkit.format, ConvertibleFormat::Nkit);
+        assert_eq!(nkit.game_id.as_deref(), Some("GALE01"));
+        assert_eq!(nkit.title.as_deref(), Some("Super Smash Bros. M"));
+        assert_eq!(nkit.original, Some(("1234abcd".to_string(), 1_459_978_240)));
+
+        let wbfs = detect(&wbfs_path).unwrap().unwrap();
+        assert_eq!(wbfs.format, ConvertibleFormat::Wbfs);
+        assert_eq!(wbfs.game_id.as_deref(), Some("GALE01"));
+
+        let mut rvz = vec![0u8; WIA_DISC_HEADER];
+        rvz[..4].copy_from_slice(RVZ_MAGIC);
+        // A Wii disc; GameCube ones are read as compressed images instead
+        rvz[0x4B] = 2;
+        rvz.extend_from_slice(&disc_header[..0x80]);
+        let rvz_path = dir.path().join("game.rvz");
+        std::fs::write(&rvz_path, &rvz).unwrap();
+        let rvz = detect(&rvz_path).unwrap().unwrap();
+        assert_eq!(rvz.format, ConvertibleFormat::Rvz);
+        assert_eq!(rvz.game_id.as_deref(), Some("GALE01"));
+
+        assert!(detect(&plain_path).unwrap().is_none());
+    }
+}
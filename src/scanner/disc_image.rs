@@ -0,0 +1,523 @@
+// src/scanner/disc_image.rs - Read-only traversal of ISO9660 and UDF images
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, RomAuditError};
+use crate::types::{FileHash, HashAlgorithms, RomDb};
+use super::hasher_optimized;
+use super::identify::{identified, IdentifiedItem};
+
+/// Sector size of optical media; volume descriptors are addressed in it
+const SECTOR_SIZE: u64 = 2048;
+/// Volume recognition starts at sector 16 in both formats
+const FIRST_DESCRIPTOR: u64 = 16;
+/// Directory nesting deeper than this is treated as a corrupt image
+const MAX_DEPTH: usize = 64;
+/// Directories larger than this are treated as a corrupt image
+const MAX_DIRECTORY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A file inside an image, stored as one or more extents
+#[derive(Debug, Clone)]
+pub struct ImageMember {
+    pub path: String,
+    pub size: u64,
+    extents: Vec<Extent>,
+}
+
+/// A run of bytes at `offset` in the image; unrecorded (sparse) extents
+/// have no offset and read as zeros
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    offset: Option<u64>,
+    len: u64,
+}
+
+/// The files of an unmatched image, each looked up in the DAT
+pub struct ImageContents {
+    pub path: PathBuf,
+    pub members: Vec<IdentifiedItem>,
+}
+
+impl ImageContents {
+    pub fn matched(&self) -> usize {
+        self.members.iter().filter(|m| !m.matches.is_empty()).count()
+    }
+}
+
+/// Hash the files inside every unmatched ISO9660/UDF image. Images that
+/// can't be parsed are reported and skipped.
+pub fn scan_images(file_hashes: &[FileHash], rom_db: &RomDb, buffer_size: usize) -> Result<Vec<ImageContents>> {
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+    let mut images = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        let members = match list_members(&file.path) {
+            Ok(Some(members)) => members,
+            Ok(None) => continue,
+            Err(RomAuditError::ParseError(e)) => {
+                eprintln!("Warning: can't read disc image {}: {}", file.path.display(), e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        println!("Scanning {} files inside {}", members.len(), file.path.display());
+        let members = hash_members(&file.path, &members, rom_db, buffer_size, algorithms)?;
+        images.push(ImageContents { path: file.path.clone(), members });
+    }
+    Ok(images)
+}
+
+/// Hash each member of an image and look it up in the DAT
+pub fn hash_members(
+    image: &Path,
+    members: &[ImageMember],
+    rom_db: &RomDb,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<Vec<IdentifiedItem>> {
+    members.iter()
+        .map(|member| {
+            let digests = hasher_optimized::calculate_hashes_reader(open_member(image, member)?, buffer_size, algorithms)?;
+            let label = format!("{}#{}", image.display(), member.path);
+            Ok(identified(label, member.size, digests, rom_db))
+        })
+        .collect()
+}
+
+/// List the files in an ISO9660 or UDF image, or `None` if it is neither.
+/// UDF is preferred on hybrid images, as its names and sizes aren't limited.
+pub fn list_members(path: &Path) -> Result<Option<Vec<ImageMember>>> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() < (FIRST_DESCRIPTOR + 1) * SECTOR_SIZE {
+        return Ok(None);
+    }
+
+    let (mut iso9660, mut udf) = (false, false);
+    for sector in FIRST_DESCRIPTOR..FIRST_DESCRIPTOR + 32 {
+        let descriptor = read_at(&mut file, sector * SECTOR_SIZE, 8)?;
+        match descriptor.get(1..6) {
+            Some(b"CD001") => iso9660 = true,
+            Some(b"NSR02") | Some(b"NSR03") => udf = true,
+            Some(b"BEA01") | Some(b"TEA01") | Some(b"BOOT2") | Some(b"CDW02") => {}
+            _ => break,
+        }
+    }
+
+    if udf {
+        match udf::list(&mut file) {
+            Ok(members) => return Ok(Some(members)),
+            Err(RomAuditError::ParseError(_)) if iso9660 => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if iso9660 {
+        return iso9660::list(&mut file).map(Some);
+    }
+    Ok(None)
+}
+
+/// Read a member's bytes straight from the image
+pub fn open_member(image: &Path, member: &ImageMember) -> Result<impl Read> {
+    let mut reader: Box<dyn Read> = Box::new(io::empty());
+    for extent in &member.extents {
+        let part: Box<dyn Read> = match extent.offset {
+            Some(offset) => {
+                let mut file = File::open(image)?;
+                file.seek(SeekFrom::Start(offset))?;
+                Box::new(file.take(extent.len))
+            }
+            None => Box::new(io::repeat(0).take(extent.len)),
+        };
+        reader = Box::new(reader.chain(part));
+    }
+    Ok(reader.take(member.size))
+}
+
+/// Read up to `len` bytes at `offset`; shorter at the end of the file
+fn read_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(len);
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(len as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn u16_le(data: &[u8], at: usize) -> u16 {
+    data.get(at..at + 2).map_or(0, |b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_le(data: &[u8], at: usize) -> u32 {
+    data.get(at..at + 4).map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u64_le(data: &[u8], at: usize) -> u64 {
+    data.get(at..at + 8).map_or(0, |b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn corrupt(message: &str) -> RomAuditError {
+    RomAuditError::ParseError(message.to_string())
+}
+
+mod iso9660 {
+    use super::*;
+
+    /// Joliet supplementary descriptors carry one of these UCS-2 escape sequences
+    const JOLIET_ESCAPES: [&[u8]; 3] = [b"%/@", b"%/C", b"%/E"];
+    /// Directory record flags
+    const DIRECTORY: u8 = 0x02;
+    const MULTI_EXTENT: u8 = 0x80;
+
+    struct Record {
+        extent: Extent,
+        flags: u8,
+        name: String,
+    }
+
+    /// Walk the Joliet tree if there is one (long Unicode names), otherwise
+    /// the primary one
+    pub fn list(file: &mut File) -> Result<Vec<ImageMember>> {
+        let mut root = None;
+        let mut joliet = false;
+        for sector in FIRST_DESCRIPTOR..FIRST_DESCRIPTOR + 32 {
+            let descriptor = read_at(file, sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+            if descriptor.get(1..6) != Some(b"CD001") {
+                break;
+            }
+            // A truncated image can end inside a descriptor
+            let Some(record) = descriptor.get(156..190) else {
+                break;
+            };
+            match descriptor[0] {
+                1 if root.is_none() => root = Some(record.to_vec()),
+                2 if descriptor.get(88..91).is_some_and(|escape| JOLIET_ESCAPES.contains(&escape)) => {
+                    root = Some(record.to_vec());
+                    joliet = true;
+                }
+                255 => break,
+                _ => {}
+            }
+        }
+
+        let root = root.ok_or_else(|| corrupt("no ISO9660 primary volume descriptor"))?;
+        let root = parse_record(&root, joliet).ok_or_else(|| corrupt("bad ISO9660 root directory"))?;
+        let mut members = Vec::new();
+        walk(file, root.extent, "", joliet, 0, &mut members)?;
+        Ok(members)
+    }
+
+    fn walk(file: &mut File, dir: Extent, prefix: &str, joliet: bool, depth: usize, members: &mut Vec<ImageMember>) -> Result<()> {
+        if depth > MAX_DEPTH || dir.len > MAX_DIRECTORY_SIZE {
+            return Err(corrupt("ISO9660 directory too deep or too large"));
+        }
+        let data = read_at(file, dir.offset.unwrap_or(0), dir.len as usize)?;
+
+        // A file larger than 4 GiB is split over consecutive records
+        let mut continues = false;
+        let mut pos = 0;
+        while pos < data.len() {
+            let len = data[pos] as usize;
+            if len == 0 {
+                // Records don't cross sector boundaries; the rest is padding
+                pos = (pos / SECTOR_SIZE as usize + 1) * SECTOR_SIZE as usize;
+                continue;
+            }
+            let Some(record) = data.get(pos..pos + len).and_then(|bytes| parse_record(bytes, joliet)) else {
+                break;
+            };
+            pos += len;
+
+            if record.name.is_empty() {
+                continue; // "." and ".."
+            }
+            let path = format!("{}{}", prefix, record.name);
+            if record.flags & DIRECTORY != 0 {
+                walk(file, record.extent, &format!("{}/", path), joliet, depth + 1, members)?;
+                continue;
+            }
+
+            match members.last_mut() {
+                Some(previous) if continues && previous.path == path => {
+                    previous.size += record.extent.len;
+                    previous.extents.push(record.extent);
+                }
+                _ => members.push(ImageMember { path, size: record.extent.len, extents: vec![record.extent] }),
+            }
+            continues = record.flags & MULTI_EXTENT != 0;
+        }
+        Ok(())
+    }
+
+    fn parse_record(bytes: &[u8], joliet: bool) -> Option<Record> {
+        let name_len = *bytes.get(32)? as usize;
+        let raw_name = bytes.get(33..33 + name_len)?;
+        let name = match raw_name {
+            [0] | [1] => String::new(),
+            _ if joliet => utf16_be(raw_name),
+            _ => raw_name.iter().map(|&b| b as char).collect(),
+        };
+        // Drop the ";1" version suffix and the dot of names without an extension
+        let name = name.split(';').next().unwrap_or("").trim_end_matches('.').to_string();
+
+        let extended_attributes = bytes[1] as u64;
+        let offset = (u32_le(bytes, 2) as u64 + extended_attributes) * SECTOR_SIZE;
+        Some(Record {
+            extent: Extent { offset: Some(offset), len: u32_le(bytes, 10) as u64 },
+            flags: bytes[25],
+            name,
+        })
+    }
+}
+
+mod udf {
+    use super::*;
+
+    /// Descriptor tag identifiers (ECMA-167)
+    const ANCHOR: u16 = 2;
+    const PARTITION: u16 = 5;
+    const LOGICAL_VOLUME: u16 = 6;
+    const TERMINATING: u16 = 8;
+    const FILE_SET: u16 = 256;
+    const FILE_IDENTIFIER: u16 = 257;
+    const FILE_ENTRY: u16 = 261;
+    const EXTENDED_FILE_ENTRY: u16 = 266;
+    /// File identifier characteristics
+    const FID_DIRECTORY: u8 = 0x02;
+    const FID_DELETED: u8 = 0x04;
+    const FID_PARENT: u8 = 0x08;
+    /// ICB file type of a directory
+    const ICB_DIRECTORY: u8 = 4;
+
+    /// A single physical partition, which covers UDF up to 2.01 as written
+    /// by mastering tools; the metadata partitions of UDF 2.50+ aren't read
+    struct Partition {
+        start: u64,
+        block_size: u64,
+    }
+
+    impl Partition {
+        fn offset(&self, block: u32) -> u64 {
+            (self.start + block as u64) * self.block_size
+        }
+    }
+
+    struct Entry {
+        size: u64,
+        directory: bool,
+        extents: Vec<Extent>,
+    }
+
+    pub fn list(file: &mut File) -> Result<Vec<ImageMember>> {
+        let anchor = read_at(file, 256 * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        if u16_le(&anchor, 0) != ANCHOR {
+            return Err(corrupt("no UDF anchor volume descriptor"));
+        }
+        let sequence_len = u32_le(&anchor, 16) as u64;
+        let sequence_start = u32_le(&anchor, 20) as u64;
+
+        let mut partition_start = None;
+        let mut block_size = SECTOR_SIZE;
+        let mut file_set = None;
+        for sector in sequence_start..sequence_start + (sequence_len / SECTOR_SIZE).min(64) {
+            let descriptor = read_at(file, sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+            match u16_le(&descriptor, 0) {
+                PARTITION if partition_start.is_none() => partition_start = Some(u32_le(&descriptor, 188) as u64),
+                LOGICAL_VOLUME => {
+                    block_size = u32_le(&descriptor, 212) as u64;
+                    file_set = Some(u32_le(&descriptor, 252));
+                    // Type 2 maps are virtual, sparable or metadata partitions
+                    if descriptor.get(440) == Some(&2) {
+                        return Err(corrupt("UDF partition type not supported"));
+                    }
+                }
+                TERMINATING => break,
+                _ => {}
+            }
+        }
+
+        let (Some(start), Some(file_set)) = (partition_start, file_set) else {
+            return Err(corrupt("incomplete UDF volume descriptor sequence"));
+        };
+        if !(512..=65536).contains(&block_size) {
+            return Err(corrupt("bad UDF block size"));
+        }
+        let partition = Partition { start, block_size };
+
+        let file_set = read_at(file, partition.offset(file_set), block_size as usize)?;
+        if u16_le(&file_set, 0) != FILE_SET {
+            return Err(corrupt("no UDF file set descriptor"));
+        }
+        let root = read_entry(file, &partition, u32_le(&file_set, 404))?;
+
+        let mut members = Vec::new();
+        walk(file, &partition, &root, "", 0, &mut members)?;
+        Ok(members)
+    }
+
+    fn walk(file: &mut File, partition: &Partition, dir: &Entry, prefix: &str, depth: usize, members: &mut Vec<ImageMember>) -> Result<()> {
+        if depth > MAX_DEPTH || dir.size > MAX_DIRECTORY_SIZE {
+            return Err(corrupt("UDF directory too deep or too large"));
+        }
+        let mut data = Vec::new();
+        for extent in &dir.extents {
+            match extent.offset {
+                Some(offset) => data.extend(read_at(file, offset, extent.len as usize)?),
+                None => data.resize(data.len() + extent.len as usize, 0),
+            }
+        }
+        data.truncate(dir.size as usize);
+
+        let mut pos = 0;
+        while pos + 38 <= data.len() && u16_le(&data, pos) == FILE_IDENTIFIER {
+            let characteristics = data[pos + 18];
+            let name_len = data[pos + 19] as usize;
+            let block = u32_le(&data, pos + 24);
+            let name_start = pos + 38 + u16_le(&data, pos + 36) as usize;
+            let name = decode_name(data.get(name_start..name_start + name_len).unwrap_or_default());
+            pos = (name_start + name_len).next_multiple_of(4);
+
+            if characteristics & (FID_PARENT | FID_DELETED) != 0 || name.is_empty() {
+                continue;
+            }
+            let entry = read_entry(file, partition, block)?;
+            let path = format!("{}{}", prefix, name);
+            if characteristics & FID_DIRECTORY != 0 || entry.directory {
+                walk(file, partition, &entry, &format!("{}/", path), depth + 1, members)?;
+            } else {
+                members.push(ImageMember { path, size: entry.size, extents: entry.extents });
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a (extended) file entry and its allocation descriptors
+    fn read_entry(file: &mut File, partition: &Partition, block: u32) -> Result<Entry> {
+        let offset = partition.offset(block);
+        let data = read_at(file, offset, partition.block_size as usize)?;
+        let (ea_len, ad_len, base) = match u16_le(&data, 0) {
+            FILE_ENTRY => (u32_le(&data, 168), u32_le(&data, 172), 176),
+            EXTENDED_FILE_ENTRY => (u32_le(&data, 208), u32_le(&data, 212), 216),
+            _ => return Err(corrupt("bad UDF file entry")),
+        };
+        let directory = data[16 + 11] == ICB_DIRECTORY;
+        let size = u64_le(&data, 56);
+        let ad_start = base + ea_len as usize;
+        let descriptors = data.get(ad_start..ad_start + ad_len as usize)
+            .ok_or_else(|| corrupt("bad UDF allocation descriptors"))?;
+
+        let short = |ad: &[u8]| (u32_le(ad, 0), u32_le(ad, 4));
+        let extents = match u16_le(&data, 16 + 18) & 7 {
+            0 => extents(descriptors.chunks_exact(8).map(short), partition)?,
+            1 => extents(descriptors.chunks_exact(16).map(short), partition)?,
+            // Small files are stored in the entry itself
+            3 => vec![Extent { offset: Some(offset + ad_start as u64), len: ad_len as u64 }],
+            _ => return Err(corrupt("unsupported UDF allocation type")),
+        };
+        Ok(Entry { size, directory, extents })
+    }
+
+    /// Turn (length, block) allocation descriptors into extents; the top two
+    /// bits of the length give the extent type
+    fn extents(descriptors: impl Iterator<Item = (u32, u32)>, partition: &Partition) -> Result<Vec<Extent>> {
+        let mut extents = Vec::new();
+        for (raw_len, block) in descriptors {
+            let len = (raw_len & 0x3FFF_FFFF) as u64;
+            if len == 0 {
+                break;
+            }
+            let offset = match raw_len >> 30 {
+                0 => Some(partition.offset(block)),
+                1 | 2 => None,
+                _ => return Err(corrupt("chained UDF allocation descriptors not supported")),
+            };
+            extents.push(Extent { offset, len });
+        }
+        Ok(extents)
+    }
+
+    /// OSTA compressed Unicode: a compression ID of 8 (Latin-1) or 16 (UCS-2)
+    fn decode_name(bytes: &[u8]) -> String {
+        match bytes.split_first() {
+            Some((8, rest)) => rest.iter().map(|&b| b as char).collect(),
+            Some((16, rest)) => utf16_be(rest),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal ISO9660 image: a primary descriptor whose root holds
+    /// GAMES/A.BIN;1, plus a file at the root
+    fn build_iso() -> Vec<u8> {
+        fn record(lba: u32, len: u32, flags: u8, name: &[u8]) -> Vec<u8> {
+            let mut r = vec![0u8; 33];
+            r[2..6].copy_from_slice(&lba.to_le_bytes());
+            r[10..14].copy_from_slice(&len.to_le_bytes());
+            r[25] = flags;
+            r[32] = name.len() as u8;
+            r.extend_from_slice(name);
+            if r.len() % 2 == 1 {
+                r.push(0);
+            }
+            r[0] = r.len() as u8;
+            r
+        }
+        let sector = SECTOR_SIZE as usize;
+        let mut image = vec![0u8; 24 * sector];
+
+        let pvd = &mut image[16 * sector..17 * sector];
+        pvd[0] = 1;
+        pvd[1..6].copy_from_slice(b"CD001");
+        pvd[156..190].copy_from_slice(&record(18, 2048, 2, &[0]));
+        let terminator = &mut image[17 * sector..18 * sector];
+        terminator[0] = 255;
+        terminator[1..6].copy_from_slice(b"CD001");
+
+        let root = [record(18, 2048, 2, &[0]), record(18, 2048, 2, &[1]),
+            record(19, 2048, 2, b"GAMES"), record(21, 3, 0, b"README.TXT;1")].concat();
+        image[18 * sector..18 * sector + root.len()].copy_from_slice(&root);
+        let games = [record(19, 2048, 2, &[0]), record(18, 2048, 2, &[1]), record(20, 4, 0, b"A.BIN;1")].concat();
+        image[19 * sector..19 * sector + games.len()].copy_from_slice(&games);
+        image[20 * sector..20 * sector + 4].copy_from_slice(b"AAAA");
+        image[21 * sector..21 * sector + 3].copy_from_slice(b"hi\n");
+        image
+    }
+
+    #[test]
+    fn test_lists_and_reads_iso9660_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.iso");
+        std::fs::write(&path, build_iso()).unwrap();
+
+        let members = list_members(&path).unwrap().unwrap();
+        let paths: Vec<_> = members.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["GAMES/A.BIN", "README.TXT"]);
+
+        let mut contents = Vec::new();
+        open_member(&path, &members[0]).unwrap().read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, b"AAAA");
+
+        let plain = dir.path().join("plain.bin");
+        std::fs::write(&plain, vec![0u8; 40000]).unwrap();
+        assert!(list_members(&plain).unwrap().is_none());
+
+        // A descriptor cut short by the end of the file doesn't panic, and a
+        // root directory claiming 4 GiB is rejected before it is read
+        let mut truncated = build_iso();
+        truncated[17 * SECTOR_SIZE as usize] = 2;
+        truncated.truncate(17 * SECTOR_SIZE as usize + 50);
+        std::fs::write(&path, &truncated).unwrap();
+        assert!(list_members(&path).is_ok());
+        let mut huge = build_iso();
+        huge[16 * SECTOR_SIZE as usize + 156 + 10..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &huge).unwrap();
+        assert!(list_members(&path).is_err());
+    }
+}
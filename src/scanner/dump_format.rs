@@ -0,0 +1,264 @@
+// src/scanner/dump_format.rs - Dumps whose bytes are arranged unlike the DAT's
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::Result;
+use crate::types::{FileDigests, FileHash, HashAlgorithms, RomEntry};
+use super::compressed::Compression;
+use super::hasher_optimized;
+
+/// Converted this many bytes at a time; a multiple of every format's block
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// SMD dumps interleave each 16 KB block: its odd bytes, then its even ones
+const SMD_BLOCK: usize = 16 * 1024;
+
+/// The copier header in front of an SMD dump
+const SMD_HEADER: u64 = 512;
+
+/// Bytes of a file `detect` looks at: the SMD header and the first block
+const HEAD_LEN: u64 = SMD_HEADER + SMD_BLOCK as u64;
+
+/// A dump in another form than the one DATs hash, which can be read in that
+/// form without converting the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Nintendo 64 with each pair of bytes swapped (`.v64`, Doctor V64)
+    N64ByteSwapped,
+    /// Nintendo 64 with each 32-bit word little-endian (`.n64`)
+    N64LittleEndian,
+    /// Mega Drive / Genesis from a Super Magic Drive copier (`.smd`): a
+    /// 512-byte header, then interleaved 16 KB blocks
+    Smd,
+}
+
+impl DumpFormat {
+    /// Recognize a format from the first bytes of a file. N64 ROMs start
+    /// with 80 37 12 40 in the big-endian `.z64` form No-Intro hashes. An
+    /// SMD dump is a 512-byte header and whole blocks, and either the header
+    /// has the AA BB signature at offset 8 or the first block has `SEGA` at
+    /// 0x100 once de-interleaved.
+    pub fn detect(head: &[u8], file_size: u64) -> Option<Self> {
+        match head {
+            [0x37, 0x80, 0x40, 0x12, ..] => return Some(DumpFormat::N64ByteSwapped),
+            [0x40, 0x12, 0x37, 0x80, ..] => return Some(DumpFormat::N64LittleEndian),
+            _ => {}
+        }
+        if file_size <= SMD_HEADER || !(file_size - SMD_HEADER).is_multiple_of(SMD_BLOCK as u64) {
+            return None;
+        }
+        let signed = head.get(8..10) == Some(&[0xAA, 0xBB][..]);
+        let sega = head.get(SMD_HEADER as usize..HEAD_LEN as usize).is_some_and(|block| {
+            let mut block = block.to_vec();
+            DumpFormat::Smd.convert(&mut block);
+            &block[0x100..0x104] == b"SEGA"
+        });
+        (signed || sega).then_some(DumpFormat::Smd)
+    }
+
+    /// The format of a plain file; compressed files are never converted
+    pub fn of_file(path: &Path) -> Result<Option<Self>> {
+        if Compression::detect(path)?.is_some() {
+            return Ok(None);
+        }
+        let mut file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        let mut head = Vec::with_capacity(HEAD_LEN as usize);
+        (&mut file).take(HEAD_LEN).read_to_end(&mut head)?;
+        Ok(Self::detect(&head, file_size))
+    }
+
+    /// The header left out of the converted form
+    fn header_len(self) -> u64 {
+        match self {
+            DumpFormat::Smd => SMD_HEADER,
+            _ => 0,
+        }
+    }
+
+    /// `v64`, `n64`, `smd`
+    pub fn name(self) -> &'static str {
+        match self {
+            DumpFormat::N64ByteSwapped => "v64",
+            DumpFormat::N64LittleEndian => "n64",
+            DumpFormat::Smd => "smd",
+        }
+    }
+
+    /// `z64` or `md`, the form it converts to
+    pub fn converted_name(self) -> &'static str {
+        self.converted_extensions()[0]
+    }
+
+    /// Extensions of DAT entries that are in the converted form
+    fn converted_extensions(self) -> &'static [&'static str] {
+        match self {
+            DumpFormat::N64ByteSwapped | DumpFormat::N64LittleEndian => &["z64"],
+            DumpFormat::Smd => &["md", "bin", "gen"],
+        }
+    }
+
+    /// Whether a file in this format is organized as `rom` in converted
+    /// form, going by the extension the DAT gives it
+    pub fn converts_to(self, rom: &RomEntry) -> bool {
+        Path::new(&rom.name).extension().and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.converted_extensions().iter().any(|wanted| ext.eq_ignore_ascii_case(wanted)))
+    }
+
+    /// Rearrange whole blocks in place; a trailing partial block is left as it is
+    fn convert(self, data: &mut [u8]) {
+        match self {
+            DumpFormat::N64ByteSwapped => data.chunks_exact_mut(2).for_each(|pair| pair.swap(0, 1)),
+            DumpFormat::N64LittleEndian => data.chunks_exact_mut(4).for_each(|word| word.reverse()),
+            DumpFormat::Smd => data.chunks_exact_mut(SMD_BLOCK).for_each(|block| {
+                let (odd, even) = block.split_at(SMD_BLOCK / 2);
+                let linear: Vec<u8> = even.iter().zip(odd).flat_map(|(&even, &odd)| [even, odd]).collect();
+                block.copy_from_slice(&linear);
+            }),
+        }
+    }
+}
+
+/// Reads a dump in the form the DAT hashes
+pub struct Converted<R> {
+    inner: R,
+    format: DumpFormat,
+    chunk: Vec<u8>,
+    start: usize,
+}
+
+impl<R: Read> Converted<R> {
+    pub fn new(inner: R, format: DumpFormat) -> Self {
+        Converted { inner, format, chunk: Vec::with_capacity(CHUNK_SIZE), start: 0 }
+    }
+}
+
+impl<R: Read> Read for Converted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.start == self.chunk.len() {
+            self.chunk.clear();
+            self.start = 0;
+            (&mut self.inner).take(CHUNK_SIZE as u64).read_to_end(&mut self.chunk)?;
+            self.format.convert(&mut self.chunk);
+        }
+        let n = buf.len().min(self.chunk.len() - self.start);
+        buf[..n].copy_from_slice(&self.chunk[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+}
+
+/// Open a file to read in converted form, past any header the converted
+/// form leaves out; None when it's in no known format
+pub fn open_converted(path: &Path) -> Result<Option<(DumpFormat, Converted<BufReader<File>>)>> {
+    let Some(format) = DumpFormat::of_file(path)? else {
+        return Ok(None);
+    };
+    Ok(Some((format, open_as(path, format)?)))
+}
+
+fn open_as(path: &Path, format: DumpFormat) -> Result<Converted<BufReader<File>>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(format.header_len()))?;
+    Ok(Converted::new(BufReader::new(file), format))
+}
+
+/// Digests of a file in converted form, with its format and converted size
+pub fn converted_digests(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<Option<(DumpFormat, FileDigests, u64)>> {
+    let Some((format, reader)) = open_converted(path)? else {
+        return Ok(None);
+    };
+    let size = std::fs::metadata(path)?.len() - format.header_len();
+    let digests = hasher_optimized::calculate_hashes_reader(reader, buffer_size, algorithms)?;
+    Ok(Some((format, digests, size)))
+}
+
+/// A file that matched nothing may be a dump in another byte order or an
+/// interleaved one: the file with the digests and matches of its converted
+/// form, when that matches
+pub fn converted_match(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+    find_matches: impl Fn(&FileDigests, u64) -> Vec<RomEntry>,
+) -> Result<Option<FileHash>> {
+    let Some((_, digests, size)) = converted_digests(path, buffer_size, algorithms)? else {
+        return Ok(None);
+    };
+    let matching_entries = find_matches(&digests, size);
+    Ok((!matching_entries.is_empty()).then(|| FileHash { path: path.to_path_buf(), digests, size, matching_entries }))
+}
+
+/// Write a dump in converted form; returns the bytes written
+pub fn copy_converted(from: &Path, to: &Path, format: DumpFormat) -> Result<u64> {
+    let mut source = open_as(from, format)?;
+    let mut target = BufWriter::new(File::create(to)?);
+    let written = io::copy(&mut source, &mut target)?;
+    target.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_n64_byte_orders() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut z64 = vec![0x80, 0x37, 0x12, 0x40];
+        z64.extend((0..=255u8).cycle().take(CHUNK_SIZE * 2 + 60));
+        let big_endian = dir.path().join("game.z64");
+        std::fs::write(&big_endian, &z64).unwrap();
+        let expected = hasher_optimized::calculate_hashes_optimized(&big_endian, 4096, HashAlgorithms::all()).unwrap();
+        assert_eq!(DumpFormat::of_file(&big_endian).unwrap(), None);
+
+        let v64: Vec<u8> = z64.chunks(2).flat_map(|pair| [pair[1], pair[0]]).collect();
+        let n64: Vec<u8> = z64.chunks(4).flat_map(|word| word.iter().rev().copied().collect::<Vec<_>>()).collect();
+        for (data, format) in [(v64, DumpFormat::N64ByteSwapped), (n64, DumpFormat::N64LittleEndian)] {
+            let path = dir.path().join(format!("game.{}", format.name()));
+            std::fs::write(&path, &data).unwrap();
+            let (found, digests, size) = converted_digests(&path, 4096, HashAlgorithms::all()).unwrap().unwrap();
+            assert_eq!((found, digests, size), (format, expected.clone(), z64.len() as u64));
+
+            let converted = dir.path().join("converted.z64");
+            copy_converted(&path, &converted, format).unwrap();
+            assert_eq!(std::fs::read(&converted).unwrap(), z64);
+        }
+
+        let rom = |name: &str| RomEntry { name: name.to_string(), ..Default::default() };
+        assert!(DumpFormat::N64ByteSwapped.converts_to(&rom("Super Mario 64 (USA).z64")));
+        assert!(!DumpFormat::N64ByteSwapped.converts_to(&rom("Super Mario 64 (USA).v64")));
+    }
+
+    #[test]
+    fn test_smd_interleave() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut md: Vec<u8> = (0..=255u8).cycle().take(SMD_BLOCK * 8).collect();
+        md[0x100..0x104].copy_from_slice(b"SEGA");
+        let bin = dir.path().join("sonic.md");
+        std::fs::write(&bin, &md).unwrap();
+        let expected = hasher_optimized::calculate_hashes_optimized(&bin, 4096, HashAlgorithms::all()).unwrap();
+
+        // Without the AA BB signature, found by the SEGA in the first block
+        let mut smd = vec![0; SMD_HEADER as usize];
+        for block in md.chunks(SMD_BLOCK) {
+            smd.extend(block.iter().skip(1).step_by(2));
+            smd.extend(block.iter().step_by(2));
+        }
+        let path = dir.path().join("sonic.smd");
+        std::fs::write(&path, &smd).unwrap();
+        let (format, digests, size) = converted_digests(&path, 4096, HashAlgorithms::all()).unwrap().unwrap();
+        assert_eq!((format, digests, size), (DumpFormat::Smd, expected, md.len() as u64));
+
+        let converted = dir.path().join("converted.md");
+        copy_converted(&path, &converted, format).unwrap();
+        assert_eq!(std::fs::read(&converted).unwrap(), md);
+        assert_eq!(DumpFormat::of_file(&bin).unwrap(), None);
+    }
+}
@@ -1,111 +1,395 @@
 // src/scanner/hasher_optimized.rs - Optimized hash calculation with memory-mapped I/O
 
 use std::fs::{File, metadata};
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::sync::OnceLock;
+use std::sync::mpsc::{channel, sync_channel};
+use std::thread;
 
 use crc32fast::Hasher as Crc32Hasher;
 use md5::Md5;
 use sha1::Sha1;
+use sha2::Sha256;
 use digest::Digest;
 use hex;
 use memmap2::Mmap;
+use xxhash_rust::xxh64::Xxh64;
 
+use crate::config::Config;
 use crate::error::Result;
 use crate::cache::HashCache;
+use crate::types::{FileDigests, HashAlgorithms};
+use super::chd::chd_sha1;
+use super::compressed::Compression;
 
-/// Threshold for using memory-mapped I/O (10 MB)
-const MMAP_THRESHOLD: u64 = 10 * 1024 * 1024;
+/// Files larger than this are memory-mapped unless configured otherwise (10 MB)
+pub const DEFAULT_MMAP_THRESHOLD: u64 = 10 * 1024 * 1024;
 
-/// Calculate hashes with caching and memory-mapped I/O optimization
+/// The configured mmap threshold; None reads every file through buffers
+static MMAP_THRESHOLD: OnceLock<Option<u64>> = OnceLock::new();
+
+/// Memory-map files above `mmap_threshold`, or none with `--no-mmap`, which
+/// suits network filesystems where mapped reads stall or fail
+pub fn init(config: &Config) {
+    let _ = MMAP_THRESHOLD.set((!config.no_mmap).then_some(config.mmap_threshold));
+}
+
+/// Whether a file of `size` bytes is read through a memory map
+pub fn use_mmap(size: u64) -> bool {
+    MMAP_THRESHOLD.get().copied().unwrap_or(Some(DEFAULT_MMAP_THRESHOLD)).is_some_and(|threshold| size > threshold)
+}
+
+/// Chunks at least this large are fed to each digest on its own thread (1 MB)
+const PARALLEL_DIGEST_THRESHOLD: usize = 1024 * 1024;
+
+/// Memory-mapped files are digested in chunks of this size so the
+/// digest threads work on the same pages at the same time (8 MB)
+const MMAP_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Told the size of every chunk digested, see `with_digest_progress`
+type DigestHook = Box<dyn FnMut(u64)>;
+
+thread_local! {
+    static ON_DIGEST: RefCell<Option<DigestHook>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, calling `on_digest` with the size of each chunk digested on this
+/// thread meanwhile, so a progress bar can move through a large file
+pub fn with_digest_progress<T>(on_digest: impl FnMut(u64) + 'static, f: impl FnOnce() -> T) -> T {
+    let previous = ON_DIGEST.with(|hook| hook.borrow_mut().replace(Box::new(on_digest)));
+    let result = f();
+    ON_DIGEST.with(|hook| *hook.borrow_mut() = previous);
+    result
+}
+
+/// Running digests for the selected hash algorithms
+#[derive(Clone)]
+pub struct Digests {
+    crc: Option<Crc32Hasher>,
+    md5: Option<Md5>,
+    sha1: Option<Sha1>,
+    sha256: Option<Sha256>,
+}
+
+impl Digests {
+    pub fn new(algorithms: HashAlgorithms) -> Self {
+        Digests {
+            crc: algorithms.crc.then(Crc32Hasher::new),
+            md5: algorithms.md5.then(Md5::new),
+            sha1: algorithms.sha1.then(Sha1::new),
+            sha256: algorithms.sha256.then(Sha256::new),
+        }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        ON_DIGEST.with(|hook| {
+            if let Some(on_digest) = hook.borrow_mut().as_mut() {
+                on_digest(chunk.len() as u64);
+            }
+        });
+        
+        if chunk.len() >= PARALLEL_DIGEST_THRESHOLD {
+            // Large chunk: run CRC32, MD5, SHA1 and SHA256 side by side so
+            // a single file isn't limited to one core
+            let Digests { crc, md5, sha1, sha256 } = self;
+            rayon::join(
+                || rayon::join(
+                    || if let Some(crc) = crc { crc.update(chunk) },
+                    || if let Some(md5) = md5 { md5.update(chunk) },
+                ),
+                || rayon::join(
+                    || if let Some(sha1) = sha1 { sha1.update(chunk) },
+                    || if let Some(sha256) = sha256 { sha256.update(chunk) },
+                ),
+            );
+            return;
+        }
+        
+        if let Some(crc) = self.crc.as_mut() {
+            crc.update(chunk);
+        }
+        if let Some(md5) = self.md5.as_mut() {
+            md5.update(chunk);
+        }
+        if let Some(sha1) = self.sha1.as_mut() {
+            sha1.update(chunk);
+        }
+        if let Some(sha256) = self.sha256.as_mut() {
+            sha256.update(chunk);
+        }
+    }
+
+    /// The hex digests; algorithms that were not selected are empty
+    pub fn finalize(self) -> FileDigests {
+        FileDigests {
+            sha1: self.sha1.map(|h| hex::encode(h.finalize())).unwrap_or_default(),
+            md5: self.md5.map(|h| hex::encode(h.finalize())).unwrap_or_default(),
+            crc: self.crc.map(|h| format!("{:08x}", h.finalize())).unwrap_or_default(),
+            sha256: self.sha256.map(|h| hex::encode(h.finalize())).unwrap_or_default(),
+        }
+    }
+}
+
+/// Digests can be fed by anything that writes, like a decompressor
+impl Write for Digests {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Calculate hashes with caching and memory-mapped I/O optimization.
+/// Returns the digests and the size; a gzip, xz or bzip2 file is hashed by
+/// its decompressed payload, and the size is the payload's. A CHD gets the
+/// SHA1 from its header, which DATs list for disks, and no MD5 or CRC.
 pub fn calculate_hashes_cached(
     path: &Path, 
     buffer_size: usize, 
+    algorithms: HashAlgorithms,
     cache: &mut HashCache
-) -> Result<(String, String, String)> {
-    // Check cache first
-    if let Some(cached) = cache.get(path) {
-        return Ok((cached.sha1, cached.md5, cached.crc));
+) -> Result<(FileDigests, u64)> {
+    // Check cache first; an entry only counts if it has every hash we need
+    if let Some(cached) = cache.get(path)
+        && algorithms.is_satisfied_by(&cached.digests)
+    {
+        let size = cached.rom_size();
+        return Ok((cached.digests, size));
     }
     
     // Calculate hashes
-    let (sha1, md5, crc) = calculate_hashes_optimized(path, buffer_size)?;
+    let (digests, content_size) = if let Some(sha1) = chd_sha1(path)? {
+        (FileDigests { sha1, ..Default::default() }, None)
+    } else {
+        match Compression::detect(path)? {
+            Some(compression) => {
+                let mut digests = Digests::new(algorithms);
+                let size = compression.decompress(path, &mut digests)?;
+                (digests.finalize(), Some(size))
+            }
+            None => (calculate_hashes_optimized(path, buffer_size, algorithms)?, None),
+        }
+    };
     
     // Store in cache
-    cache.insert(path, sha1.clone(), md5.clone(), crc.clone())?;
+    cache.insert(path, digests.clone(), content_size)?;
     
-    Ok((sha1, md5, crc))
+    let size = match content_size {
+        Some(size) => size,
+        None => metadata(path)?.len(),
+    };
+    Ok((digests, size))
 }
 
-/// Calculate SHA1, MD5, and CRC32 hashes for a file with optimizations
-pub fn calculate_hashes_optimized(path: &Path, buffer_size: usize) -> Result<(String, String, String)> {
+/// Like `calculate_hashes_cached`, but first fingerprint the file with
+/// xxHash64, which is several times faster than the cryptographic digests.
+/// Content seen before (a copy, or a file whose timestamp changed) gets the
+/// digests it had then; only new content is fully hashed. A file of a size
+/// not fingerprinted before can't be a copy, so it is read once, for its
+/// digests and (unless compressed or a CHD) its fingerprint together. Also
+/// returns whether the digests were reused.
+pub fn calculate_hashes_prepass(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+    cache: &mut HashCache,
+) -> Result<(FileDigests, u64, bool)> {
+    if let Some(cached) = cache.get(path)
+        && algorithms.is_satisfied_by(&cached.digests)
+    {
+        let size = cached.rom_size();
+        return Ok((cached.digests, size, true));
+    }
+    
+    let file_size = metadata(path)?.len();
+    if !cache.has_fingerprints(file_size) {
+        if chd_sha1(path)?.is_some() || Compression::detect(path)?.is_some() {
+            let (digests, rom_size) = calculate_hashes_cached(path, buffer_size, algorithms, cache)?;
+            return Ok((digests, rom_size, false));
+        }
+        let (digests, fingerprint) = hash_and_fingerprint(path, buffer_size, algorithms)?;
+        cache.insert(path, digests.clone(), None)?;
+        cache.insert_fingerprint(file_size, fingerprint, digests.clone(), file_size);
+        return Ok((digests, file_size, false));
+    }
+    
+    let fingerprint = fingerprint(path, buffer_size)?;
+    if let Some((digests, rom_size)) = cache.get_fingerprint(file_size, fingerprint)
+        && algorithms.is_satisfied_by(&digests)
+    {
+        cache.insert(path, digests.clone(), (rom_size != file_size).then_some(rom_size))?;
+        return Ok((digests, rom_size, true));
+    }
+    
+    let (digests, rom_size) = calculate_hashes_cached(path, buffer_size, algorithms, cache)?;
+    cache.insert_fingerprint(file_size, fingerprint, digests.clone(), rom_size);
+    Ok((digests, rom_size, false))
+}
+
+/// xxHash64 of a file's bytes
+pub fn fingerprint(path: &Path, buffer_size: usize) -> Result<u64> {
+    let file = File::open(path)?;
+    let mut hasher = Xxh64::new(0);
+    if use_mmap(file.metadata()?.len()) {
+        hasher.update(&unsafe { Mmap::map(&file)? });
+    } else {
+        let mut reader = BufReader::new(file);
+        let mut buffer = vec![0; buffer_size];
+        loop {
+            match reader.read(&mut buffer)? {
+                0 => break,
+                n => hasher.update(&buffer[..n]),
+            }
+        }
+    }
+    Ok(hasher.digest())
+}
+
+/// The selected digests and the xxHash64 of a file, from a single read
+fn hash_and_fingerprint(path: &Path, buffer_size: usize, algorithms: HashAlgorithms) -> Result<(FileDigests, u64)> {
+    let file = File::open(path)?;
+    let mut digests = Digests::new(algorithms);
+    let mut hasher = Xxh64::new(0);
+    if use_mmap(file.metadata()?.len()) {
+        let mmap = unsafe { Mmap::map(&file)? };
+        #[cfg(unix)]
+        let _ = mmap.advise(memmap2::Advice::Sequential);
+        for chunk in mmap.chunks(MMAP_CHUNK_SIZE) {
+            hasher.update(chunk);
+            digests.update(chunk);
+        }
+    } else {
+        let mut reader = BufReader::new(file);
+        let mut buffer = vec![0; buffer_size];
+        loop {
+            match reader.read(&mut buffer)? {
+                0 => break,
+                n => {
+                    hasher.update(&buffer[..n]);
+                    digests.update(&buffer[..n]);
+                }
+            }
+        }
+    }
+    Ok((digests.finalize(), hasher.digest()))
+}
+
+/// Calculate the selected hashes for a file with optimizations
+pub fn calculate_hashes_optimized(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<FileDigests> {
     let file_size = metadata(path)?.len();
     
     // Use memory-mapped I/O for large files
-    if file_size > MMAP_THRESHOLD {
-        calculate_hashes_mmap(path)
+    if use_mmap(file_size) {
+        calculate_hashes_mmap(path, algorithms)
+    } else if file_size > buffer_size as u64 {
+        calculate_hashes_read_ahead(File::open(path)?, buffer_size, algorithms)
     } else {
-        calculate_hashes_buffered(path, buffer_size)
+        calculate_hashes_buffered(path, buffer_size, algorithms)
     }
 }
 
 /// Calculate hashes using memory-mapped I/O for large files
-fn calculate_hashes_mmap(path: &Path) -> Result<(String, String, String)> {
+fn calculate_hashes_mmap(path: &Path, algorithms: HashAlgorithms) -> Result<FileDigests> {
     let file = File::open(path)?;
     let mmap = unsafe { Mmap::map(&file)? };
+    // Let the kernel read ahead while the digests work on earlier pages
+    #[cfg(unix)]
+    let _ = mmap.advise(memmap2::Advice::Sequential);
     
-    let mut crc = Crc32Hasher::new();
-    let mut md5 = Md5::new();
-    let mut sha1 = Sha1::new();
+    // Process the memory-mapped file chunk by chunk
+    let mut digests = Digests::new(algorithms);
+    for chunk in mmap.chunks(MMAP_CHUNK_SIZE) {
+        digests.update(chunk);
+    }
     
-    // Process the entire memory-mapped file
-    let data = &mmap[..];
-    crc.update(data);
-    md5.update(data);
-    sha1.update(data);
+    Ok(digests.finalize())
+}
+
+/// Read a file on a thread of its own, one buffer ahead of the digests,
+/// so reading the next chunk overlaps hashing this one and a file of many
+/// chunks is bounded by the disk rather than the CPU
+fn calculate_hashes_read_ahead(
+    mut file: File,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<FileDigests> {
+    let (filled, chunks) = sync_channel::<std::io::Result<(Vec<u8>, usize)>>(1);
+    let (recycle, buffers) = channel::<Vec<u8>>();
+    for _ in 0..2 {
+        let _ = recycle.send(vec![0; buffer_size]);
+    }
     
-    Ok((
-        hex::encode(sha1.finalize()),
-        hex::encode(md5.finalize()),
-        format!("{:08x}", crc.finalize()),
-    ))
+    thread::scope(move |scope| {
+        scope.spawn(move || {
+            // Ends at the end of the file, or once the digests stop listening
+            for mut buffer in buffers {
+                let read = file.read(&mut buffer).map(|n| (buffer, n));
+                let done = matches!(read, Ok((_, 0)) | Err(_));
+                if filled.send(read).is_err() || done {
+                    break;
+                }
+            }
+        });
+        
+        let mut digests = Digests::new(algorithms);
+        for chunk in chunks {
+            let (buffer, n) = chunk?;
+            if n == 0 {
+                break;
+            }
+            digests.update(&buffer[..n]);
+            let _ = recycle.send(buffer);
+        }
+        Ok(digests.finalize())
+    })
 }
 
 /// Calculate hashes using buffered I/O for smaller files
-fn calculate_hashes_buffered(path: &Path, buffer_size: usize) -> Result<(String, String, String)> {
+fn calculate_hashes_buffered(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<FileDigests> {
     let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
+    calculate_hashes_reader(BufReader::new(file), buffer_size, algorithms)
+}
+
+/// Calculate the selected hashes for any byte stream (e.g. an archive member)
+pub fn calculate_hashes_reader(
+    mut reader: impl Read,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<FileDigests> {
     let mut buffer = vec![0; buffer_size];
 
-    let mut crc = Crc32Hasher::new();
-    let mut md5 = Md5::new();
-    let mut sha1 = Sha1::new();
+    let mut digests = Digests::new(algorithms);
 
     loop {
         match reader.read(&mut buffer)? {
             0 => break,
-            n => {
-                let chunk = &buffer[..n];
-                crc.update(chunk);
-                md5.update(chunk);
-                sha1.update(chunk);
-            }
+            n => digests.update(&buffer[..n]),
         }
     }
 
-    Ok((
-        hex::encode(sha1.finalize()),
-        hex::encode(md5.finalize()),
-        format!("{:08x}", crc.finalize()),
-    ))
+    Ok(digests.finalize())
 }
 
 /// Async version of hash calculation for use with tokio
 #[allow(dead_code)]
 pub async fn calculate_hashes_async(
     path: PathBuf, 
-    buffer_size: usize
-) -> Result<(String, String, String)> {
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<FileDigests> {
     use tokio::fs::File;
     use tokio::io::{AsyncReadExt, BufReader};
     
@@ -114,36 +398,25 @@ pub async fn calculate_hashes_async(
     let file_size = metadata.len();
     
     // For large files, use blocking thread pool with mmap
-    if file_size > MMAP_THRESHOLD {
+    if use_mmap(file_size) {
         tokio::task::spawn_blocking(move || {
-            calculate_hashes_mmap(&path)
+            calculate_hashes_mmap(&path, algorithms)
         }).await?
     } else {
         // Async buffered reading for smaller files
         let mut reader = BufReader::new(file);
         let mut buffer = vec![0; buffer_size];
         
-        let mut crc = Crc32Hasher::new();
-        let mut md5 = Md5::new();
-        let mut sha1 = Sha1::new();
+        let mut digests = Digests::new(algorithms);
         
         loop {
             match reader.read(&mut buffer).await? {
                 0 => break,
-                n => {
-                    let chunk = &buffer[..n];
-                    crc.update(chunk);
-                    md5.update(chunk);
-                    sha1.update(chunk);
-                }
+                n => digests.update(&buffer[..n]),
             }
         }
         
-        Ok((
-            hex::encode(sha1.finalize()),
-            hex::encode(md5.finalize()),
-            format!("{:08x}", crc.finalize()),
-        ))
+        Ok(digests.finalize())
     }
 }
 
@@ -152,8 +425,9 @@ pub async fn calculate_hashes_async(
 pub async fn calculate_hashes_batch(
     paths: Vec<PathBuf>,
     buffer_size: usize,
+    algorithms: HashAlgorithms,
     max_concurrent: usize,
-) -> Vec<Result<(PathBuf, String, String, String)>> {
+) -> Vec<Result<(PathBuf, FileDigests)>> {
     use tokio::sync::Semaphore;
     use std::sync::Arc;
     
@@ -165,13 +439,10 @@ pub async fn calculate_hashes_batch(
         let path_clone = path.clone();
         
         let task = tokio::spawn(async move {
-            let result = calculate_hashes_async(path_clone.clone(), buffer_size).await;
+            let result = calculate_hashes_async(path_clone.clone(), buffer_size, algorithms).await;
             drop(permit); // Release semaphore
             
-            match result {
-                Ok((sha1, md5, crc)) => Ok((path_clone, sha1, md5, crc)),
-                Err(e) => Err(e),
-            }
+            result.map(|digests| (path_clone, digests))
         });
         
         tasks.push(task);
@@ -206,11 +477,101 @@ mod tests {
         let mut file = fs::File::create(&file_path).unwrap();
         file.write_all(b"Hello, World!").unwrap();
         
-        let (sha1, md5, crc) = calculate_hashes_optimized(&file_path, 1024).unwrap();
+        let digests = calculate_hashes_optimized(&file_path, 1024, HashAlgorithms::all()).unwrap();
+        
+        assert_eq!(digests.sha1, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+        assert_eq!(digests.md5, "65a8e27d8879283831b664bd8b7f0ad4");
+        assert_eq!(digests.crc, "ec4ac3d0");
+        assert_eq!(digests.sha256, "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f");
+    }
+    
+    #[test]
+    fn test_hash_calculation_crc_only() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.rom");
+        
+        let mut file = fs::File::create(&file_path).unwrap();
+        file.write_all(b"Hello, World!").unwrap();
+        
+        let crc_only = HashAlgorithms { crc: true, ..HashAlgorithms::none() };
+        let digests = calculate_hashes_optimized(&file_path, 1024, crc_only).unwrap();
+        
+        assert!(digests.sha1.is_empty());
+        assert!(digests.md5.is_empty());
+        assert!(digests.sha256.is_empty());
+        assert_eq!(digests.crc, "ec4ac3d0");
+    }
+    
+    #[test]
+    fn test_parallel_digests_match_sequential() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("large.rom");
+        
+        let data: Vec<u8> = (0..3 * PARALLEL_DIGEST_THRESHOLD).map(|i| (i % 251) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+        
+        // 4 KB reads stay sequential, 2 MB reads go through the parallel path
+        let sequential = calculate_hashes_optimized(&file_path, 4096, HashAlgorithms::all()).unwrap();
+        let parallel = calculate_hashes_optimized(&file_path, 2 * PARALLEL_DIGEST_THRESHOLD, HashAlgorithms::all()).unwrap();
+        
+        assert_eq!(sequential, parallel);
+    }
+    
+    #[test]
+    fn test_read_ahead_matches_single_thread() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("chunks.rom");
+        
+        let data: Vec<u8> = (0..100_000).map(|i| (i % 241) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+        
+        let read_ahead = calculate_hashes_read_ahead(fs::File::open(&file_path).unwrap(), 4096, HashAlgorithms::all()).unwrap();
+        let single = calculate_hashes_reader(&data[..], 4096, HashAlgorithms::all()).unwrap();
+        
+        assert_eq!(read_ahead, single);
+    }
+    
+    #[test]
+    fn test_digest_progress_counts_every_chunk() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("chunks.rom");
+        fs::write(&file_path, vec![0x5A; 10_000]).unwrap();
+        
+        let seen = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = seen.clone();
+        let digests = with_digest_progress(
+            move |chunk| counter.set(counter.get() + chunk),
+            || calculate_hashes_optimized(&file_path, 1024, HashAlgorithms::all()),
+        ).unwrap();
+        
+        assert_eq!(seen.get(), 10_000);
+        assert_eq!(digests, calculate_hashes_reader(&vec![0x5A; 10_000][..], 1024, HashAlgorithms::all()).unwrap());
+        // The hook is gone once the closure returns
+        calculate_hashes_optimized(&file_path, 1024, HashAlgorithms::all()).unwrap();
+        assert_eq!(seen.get(), 10_000);
+    }
+    
+    #[test]
+    fn test_prepass_reuses_digests_of_known_content() {
+        let dir = tempdir().unwrap();
+        let original = dir.path().join("game.rom");
+        let copy = dir.path().join("copy.rom");
+        fs::write(&original, b"Hello, World!").unwrap();
+        fs::write(&copy, b"Hello, World!").unwrap();
+        let mut cache = HashCache::load(&dir.path().join("cache.bin"));
+        
+        let (digests, size, reused) = calculate_hashes_prepass(&original, 1024, HashAlgorithms::all(), &mut cache).unwrap();
+        assert!(!reused);
+        assert_eq!((digests.crc.as_str(), size), ("ec4ac3d0", 13));
+        assert_eq!(cache.get_fingerprint(13, fingerprint(&original, 1024).unwrap()), Some((digests.clone(), 13)));
+        let (copied, _, reused) = calculate_hashes_prepass(&copy, 1024, HashAlgorithms::all(), &mut cache).unwrap();
+        assert!(reused);
+        assert_eq!(copied, digests);
         
-        assert_eq!(sha1, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
-        assert_eq!(md5, "65a8e27d8879283831b664bd8b7f0ad4");
-        assert_eq!(crc, "ec4ac3d0");
+        fs::write(&copy, b"Hello, Worle!").unwrap();
+        let (changed, _, reused) = calculate_hashes_prepass(&copy, 1024, HashAlgorithms::all(), &mut cache).unwrap();
+        assert!(!reused);
+        assert_ne!(changed.crc, digests.crc);
     }
     
     #[tokio::test]
@@ -221,7 +582,7 @@ mod tests {
         let mut file = fs::File::create(&file_path).unwrap();
         file.write_all(b"Async test data").unwrap();
         
-        let result = calculate_hashes_async(file_path.to_path_buf(), 1024).await;
+        let result = calculate_hashes_async(file_path.to_path_buf(), 1024, HashAlgorithms::all()).await;
         assert!(result.is_ok());
     }
 }
\ No newline at end of file
@@ -0,0 +1,612 @@
+// src/scanner/header.rs - Copier headers left out of DAT hashes
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::config::{Config, LynxForm};
+use crate::error::{Result, RomAuditError};
+use crate::types::{DatHeader, FileDigests, FileHash, HashAlgorithms, RomEntry};
+use super::compressed::Compression;
+use super::hasher_optimized::{self, Digests};
+
+/// The No-Intro header skippers romaudit_cli knows without a headers directory
+const BUILT_IN: &[(&str, &str)] = &[
+    ("No-Intro_NES.xml", r#"<detector><name>Nintendo Famicom/NES</name>
+        <rule start_offset="10"><data offset="0" value="4E45531A" result="true"/></rule>
+    </detector>"#),
+    ("No-Intro_FDS.xml", r#"<detector><name>Nintendo Famicom Disk System</name>
+        <rule start_offset="10"><data offset="0" value="4644531A" result="true"/></rule>
+    </detector>"#),
+    ("No-Intro_LNX.xml", r#"<detector><name>Atari Lynx</name>
+        <rule start_offset="40"><data offset="0" value="4C594E58" result="true"/></rule>
+        <rule start_offset="40"><data offset="6" value="425339" result="true"/></rule>
+    </detector>"#),
+    ("No-Intro_A7800.xml", r#"<detector><name>Atari 7800</name>
+        <rule start_offset="80"><data offset="1" value="415441524937383030" result="true"/></rule>
+        <rule start_offset="80"><data offset="64" value="41435455414C20434152542044415441205354415254532048455245" result="true"/></rule>
+    </detector>"#),
+];
+
+/// Headers are at the start of a file; tests past this many bytes are never read
+const MAX_HEAD: usize = 64 * 1024;
+
+/// A header skip definition, as clrmamepro and No-Intro publish them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipper {
+    /// The file name DATs refer to it by, e.g. `No-Intro_NES.xml`
+    pub file_name: String,
+    pub name: String,
+    pub rules: Vec<Rule>,
+}
+
+/// One header layout: the tests that recognize it and the part of the file
+/// that is hashed without it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub start: u64,
+    /// None for the end of the file
+    pub end: Option<u64>,
+    pub operation: Operation,
+    tests: Vec<Test>,
+}
+
+/// How the bytes that are kept are rearranged before hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    None,
+    /// Reverse the bits of each byte
+    BitSwap,
+    /// Swap each pair of bytes
+    ByteSwap,
+    /// Reverse each group of four bytes
+    WordSwap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    Data { offset: u64, value: Vec<u8>, result: bool },
+    Masked { op: MaskOp, offset: u64, mask: Vec<u8>, value: Vec<u8>, result: bool },
+    Bit { offset: u64, bit: u8, value: bool, result: bool },
+    /// None for a power of two
+    FileSize { size: Option<u64>, operator: std::cmp::Ordering, result: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MaskOp {
+    And,
+    Or,
+    Xor,
+}
+
+/// What a file hashes to without its header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headerless {
+    pub digests: FileDigests,
+    /// Bytes hashed, which is what the DAT's size counts
+    pub size: u64,
+    /// Where the hashed bytes start
+    pub header: u64,
+}
+
+impl Skipper {
+    /// Read a detector XML: `<detector>` with a `<name>` and `<rule>`s of
+    /// `<data>`, `<and>`, `<or>`, `<xor>`, `<bit>` and `<file>` tests
+    pub fn parse(file_name: &str, xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+        let mut skipper = Skipper { file_name: file_name.to_string(), name: String::new(), rules: Vec::new() };
+        let mut in_name = false;
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) if e.name().as_ref() == b"name" => in_name = true,
+                Event::End(e) if e.name().as_ref() == b"name" => in_name = false,
+                Event::Text(e) if in_name => skipper.name.push_str(&e.decode().map_err(quick_xml::Error::from)?),
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"rule" => skipper.rules.push(read_rule(&e)?),
+                Event::Start(e) | Event::Empty(e) if skipper.rules.last().is_some() => {
+                    if let Some(test) = read_test(&e)?
+                        && let Some(rule) = skipper.rules.last_mut()
+                    {
+                        rule.tests.push(test);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        if skipper.rules.is_empty() {
+            return Err(RomAuditError::ParseError(format!("{}: header skipper without rules", file_name)));
+        }
+        Ok(skipper)
+    }
+
+    /// The first rule whose tests all pass for a file starting with `head`
+    pub fn matching_rule(&self, head: &[u8], file_size: u64) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.tests.iter().all(|test| test.passes(head, file_size)))
+    }
+
+    /// How many leading bytes the tests look at
+    fn head_len(&self) -> usize {
+        self.rules.iter().flat_map(|rule| &rule.tests).map(Test::reach).max().unwrap_or(0).min(MAX_HEAD)
+    }
+}
+
+impl Rule {
+    /// The part of a file of `file_size` bytes that is hashed; None when
+    /// the header would leave nothing
+    pub fn range(&self, file_size: u64) -> Option<(u64, u64)> {
+        let end = self.end.unwrap_or(file_size).min(file_size);
+        (self.start < end).then_some((self.start, end))
+    }
+}
+
+impl Operation {
+    fn apply(self, data: &mut [u8]) {
+        match self {
+            Operation::None => {}
+            Operation::BitSwap => data.iter_mut().for_each(|byte| *byte = byte.reverse_bits()),
+            Operation::ByteSwap => data.chunks_exact_mut(2).for_each(|pair| pair.swap(0, 1)),
+            Operation::WordSwap => data.chunks_exact_mut(4).for_each(|word| word.reverse()),
+        }
+    }
+}
+
+impl Test {
+    fn passes(&self, head: &[u8], file_size: u64) -> bool {
+        // An offset past the end of memory fails like one past the end of the file
+        let bytes = |offset: u64, len: usize| head.get(usize::try_from(offset).ok()?..end_of(offset, len)?);
+        match self {
+            Test::Data { offset, value, result } => (bytes(*offset, value.len()) == Some(value)) == *result,
+            Test::Masked { op, offset, mask, value, result } => {
+                let matches = bytes(*offset, mask.len()).is_some_and(|found| {
+                    found.iter().zip(mask).map(|(byte, mask)| match op {
+                        MaskOp::And => byte & mask,
+                        MaskOp::Or => byte | mask,
+                        MaskOp::Xor => byte ^ mask,
+                    }).eq(value.iter().copied())
+                });
+                matches == *result
+            }
+            Test::Bit { offset, bit, value, result } => {
+                let set = bytes(*offset, 1).map(|byte| byte[0] >> bit & 1 == 1);
+                (set == Some(*value)) == *result
+            }
+            Test::FileSize { size, operator, result } => {
+                let matches = match size {
+                    Some(size) => file_size.cmp(size) == *operator,
+                    None => file_size.is_power_of_two(),
+                };
+                matches == *result
+            }
+        }
+    }
+
+    /// The end of the bytes the test reads
+    fn reach(&self) -> usize {
+        let reach = match self {
+            Test::Data { offset, value, .. } => end_of(*offset, value.len()),
+            Test::Masked { offset, mask, .. } => end_of(*offset, mask.len()),
+            Test::Bit { offset, .. } => end_of(*offset, 1),
+            Test::FileSize { .. } => Some(0),
+        };
+        reach.unwrap_or(usize::MAX)
+    }
+}
+
+/// End of `len` bytes at `offset`, if it can be addressed at all
+fn end_of(offset: u64, len: usize) -> Option<usize> {
+    usize::try_from(offset).ok()?.checked_add(len)
+}
+
+fn read_rule(e: &BytesStart) -> Result<Rule> {
+    let mut rule = Rule { start: 0, end: None, operation: Operation::None, tests: Vec::new() };
+    for attr in e.attributes().flatten() {
+        let value = attr.unescape_value()?;
+        match attr.key.as_ref() {
+            b"start_offset" => rule.start = hex_number(&value)?,
+            b"end_offset" if !value.eq_ignore_ascii_case("EOF") => rule.end = Some(hex_number(&value)?),
+            b"operation" => rule.operation = match value.as_ref() {
+                "none" => Operation::None,
+                "bitswap" => Operation::BitSwap,
+                "byteswap" => Operation::ByteSwap,
+                "wordswap" => Operation::WordSwap,
+                other => return Err(RomAuditError::ParseError(format!("unknown header skipper operation '{}'", other))),
+            },
+            _ => {}
+        }
+    }
+    Ok(rule)
+}
+
+/// A test element of a rule; None for elements that aren't tests
+fn read_test(e: &BytesStart) -> Result<Option<Test>> {
+    let mut offset = 0;
+    let mut value = String::new();
+    let mut mask = String::new();
+    let mut bit = 0;
+    let mut size = String::new();
+    let mut operator = std::cmp::Ordering::Equal;
+    let mut result = true;
+    for attr in e.attributes().flatten() {
+        let text = attr.unescape_value()?;
+        match attr.key.as_ref() {
+            b"offset" => offset = hex_number(&text)?,
+            b"value" => value = text.to_string(),
+            b"mask" => mask = text.to_string(),
+            b"bit" => bit = text.parse().map_err(|_| RomAuditError::ParseError(format!("bad bit '{}'", text)))?,
+            b"size" => size = text.to_string(),
+            b"operator" => operator = match text.as_ref() {
+                "less" => std::cmp::Ordering::Less,
+                "greater" => std::cmp::Ordering::Greater,
+                _ => std::cmp::Ordering::Equal,
+            },
+            b"result" => result = text != "false",
+            _ => {}
+        }
+    }
+    let masked = |op| -> Result<Option<Test>> {
+        Ok(Some(Test::Masked { op, offset, mask: hex_bytes(&mask)?, value: hex_bytes(&value)?, result }))
+    };
+    match e.name().as_ref() {
+        b"data" => Ok(Some(Test::Data { offset, value: hex_bytes(&value)?, result })),
+        b"and" => masked(MaskOp::And),
+        b"or" => masked(MaskOp::Or),
+        b"xor" => masked(MaskOp::Xor),
+        b"bit" => Ok(Some(Test::Bit { offset, bit: bit.min(7), value: value == "1", result })),
+        b"file" => {
+            let size = if size.eq_ignore_ascii_case("PO2") { None } else { Some(hex_number(&size)?) };
+            Ok(Some(Test::FileSize { size, operator, result }))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn hex_number(text: &str) -> Result<u64> {
+    u64::from_str_radix(text.trim(), 16).map_err(|_| RomAuditError::ParseError(format!("bad hex number '{}'", text)))
+}
+
+fn hex_bytes(text: &str) -> Result<Vec<u8>> {
+    hex::decode(text.trim()).map_err(|_| RomAuditError::ParseError(format!("bad hex bytes '{}'", text)))
+}
+
+/// The skippers a scan tries, set once the DAT is known
+static SKIPPERS: OnceLock<Vec<Skipper>> = OnceLock::new();
+
+/// Load the built-in skippers and those in `header_skippers`, which replace
+/// built-ins of the same file name. A DAT that names its skipper with
+/// `<clrmamepro header>` gets just that one; otherwise all of them are
+/// tried, and their tests keep them to the files they were written for.
+pub fn init(config: &Config, dat_header: &DatHeader) -> Result<()> {
+    let mut skippers = Vec::new();
+    if let Some(dir) = &config.header_skippers {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("xml")) {
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                skippers.push(Skipper::parse(&file_name, &std::fs::read_to_string(&path)?)?);
+            }
+        }
+    }
+    for (file_name, xml) in BUILT_IN {
+        if !skippers.iter().any(|s: &Skipper| s.file_name.eq_ignore_ascii_case(file_name)) {
+            skippers.push(Skipper::parse(file_name, xml)?);
+        }
+    }
+    if let Some(wanted) = &dat_header.skipper {
+        match skippers.iter().position(|s| s.file_name.eq_ignore_ascii_case(wanted)) {
+            Some(index) => {
+                println!("{}", tr!("dat-header-skipper", name = skippers[index].name.clone()));
+                skippers = vec![skippers.swap_remove(index)];
+            }
+            None => println!("{}", tr!("dat-header-skipper-missing", file = wanted.clone())),
+        }
+    }
+    let _ = SKIPPERS.set(skippers);
+    Ok(())
+}
+
+/// The skippers `init` loaded; none before it runs
+pub fn skippers() -> &'static [Skipper] {
+    SKIPPERS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Hash a plain file without the header the first matching skipper finds;
+/// None when no skipper recognizes it. Archives and CHDs never have one.
+pub fn headerless_digests(
+    path: &Path,
+    skippers: &[Skipper],
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+) -> Result<Option<Headerless>> {
+    if skippers.is_empty() || Compression::detect(path)?.is_some() || super::chd::chd_sha1(path)?.is_some() {
+        return Ok(None);
+    }
+
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let head_len = skippers.iter().map(Skipper::head_len).max().unwrap_or(0);
+    let mut head = Vec::with_capacity(head_len);
+    (&mut file).take(head_len as u64).read_to_end(&mut head)?;
+    let Some(rule) = skippers.iter().find_map(|skipper| skipper.matching_rule(&head, file_size)) else {
+        return Ok(None);
+    };
+    let Some((start, end)) = rule.range(file_size) else {
+        return Ok(None);
+    };
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut kept = BufReader::new(file).take(end - start);
+    let digests = if rule.operation == Operation::None {
+        hasher_optimized::calculate_hashes_reader(kept, buffer_size, algorithms)?
+    } else {
+        let mut data = Vec::new();
+        kept.read_to_end(&mut data)?;
+        rule.operation.apply(&mut data);
+        let mut digests = Digests::new(algorithms);
+        digests.update(&data);
+        digests.finalize()
+    };
+    Ok(Some(Headerless { digests, size: end - start, header: start }))
+}
+
+/// A file that matched nothing may be a dump whose copier header the DAT's
+/// hashes leave out: the file with the digests, size and matches of what
+/// follows its header, when that matches
+pub fn headerless_match(
+    path: &Path,
+    buffer_size: usize,
+    algorithms: HashAlgorithms,
+    find_matches: impl Fn(&FileDigests, u64) -> Vec<RomEntry>,
+) -> Result<Option<FileHash>> {
+    let Some(headerless) = headerless_digests(path, skippers(), buffer_size, algorithms)? else {
+        return Ok(None);
+    };
+    let matching_entries = find_matches(&headerless.digests, headerless.size);
+    Ok((!matching_entries.is_empty()).then(|| FileHash {
+        path: path.to_path_buf(),
+        digests: headerless.digests,
+        size: headerless.size,
+        matching_entries,
+    }))
+}
+
+/// A copier header that an organized copy can leave out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopierHeader {
+    pub len: u64,
+    /// `iNES`, `NES 2.0`, `LNX`, or the name of the skipper that found it
+    pub kind: String,
+}
+
+/// What organizing does with a dump's copier header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderChange {
+    /// Leave out the header the file was matched without
+    Strip(CopierHeader),
+    /// Put a header in front of a headerless dump
+    Add { header: Vec<u8>, kind: String },
+}
+
+impl HeaderChange {
+    /// `stripped iNES`, `added LNX`
+    pub fn describe(&self) -> String {
+        match self {
+            HeaderChange::Strip(header) => format!("stripped {}", header.kind),
+            HeaderChange::Add { kind, .. } => format!("added {}", kind),
+        }
+    }
+}
+
+/// The header of a file that was matched without it: what comes before
+/// the part whose size the file was matched at. None for other files.
+pub fn matched_header(file_hash: &FileHash, skippers: &[Skipper]) -> Result<Option<CopierHeader>> {
+    let file_size = std::fs::metadata(&file_hash.path)?.len();
+    if file_size <= file_hash.size || file_hash.matching_entries.is_empty() {
+        return Ok(None);
+    }
+    let head_len = skippers.iter().map(Skipper::head_len).max().unwrap_or(0);
+    let mut head = Vec::with_capacity(head_len);
+    File::open(&file_hash.path)?.take(head_len as u64).read_to_end(&mut head)?;
+    for skipper in skippers {
+        if let Some(rule) = skipper.matching_rule(&head, file_size)
+            && rule.range(file_size).is_some_and(|(start, end)| end - start == file_hash.size)
+        {
+            let kind = header_kind(&head).unwrap_or(&skipper.name).to_string();
+            return Ok(Some(CopierHeader { len: rule.start, kind }));
+        }
+    }
+    Ok(None)
+}
+
+/// `iNES` or `NES 2.0` for a file starting with an iNES header (NES 2.0
+/// sets bits 2-3 of byte 7 to 10), `LNX` for an Atari Lynx one
+pub fn header_kind(head: &[u8]) -> Option<&'static str> {
+    match head {
+        [b'N', b'E', b'S', 0x1a, _, _, _, flags, ..] if flags & 0x0c == 0x08 => Some("NES 2.0"),
+        [b'N', b'E', b'S', 0x1a, ..] => Some("iNES"),
+        [b'L', b'Y', b'N', b'X', ..] => Some("LNX"),
+        _ => None,
+    }
+}
+
+/// What to do with the header of a file organized as `rom`: strip the one
+/// it was matched without when `strip_headers` is set, and for Lynx ROMs
+/// whatever `lynx_form` asks. None leaves the file as it is.
+pub fn header_change(
+    file_hash: &FileHash,
+    rom: &RomEntry,
+    strip_headers: bool,
+    lynx_form: LynxForm,
+    skippers: &[Skipper],
+) -> Result<Option<HeaderChange>> {
+    if let Some(header) = matched_header(file_hash, skippers)? {
+        let strip = match lynx_form {
+            LynxForm::Headered if header.kind == "LNX" => false,
+            LynxForm::Headerless if header.kind == "LNX" => true,
+            _ => strip_headers,
+        };
+        return Ok(strip.then_some(HeaderChange::Strip(header)));
+    }
+    if lynx_form != LynxForm::Headered || !is_lynx_rom(rom) {
+        return Ok(None);
+    }
+    let mut head = [0; 4];
+    let read = File::open(&file_hash.path)?.read(&mut head)?;
+    if header_kind(&head[..read]) == Some("LNX") {
+        return Ok(None);
+    }
+    Ok(lnx_header(file_hash.size, &rom.game).map(|header| HeaderChange::Add { header, kind: "LNX".to_string() }))
+}
+
+/// Lynx ROMs are `.lyx` without a header and `.lnx` with one
+fn is_lynx_rom(rom: &RomEntry) -> bool {
+    Path::new(&rom.name).extension().and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lyx") || ext.eq_ignore_ascii_case("lnx"))
+}
+
+/// The 64-byte LNX header for a headerless Lynx ROM: the page size of bank
+/// 0 (the ROM holds 256 pages), version 1, the cartridge name, no
+/// manufacturer and no rotation. None for a size no cartridge has.
+pub fn lnx_header(rom_size: u64, name: &str) -> Option<Vec<u8>> {
+    let page_size = u16::try_from(rom_size / 256).ok().filter(|pages| *pages > 0 && rom_size.is_multiple_of(256))?;
+    let mut header = vec![0; 64];
+    header[..4].copy_from_slice(b"LYNX");
+    header[4..6].copy_from_slice(&page_size.to_le_bytes());
+    header[8..10].copy_from_slice(&1u16.to_le_bytes());
+    let name: Vec<u8> = name.bytes().filter(u8::is_ascii).take(31).collect();
+    header[10..10 + name.len()].copy_from_slice(&name);
+    Some(header)
+}
+
+/// Copy a file with its header stripped or added; returns the bytes written
+pub fn copy_with_change(from: &Path, to: &Path, change: &HeaderChange) -> Result<u64> {
+    let mut source = BufReader::new(File::open(from)?);
+    let mut target = std::io::BufWriter::new(File::create(to)?);
+    let mut written = 0;
+    match change {
+        HeaderChange::Strip(header) => {
+            source.seek(SeekFrom::Start(header.len))?;
+        }
+        HeaderChange::Add { header, .. } => {
+            target.write_all(header)?;
+            written += header.len() as u64;
+        }
+    }
+    written += std::io::copy(&mut source, &mut target)?;
+    target.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headerless_digests_of_headered_dumps() {
+        let built_in: Vec<Skipper> = BUILT_IN.iter().map(|(file_name, xml)| Skipper::parse(file_name, xml).unwrap()).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let rom: Vec<u8> = (0..=255).cycle().take(32 * 1024).collect();
+        let plain = dir.path().join("plain.nes");
+        std::fs::write(&plain, &rom).unwrap();
+        let expected = hasher_optimized::calculate_hashes_optimized(&plain, 4096, HashAlgorithms::all()).unwrap();
+
+        let mut nes = b"NES\x1a\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        nes.extend_from_slice(&rom);
+        let headered = dir.path().join("headered.nes");
+        std::fs::write(&headered, &nes).unwrap();
+        let headerless = headerless_digests(&headered, &built_in, 4096, HashAlgorithms::all()).unwrap().unwrap();
+        assert_eq!(headerless, Headerless { digests: expected, size: rom.len() as u64, header: 16 });
+
+        // Rules after the first, a file that only starts like a header, and no header at all
+        let mut lynx = vec![0; 64];
+        lynx[6..9].copy_from_slice(b"BS9");
+        lynx.extend_from_slice(&rom);
+        std::fs::write(&headered, &lynx).unwrap();
+        assert_eq!(headerless_digests(&headered, &built_in, 4096, HashAlgorithms::all()).unwrap().unwrap().header, 64);
+        std::fs::write(&headered, b"NES\x1a").unwrap();
+        assert_eq!(headerless_digests(&headered, &built_in, 4096, HashAlgorithms::all()).unwrap(), None);
+        assert_eq!(headerless_digests(&plain, &built_in, 4096, HashAlgorithms::all()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_strip_nes_2_header() {
+        let built_in: Vec<Skipper> = BUILT_IN.iter().map(|(file_name, xml)| Skipper::parse(file_name, xml).unwrap()).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let rom = vec![0x4c; 8192];
+        let mut nes = b"NES\x1a\x01\x01\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        nes.extend_from_slice(&rom);
+        let path = dir.path().join("game.nes");
+        std::fs::write(&path, &nes).unwrap();
+
+        let entry = RomEntry { game: "Game".to_string(), name: "Game.nes".to_string(), size: Some(8192), ..Default::default() };
+        let mut file_hash = FileHash { path: path.clone(), digests: FileDigests::default(), size: 8192, matching_entries: vec![entry] };
+        let header = matched_header(&file_hash, &built_in).unwrap().unwrap();
+        assert_eq!(header, CopierHeader { len: 16, kind: "NES 2.0".to_string() });
+
+        let stripped = dir.path().join("Game.nes");
+        assert_eq!(copy_with_change(&path, &stripped, &HeaderChange::Strip(header)).unwrap(), 8192);
+        assert_eq!(std::fs::read(&stripped).unwrap(), rom);
+
+        // A file matched with its header has nothing to strip
+        file_hash.size = nes.len() as u64;
+        assert_eq!(matched_header(&file_hash, &built_in).unwrap(), None);
+        assert_eq!(header_kind(b"NES\x1a\x01\x01\x00\x00"), Some("iNES"));
+    }
+
+    #[test]
+    fn test_lynx_form() {
+        let built_in: Vec<Skipper> = BUILT_IN.iter().map(|(file_name, xml)| Skipper::parse(file_name, xml).unwrap()).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let rom: Vec<u8> = (0..=255).cycle().take(128 * 1024).collect();
+        let plain = dir.path().join("game.lyx");
+        std::fs::write(&plain, &rom).unwrap();
+        let entry = RomEntry { game: "Chip's Challenge (USA, Europe)".to_string(), name: "Chip's Challenge (USA, Europe).lyx".to_string(), ..Default::default() };
+        let file_hash = FileHash { path: plain.clone(), digests: FileDigests::default(), size: rom.len() as u64, matching_entries: vec![entry.clone()] };
+
+        // A headerless dump gets a made-up header, which the skipper then finds
+        let change = header_change(&file_hash, &entry, false, LynxForm::Headered, &built_in).unwrap().unwrap();
+        assert_eq!(change.describe(), "added LNX");
+        let headered = dir.path().join("game.lnx");
+        assert_eq!(copy_with_change(&plain, &headered, &change).unwrap(), rom.len() as u64 + 64);
+        let data = std::fs::read(&headered).unwrap();
+        assert_eq!((&data[..6], &data[10..16]), (&b"LYNX\x00\x02"[..], &b"Chip's"[..]));
+        let expected = hasher_optimized::calculate_hashes_optimized(&plain, 4096, HashAlgorithms::all()).unwrap();
+        assert_eq!(headerless_digests(&headered, &built_in, 4096, HashAlgorithms::all()).unwrap().unwrap().digests, expected);
+
+        // The headered copy matched without its header is stripped or kept as asked
+        let file_hash = FileHash { path: headered, ..file_hash };
+        let stripped = header_change(&file_hash, &entry, false, LynxForm::Headerless, &built_in).unwrap().unwrap();
+        assert_eq!(stripped.describe(), "stripped LNX");
+        assert_eq!(header_change(&file_hash, &entry, true, LynxForm::Headered, &built_in).unwrap(), None);
+        assert_eq!(header_change(&file_hash, &entry, false, LynxForm::AsFound, &built_in).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_skipper_tests() {
+        let skipper = Skipper::parse("test.xml", r#"<?xml version="1.0"?>
+            <detector>
+                <name>Test</name>
+                <rule start_offset="200" end_offset="EOF" operation="byteswap">
+                    <file size="PO2" result="false"/>
+                    <and offset="0" mask="F0" value="A0"/>
+                    <bit offset="1" bit="0" value="1"/>
+                </rule>
+            </detector>"#).unwrap();
+        assert_eq!(skipper.name, "Test");
+        let rule = skipper.matching_rule(&[0xA5, 0x01], 0x300).unwrap();
+        assert_eq!((rule.range(0x300), rule.operation), (Some((0x200, 0x300)), Operation::ByteSwap));
+        assert!(skipper.matching_rule(&[0xA5, 0x01], 0x400).is_none());
+        assert!(skipper.matching_rule(&[0xB5, 0x01], 0x300).is_none());
+        assert!(skipper.matching_rule(&[0xA5, 0x02], 0x300).is_none());
+
+        // Offsets too large to address fail instead of overflowing
+        let skipper = Skipper::parse("huge.xml", r#"<detector><name>Huge</name>
+            <rule><data offset="FFFFFFFFFFFFFFFF" value="00" result="true"/></rule></detector>"#).unwrap();
+        assert!(skipper.matching_rule(&[0; 16], 16).is_none());
+        assert_eq!(skipper.head_len(), MAX_HEAD);
+    }
+}
@@ -0,0 +1,113 @@
+// src/scanner/identify.rs - Identify a single file against the DAT
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::error::Result;
+use crate::types::{FileDigests, HashAlgorithms, RomDb, RomEntry};
+use super::{find_matching_entries, hasher_optimized};
+
+/// ZIP local file header signature
+const ZIP_MAGIC: &[u8; 4] = b"PK\x03\x04";
+
+/// A hashed file, or one member of a ZIP archive, with the DAT entries it matches
+pub struct IdentifiedItem {
+    pub label: String,
+    pub size: u64,
+    pub digests: FileDigests,
+    pub matches: Vec<RomEntry>,
+}
+
+/// Hash a file (every member, if it is a ZIP archive or an unmatched disc
+/// image; the header SHA1 of a CHD) and look each one up in the DAT
+pub fn identify_file(path: &Path, rom_db: &RomDb, buffer_size: usize) -> Result<Vec<IdentifiedItem>> {
+    let algorithms = HashAlgorithms::all();
+
+    if let Some(sha1) = super::chd::chd_sha1(path)? {
+        let size = std::fs::metadata(path)?.len();
+        return Ok(vec![identified(path.display().to_string(), size, FileDigests { sha1, ..Default::default() }, rom_db)]);
+    }
+    if !is_zip(path)? {
+        let digests = hasher_optimized::calculate_hashes_optimized(path, buffer_size, algorithms)?;
+        let size = std::fs::metadata(path)?.len();
+        let mut items = vec![identified(path.display().to_string(), size, digests, rom_db)];
+        // An image that isn't itself in the DAT may hold files that are
+        if items[0].matches.is_empty()
+            && let Some(members) = super::disc_image::list_members(path)?
+        {
+            items.extend(super::disc_image::hash_members(path, &members, rom_db, buffer_size, algorithms)?);
+        }
+        return Ok(items);
+    }
+
+    let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let mut items = Vec::new();
+    for index in 0..archive.len() {
+        let member = archive.by_index(index)?;
+        if member.is_dir() {
+            continue;
+        }
+        let label = format!("{}#{}", path.display(), member.name());
+        let size = member.size();
+        let digests = hasher_optimized::calculate_hashes_reader(member, buffer_size, algorithms)?;
+        items.push(identified(label, size, digests, rom_db));
+    }
+
+    Ok(items)
+}
+
+pub fn identified(label: String, size: u64, digests: FileDigests, rom_db: &RomDb) -> IdentifiedItem {
+    let mut matches = find_matching_entries(rom_db, &digests, size);
+    matches.sort_by(|a, b| (&a.game, &a.name).cmp(&(&b.game, &b.name)));
+
+    IdentifiedItem { label, size, digests, matches }
+}
+
+pub fn is_zip(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path)?;
+    let read = file.read(&mut magic)?;
+    Ok(read == magic.len() && &magic == ZIP_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use crate::types::{RomHashes, RomStatus};
+
+    #[test]
+    fn test_identify_zip_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pack.zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("a.bin", options).unwrap();
+        writer.write_all(b"AAAA").unwrap();
+        writer.start_file("b.bin", options).unwrap();
+        writer.write_all(b"unknown").unwrap();
+        writer.finish().unwrap();
+
+        let entry = RomEntry {
+            name: "a.bin".to_string(),
+            game: "Game A".to_string(),
+            hashes: RomHashes { crc: Some("9b0d08f1".to_string()), ..Default::default() },
+            size: Some(4),
+            is_disk: false,
+            optional: false,
+            merge: None,
+            status: RomStatus::Good,
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert("9b0d08f1".to_string(), vec![entry]);
+
+        let items = identify_file(&path, &rom_db, 4096).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items[0].label.ends_with("pack.zip#a.bin"));
+        assert_eq!(items[0].size, 4);
+        assert_eq!(items[0].matches.len(), 1);
+        assert!(items[1].matches.is_empty());
+    }
+}
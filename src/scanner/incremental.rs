@@ -1,13 +1,15 @@
 // src/scanner/incremental.rs - Incremental scanning logic
 
 use std::collections::HashMap;
-use std::fs::metadata;
+use std::fs::{metadata, File};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{Config, MtimeTolerance};
 use crate::error::Result;
-use crate::types::{FileHash, RomDb};
+use crate::types::{FileDigests, FileHash, RomDb};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileScanState {
@@ -16,6 +18,49 @@ pub struct FileScanState {
     pub modified: SystemTime,
     pub sha1: String,
     pub last_scanned: SystemTime,
+    /// Hash of samples of the contents, recorded where spot checks are enabled
+    #[serde(default)]
+    pub sample: Option<String>,
+}
+
+/// Whether a recorded modification time still describes the file
+pub fn mtime_matches(recorded: SystemTime, current: SystemTime, tolerance: MtimeTolerance) -> bool {
+    mtime_difference(recorded, current, tolerance).is_some()
+}
+
+/// How far apart two modification times are after allowing for the
+/// tolerance: zero if they are equal, None if the file has changed
+fn mtime_difference(recorded: SystemTime, current: SystemTime, tolerance: MtimeTolerance) -> Option<Duration> {
+    /// DST and time zone offsets are whole quarter hours, at most 14 hours
+    const SHIFT_STEP: u64 = 15 * 60;
+    const MAX_SHIFT: u64 = 14 * 60 * 60;
+    
+    let difference = recorded.duration_since(current).or_else(|_| current.duration_since(recorded)).ok()?;
+    let granularity = Duration::from_secs(tolerance.granularity_secs);
+    if difference <= granularity {
+        return Some(difference);
+    }
+    if tolerance.ignore_shifts && difference.as_secs() <= MAX_SHIFT + tolerance.granularity_secs {
+        let shift = Duration::from_secs((difference.as_secs() + SHIFT_STEP / 2) / SHIFT_STEP * SHIFT_STEP);
+        let off_by = difference.abs_diff(shift);
+        return (off_by <= granularity).then_some(difference);
+    }
+    None
+}
+
+/// Hash of the first, middle and last 64 KiB of a file
+pub fn sample_hash(path: &Path) -> std::io::Result<String> {
+    const SAMPLE: u64 = 64 * 1024;
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; SAMPLE as usize];
+    for offset in [0, size.saturating_sub(SAMPLE) / 2, size.saturating_sub(SAMPLE)] {
+        file.seek(SeekFrom::Start(offset))?;
+        let read = file.by_ref().take(SAMPLE).read(&mut buffer)?;
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,48 +68,49 @@ pub struct IncrementalScanState {
     pub files: HashMap<PathBuf, FileScanState>,
     pub last_full_scan: Option<SystemTime>,
     pub version: u32,
+    /// Where the state was loaded from and is saved to
+    #[serde(skip)]
+    path: PathBuf,
 }
 
 impl IncrementalScanState {
     const STATE_VERSION: u32 = 1;
-    const STATE_FILE: &'static str = ".romaudit_scan_state.json";
+    pub const STATE_FILE: &'static str = ".romaudit_scan_state.json";
     
     pub fn new() -> Self {
         IncrementalScanState {
             files: HashMap::new(),
             last_full_scan: None,
             version: Self::STATE_VERSION,
+            path: PathBuf::from(Self::STATE_FILE),
         }
     }
     
     /// Load scan state from disk
-    pub fn load() -> Result<Self> {
-        let state_path = Path::new(Self::STATE_FILE);
-        if !state_path.exists() {
-            return Ok(Self::new());
-        }
-        
-        let content = std::fs::read_to_string(state_path)?;
-        let state: IncrementalScanState = serde_json::from_str(&content)?;
+    pub fn load(state_path: &Path) -> Self {
+        let fresh = IncrementalScanState { path: state_path.to_path_buf(), ..Self::new() };
+        let Ok(content) = std::fs::read_to_string(state_path) else {
+            return fresh;
+        };
         
-        if state.version != Self::STATE_VERSION {
-            // Version mismatch, start fresh
-            Ok(Self::new())
-        } else {
-            Ok(state)
+        match serde_json::from_str::<IncrementalScanState>(&content) {
+            Ok(state) if state.version == Self::STATE_VERSION => IncrementalScanState { path: fresh.path, ..state },
+            // Corrupted or version mismatch, start fresh
+            _ => fresh,
         }
     }
     
     /// Save scan state to disk
     pub fn save(&self) -> Result<()> {
-        let state_path = Path::new(Self::STATE_FILE);
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(state_path, content)?;
+        std::fs::write(&self.path, content)?;
         Ok(())
     }
     
-    /// Check if a file needs to be rescanned
-    pub fn needs_rescan(&self, path: &Path) -> bool {
+    /// Check if a file needs to be rescanned. Within the tolerance a file
+    /// counts as unchanged; with spot checks, only if samples of its
+    /// contents still hash the same.
+    pub fn needs_rescan(&self, path: &Path, tolerance: MtimeTolerance) -> bool {
         if !path.exists() {
             return false;
         }
@@ -76,15 +122,20 @@ impl IncrementalScanState {
         match self.files.get(path) {
             Some(state) => {
                 // Check if file has been modified
-                state.size != meta.len() || 
-                state.modified != meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                match mtime_difference(state.modified, modified, tolerance) {
+                    _ if state.size != meta.len() => true,
+                    None => true,
+                    Some(difference) if difference.is_zero() || !tolerance.spot_check => false,
+                    Some(_) => state.sample.is_none() || state.sample.as_deref() != sample_hash(path).ok().as_deref(),
+                }
             }
             None => true, // New file, needs scanning
         }
     }
     
     /// Update the state for a scanned file
-    pub fn update_file(&mut self, path: &Path, sha1: String) -> Result<()> {
+    pub fn update_file(&mut self, path: &Path, sha1: String, tolerance: MtimeTolerance) -> Result<()> {
         let meta = metadata(path)?;
         
         let state = FileScanState {
@@ -93,6 +144,7 @@ impl IncrementalScanState {
             modified: meta.modified()?,
             sha1,
             last_scanned: SystemTime::now(),
+            sample: if tolerance.spot_check { Some(sample_hash(path)?) } else { None },
         };
         
         self.files.insert(path.to_path_buf(), state);
@@ -106,9 +158,9 @@ impl IncrementalScanState {
     }
     
     /// Get files that need scanning based on current state
-    pub fn get_files_to_scan(&self, all_files: &[PathBuf]) -> Vec<PathBuf> {
+    pub fn get_files_to_scan(&self, all_files: &[PathBuf], config: &Config) -> Vec<PathBuf> {
         all_files.iter()
-            .filter(|path| self.needs_rescan(path))
+            .filter(|path| self.needs_rescan(path, config.mtime_tolerance(path)))
             .cloned()
             .collect()
     }
@@ -167,10 +219,13 @@ pub async fn incremental_scan(
     
     // Collect all files
     let config = crate::config::Config::default();
-    let all_files = collect_files_recursively(base_path, &config)?;
+    let all_files = collect_files_recursively(base_path, &config, &mut Vec::new())?;
+    
+    // Only compute the digests the DAT actually uses
+    let algorithms = crate::types::HashAlgorithms::from_rom_db(rom_db);
     
     // Determine which files need scanning
-    let files_to_scan = scan_state.get_files_to_scan(&all_files);
+    let files_to_scan = scan_state.get_files_to_scan(&all_files, &config);
     
     println!("Incremental scan: {} files to check, {} need scanning", 
              all_files.len(), files_to_scan.len());
@@ -186,10 +241,10 @@ pub async fn incremental_scan(
                 if let Some(entries) = rom_db.get(&state.sha1) {
                     results.push(FileHash {
                         path: file_path.clone(),
-                        sha1: state.sha1.clone(),
-                        md5: String::new(), // Not stored in incremental state
-                        crc: String::new(), // Not stored in incremental state
-                        matching_entries: entries.clone(),
+                        // Only the primary hash is stored in incremental state
+                        digests: FileDigests { sha1: state.sha1.clone(), ..Default::default() },
+                        size: state.size,
+                        matching_entries: crate::scanner::prefer_size_matches(entries.clone(), state.size),
                     });
                 }
             }
@@ -198,19 +253,23 @@ pub async fn incremental_scan(
     
     // Scan only the files that need it
     for file_path in files_to_scan {
-        match calculate_hashes_cached(&file_path, buffer_size, cache) {
-            Ok((sha1, md5, crc)) => {
+        match calculate_hashes_cached(&file_path, buffer_size, algorithms, cache) {
+            Ok((digests, size)) => {
+                let file_hash = FileHash {
+                    path: file_path,
+                    digests,
+                    size,
+                    matching_entries: Vec::new(),
+                };
+                
                 // Update scan state
-                scan_state.update_file(&file_path, sha1.clone())?;
+                scan_state.update_file(&file_hash.path, file_hash.primary_hash().to_string(), MtimeTolerance::default())?;
                 
                 // Look up matching ROM entries
-                if let Some(entries) = rom_db.get(&sha1) {
+                if let Some(entries) = rom_db.get(file_hash.primary_hash()) {
                     results.push(FileHash {
-                        path: file_path,
-                        sha1,
-                        md5,
-                        crc,
-                        matching_entries: entries.clone(),
+                        matching_entries: crate::scanner::prefer_size_matches(entries.clone(), file_hash.size),
+                        ..file_hash
                     });
                 }
             }
@@ -255,12 +314,14 @@ mod tests {
         
         let mut state = IncrementalScanState::new();
         
+        let exact = MtimeTolerance::default();
+        
         // New file should need rescan
-        assert!(state.needs_rescan(&file_path));
+        assert!(state.needs_rescan(&file_path, exact));
         
         // After updating, should not need rescan
-        state.update_file(&file_path, "fake_sha1".to_string()).unwrap();
-        assert!(!state.needs_rescan(&file_path));
+        state.update_file(&file_path, "fake_sha1".to_string(), exact).unwrap();
+        assert!(!state.needs_rescan(&file_path, exact));
         
         // Modify file
         std::thread::sleep(std::time::Duration::from_millis(10));
@@ -271,6 +332,34 @@ mod tests {
         file.write_all(b"more data").unwrap();
         
         // Should need rescan after modification
-        assert!(state.needs_rescan(&file_path));
+        assert!(state.needs_rescan(&file_path, exact));
+    }
+    
+    #[test]
+    fn test_mtime_tolerance() {
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let fat = MtimeTolerance { granularity_secs: 2, ..Default::default() };
+        let dst = MtimeTolerance { ignore_shifts: true, ..fat };
+        
+        assert!(mtime_matches(base, base + Duration::from_millis(1500), fat));
+        assert!(!mtime_matches(base, base + Duration::from_secs(3), fat));
+        assert!(!mtime_matches(base, base + Duration::from_secs(3600), fat));
+        assert!(mtime_matches(base + Duration::from_secs(3601), base, dst));
+        assert!(mtime_matches(base, base + Duration::from_secs(5 * 3600 + 1800), dst));
+        assert!(!mtime_matches(base, base + Duration::from_secs(3600 + 300), dst));
+        assert!(!mtime_matches(base, base + Duration::from_secs(20 * 3600), dst));
+        
+        // A shifted time is only trusted while samples of the contents match
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.rom");
+        fs::write(&file_path, b"test data").unwrap();
+        let verify = MtimeTolerance { spot_check: true, ..dst };
+        let mut state = IncrementalScanState::new();
+        state.update_file(&file_path, "fake_sha1".to_string(), verify).unwrap();
+        let recorded = state.files.get_mut(&file_path).unwrap();
+        recorded.modified += Duration::from_secs(3600);
+        assert!(!state.needs_rescan(&file_path, verify));
+        fs::write(&file_path, b"Test data").unwrap();
+        assert!(state.needs_rescan(&file_path, verify));
     }
 }
\ No newline at end of file
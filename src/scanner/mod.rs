@@ -4,145 +4,238 @@ pub mod hasher;
 pub mod hasher_optimized;
 pub mod collector;
 pub mod incremental;
+pub mod identify;
+pub mod multipart;
+pub mod overdump;
+pub mod trimmed;
+pub mod nodump;
+pub mod retroachievements;
+pub mod convertible;
+pub mod scrub;
+pub mod disc_image;
+pub mod remote;
+pub mod torrent;
+pub mod classify;
+pub mod zip_check;
+pub mod compressed;
+pub mod wia;
+pub mod chd;
+pub mod header;
+pub mod dump_format;
 
-use std::path::Path;
-use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::error::Result;
-use crate::types::{FileHash, RomDb, RomEntry};
+use crate::error::{Result, RomAuditError};
+use crate::types::{FileDigests, FileHash, HashAlgorithms, RomDb, RomEntry, RomStatus};
 use crate::config::Config;
 use crate::cache::HashCache;
+use crate::progress::Progress;
 
 pub struct Scanner {
     config: Config,
     interrupted: Arc<AtomicBool>,
     cache: HashCache,
     incremental_state: incremental::IncrementalScanState,
+    unreadable: Vec<collector::Unreadable>,
 }
 
 impl Scanner {
     pub fn new(config: Config, interrupted: Arc<AtomicBool>) -> Self {
-        let cache = HashCache::load().unwrap_or_else(|_| HashCache::new());
-        let incremental_state = incremental::IncrementalScanState::load()
-            .unwrap_or_else(|_| incremental::IncrementalScanState::new());
+        let cache = HashCache::load(&config.state_path(HashCache::CACHE_FILE));
+        let incremental_state = incremental::IncrementalScanState::load(&config.state_path(incremental::IncrementalScanState::STATE_FILE));
         
         Scanner { 
             config, 
             interrupted,
             cache,
             incremental_state,
+            unreadable: Vec::new(),
         }
     }
     
+    /// Directories and files skipped by the last scan because they couldn't be read
+    pub fn unreadable(&self) -> &[collector::Unreadable] {
+        &self.unreadable
+    }
+    
     /// Scan files and calculate hashes, identifying which games are present
     pub fn scan_files(
         &mut self,
         scan_path: &Path,
         rom_db: &RomDb,
+    ) -> Result<(Vec<FileHash>, HashSet<String>)> {
+        self.scan_files_into(scan_path, rom_db, Some)
+    }
+    
+    /// Like `scan_files`, but hand each file to `consume` as soon as it is
+    /// hashed, in collection order. Files it gives back are returned with
+    /// the rest; those it keeps, typically by sending them down a
+    /// pipeline, are not.
+    pub fn scan_files_into(
+        &mut self,
+        scan_path: &Path,
+        rom_db: &RomDb,
+        mut consume: impl FnMut(FileHash) -> Option<FileHash>,
     ) -> Result<(Vec<FileHash>, HashSet<String>)> {
         // Collect files
-        let all_files = collector::collect_files_recursively(scan_path, &self.config)?;
+        self.unreadable.clear();
+        let all_files = collector::collect_files_recursively(scan_path, &self.config, &mut self.unreadable)?;
+        
+        // Only compute the digests the DAT actually uses, unless all of them
+        // are wanted in the cache; a quick audit only computes CRC32. Its
+        // cache entries lack the other digests, so the next full run hashes
+        // the files again and confirms the matches.
+        let mut algorithms = HashAlgorithms::from_rom_db(rom_db);
+        if self.config.quick && algorithms.crc {
+            algorithms = HashAlgorithms { crc: true, ..HashAlgorithms::none() };
+        } else if self.config.all_hashes {
+            algorithms = HashAlgorithms { sha1: true, md5: true, crc: true, ..algorithms };
+        } else if algorithms != HashAlgorithms::all() {
+            println!("{}", tr!("scan-hash-subset", algorithms = algorithms.names().join("+")));
+        }
+        if self.config.quick && !algorithms.crc {
+            println!("{}", tr!("scan-quick-no-crc"));
+        }
         
-        // Determine which files need scanning (incremental)
-        let files_to_scan = self.incremental_state.get_files_to_scan(&all_files);
-        let using_incremental = files_to_scan.len() < all_files.len();
+        // Files no DAT size comes close to are unknown without hashing them
+        let prefilter = self.config.size_prefilter.and_then(|slack| SizePrefilter::new(rom_db, slack));
         
-        if using_incremental {
-            println!("Incremental scan: {} total files, {} need scanning, {} cached",
-                     all_files.len(), files_to_scan.len(), all_files.len() - files_to_scan.len());
+        // Determine which files need scanning (incremental); the others use
+        // their cached digests, unless those lack a hash this DAT needs
+        let needs_rescan: HashSet<PathBuf> = self.incremental_state.get_files_to_scan(&all_files, &self.config)
+            .into_iter()
+            .collect();
+        let mut cached = HashMap::new();
+        for file in all_files.iter().filter(|file| !needs_rescan.contains(*file)) {
+            if let Some(cached_info) = self.cache.get_within(file, self.config.mtime_tolerance(file))
+                && algorithms.is_satisfied_by(&cached_info.digests)
+            {
+                cached.insert(file.clone(), cached_info);
+            }
+        }
+        let to_scan = all_files.len() - cached.len();
+        
+        if !cached.is_empty() {
+            println!("{}", tr!("scan-incremental",
+                total = all_files.len(), scanning = to_scan, cached = cached.len()));
         } else {
-            println!("Scanning {} files to identify games and calculate hashes...", all_files.len());
+            println!("{}", tr!("scan-start", files = all_files.len()));
         }
-        println!("This may take a while for large collections.");
+        println!("{}", tr!("scan-slow-note"));
         
-        let bar = ProgressBar::new(files_to_scan.len() as u64);
-        bar.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg} [{eta_precise}]"
-            ).unwrap(),
-        );
+        // The bar counts bytes, so a large image moves it as it is hashed
+        // rather than stalling it, and the ETA holds for mixed sizes
+        let file_sizes: HashMap<PathBuf, u64> = all_files.iter()
+            .filter(|file| !cached.contains_key(*file))
+            .map(|file| (file.clone(), std::fs::metadata(file).map_or(0, |meta| meta.len())))
+            .collect();
+        let bar = Arc::new(Progress::bytes(
+            "scan",
+            file_sizes.values().sum(),
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {binary_bytes_per_sec} {msg} [{eta_precise}]",
+        ));
+        let mut hashed_bytes = 0;
         
+        // Go through the files in collection order, so whatever consumes
+        // them sees the same order on every run
         let mut file_hashes = Vec::new();
         let mut games_with_files = HashSet::new();
-        
-        // First, add cached results for files that haven't changed
-        for file in &all_files {
-            if !files_to_scan.contains(file) {
-                // Use cached data
-                if let Some(cached_info) = self.cache.get(file) {
-                    let matching_entries = find_matching_entries(rom_db, &cached_info.sha1, &cached_info.md5, &cached_info.crc);
-                    
-                    for entry in &matching_entries {
-                        games_with_files.insert(entry.game.clone());
-                    }
-                    
-                    file_hashes.push(FileHash {
-                        path: file.clone(),
-                        sha1: cached_info.sha1,
-                        md5: cached_info.md5,
-                        crc: cached_info.crc,
-                        matching_entries,
-                    });
+        let mut reused_digests = 0;
+        let mut prefiltered = 0;
+        let mut headerless = 0;
+        let mut converted = 0;
+        let hashing = tr!("progress-hashing");
+        for file in all_files {
+            let file_hash = if let Some(cached_info) = cached.remove(&file) {
+                let matching_entries = self.matching_entries(rom_db, &cached_info.digests, cached_info.rom_size());
+                FileHash {
+                    path: file,
+                    size: cached_info.rom_size(),
+                    digests: cached_info.digests,
+                    matching_entries,
                 }
-            }
-        }
-        
-        // Now scan only the files that need it
-        for file in files_to_scan {
-            // Check for interruption
-            if self.interrupted.load(Ordering::Relaxed) {
-                bar.finish_with_message("Interrupted by user!");
-                println!("\nProcess interrupted during scanning.");
-                return Ok((file_hashes, games_with_files));
-            }
-            
-            let filename = file.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-                .to_string();
-            
-            bar.set_message(format!("Hashing: {}", 
-                if filename.len() > 40 { 
-                    format!("...{}", &filename[filename.len()-37..]) 
-                } else { 
-                    filename.clone() 
+            } else {
+                // Check for interruption
+                if self.interrupted.load(Ordering::Relaxed) {
+                    bar.finish_with_message(tr!("interrupted-by-user"));
+                    println!("\n{}", tr!("scan-interrupted"));
+                    return Ok((file_hashes, games_with_files));
                 }
-            ));
-            
-            // Calculate hashes with optimizations
-            let (sha1, md5, crc) = hasher_optimized::calculate_hashes_cached(
-                &file, 
-                self.config.buffer_size,
-                &mut self.cache
-            )?;
-            
-            // Update incremental state
-            self.incremental_state.update_file(&file, sha1.clone())?;
+                
+                let filename = file.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+                
+                bar.set_current_file(&hashing, &filename);
+                
+                let size = file_sizes.get(&file).copied().unwrap_or(0);
+                let (start, progress) = (hashed_bytes, bar.clone());
+                let mut digested = 0;
+                let hashed = hasher_optimized::with_digest_progress(
+                    move |chunk| {
+                        digested += chunk;
+                        progress.set_position(start + digested.min(size));
+                    },
+                    || self.hash_file(file, rom_db, algorithms, prefilter.as_ref(), &mut reused_digests, &mut prefiltered),
+                )?;
+                hashed_bytes += size;
+                bar.set_position(hashed_bytes);
+                match hashed {
+                    Some(file_hash) => file_hash,
+                    None => continue,
+                }
+            };
             
-            // Find matching ROM entries
-            let matching_entries = find_matching_entries(rom_db, &sha1, &md5, &crc);
+            // A file that matches nothing may be a dump with a copier header
+            // the DAT's hashes leave out, or in another byte order. Such dumps
+            // are cartridge-sized, so the digests they match by aren't cached.
+            let file_hash = if file_hash.matching_entries.is_empty() {
+                let find_matches = |digests: &FileDigests, size| self.matching_entries(rom_db, digests, size);
+                let buffer_size = self.config.buffer_size;
+                let rematched = match header::headerless_match(&file_hash.path, buffer_size, algorithms, find_matches) {
+                    Ok(None) => dump_format::converted_match(&file_hash.path, buffer_size, algorithms, find_matches)
+                        .map(|matched| matched.map(|matched| (matched, &mut converted))),
+                    matched => matched.map(|matched| matched.map(|matched| (matched, &mut headerless))),
+                };
+                match rematched {
+                    Ok(Some((matched, count))) => {
+                        *count += 1;
+                        matched
+                    }
+                    Ok(None) | Err(RomAuditError::Io(_)) => file_hash,
+                    Err(e) => return Err(e),
+                }
+            } else {
+                file_hash
+            };
             
             // Track which games have files present
-            for entry in &matching_entries {
+            for entry in &file_hash.matching_entries {
                 games_with_files.insert(entry.game.clone());
             }
-            
-            file_hashes.push(FileHash {
-                path: file,
-                sha1,
-                md5,
-                crc,
-                matching_entries,
-            });
-            
-            bar.inc(1);
+            if let Some(file_hash) = consume(file_hash) {
+                file_hashes.push(file_hash);
+            }
         }
         
-        bar.finish_with_message(format!("Found {} games with files present", games_with_files.len()));
+        bar.finish_with_message(tr!("scan-games-present", games = games_with_files.len()));
+        if self.config.fast_prepass {
+            println!("{}", tr!("scan-prepass", reused = reused_digests, hashed = to_scan - reused_digests - prefiltered));
+        }
+        if prefilter.is_some() {
+            println!("{}", tr!("scan-size-prefilter", skipped = prefiltered));
+        }
+        if headerless > 0 {
+            println!("{}", tr!("scan-headerless", files = headerless));
+        }
+        if converted > 0 {
+            println!("{}", tr!("scan-converted", files = converted));
+        }
         
         // Save cache and incremental state
         self.cache.save()?;
@@ -150,14 +243,250 @@ impl Scanner {
         
         Ok((file_hashes, games_with_files))
     }
+    
+    /// Hash a file that isn't cached, or skip it when the size prefilter
+    /// says it can't match; None when it can't be read
+    fn hash_file(
+        &mut self,
+        file: PathBuf,
+        rom_db: &RomDb,
+        algorithms: HashAlgorithms,
+        prefilter: Option<&SizePrefilter>,
+        reused_digests: &mut usize,
+        prefiltered: &mut usize,
+    ) -> Result<Option<FileHash>> {
+        if let Some(prefilter) = prefilter {
+            match prefilter.skips(&file) {
+                Ok(Some(size)) => {
+                    // Neither cached nor recorded as scanned, so a later DAT can still match it
+                    *prefiltered += 1;
+                    return Ok(Some(FileHash { path: file, digests: FileDigests::default(), size, matching_entries: Vec::new() }));
+                }
+                Ok(None) => {}
+                Err(RomAuditError::Io(e)) => {
+                    self.unreadable.push(collector::Unreadable { path: file, error: e.to_string() });
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        
+        // Calculate hashes with optimizations; files we can't read are skipped
+        let hashed = if self.config.fast_prepass {
+            hasher_optimized::calculate_hashes_prepass(&file, self.config.buffer_size, algorithms, &mut self.cache)
+                .map(|(digests, size, reused)| {
+                    *reused_digests += usize::from(reused);
+                    (digests, size)
+                })
+        } else {
+            hasher_optimized::calculate_hashes_cached(&file, self.config.buffer_size, algorithms, &mut self.cache)
+        };
+        let (digests, size) = match hashed {
+            Ok(hashed) => hashed,
+            Err(RomAuditError::Io(e)) => {
+                self.unreadable.push(collector::Unreadable { path: file, error: e.to_string() });
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        
+        let matching_entries = self.matching_entries(rom_db, &digests, size);
+        let file_hash = FileHash {
+            path: file,
+            digests,
+            size,
+            matching_entries,
+        };
+        
+        // Update incremental state
+        let tolerance = self.config.mtime_tolerance(&file_hash.path);
+        self.incremental_state.update_file(&file_hash.path, file_hash.primary_hash().to_string(), tolerance)?;
+        Ok(Some(file_hash))
+    }
+    
+    /// The entries a file matches; in a quick audit a CRC32 match only
+    /// counts when the DAT size agrees too
+    fn matching_entries(&self, rom_db: &RomDb, digests: &FileDigests, size: u64) -> Vec<RomEntry> {
+        let mut entries = find_matching_entries(rom_db, digests, size);
+        if self.config.quick && digests.sha1.is_empty() && digests.md5.is_empty() && digests.sha256.is_empty() {
+            entries.retain(|entry| entry.size_matches(size));
+        }
+        entries
+    }
+}
+
+/// The ROM sizes of a DAT, to tell files no ROM can match without hashing them
+pub struct SizePrefilter {
+    sizes: BTreeSet<u64>,
+    slack: u64,
+}
+
+impl SizePrefilter {
+    /// None when a ROM has no size, since then any file could match it.
+    /// Disks are left out: CHDs are never skipped.
+    pub fn new(rom_db: &RomDb, slack: u64) -> Option<Self> {
+        let mut sizes = BTreeSet::new();
+        for entry in rom_db.values().flatten().filter(|entry| !entry.is_disk) {
+            sizes.insert(entry.size?);
+        }
+        Some(SizePrefilter { sizes, slack })
+    }
+    
+    /// Whether a ROM's size is within the slack of `size`
+    pub fn could_match(&self, size: u64) -> bool {
+        self.sizes.range(size.saturating_sub(self.slack)..=size.saturating_add(self.slack)).next().is_some()
+    }
+    
+    /// The size of a file that can't match, so needn't be hashed. CHDs and
+    /// compressed files are always hashed, since their size isn't the ROM's.
+    pub fn skips(&self, path: &Path) -> Result<Option<u64>> {
+        let size = std::fs::metadata(path)?.len();
+        if self.could_match(size) || chd::chd_sha1(path)?.is_some() || compressed::Compression::detect(path)?.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(size))
+    }
+}
+
+/// Find all ROM entries matching the given hashes, each entry once. When
+/// entries of different sizes share a hash (usually a CRC32 collision), only
+/// those consistent with the file size are kept.
+pub fn find_matching_entries(rom_db: &RomDb, digests: &FileDigests, size: u64) -> Vec<RomEntry> {
+    let mut entries: Vec<RomEntry> = Vec::new();
+    let candidates = digests.computed()
+        .filter_map(|hash| rom_db.get(hash))
+        .flatten();
+    // An entry is indexed under each of its hashes, so it can be found more than once
+    for entry in candidates {
+        if !entries.iter().any(|e| e.game == entry.game && e.name == entry.name) {
+            entries.push(entry.clone());
+        }
+    }
+    prefer_size_matches(entries, size)
+}
+
+/// Keep the entries whose DAT size is unknown or equal to `size`, unless that
+/// would drop all of them
+pub fn prefer_size_matches(entries: Vec<RomEntry>, size: u64) -> Vec<RomEntry> {
+    if entries.iter().any(|entry| entry.size_matches(size)) {
+        entries.into_iter().filter(|entry| entry.size_matches(size)).collect()
+    } else {
+        entries
+    }
+}
+
+/// A file that matches DAT entries by hash, but none of them at its size
+#[derive(Debug)]
+pub struct SizeMismatch {
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    pub matches: Vec<RomEntry>,
 }
 
-/// Find all ROM entries matching the given hashes
-fn find_matching_entries(rom_db: &RomDb, sha1: &str, md5: &str, crc: &str) -> Vec<RomEntry> {
-    [sha1, md5, crc]
-        .iter()
-        .filter_map(|hash| rom_db.get(*hash))
-        .flatten()
-        .cloned()
+/// Files whose hash matches but whose size doesn't: truncated or overdumped
+/// copies whose CRC32 happens to collide, or a DAT with a wrong size. They
+/// are still organized, as the hash says.
+pub fn size_mismatches(file_hashes: &[FileHash]) -> Vec<SizeMismatch> {
+    file_hashes.iter()
+        .filter(|f| !f.matching_entries.is_empty() && !f.matching_entries.iter().any(|e| e.size_matches(f.size)))
+        .map(|f| SizeMismatch { path: f.path.clone(), size: f.size, matches: f.matching_entries.clone() })
         .collect()
-}
\ No newline at end of file
+}
+
+/// A file that matches DAT entries the DAT marks `status="baddump"`
+#[derive(Debug)]
+pub struct BadDumpMatch {
+    pub path: std::path::PathBuf,
+    pub matches: Vec<RomEntry>,
+}
+
+/// Files that are known bad dumps. They are organized like any match, since
+/// no good dump is known, but are worth replacing once one is.
+pub fn bad_dump_matches(file_hashes: &[FileHash]) -> Vec<BadDumpMatch> {
+    file_hashes.iter()
+        .filter_map(|f| {
+            let matches: Vec<RomEntry> = f.matching_entries.iter()
+                .filter(|e| e.status == RomStatus::BadDump)
+                .cloned()
+                .collect();
+            (!matches.is_empty()).then(|| BadDumpMatch { path: f.path.clone(), matches })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RomHashes;
+
+    #[test]
+    fn test_find_matching_entries_breaks_crc_ties_by_size() {
+        let entry = |game: &str, size: u64| RomEntry {
+            name: format!("{}.bin", game),
+            game: game.to_string(),
+            hashes: RomHashes { sha1: Some(format!("sha1-{}", game)), crc: Some("deadbeef".to_string()), ..Default::default() },
+            size: Some(size),
+            is_disk: false,
+            optional: false,
+            merge: None,
+            status: RomStatus::Good,
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert("deadbeef".to_string(), vec![entry("Small", 16), entry("Large", 32)]);
+        rom_db.insert("sha1-Large".to_string(), vec![entry("Large", 32)]);
+
+        let games = |entries: Vec<RomEntry>| entries.into_iter().map(|e| e.game).collect::<Vec<_>>();
+        let crc = FileDigests { crc: "deadbeef".to_string(), ..Default::default() };
+        let sha1 = FileDigests { sha1: "sha1-Large".to_string(), ..crc.clone() };
+        assert_eq!(games(find_matching_entries(&rom_db, &crc, 32)), ["Large"]);
+        assert_eq!(games(find_matching_entries(&rom_db, &sha1, 32)), ["Large"]);
+        // No size agrees: keep every hash match rather than dropping the file
+        assert_eq!(games(find_matching_entries(&rom_db, &crc, 8)), ["Small", "Large"]);
+
+        let file = |size: u64| FileHash {
+            path: format!("{}.bin", size).into(),
+            digests: crc.clone(),
+            size,
+            matching_entries: find_matching_entries(&rom_db, &crc, size),
+        };
+        let mismatches = size_mismatches(&[file(32), file(8)]);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!((mismatches[0].size, mismatches[0].matches.len()), (8, 2));
+
+        let mut bad = file(32);
+        assert!(bad_dump_matches(std::slice::from_ref(&bad)).is_empty());
+        bad.matching_entries[0].status = RomStatus::BadDump;
+        assert_eq!(bad_dump_matches(&[bad])[0].matches[0].game, "Large");
+    }
+
+    #[test]
+    fn test_size_prefilter() {
+        let entry = |size: Option<u64>| RomEntry {
+            name: "game.nes".to_string(),
+            game: "Game".to_string(),
+            hashes: RomHashes { crc: Some("deadbeef".to_string()), ..Default::default() },
+            size,
+            is_disk: false,
+            optional: false,
+            merge: None,
+            status: RomStatus::Good,
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert("deadbeef".to_string(), vec![entry(Some(40960))]);
+
+        let prefilter = SizePrefilter::new(&rom_db, 16).unwrap();
+        assert!(prefilter.could_match(40960));
+        // An iNES header adds 16 bytes
+        assert!(prefilter.could_match(40976));
+        assert!(!prefilter.could_match(40977));
+        assert!(!prefilter.could_match(1024));
+
+        let dir = tempfile::tempdir().unwrap();
+        let unknown = dir.path().join("unknown.bin");
+        std::fs::write(&unknown, vec![0xAB; 1024]).unwrap();
+        assert_eq!(prefilter.skips(&unknown).unwrap(), Some(1024));
+
+        rom_db.insert("cafebabe".to_string(), vec![entry(None)]);
+        assert!(SizePrefilter::new(&rom_db, 16).is_none());
+    }
+}
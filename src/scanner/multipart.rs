@@ -0,0 +1,200 @@
+// src/scanner/multipart.rs - Files split into numbered parts
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::types::{FileHash, HashAlgorithms, RomDb};
+use super::{find_matching_entries, hasher_optimized};
+
+/// A split file whose parts matched nothing on their own, but do joined
+#[derive(Debug)]
+pub struct SplitFile {
+    /// The parts in order; the joined file hash has the first one's path
+    pub parts: Vec<PathBuf>,
+    pub file_hash: FileHash,
+}
+
+/// The name of the file a first part is split from: `game.iso.001` is part
+/// of `game.iso`, and `game.z01` of the split archive `game.zip`
+pub fn joined_name(path: &Path) -> Option<PathBuf> {
+    let extension = path.extension()?.to_str()?;
+    if extension.len() >= 3 && extension.bytes().all(|b| b.is_ascii_digit()) && extension.parse() == Ok(1u32) {
+        return path.file_stem().map(PathBuf::from);
+    }
+    if extension.eq_ignore_ascii_case("z01") {
+        let zip = if extension.starts_with('Z') { "ZIP" } else { "zip" };
+        return Some(PathBuf::from(path.file_name()?).with_extension(zip));
+    }
+    None
+}
+
+/// All parts of the split file `first` starts, in order: `game.iso.001`,
+/// `game.iso.002`, ... as long as they follow on, or `game.z01`, `game.z02`,
+/// ... and last `game.zip`. None unless `first` is a first part with at
+/// least one more part next to it.
+pub fn split_parts(first: &Path) -> Result<Option<Vec<PathBuf>>> {
+    let Some(joined) = joined_name(first) else {
+        return Ok(None);
+    };
+    let extension = first.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let zip = extension.eq_ignore_ascii_case("z01");
+
+    let mut parts = vec![first.to_path_buf()];
+    loop {
+        let number = parts.len() + 1;
+        let next = if zip {
+            first.with_extension(format!("{}{:02}", &extension[..1], number))
+        } else {
+            first.with_extension(format!("{:0width$}", number, width = extension.len()))
+        };
+        if !next.is_file() {
+            break;
+        }
+        parts.push(next);
+    }
+    if zip {
+        let last = first.with_file_name(joined);
+        if !last.is_file() {
+            return Ok(None);
+        }
+        parts.push(last);
+    }
+    Ok((parts.len() > 1).then_some(parts))
+}
+
+/// The parts a scanned file stands for, when it was hashed joined with the
+/// rest of its split
+pub fn joined_parts(file_hash: &FileHash) -> Result<Option<Vec<PathBuf>>> {
+    let Some(parts) = split_parts(&file_hash.path)? else {
+        return Ok(None);
+    };
+    let mut size = 0;
+    for part in &parts {
+        size += fs::metadata(part)?.len();
+    }
+    Ok((size == file_hash.size).then_some(parts))
+}
+
+/// Reads the parts of a split file one after another
+pub struct Joined {
+    current: Option<File>,
+    rest: std::vec::IntoIter<File>,
+}
+
+impl Joined {
+    pub fn open(parts: &[PathBuf]) -> Result<Self> {
+        let files = parts.iter().map(File::open).collect::<io::Result<Vec<_>>>()?;
+        let mut rest = files.into_iter();
+        Ok(Joined { current: rest.next(), rest })
+    }
+}
+
+impl Read for Joined {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(part) = &mut self.current {
+            let n = part.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.current = self.rest.next();
+        }
+        Ok(0)
+    }
+}
+
+/// Hash each split file whose parts all matched nothing as the one file
+/// they make up, and keep those that match the DAT joined
+pub fn join_split_files(file_hashes: &[FileHash], rom_db: &RomDb, buffer_size: usize) -> Result<Vec<SplitFile>> {
+    let unmatched: HashSet<&Path> = file_hashes.iter()
+        .filter(|f| f.matching_entries.is_empty())
+        .map(|f| f.path.as_path())
+        .collect();
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+
+    let mut split_files = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        let Some(parts) = split_parts(&file.path)? else {
+            continue;
+        };
+        if !parts.iter().all(|part| unmatched.contains(part.as_path())) {
+            continue;
+        }
+        let mut size = 0;
+        for part in &parts {
+            size += fs::metadata(part)?.len();
+        }
+        let digests = hasher_optimized::calculate_hashes_reader(Joined::open(&parts)?, buffer_size, algorithms)?;
+        let matching_entries = find_matching_entries(rom_db, &digests, size);
+        if !matching_entries.is_empty() {
+            let file_hash = FileHash { path: file.path.clone(), digests, size, matching_entries };
+            split_files.push(SplitFile { parts, file_hash });
+        }
+    }
+    Ok(split_files)
+}
+
+/// Write the parts as one file; returns the bytes written
+pub fn copy_joined(parts: &[PathBuf], to: &Path) -> Result<u64> {
+    let mut target = BufWriter::new(File::create(to)?);
+    let written = io::copy(&mut Joined::open(parts)?, &mut target)?;
+    target.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomEntry, RomHashes};
+
+    #[test]
+    fn test_join_split_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let image: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+        let numbered: Vec<PathBuf> = (1..=3).map(|n| dir.path().join(format!("game.iso.{:03}", n))).collect();
+        for (part, data) in numbered.iter().zip(image.chunks(4096)) {
+            fs::write(part, data).unwrap();
+        }
+        assert_eq!(split_parts(&numbered[0]).unwrap().unwrap(), numbered);
+        assert_eq!(split_parts(&numbered[1]).unwrap(), None);
+        assert_eq!(joined_name(&numbered[0]).unwrap(), Path::new("game.iso"));
+
+        let crc = format!("{:08x}", crc32fast::hash(&image));
+        let entry = RomEntry {
+            name: "Game.iso".to_string(),
+            game: "Game".to_string(),
+            hashes: RomHashes { crc: Some(crc.clone()), ..Default::default() },
+            size: Some(image.len() as u64),
+            ..Default::default()
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert(crc, vec![entry]);
+
+        let unmatched: Vec<FileHash> = numbered.iter().map(|part| FileHash {
+            path: part.clone(),
+            digests: FileDigests::default(),
+            size: fs::metadata(part).unwrap().len(),
+            matching_entries: Vec::new(),
+        }).collect();
+        let split_files = join_split_files(&unmatched, &rom_db, 4096).unwrap();
+        assert_eq!(split_files.len(), 1);
+        assert_eq!(split_files[0].parts, numbered);
+        assert_eq!(split_files[0].file_hash.size, image.len() as u64);
+        assert_eq!(joined_parts(&split_files[0].file_hash).unwrap().unwrap(), numbered);
+
+        let joined = dir.path().join("joined.iso");
+        copy_joined(&numbered, &joined).unwrap();
+        assert_eq!(fs::read(&joined).unwrap(), image);
+
+        // A split archive ends with its .zip
+        let archive = ["game.z01", "game.z02", "game.zip"].map(|name| dir.path().join(name));
+        for part in &archive {
+            fs::write(part, b"PK").unwrap();
+        }
+        assert_eq!(split_parts(&archive[0]).unwrap().unwrap(), archive);
+        fs::remove_file(&archive[2]).unwrap();
+        assert_eq!(split_parts(&archive[0]).unwrap(), None);
+    }
+}
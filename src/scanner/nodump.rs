@@ -0,0 +1,123 @@
+// src/scanner/nodump.rs - Name and size based matching for entries without hashes
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::types::{FileHash, GameRoms, RomEntry};
+
+/// Share of name words two names must have in common to be a probable match
+const MIN_NAME_SIMILARITY: f64 = 0.6;
+
+/// An unmatched file that is probably the dump of a nodump entry. It is
+/// never verified, since there are no hashes to verify it against.
+#[derive(Debug)]
+pub struct ProbableMatch {
+    pub path: PathBuf,
+    pub size: u64,
+    pub rom: RomEntry,
+}
+
+/// Pair unmatched files with hashless (nodump) DAT entries by name and size.
+/// The size has to be equal when the DAT gives one; without a size, the
+/// names have to be equal once case and punctuation are ignored.
+pub fn find_probable_matches(file_hashes: &[FileHash], game_roms: &GameRoms) -> Result<Vec<ProbableMatch>> {
+    let mut nodumps: Vec<&RomEntry> = game_roms.values()
+        .flatten()
+        .filter(|rom| !rom.is_disk && rom.hashes.is_empty())
+        .collect();
+    if nodumps.is_empty() {
+        return Ok(Vec::new());
+    }
+    nodumps.sort_by(|a, b| (&a.game, &a.name).cmp(&(&b.game, &b.name)));
+
+    let mut matches = Vec::new();
+    let mut claimed = HashSet::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        let size = file.size;
+        let file_words = name_words(&file_stem(&file.path));
+
+        let best = nodumps.iter()
+            .filter(|rom| !claimed.contains(&(&rom.game, &rom.name)))
+            .filter_map(|rom| {
+                let similarity = name_similarity(&file_words, &name_words(&rom.game))
+                    .max(name_similarity(&file_words, &name_words(&file_stem(Path::new(&rom.name)))));
+                let plausible = match rom.size {
+                    Some(rom_size) => rom_size == size && similarity >= MIN_NAME_SIMILARITY,
+                    None => similarity == 1.0,
+                };
+                plausible.then_some((similarity, *rom))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        if let Some((_, rom)) = best {
+            claimed.insert((&rom.game, &rom.name));
+            matches.push(ProbableMatch { path: file.path.clone(), size, rom: rom.clone() });
+        }
+    }
+
+    Ok(matches)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Lowercase alphanumeric words of a name
+fn name_words(name: &str) -> HashSet<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard similarity of two word sets
+fn name_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn nodump(game: &str, size: Option<u64>) -> RomEntry {
+        RomEntry {
+            name: format!("{}.bin", game),
+            game: game.to_string(),
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_probable_matches_need_size_and_similar_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let unmatched = |name: &str, len: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; len]).unwrap();
+            FileHash { path, digests: Default::default(), size: len as u64, matching_entries: Vec::new() }
+        };
+
+        let mut game_roms = GameRoms::new();
+        for rom in [nodump("Rare Game (Japan)", Some(8)), nodump("Other Game (USA)", None)] {
+            game_roms.insert(rom.game.clone(), vec![rom]);
+        }
+
+        let files = vec![
+            unmatched("rare game (japan) (cart dump).bin", 8),
+            unmatched("Rare Game (Japan).bin", 4),
+            unmatched("other_game_usa.rom", 3),
+        ];
+        let matches = find_probable_matches(&files, &game_roms).unwrap();
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].rom.game, "Rare Game (Japan)");
+        assert!(matches[0].path.ends_with("rare game (japan) (cart dump).bin"));
+        assert_eq!(matches[1].rom.game, "Other Game (USA)");
+    }
+}
@@ -0,0 +1,170 @@
+// src/scanner/overdump.rs - Overdump detection and trimming
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use crate::error::Result;
+use crate::types::{FileHash, HashAlgorithms, RomDb, RomEntry};
+use super::compressed::Compression;
+use super::hasher_optimized::Digests;
+use super::{find_matching_entries, hasher_optimized};
+
+/// A file that is larger than a DAT entry, but whose leading bytes match it:
+/// cutting it down to the verified size gives the DAT's dump
+#[derive(Debug)]
+pub struct Overdump {
+    pub path: PathBuf,
+    pub file_size: u64,
+    pub verified_size: u64,
+    /// The byte everything past the verified size is, when it's all one
+    /// (usually 0x00 or 0xFF padding)
+    pub padding: Option<u8>,
+    pub matches: Vec<RomEntry>,
+}
+
+/// Check every file that matched nothing for overdumps
+pub fn detect_overdumps(file_hashes: &[FileHash], rom_db: &RomDb) -> Result<Vec<Overdump>> {
+    let sizes: BTreeSet<u64> = rom_db.values().flatten().filter_map(|rom| rom.size).filter(|&size| size > 0).collect();
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+
+    let mut overdumps = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        if let Some(overdump) = find_overdump(&file.path, &sizes, rom_db, algorithms)? {
+            overdumps.push(overdump);
+        }
+    }
+    Ok(overdumps)
+}
+
+/// Hash the file once up to the largest DAT size below its own, and look up
+/// the digests of its first bytes at each DAT size on the way, smallest first
+fn find_overdump(
+    path: &Path,
+    sizes: &BTreeSet<u64>,
+    rom_db: &RomDb,
+    algorithms: HashAlgorithms,
+) -> Result<Option<Overdump>> {
+    if Compression::detect(path)?.is_some() {
+        return Ok(None);
+    }
+    let file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    if sizes.range(..file_size).next().is_none() {
+        return Ok(None);
+    }
+    let mmap = unsafe { Mmap::map(&file)? };
+
+    let mut digests = Digests::new(algorithms);
+    let mut hashed = 0;
+    for &size in sizes.range(..file_size) {
+        digests.update(&mmap[hashed as usize..size as usize]);
+        hashed = size;
+
+        let mut matches: Vec<RomEntry> = find_matching_entries(rom_db, &digests.clone().finalize(), size)
+            .into_iter()
+            .filter(|rom| rom.size == Some(size))
+            .collect();
+        matches.sort_by(|a, b| (&a.game, &a.name).cmp(&(&b.game, &b.name)));
+
+        if !matches.is_empty() {
+            return Ok(Some(Overdump {
+                path: path.to_path_buf(),
+                file_size,
+                verified_size: size,
+                padding: padding(&mmap[size as usize..]),
+                matches,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The byte `rest` is made of, if it's all the same byte
+fn padding(rest: &[u8]) -> Option<u8> {
+    let (&first, _) = rest.split_first()?;
+    rest.iter().all(|&b| b == first).then_some(first)
+}
+
+/// Write the overdump cut to its verified size next to the original, and
+/// re-hash the result. Returns the trimmed file if it still matches the DAT.
+pub fn trim_overdump(overdump: &Overdump, rom_db: &RomDb, buffer_size: usize) -> Result<Option<FileHash>> {
+    let trimmed_path = trimmed_path(&overdump.path);
+    let mut source = File::open(&overdump.path)?.take(overdump.verified_size);
+    std::io::copy(&mut source, &mut File::create(&trimmed_path)?)?;
+
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+    let digests = hasher_optimized::calculate_hashes_optimized(&trimmed_path, buffer_size, algorithms)?;
+    let matching_entries = find_matching_entries(rom_db, &digests, overdump.verified_size);
+
+    if matching_entries.is_empty() {
+        fs::remove_file(&trimmed_path)?;
+        return Ok(None);
+    }
+
+    Ok(Some(FileHash { path: trimmed_path, digests, size: overdump.verified_size, matching_entries }))
+}
+
+/// "game.bin" becomes "game.trimmed.bin"
+fn trimmed_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.trimmed.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.trimmed", stem),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomHashes, RomStatus};
+
+    #[test]
+    fn test_detect_and_trim_overdump() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.bin");
+        let mut data = b"ROM!".to_vec();
+        data.extend([0xFF; 4]);
+        fs::write(&path, &data).unwrap();
+
+        // CRC32 of "ROM!" at its real size of 4 bytes
+        let crc = format!("{:08x}", crc32fast::hash(b"ROM!"));
+        let entry = RomEntry {
+            name: "game.bin".to_string(),
+            game: "Game".to_string(),
+            hashes: RomHashes { crc: Some(crc.clone()), ..Default::default() },
+            size: Some(4),
+            is_disk: false,
+            optional: false,
+            merge: None,
+            status: RomStatus::Good,
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert(crc, vec![entry]);
+
+        let unmatched = FileHash {
+            path: path.clone(),
+            digests: FileDigests { crc: "ffffffff".to_string(), ..Default::default() },
+            size: 8,
+            matching_entries: Vec::new(),
+        };
+        let overdumps = detect_overdumps(std::slice::from_ref(&unmatched), &rom_db).unwrap();
+        assert_eq!(overdumps.len(), 1);
+        assert_eq!((overdumps[0].file_size, overdumps[0].verified_size, overdumps[0].padding), (8, 4, Some(0xFF)));
+
+        let trimmed = trim_overdump(&overdumps[0], &rom_db, 4096).unwrap().unwrap();
+        assert_eq!(trimmed.path, dir.path().join("game.trimmed.bin"));
+        assert_eq!(fs::read(&trimmed.path).unwrap(), b"ROM!");
+        assert_eq!(trimmed.matching_entries.len(), 1);
+
+        // Leading bytes that match, followed by anything but padding
+        fs::write(&path, b"ROM!junk").unwrap();
+        let overdumps = detect_overdumps(&[unmatched], &rom_db).unwrap();
+        assert_eq!((overdumps[0].verified_size, overdumps[0].padding), (4, None));
+    }
+}
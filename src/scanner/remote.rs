@@ -0,0 +1,205 @@
+// src/scanner/remote.rs - Audit remote sources by streaming them through rclone
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Deserialize;
+
+use crate::error::{Result, RomAuditError};
+use crate::progress::Progress;
+use crate::types::{FileDigests, HashAlgorithms, RomDb, RomEntry};
+use super::{find_matching_entries, hasher_optimized};
+use super::collector::Unreadable;
+
+/// rclone does the transport, so any backend it supports works
+const RCLONE: &str = "rclone";
+
+/// A scan source reachable through rclone: an `sftp://`, `smb://` or
+/// `http(s)://` URL, or a configured rclone remote such as `seedbox:roms`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSource {
+    /// The source as rclone addresses it
+    pub spec: String,
+}
+
+/// A file on the remote, with the hashes the backend could provide
+#[derive(Debug, Deserialize)]
+pub struct RemoteFile {
+    #[serde(rename = "Path")]
+    pub path: String,
+    #[serde(rename = "Size")]
+    pub size: u64,
+    #[serde(rename = "Hashes", default)]
+    hashes: HashMap<String, String>,
+}
+
+/// A remote file with the DAT entries it matches
+pub struct RemoteMatch {
+    pub file: RemoteFile,
+    pub matches: Vec<RomEntry>,
+    /// Whether the backend's own hashes were used instead of downloading
+    pub remote_hashes: bool,
+}
+
+impl RemoteSource {
+    /// Translate a URL to an rclone on-the-fly remote; anything else with a
+    /// colon is taken to be a configured rclone remote
+    pub fn parse(source: &str) -> Result<Self> {
+        let spec = if let Some((scheme, rest)) = source.split_once("://") {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let (user, host) = match authority.rsplit_once('@') {
+                Some((user, host)) => (Some(user), host),
+                None => (None, authority),
+            };
+            let (host, port) = match host.rsplit_once(':') {
+                Some((host, port)) if port.parse::<u16>().is_ok() => (host, Some(port)),
+                _ => (host, None),
+            };
+            if host.is_empty() {
+                return Err(RomAuditError::InvalidPath(source.to_string()));
+            }
+
+            let mut options = vec![format!("host={}", host)];
+            options.extend(user.map(|user| format!("user={}", user)));
+            options.extend(port.map(|port| format!("port={}", port)));
+            match scheme {
+                "sftp" => format!(":sftp,{}:/{}", options.join(","), path),
+                "smb" => format!(":smb,{}:{}", options.join(","), path),
+                // The http backend takes the whole URL, which must name a directory
+                "http" | "https" => format!(":http,url='{}/':", source.trim_end_matches('/')),
+                _ => return Err(RomAuditError::InvalidPath(format!("unsupported scheme in {}", source))),
+            }
+        } else if source.contains(':') {
+            source.to_string()
+        } else {
+            return Err(RomAuditError::InvalidPath(format!("{} is not a URL or rclone remote", source)));
+        };
+        Ok(RemoteSource { spec })
+    }
+
+    /// Every file below the source, with whatever hashes the backend stores
+    pub fn list(&self) -> Result<Vec<RemoteFile>> {
+        let output = Command::new(RCLONE)
+            .args(["lsjson", "--recursive", "--files-only", "--hash"])
+            .arg(&self.spec)
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(rclone_error)?;
+        if !output.status.success() {
+            return Err(RomAuditError::Custom(format!("rclone lsjson {} failed: {}", self.spec, output.status)));
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// Hash one remote file as it streams in
+    pub fn hash(&self, file: &RemoteFile, buffer_size: usize, algorithms: HashAlgorithms) -> Result<FileDigests> {
+        let mut child = Command::new(RCLONE)
+            .arg("cat")
+            .arg(self.join(&file.path))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(rclone_error)?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let hashes = hasher_optimized::calculate_hashes_reader(stdout, buffer_size, algorithms);
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(RomAuditError::Custom(format!("rclone cat {} failed: {}", file.path, status)));
+        }
+        hashes
+    }
+
+    fn join(&self, path: &str) -> String {
+        if self.spec.ends_with(':') || self.spec.ends_with('/') {
+            format!("{}{}", self.spec, path)
+        } else {
+            format!("{}/{}", self.spec, path)
+        }
+    }
+}
+
+/// Explain a missing rclone rather than failing with a bare "not found"
+fn rclone_error(error: std::io::Error) -> RomAuditError {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        RomAuditError::ConfigError("remote sources need rclone (https://rclone.org) on the PATH".to_string())
+    } else {
+        error.into()
+    }
+}
+
+/// Match every file of a remote source against the DAT, using the backend's
+/// hashes when they cover what the DAT needs and streaming the file otherwise.
+/// Files that can't be streamed are skipped and added to `unreadable`.
+pub fn audit_remote(
+    source: &RemoteSource,
+    rom_db: &RomDb,
+    buffer_size: usize,
+    interrupted: &AtomicBool,
+    unreadable: &mut Vec<Unreadable>,
+) -> Result<Vec<RemoteMatch>> {
+    let files = source.list()?;
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+
+    let bar = Progress::new(
+        "remote",
+        files.len() as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg} [{eta_precise}]",
+    );
+    let mut results = Vec::new();
+    let hashing = tr!("progress-hashing");
+    for file in files {
+        if interrupted.load(Ordering::Relaxed) {
+            bar.finish_with_message(tr!("interrupted-by-user"));
+            return Ok(results);
+        }
+        bar.set_current_file(&hashing, file.path.rsplit('/').next().unwrap_or(&file.path));
+
+        let remote_hash = |name: &str| file.hashes.get(name).map(|h| h.to_lowercase()).unwrap_or_default();
+        let digests = FileDigests {
+            sha1: remote_hash("sha1"),
+            md5: remote_hash("md5"),
+            crc: remote_hash("crc32"),
+            sha256: remote_hash("sha256"),
+        };
+        let remote_hashes = algorithms.is_satisfied_by(&digests);
+        let digests = if remote_hashes {
+            digests
+        } else {
+            match source.hash(&file, buffer_size, algorithms) {
+                Ok(digests) => digests,
+                // Without rclone no file can be read
+                Err(e @ RomAuditError::ConfigError(_)) => return Err(e),
+                Err(e) => {
+                    unreadable.push(Unreadable { path: PathBuf::from(&file.path), error: e.to_string() });
+                    bar.inc(1);
+                    continue;
+                }
+            }
+        };
+
+        let matches = find_matching_entries(rom_db, &digests, file.size);
+        results.push(RemoteMatch { file, matches, remote_hashes });
+        bar.inc(1);
+    }
+    bar.finish_with_message(tr!("remote-checked", files = results.len()));
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_sources() {
+        let spec = |source: &str| RemoteSource::parse(source).unwrap().spec;
+        assert_eq!(spec("sftp://me@seedbox.example:2222/home/me/roms"), ":sftp,host=seedbox.example,user=me,port=2222:/home/me/roms");
+        assert_eq!(spec("smb://nas/share/roms"), ":smb,host=nas:share/roms");
+        assert_eq!(spec("https://example.com/roms/"), ":http,url='https://example.com/roms/':");
+        assert_eq!(spec("seedbox:roms"), "seedbox:roms");
+        assert!(RemoteSource::parse("ftp://example.com/roms").is_err());
+        assert!(RemoteSource::parse("local/dir").is_err());
+    }
+}
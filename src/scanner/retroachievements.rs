@@ -0,0 +1,156 @@
+// src/scanner/retroachievements.rs - RetroAchievements-compatible ROM hashes
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use md5::{Digest, Md5};
+use memmap2::Mmap;
+
+use crate::error::Result;
+use crate::organizer::processor::rom_destination;
+use crate::organizer::rules::GameFolders;
+use crate::types::{GameRoms, KnownRoms};
+
+/// Disc images are hashed from selected tracks and sectors, which isn't supported
+const DISC_EXTENSIONS: &[&str] = &["chd", "cue", "gdi", "iso", "cso", "rvz"];
+
+/// How a ROM is prepared before it is MD5-hashed, following rcheevos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaMethod {
+    Whole,
+    /// Skip a copier or emulator header of this many bytes
+    SkipHeader(usize),
+    /// Convert a byte-swapped (.v64) or little-endian (.n64) N64 ROM to big-endian first
+    N64ByteOrder,
+}
+
+/// RetroAchievements hash of one organized ROM
+#[derive(Debug)]
+pub struct RaHash {
+    pub game: String,
+    pub rom: String,
+    pub path: PathBuf,
+    pub hash: String,
+    pub method: RaMethod,
+}
+
+/// Compute the RA hash of every organized ROM recorded in the database.
+/// ROMs that are missing on disk or are disc images are left out.
+pub fn collection_ra_hashes(
+    game_roms: &GameRoms,
+    known_roms: &KnownRoms,
+    games_needing_folders: &GameFolders,
+    rom_dir: &str,
+) -> Result<Vec<RaHash>> {
+    let present: BTreeSet<(&str, &str)> = known_roms.values()
+        .flatten()
+        .map(|(game, rom)| (game.as_str(), rom.as_str()))
+        .collect();
+
+    let mut hashes = Vec::new();
+    for (game, rom_name) in present {
+        let Some(rom) = game_roms.get(game).into_iter().flatten().find(|rom| rom.name == rom_name) else {
+            continue;
+        };
+        if rom.is_disk {
+            continue;
+        }
+        let path = games_needing_folders.locate(rom, rom_destination(rom, games_needing_folders, rom_dir));
+        if !path.is_file() {
+            continue;
+        }
+        if let Some((hash, method)) = ra_hash(&path)? {
+            hashes.push(RaHash { game: game.to_string(), rom: rom_name.to_string(), path, hash, method });
+        }
+    }
+    Ok(hashes)
+}
+
+/// RA hash of a single file, or `None` for disc images
+pub fn ra_hash(path: &Path) -> Result<Option<(String, RaMethod)>> {
+    let extension = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if DISC_EXTENSIONS.contains(&extension.as_str()) {
+        return Ok(None);
+    }
+
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(Some((hex::encode(Md5::digest([])), RaMethod::Whole)));
+    }
+    let data = unsafe { Mmap::map(&file)? };
+
+    let method = detect_method(&data, &extension);
+    let hash = match method {
+        RaMethod::Whole => Md5::digest(&data[..]),
+        RaMethod::SkipHeader(header) => Md5::digest(&data[header..]),
+        RaMethod::N64ByteOrder => Md5::digest(n64_big_endian(&data)),
+    };
+    Ok(Some((hex::encode(hash), method)))
+}
+
+fn detect_method(data: &[u8], extension: &str) -> RaMethod {
+    let len = data.len();
+    if data.starts_with(b"NES\x1a") || data.starts_with(b"FDS\x1a") {
+        return RaMethod::SkipHeader(16);
+    }
+    if data.starts_with(b"LYNX\0") {
+        return RaMethod::SkipHeader(64);
+    }
+    if data.get(1..10) == Some(b"ATARI7800") {
+        return RaMethod::SkipHeader(128);
+    }
+    if matches!(data.get(..4), Some([0x37, 0x80, 0x40, 0x12] | [0x40, 0x12, 0x37, 0x80])) {
+        return RaMethod::N64ByteOrder;
+    }
+    match extension {
+        "sfc" | "smc" | "swc" | "fig" if len % 8192 == 512 => RaMethod::SkipHeader(512),
+        "pce" if len % 131072 == 512 => RaMethod::SkipHeader(512),
+        _ => RaMethod::Whole,
+    }
+}
+
+/// Reorder an N64 ROM to big-endian (.z64) byte order
+fn n64_big_endian(data: &[u8]) -> Vec<u8> {
+    match data[..4] {
+        // Byte-swapped (.v64): swap each pair of bytes
+        [0x37, 0x80, 0x40, 0x12] => data.chunks(2).flat_map(|pair| pair.iter().rev()).copied().collect(),
+        // Little-endian (.n64): reverse each 32-bit word
+        _ => data.chunks(4).flat_map(|word| word.iter().rev()).copied().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ra_hash_skips_headers_and_fixes_n64_byte_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            path
+        };
+
+        let prg = [0xAAu8; 32];
+        let mut ines = b"NES\x1a".to_vec();
+        ines.extend([0u8; 12]);
+        ines.extend(prg);
+        let (hash, method) = ra_hash(&write("game.nes", &ines)).unwrap().unwrap();
+        assert_eq!(method, RaMethod::SkipHeader(16));
+        assert_eq!(hash, hex::encode(Md5::digest(prg)));
+
+        let z64 = [0x80, 0x37, 0x12, 0x40, 1, 2, 3, 4];
+        let v64 = [0x37, 0x80, 0x40, 0x12, 2, 1, 4, 3];
+        let n64 = [0x40, 0x12, 0x37, 0x80, 4, 3, 2, 1];
+        let expected = hex::encode(Md5::digest(z64));
+        assert_eq!(ra_hash(&write("a.z64", &z64)).unwrap().unwrap(), (expected.clone(), RaMethod::Whole));
+        assert_eq!(ra_hash(&write("a.v64", &v64)).unwrap().unwrap(), (expected.clone(), RaMethod::N64ByteOrder));
+        assert_eq!(ra_hash(&write("a.n64", &n64)).unwrap().unwrap(), (expected, RaMethod::N64ByteOrder));
+
+        assert!(ra_hash(&write("disc.chd", b"MComprHD")).unwrap().is_none());
+    }
+}
@@ -0,0 +1,363 @@
+// src/scanner/scrub.rs - Re-verify a slice of the organized collection on each run
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::logger::present_roms;
+use crate::organizer::processor::rom_destination;
+use crate::organizer::rules::GameFolders;
+use crate::progress::Progress;
+use crate::types::{FileDigests, GameRoms, HashAlgorithms, KnownRoms, RomEntry};
+use super::{dump_format, hasher_optimized, header};
+
+/// When each organized file was last verified, so every run continues with
+/// the files that were checked longest ago
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrubState {
+    pub verified: HashMap<PathBuf, SystemTime>,
+    /// Files whose hashes were taken from elsewhere (`import-have`) rather
+    /// than computed; the next scrub verifies all of them, whatever its percentage
+    #[serde(default)]
+    pub unverified: HashSet<PathBuf>,
+    pub version: u32,
+    /// Where the state was loaded from and is saved to
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ScrubState {
+    const STATE_VERSION: u32 = 1;
+    pub const STATE_FILE: &'static str = ".romaudit_scrub_state.json";
+
+    /// Load scrub state from disk, starting fresh if it's missing or unreadable
+    pub fn load(state_path: &Path) -> Self {
+        let fresh = ScrubState {
+            verified: HashMap::new(),
+            unverified: HashSet::new(),
+            version: Self::STATE_VERSION,
+            path: state_path.to_path_buf(),
+        };
+        let Ok(content) = std::fs::read_to_string(state_path) else {
+            return fresh;
+        };
+
+        match serde_json::from_str::<ScrubState>(&content) {
+            Ok(state) if state.version == Self::STATE_VERSION => ScrubState { path: fresh.path, ..state },
+            _ => fresh,
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// An organized file that no longer matches its DAT entry
+#[derive(Debug)]
+pub struct ScrubFailure {
+    pub path: PathBuf,
+    pub game: String,
+    pub rom: String,
+    pub problem: String,
+}
+
+/// Outcome of one scrub pass
+#[derive(Debug, Default)]
+pub struct ScrubReport {
+    pub checked: usize,
+    pub total: usize,
+    pub failures: Vec<ScrubFailure>,
+}
+
+/// Re-hash `scrub_percent` of the organized files, least recently verified
+/// first, and compare them with the DAT, along with every file still marked
+/// unverified. Disks are skipped: their DAT SHA1 is the CHD's internal hash,
+/// not the file's.
+pub fn scrub(
+    game_roms: &GameRoms,
+    known_roms: &KnownRoms,
+    games_needing_folders: &GameFolders,
+    config: &Config,
+    state: &mut ScrubState,
+    interrupted: &AtomicBool,
+) -> Result<ScrubReport> {
+    let present = present_roms(known_roms);
+    let mut organized: Vec<(PathBuf, &RomEntry)> = game_roms.iter()
+        .flat_map(|(game, roms)| {
+            let present = &present;
+            roms.iter().filter(move |rom| !rom.is_disk && present.contains(&(game.as_str(), rom.name.as_str())))
+        })
+        .map(|rom| (games_needing_folders.locate(rom, rom_destination(rom, games_needing_folders, &config.rom_dir)), rom))
+        .collect();
+
+    // Forget files that are no longer part of the collection
+    let paths: HashSet<&PathBuf> = organized.iter().map(|(path, _)| path).collect();
+    state.verified.retain(|path, _| paths.contains(path));
+    let unverified = organized.iter().filter(|(path, _)| state.unverified.contains(path)).count();
+
+    let order = |path: &PathBuf| (!state.unverified.contains(path), state.verified.get(path).copied().unwrap_or(UNIX_EPOCH));
+    organized.sort_by(|(a, _), (b, _)| order(a).cmp(&order(b)).then_with(|| a.cmp(b)));
+
+    let total = organized.len();
+    let percent = config.scrub_percent.unwrap_or(0).min(100) as usize;
+    let slice = (total * percent).div_ceil(100).max(unverified);
+    let mut report = ScrubReport { total, ..Default::default() };
+
+    let bar = Progress::new(
+        "scrub",
+        slice as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+    );
+    for (path, rom) in organized.into_iter().take(slice) {
+        if interrupted.load(Ordering::Relaxed) {
+            break;
+        }
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string();
+        bar.set_current_file("Verifying", &filename);
+
+        match verify(&path, rom, config.buffer_size) {
+            Ok(None) => {
+                state.unverified.remove(&path);
+                state.verified.insert(path, SystemTime::now());
+            }
+            Ok(Some(problem)) => report.failures.push(failure(path, rom, problem)),
+            Err(e) => report.failures.push(failure(path, rom, e.to_string())),
+        }
+        report.checked += 1;
+        bar.inc(1);
+    }
+    bar.finish_with_message(format!("Verified {} of {} organized files", report.checked, total));
+
+    Ok(report)
+}
+
+/// Organized ROMs a `--quick` audit placed: recorded under their CRC32
+/// although their DAT entry has a stronger hash, as a full scan would have
+/// recorded them under. Returns the recorded hash with each.
+pub fn quick_matches<'a>(game_roms: &'a GameRoms, known_roms: &KnownRoms) -> Vec<(String, &'a RomEntry)> {
+    let mut matches = Vec::new();
+    for (hash, entries) in known_roms {
+        for (game, name) in entries {
+            let Some(rom) = game_roms.get(game).into_iter().flatten().find(|rom| &rom.name == name) else {
+                continue;
+            };
+            let stronger = rom.hashes.sha1.is_some() || rom.hashes.md5.is_some() || rom.hashes.sha256.is_some();
+            if !rom.is_disk && stronger && rom.hashes.crc.as_ref() == Some(hash) {
+                matches.push((hash.clone(), rom));
+            }
+        }
+    }
+    matches.sort_by(|(_, a), (_, b)| (&a.game, &a.name).cmp(&(&b.game, &b.name)));
+    matches
+}
+
+/// Confirm what a `--quick` audit placed by hashing the files with every
+/// algorithm their DAT entries have. Confirmed ROMs are recorded under their
+/// full hash; ROMs that fail are forgotten, so their games are missing again.
+pub fn confirm_quick_matches(
+    game_roms: &GameRoms,
+    known_roms: &mut KnownRoms,
+    games_needing_folders: &GameFolders,
+    config: &Config,
+    interrupted: &AtomicBool,
+) -> Result<ScrubReport> {
+    let matches = quick_matches(game_roms, known_roms);
+    let mut report = ScrubReport { total: matches.len(), ..Default::default() };
+    if matches.is_empty() {
+        return Ok(report);
+    }
+
+    let bar = Progress::new(
+        "confirm",
+        matches.len() as u64,
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+    );
+    for (crc, rom) in matches {
+        if interrupted.load(Ordering::Relaxed) {
+            break;
+        }
+        let path = games_needing_folders.locate(rom, rom_destination(rom, games_needing_folders, &config.rom_dir));
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string();
+        bar.set_current_file("Confirming", &filename);
+
+        let problem = match verify(&path, rom, config.buffer_size) {
+            Ok(problem) => problem,
+            Err(e) => Some(e.to_string()),
+        };
+        let recorded = (rom.game.clone(), rom.name.clone());
+        if let Some(entries) = known_roms.get_mut(&crc) {
+            entries.retain(|entry| *entry != recorded);
+            if entries.is_empty() {
+                known_roms.remove(&crc);
+            }
+        }
+        match problem {
+            None => {
+                let full_hash = [&rom.hashes.sha1, &rom.hashes.sha256, &rom.hashes.md5].into_iter().flatten().next();
+                if let Some(full_hash) = full_hash {
+                    known_roms.entry(full_hash.clone()).or_default().push(recorded);
+                }
+            }
+            Some(problem) => report.failures.push(failure(path, rom, problem)),
+        }
+        report.checked += 1;
+        bar.inc(1);
+    }
+    bar.finish_with_message(format!("Confirmed {} of {} quick-audit matches", report.checked - report.failures.len(), report.total));
+
+    Ok(report)
+}
+
+fn failure(path: PathBuf, rom: &RomEntry, problem: String) -> ScrubFailure {
+    ScrubFailure { path, game: rom.game.clone(), rom: rom.name.clone(), problem }
+}
+
+/// Hash a file with the algorithms its DAT entry has; `Some` describes a
+/// mismatch. A headered dump whose size is the ROM's without the header is
+/// hashed without it, and a dump in another byte order converted, as the
+/// scan matched them.
+fn verify(path: &Path, rom: &RomEntry, buffer_size: usize) -> Result<Option<String>> {
+    let algorithms = HashAlgorithms {
+        sha1: rom.hashes.sha1.is_some(),
+        md5: rom.hashes.md5.is_some(),
+        crc: rom.hashes.crc.is_some(),
+        sha256: rom.hashes.sha256.is_some(),
+    };
+    let size = std::fs::metadata(path)?.len();
+    let headerless = if rom.size_matches(size) {
+        None
+    } else {
+        header::headerless_digests(path, header::skippers(), buffer_size, algorithms)?
+            .filter(|headerless| rom.size_matches(headerless.size))
+    };
+    let found = match headerless {
+        Some(headerless) => headerless.digests,
+        None if !rom.size_matches(size) => {
+            // An SMD dump is its ROM with a header in front
+            if let Some((_, converted, converted_size)) = dump_format::converted_digests(path, buffer_size, algorithms)?
+                && rom.size_matches(converted_size)
+            {
+                return Ok(hash_mismatch(rom, &converted));
+            }
+            return Ok(Some(format!("size is {} bytes, expected {}", size, rom.size.unwrap_or_default())));
+        }
+        None => hasher_optimized::calculate_hashes_optimized(path, buffer_size, algorithms)?,
+    };
+    let problem = hash_mismatch(rom, &found);
+    if problem.is_some()
+        && let Some((_, converted, _)) = dump_format::converted_digests(path, buffer_size, algorithms)?
+        && hash_mismatch(rom, &converted).is_none()
+    {
+        return Ok(None);
+    }
+    Ok(problem)
+}
+
+/// The first hash of the DAT entry that `found` doesn't have
+fn hash_mismatch(rom: &RomEntry, found: &FileDigests) -> Option<String> {
+    let checks = [
+        ("crc", &rom.hashes.crc, &found.crc),
+        ("md5", &rom.hashes.md5, &found.md5),
+        ("sha1", &rom.hashes.sha1, &found.sha1),
+        ("sha256", &rom.hashes.sha256, &found.sha256),
+    ];
+    checks.into_iter().find_map(|(label, expected, found)| {
+        expected.as_ref().filter(|expected| *expected != found).map(|expected| format!("{} is {}, expected {}", label, found, expected))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RomHashes;
+
+    #[test]
+    fn test_scrub_rotates_through_collection_and_finds_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            rom_dir: dir.path().to_str().unwrap().to_string(),
+            scrub_percent: Some(50),
+            ..Config::default()
+        };
+
+        let mut game_roms = GameRoms::new();
+        let mut known_roms = KnownRoms::new();
+        for (game, crc) in [("A", "352441c2"), ("B", "352441c2")] {
+            game_roms.insert(game.to_string(), vec![RomEntry {
+                name: format!("{}.bin", game),
+                game: game.to_string(),
+                hashes: RomHashes { crc: Some(crc.to_string()), ..Default::default() },
+                size: Some(3),
+                ..Default::default()
+            }]);
+            known_roms.entry(crc.to_string()).or_default().push((game.to_string(), format!("{}.bin", game)));
+        }
+        std::fs::write(dir.path().join("A.bin"), b"abc").unwrap();
+        std::fs::write(dir.path().join("B.bin"), b"abd").unwrap();
+
+        let mut state = ScrubState::load(&dir.path().join(ScrubState::STATE_FILE));
+        let interrupted = AtomicBool::new(false);
+        let run = |state: &mut ScrubState| {
+            scrub(&game_roms, &known_roms, &GameFolders::default(), &config, state, &interrupted).unwrap()
+        };
+
+        // A is verified first; B is next in line and fails until repaired
+        let first = run(&mut state);
+        assert_eq!((first.checked, first.total), (1, 2));
+        assert!(first.failures.is_empty());
+        let second = run(&mut state);
+        assert_eq!(second.failures.len(), 1);
+        assert_eq!(second.failures[0].game, "B");
+        let third = run(&mut state);
+        assert_eq!(third.failures[0].game, "B");
+
+        // Imported, unverified files are all checked whatever the percentage
+        state.unverified.extend([dir.path().join("A.bin"), dir.path().join("B.bin")]);
+        let imported = run(&mut state);
+        assert_eq!((imported.checked, imported.failures.len()), (2, 1));
+        assert_eq!(state.unverified, HashSet::from([dir.path().join("B.bin")]));
+    }
+
+    #[test]
+    fn test_confirm_quick_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config { rom_dir: dir.path().to_str().unwrap().to_string(), ..Config::default() };
+
+        // Both were placed by CRC32; only A's content has the DAT's SHA1
+        let mut game_roms = GameRoms::new();
+        let mut known_roms = KnownRoms::new();
+        for game in ["A", "B"] {
+            game_roms.insert(game.to_string(), vec![RomEntry {
+                name: format!("{}.bin", game),
+                game: game.to_string(),
+                hashes: RomHashes {
+                    crc: Some("352441c2".to_string()),
+                    sha1: Some("a9993e364706816aba3e25717850c26c9cd0d89d".to_string()),
+                    ..Default::default()
+                },
+                size: Some(3),
+                ..Default::default()
+            }]);
+            known_roms.entry("352441c2".to_string()).or_default().push((game.to_string(), format!("{}.bin", game)));
+        }
+        std::fs::write(dir.path().join("A.bin"), b"abc").unwrap();
+        std::fs::write(dir.path().join("B.bin"), b"abd").unwrap();
+        assert_eq!(quick_matches(&game_roms, &known_roms).len(), 2);
+
+        let report = confirm_quick_matches(&game_roms, &mut known_roms, &GameFolders::default(), &config, &AtomicBool::new(false)).unwrap();
+        assert_eq!((report.checked, report.total), (2, 2));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].game, "B");
+        assert_eq!(known_roms.len(), 1);
+        assert_eq!(known_roms["a9993e364706816aba3e25717850c26c9cd0d89d"], [("A".to_string(), "A.bin".to_string())]);
+        assert!(quick_matches(&game_roms, &known_roms).is_empty());
+    }
+}
@@ -0,0 +1,463 @@
+// src/scanner/torrent.rs - Check a local copy of a torrent against its piece hashes and the DAT
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use digest::Digest;
+use sha1::Sha1;
+
+use crate::error::{Result, RomAuditError};
+use crate::types::{GameRoms, HashAlgorithms, RomDb, RomEntry};
+use super::find_matching_entries;
+use super::hasher_optimized::Digests;
+
+/// Lists and dictionaries nest far less than this in any real .torrent
+const MAX_NESTING: usize = 64;
+
+/// The parts of a (v1) .torrent file needed to verify the data
+#[derive(Debug)]
+pub struct Torrent {
+    pub name: String,
+    pub piece_length: u64,
+    pieces: Vec<[u8; 20]>,
+    pub files: Vec<TorrentFile>,
+}
+
+/// A file of the torrent, relative to the download directory
+#[derive(Debug)]
+pub struct TorrentFile {
+    pub path: PathBuf,
+    pub length: u64,
+    offset: u64,
+    /// BEP 47 padding: zeros that only align the next file to a piece
+    padding: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    Complete,
+    /// Present, but some of its pieces don't verify
+    Incomplete { bad_pieces: usize },
+    Missing,
+}
+
+/// Verification result for one file, with the DAT entries it is (or would be)
+pub struct FileCheck<'a> {
+    pub file: &'a TorrentFile,
+    pub state: FileState,
+    /// Matched by hash when the whole file could be read, by name otherwise
+    pub dat_entries: Vec<RomEntry>,
+    /// The file's own hashes match the DAT, so it's good even if a piece it
+    /// shares with a damaged neighbour fails
+    pub dat_verified: bool,
+}
+
+impl FileCheck<'_> {
+    pub fn is_good(&self) -> bool {
+        self.state == FileState::Complete || self.dat_verified
+    }
+}
+
+pub struct TorrentCheck<'a> {
+    pub torrent: &'a Torrent,
+    pub good_pieces: usize,
+    pub files: Vec<FileCheck<'a>>,
+}
+
+impl Torrent {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let (root, _) = Bencode::parse(&data).ok_or_else(|| bad_torrent("not bencoded"))?;
+        let info = root.get(b"info").ok_or_else(|| bad_torrent("no info dictionary"))?;
+
+        let name = info.get(b"name").and_then(Bencode::text).ok_or_else(|| bad_torrent("no name"))?;
+        let piece_length = info.get(b"piece length").and_then(Bencode::int)
+            .filter(|&len| len > 0)
+            .ok_or_else(|| bad_torrent("no piece length"))?;
+        let pieces = info.get(b"pieces").and_then(Bencode::bytes)
+            .filter(|pieces| pieces.len() % 20 == 0)
+            .ok_or_else(|| bad_torrent("no v1 piece hashes (v2-only torrents aren't supported)"))?
+            .chunks_exact(20)
+            .map(|hash| hash.try_into().unwrap())
+            .collect();
+
+        let mut files = Vec::new();
+        let mut offset = 0;
+        if let Some(Bencode::List(list)) = info.get(b"files") {
+            for file in list {
+                let length = file.get(b"length").and_then(Bencode::int).ok_or_else(|| bad_torrent("file without length"))?;
+                let padding = file.get(b"attr").and_then(Bencode::bytes).is_some_and(|attr| attr.contains(&b'p'));
+                let Some(Bencode::List(parts)) = file.get(b"path") else {
+                    return Err(bad_torrent("file without path"));
+                };
+                let mut path = PathBuf::from(&name);
+                for part in parts {
+                    let part = part.text().ok_or_else(|| bad_torrent("bad file path"))?;
+                    // Never let a torrent point outside the download directory
+                    if part.is_empty() || part == "." || part == ".." || part.contains(['/', '\\']) {
+                        return Err(bad_torrent("unsafe file path"));
+                    }
+                    path.push(part);
+                }
+                files.push(TorrentFile { path, length, offset, padding });
+                offset = offset.checked_add(length).ok_or_else(|| bad_torrent("files too large"))?;
+            }
+        } else {
+            let length = info.get(b"length").and_then(Bencode::int).ok_or_else(|| bad_torrent("no length"))?;
+            files.push(TorrentFile { path: PathBuf::from(&name), length, offset, padding: false });
+        }
+
+        Ok(Torrent { name, piece_length, pieces, files })
+    }
+
+    /// Files that hold data, leaving out BEP 47 padding
+    pub fn data_files(&self) -> impl Iterator<Item = &TorrentFile> {
+        self.files.iter().filter(|file| !file.padding)
+    }
+
+    pub fn pieces_len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    fn total_length(&self) -> u64 {
+        self.files.iter().map(|f| f.length).sum()
+    }
+
+    /// Pieces overlapping a file
+    fn piece_range(&self, file: &TorrentFile) -> std::ops::Range<usize> {
+        let first = (file.offset / self.piece_length) as usize;
+        let end = (file.offset + file.length).div_ceil(self.piece_length) as usize;
+        first..end.max(first)
+    }
+}
+
+fn bad_torrent(message: &str) -> RomAuditError {
+    RomAuditError::ParseError(format!("invalid torrent: {}", message))
+}
+
+/// Verify the data of `torrent` under `dir` piece by piece in a single pass,
+/// hashing every complete file for the DAT on the way
+pub fn check_torrent<'a>(
+    torrent: &'a Torrent,
+    dir: &Path,
+    rom_db: &RomDb,
+    game_roms: &GameRoms,
+    buffer_size: usize,
+) -> Result<TorrentCheck<'a>> {
+    if torrent.pieces.len() as u64 != torrent.total_length().div_ceil(torrent.piece_length) {
+        return Err(bad_torrent("piece count doesn't match the file sizes"));
+    }
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+    let mut pieces = PieceChecker::new(torrent);
+    let mut file_hashes = Vec::with_capacity(torrent.files.len());
+    let mut buffer = vec![0; buffer_size];
+
+    for file in &torrent.files {
+        if file.padding {
+            buffer.fill(0);
+            let mut left = file.length;
+            while left > 0 {
+                let n = left.min(buffer.len() as u64) as usize;
+                pieces.feed(&buffer[..n]);
+                left -= n as u64;
+            }
+            file_hashes.push(None);
+            continue;
+        }
+        let Ok(handle) = File::open(dir.join(&file.path)) else {
+            pieces.skip(file.length);
+            file_hashes.push(None);
+            continue;
+        };
+        let mut reader = BufReader::new(handle).take(file.length);
+        let mut digests = Digests::new(algorithms);
+        let mut read = 0;
+        loop {
+            match reader.read(&mut buffer)? {
+                0 => break,
+                n => {
+                    pieces.feed(&buffer[..n]);
+                    digests.update(&buffer[..n]);
+                    read += n as u64;
+                }
+            }
+        }
+        // A short file fails every piece past its end
+        pieces.skip(file.length - read);
+        file_hashes.push((read == file.length).then(|| digests.finalize()));
+    }
+    let good = pieces.finish();
+
+    let mut by_name: HashMap<&str, Vec<&RomEntry>> = HashMap::new();
+    for rom in game_roms.values().flatten() {
+        by_name.entry(rom.name.as_str()).or_default().push(rom);
+    }
+
+    let files = torrent.files.iter().zip(file_hashes)
+        .filter(|(file, _)| !file.padding)
+        .map(|(file, hashes)| {
+            let bad_pieces = torrent.piece_range(file).filter(|&i| !good[i]).count();
+            let state = match &hashes {
+                None if bad_pieces > 0 || file.length > 0 => FileState::Missing,
+                _ if bad_pieces > 0 => FileState::Incomplete { bad_pieces },
+                _ => FileState::Complete,
+            };
+            let by_hash = match hashes {
+                Some(digests) => find_matching_entries(rom_db, &digests, file.length),
+                None => Vec::new(),
+            };
+            let dat_verified = !by_hash.is_empty();
+            let dat_entries = if dat_verified || state == FileState::Complete {
+                by_hash
+            } else {
+                let name = file.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                by_name.get(name).into_iter().flatten().map(|rom| (*rom).clone()).collect()
+            };
+            FileCheck { file, state, dat_entries, dat_verified }
+        })
+        .collect();
+
+    Ok(TorrentCheck { torrent, good_pieces: good.iter().filter(|&&ok| ok).count(), files })
+}
+
+impl TorrentCheck<'_> {
+    /// Games with at least one file in the torrent, and whether all of those are complete
+    pub fn games(&self) -> BTreeMap<&str, bool> {
+        let mut games = BTreeMap::new();
+        for check in &self.files {
+            for rom in &check.dat_entries {
+                let complete = games.entry(rom.game.as_str()).or_insert(true);
+                *complete &= check.is_good();
+            }
+        }
+        games
+    }
+}
+
+/// Feeds the torrent's data through SHA1 piece by piece; skipped bytes fail
+/// their pieces without being hashed
+struct PieceChecker<'a> {
+    torrent: &'a Torrent,
+    total: u64,
+    hasher: Sha1,
+    filled: u64,
+    failed: bool,
+    good: Vec<bool>,
+}
+
+impl<'a> PieceChecker<'a> {
+    fn new(torrent: &'a Torrent) -> Self {
+        PieceChecker { torrent, total: torrent.total_length(), hasher: Sha1::new(), filled: 0, failed: false, good: Vec::new() }
+    }
+
+    fn current_length(&self) -> u64 {
+        let start = self.good.len() as u64 * self.torrent.piece_length;
+        self.torrent.piece_length.min(self.total - start)
+    }
+
+    fn feed(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = (self.current_length() - self.filled).min(data.len() as u64) as usize;
+            if !self.failed {
+                self.hasher.update(&data[..take]);
+            }
+            data = &data[take..];
+            self.advance(take as u64);
+        }
+    }
+
+    fn skip(&mut self, mut len: u64) {
+        while len > 0 {
+            let take = (self.current_length() - self.filled).min(len);
+            self.failed = true;
+            len -= take;
+            self.advance(take);
+        }
+    }
+
+    fn advance(&mut self, len: u64) {
+        self.filled += len;
+        if self.filled == self.current_length() {
+            let hash = self.hasher.finalize_reset();
+            let index = self.good.len();
+            self.good.push(!self.failed && hash.as_slice() == self.torrent.pieces[index]);
+            self.filled = 0;
+            self.failed = false;
+        }
+    }
+
+    fn finish(self) -> Vec<bool> {
+        self.good
+    }
+}
+
+/// Just enough bencode to read a .torrent file
+#[derive(Debug)]
+enum Bencode<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Bencode<'a>>),
+    Dict(Vec<(&'a [u8], Bencode<'a>)>),
+}
+
+impl<'a> Bencode<'a> {
+    /// Parse one value, returning it and the unparsed rest
+    fn parse(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        Self::parse_nested(data, 0)
+    }
+
+    fn parse_nested(data: &'a [u8], depth: usize) -> Option<(Self, &'a [u8])> {
+        match *data.first()? {
+            b'i' => {
+                let end = data.iter().position(|&b| b == b'e')?;
+                let value = std::str::from_utf8(&data[1..end]).ok()?.parse().ok()?;
+                Some((Bencode::Int(value), &data[end + 1..]))
+            }
+            b'l' | b'd' if depth < MAX_NESTING => {
+                let dict = data[0] == b'd';
+                let mut rest = &data[1..];
+                let (mut items, mut entries) = (Vec::new(), Vec::new());
+                while *rest.first()? != b'e' {
+                    if dict {
+                        let (Bencode::Bytes(key), after_key) = Bencode::parse_nested(rest, depth + 1)? else {
+                            return None;
+                        };
+                        let (value, after_value) = Bencode::parse_nested(after_key, depth + 1)?;
+                        entries.push((key, value));
+                        rest = after_value;
+                    } else {
+                        let (item, after) = Bencode::parse_nested(rest, depth + 1)?;
+                        items.push(item);
+                        rest = after;
+                    }
+                }
+                let value = if dict { Bencode::Dict(entries) } else { Bencode::List(items) };
+                Some((value, &rest[1..]))
+            }
+            b'0'..=b'9' => {
+                let colon = data.iter().position(|&b| b == b':')?;
+                let len: usize = std::str::from_utf8(&data[..colon]).ok()?.parse().ok()?;
+                let bytes = data.get(colon + 1..colon + 1 + len)?;
+                Some((Bencode::Bytes(bytes), &data[colon + 1 + len..]))
+            }
+            _ => None,
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&Bencode<'a>> {
+        match self {
+            Bencode::Dict(entries) => entries.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// A non-negative integer; lengths and sizes are never negative
+    fn int(&self) -> Option<u64> {
+        match self {
+            Bencode::Int(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Bencode::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<String> {
+        self.bytes().map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RomHashes;
+
+    fn bytes(data: &[u8]) -> Vec<u8> {
+        [format!("{}:", data.len()).into_bytes(), data.to_vec()].concat()
+    }
+
+    #[test]
+    fn test_check_torrent_finds_damaged_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // Three 3-byte files in 4-byte pieces: "abc" "def" "ghi"
+        let content = b"abcdefghi";
+        let pieces: Vec<u8> = content.chunks(4).flat_map(|piece| Sha1::digest(piece).to_vec()).collect();
+        let file = |name: &str| [b"d6:lengthi3e4:pathl".to_vec(), bytes(name.as_bytes()), b"ee".to_vec()].concat();
+        let torrent = [
+            b"d4:infod5:filesl".to_vec(), file("a.bin"), file("b.bin"), file("c.bin"),
+            b"e4:name4:pack12:piece lengthi4e6:pieces".to_vec(), bytes(&pieces), b"ee".to_vec(),
+        ].concat();
+        let torrent_path = dir.path().join("pack.torrent");
+        std::fs::write(&torrent_path, torrent).unwrap();
+
+        std::fs::create_dir(dir.path().join("pack")).unwrap();
+        std::fs::write(dir.path().join("pack/a.bin"), b"abc").unwrap();
+        std::fs::write(dir.path().join("pack/b.bin"), b"Xef").unwrap();
+
+        let entry = |name: &str, crc: &str| RomEntry {
+            name: name.to_string(),
+            game: name.trim_end_matches(".bin").to_string(),
+            hashes: RomHashes { crc: Some(crc.to_string()), ..Default::default() },
+            size: Some(3),
+            ..Default::default()
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert("352441c2".to_string(), vec![entry("a.bin", "352441c2")]);
+        let mut game_roms = GameRoms::new();
+        game_roms.insert("a".to_string(), vec![entry("a.bin", "352441c2")]);
+        game_roms.insert("c".to_string(), vec![entry("c.bin", "00000000")]);
+
+        let torrent = Torrent::load(&torrent_path).unwrap();
+        let check = check_torrent(&torrent, dir.path(), &rom_db, &game_roms, 2).unwrap();
+        let states: Vec<_> = check.files.iter().map(|f| f.state).collect();
+        // Piece 0 covers a.bin and the start of the damaged b.bin
+        assert_eq!(states, [
+            FileState::Incomplete { bad_pieces: 1 },
+            FileState::Incomplete { bad_pieces: 2 },
+            FileState::Missing,
+        ]);
+        assert_eq!(check.good_pieces, 0);
+        // a.bin is intact: its own hash matches the DAT
+        assert!(check.files[0].is_good());
+        assert!(!check.files[1].is_good());
+        assert_eq!(check.files[0].dat_entries[0].game, "a");
+        assert_eq!(check.files[2].dat_entries[0].game, "c");
+    }
+
+    #[test]
+    fn test_torrent_padding_and_malformed_input() {
+        let dir = tempfile::tempdir().unwrap();
+        // "abc" padded to a 4-byte piece, then "defg"
+        let pieces: Vec<u8> = [&b"abc\0"[..], b"defg"].iter().flat_map(|piece| Sha1::digest(piece).to_vec()).collect();
+        let file = |name: &str, length: u64, attr: &str| [
+            format!("d4:attr{}:{}6:lengthi{}e4:pathl", attr.len(), attr, length).into_bytes(), bytes(name.as_bytes()), b"ee".to_vec(),
+        ].concat();
+        let torrent = [
+            b"d4:infod5:filesl".to_vec(), file("a.bin", 3, ""), file("1", 1, "p"), file("b.bin", 4, "x"),
+            b"e4:name4:pack12:piece lengthi4e6:pieces".to_vec(), bytes(&pieces), b"ee".to_vec(),
+        ].concat();
+        let torrent_path = dir.path().join("pack.torrent");
+        std::fs::write(&torrent_path, &torrent).unwrap();
+        std::fs::create_dir(dir.path().join("pack")).unwrap();
+        std::fs::write(dir.path().join("pack/a.bin"), b"abc").unwrap();
+        std::fs::write(dir.path().join("pack/b.bin"), b"defg").unwrap();
+
+        let torrent = Torrent::load(&torrent_path).unwrap();
+        let check = check_torrent(&torrent, dir.path(), &RomDb::new(), &GameRoms::new(), 2).unwrap();
+        assert_eq!(check.good_pieces, 2);
+        assert_eq!(check.files.len(), 2);
+        assert!(check.files.iter().all(|f| f.state == FileState::Complete));
+
+        // Deep nesting fails instead of overflowing the stack, negative lengths are rejected
+        let nested = [vec![b'l'; 100_000], vec![b'e'; 100_000]].concat();
+        assert!(Bencode::parse(&nested).is_none());
+        std::fs::write(&torrent_path, b"d4:infod6:lengthi-1e4:name1:a12:piece lengthi4e6:pieces0:ee").unwrap();
+        assert!(Torrent::load(&torrent_path).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// src/scanner/trimmed.rs - Trimmed NDS and 3DS dumps
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::types::{FileHash, HashAlgorithms, RomDb, RomEntry};
+use super::compressed::Compression;
+use super::{find_matching_entries, hasher_optimized};
+
+/// Cartridge space past the end of the data is 0xFF, which trimming cuts off
+const PADDING: u8 = 0xFF;
+
+/// Bytes of the header that give the cartridge size
+const HEAD_LEN: u64 = 0x200;
+
+/// CRC-16 of the Nintendo logo, at 0x15C of every NDS header
+const NDS_LOGO_CRC: [u8; 2] = [0x56, 0xCF];
+
+/// A file smaller than its cartridge that matches a DAT entry once padded
+/// back to the cartridge size
+#[derive(Debug)]
+pub struct Trimmed {
+    pub path: PathBuf,
+    /// `NDS` or `3DS`
+    pub system: &'static str,
+    pub file_size: u64,
+    pub cart_size: u64,
+    pub matches: Vec<RomEntry>,
+}
+
+/// The system and cartridge size a header gives. An NDS header has its
+/// capacity at 0x14 as 128 KB << n; a 3DS (NCSD) one has its image size at
+/// 0x104 in media units, 512 bytes << the unit size flag at 0x18E.
+pub fn cart_size(head: &[u8]) -> Option<(&'static str, u64)> {
+    if head.get(0x100..0x104) == Some(b"NCSD") {
+        let units = u32::from_le_bytes(head.get(0x104..0x108)?.try_into().ok()?) as u64;
+        let unit_size = 0x200u64.checked_shl(*head.get(0x18E)? as u32)?;
+        return Some(("3DS", units * unit_size));
+    }
+    if head.get(0x15C..0x15E) == Some(&NDS_LOGO_CRC[..]) {
+        let capacity = *head.get(0x14)?;
+        return (capacity <= 15).then(|| ("NDS", (128 * 1024) << capacity));
+    }
+    None
+}
+
+/// Check every file that matched nothing for a trimmed NDS or 3DS dump
+pub fn detect_trimmed(file_hashes: &[FileHash], rom_db: &RomDb, buffer_size: usize) -> Result<Vec<Trimmed>> {
+    let sizes: BTreeSet<u64> = rom_db.values().flatten().filter_map(|rom| rom.size).collect();
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+
+    let mut trimmed = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        if let Some(found) = find_trimmed(&file.path, &sizes, rom_db, algorithms, buffer_size)? {
+            trimmed.push(found);
+        }
+    }
+    Ok(trimmed)
+}
+
+/// Hash the file padded to the cartridge size its header gives, when the DAT
+/// has a ROM of that size
+fn find_trimmed(
+    path: &Path,
+    sizes: &BTreeSet<u64>,
+    rom_db: &RomDb,
+    algorithms: HashAlgorithms,
+    buffer_size: usize,
+) -> Result<Option<Trimmed>> {
+    if Compression::detect(path)?.is_some() {
+        return Ok(None);
+    }
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let mut head = Vec::with_capacity(HEAD_LEN as usize);
+    (&mut file).take(HEAD_LEN).read_to_end(&mut head)?;
+
+    let Some((system, cart_size)) = cart_size(&head) else {
+        return Ok(None);
+    };
+    if file_size >= cart_size || !sizes.contains(&cart_size) {
+        return Ok(None);
+    }
+
+    let digests = hasher_optimized::calculate_hashes_reader(padded(path, cart_size)?, buffer_size, algorithms)?;
+    let mut matches: Vec<RomEntry> = find_matching_entries(rom_db, &digests, cart_size)
+        .into_iter()
+        .filter(|rom| rom.size == Some(cart_size))
+        .collect();
+    if matches.is_empty() {
+        return Ok(None);
+    }
+    matches.sort_by(|a, b| (&a.game, &a.name).cmp(&(&b.game, &b.name)));
+
+    Ok(Some(Trimmed { path: path.to_path_buf(), system, file_size, cart_size, matches }))
+}
+
+/// The file followed by padding up to `size`
+fn padded(path: &Path, size: u64) -> Result<impl Read> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(file.chain(io::repeat(PADDING)).take(size))
+}
+
+/// Write the dump padded back to its cartridge size next to the original,
+/// and re-hash the result. Returns the padded file if it still matches the DAT.
+pub fn repad_trimmed(trimmed: &Trimmed, rom_db: &RomDb, buffer_size: usize) -> Result<Option<FileHash>> {
+    let padded_path = padded_path(&trimmed.path);
+    let mut target = BufWriter::new(File::create(&padded_path)?);
+    io::copy(&mut padded(&trimmed.path, trimmed.cart_size)?, &mut target)?;
+    target.flush()?;
+    drop(target);
+
+    let algorithms = HashAlgorithms::from_rom_db(rom_db);
+    let digests = hasher_optimized::calculate_hashes_optimized(&padded_path, buffer_size, algorithms)?;
+    let matching_entries = find_matching_entries(rom_db, &digests, trimmed.cart_size);
+
+    if matching_entries.is_empty() {
+        fs::remove_file(&padded_path)?;
+        return Ok(None);
+    }
+
+    Ok(Some(FileHash { path: padded_path, digests, size: trimmed.cart_size, matching_entries }))
+}
+
+/// "game.nds" becomes "game.padded.nds"
+fn padded_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.padded.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.padded", stem),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FileDigests, RomHashes};
+
+    #[test]
+    fn test_detect_and_repad_trimmed_nds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.nds");
+
+        // A 128 KB cartridge holding 0x300 bytes of data
+        let mut full = vec![PADDING; 128 * 1024];
+        full[..0x300].iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        full[0x14] = 0;
+        full[0x15C..0x15E].copy_from_slice(&NDS_LOGO_CRC);
+        fs::write(&path, &full[..0x300]).unwrap();
+
+        let crc = format!("{:08x}", crc32fast::hash(&full));
+        let entry = RomEntry {
+            name: "game.nds".to_string(),
+            game: "Game".to_string(),
+            hashes: RomHashes { crc: Some(crc.clone()), ..Default::default() },
+            size: Some(full.len() as u64),
+            ..Default::default()
+        };
+        let mut rom_db = RomDb::new();
+        rom_db.insert(crc, vec![entry]);
+
+        let unmatched = FileHash {
+            path: path.clone(),
+            digests: FileDigests { crc: "ffffffff".to_string(), ..Default::default() },
+            size: 0x300,
+            matching_entries: Vec::new(),
+        };
+        let trimmed = detect_trimmed(&[unmatched], &rom_db, 4096).unwrap();
+        assert_eq!(trimmed.len(), 1);
+        assert_eq!((trimmed[0].system, trimmed[0].file_size, trimmed[0].cart_size), ("NDS", 0x300, 128 * 1024));
+
+        let repadded = repad_trimmed(&trimmed[0], &rom_db, 4096).unwrap().unwrap();
+        assert_eq!(repadded.path, dir.path().join("game.padded.nds"));
+        assert_eq!(fs::read(&repadded.path).unwrap(), full);
+        assert_eq!(repadded.matching_entries.len(), 1);
+
+        let mut ncsd = vec![0; 0x200];
+        ncsd[0x100..0x104].copy_from_slice(b"NCSD");
+        ncsd[0x104..0x108].copy_from_slice(&0x40000u32.to_le_bytes());
+        assert_eq!(cart_size(&ncsd), Some(("3DS", 128 * 1024 * 1024)));
+    }
+}
@@ -0,0 +1,427 @@
+// src/scanner/wia.rs - GameCube disc images in WIA and RVZ format
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Length of the first header plus the start of the second, up to the disc type
+pub const DETECT_LEN: usize = 0x4C;
+/// Second header: disc type, compression, chunk size, disc header and tables
+const HEADER_LEN: usize = 0x48 + 0xDC;
+/// Bytes of the disc header kept uncompressed in the second header
+const DISC_HEADER_LEN: usize = 0x80;
+/// A group starts on a boundary of this many bytes of the disc
+const BLOCK_SIZE: u64 = 0x8000;
+/// Chunks, groups and tables larger than this aren't made by any tool
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+/// Set in an RVZ group size when the group is compressed, and in a packed
+/// run size when the run is junk data
+const RVZ_FLAG: u32 = 0x8000_0000;
+
+const DISC_TYPE_GAMECUBE: u32 = 1;
+
+/// Whether the start of a WIA or RVZ file says it holds a GameCube disc.
+/// Wii discs are stored decrypted and without their hashes, which would have
+/// to be redone to get the Redump image back, so only GameCube ones are read.
+pub fn is_gamecube(head: &[u8]) -> bool {
+    head.get(0x48..0x4C) == Some(&DISC_TYPE_GAMECUBE.to_be_bytes()[..])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    None,
+    Purge,
+    Bzip2,
+    Lzma,
+    Lzma2,
+    Zstd,
+}
+
+/// A stretch of the disc outside any partition, stored as a run of groups
+struct RawData {
+    offset: u64,
+    size: u64,
+    group: usize,
+    groups: usize,
+}
+
+/// Where a group is in the file. For RVZ, `compressed` can be false for a
+/// group stored as it is, and `packed` is the size of the group before its
+/// junk runs were expanded, or 0 if it has none.
+struct Group {
+    offset: u64,
+    size: u64,
+    compressed: bool,
+    packed: u64,
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("not a valid WIA/RVZ image: {}", what))
+}
+
+fn be32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn be64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_be_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Write the GameCube ISO a WIA (`rvz` false) or RVZ file holds. The first
+/// 0x80 bytes of the disc come from the header, the rest from groups of
+/// `chunk_size` bytes listed in the raw data table; whatever no table entry
+/// covers is zeros. All sizes come from the file and are checked against its
+/// length before anything is allocated for them.
+pub fn decompress(mut input: impl Read + Seek, rvz: bool, output: &mut impl Write) -> io::Result<()> {
+    let input_len = input.seek(SeekFrom::End(0))?;
+    input.seek(SeekFrom::Start(0))?;
+    let mut header = [0u8; HEADER_LEN];
+    input.read_exact(&mut header)?;
+    if !is_gamecube(&header) {
+        return Err(invalid("not a GameCube disc"));
+    }
+    let disc = &header[0x48..];
+    let iso_size = be64(&header, 0x24);
+    let method = match be32(disc, 0x04) {
+        0 => Method::None,
+        1 if !rvz => Method::Purge,
+        2 => Method::Bzip2,
+        3 => Method::Lzma,
+        4 => Method::Lzma2,
+        5 if rvz => Method::Zstd,
+        _ => return Err(invalid("unknown compression")),
+    };
+    let chunk_size = be32(disc, 0x0C) as u64;
+    if chunk_size == 0 || !chunk_size.is_multiple_of(BLOCK_SIZE) || chunk_size > MAX_SIZE {
+        return Err(invalid("bad chunk size"));
+    }
+    let props = &disc[0xD5..0xD5 + (disc[0xD4] as usize).min(7)];
+    let codec = Codec { method, props };
+
+    let table = |count: u32, entry_len: u64, offset: u64, size: u32, input: &mut dyn ReadSeek| -> io::Result<Vec<u8>> {
+        let len = (count as u64).checked_mul(entry_len).filter(|&len| len <= MAX_SIZE)
+            .ok_or_else(|| invalid("table too large"))?;
+        let stored = read_stored(input, offset, size as u64, input_len)?;
+        codec.decode(&stored, len)
+    };
+    let raw_table = table(be32(disc, 0xB4), 0x18, be64(disc, 0xB8), be32(disc, 0xC0), &mut input)?;
+    let group_len = if rvz { 0x0C } else { 0x08 };
+    let group_table = table(be32(disc, 0xC4), group_len, be64(disc, 0xC8), be32(disc, 0xD0), &mut input)?;
+
+    let mut raw_data: Vec<RawData> = raw_table.chunks_exact(0x18).map(|entry| RawData {
+        offset: be64(entry, 0x00),
+        size: be64(entry, 0x08),
+        group: be32(entry, 0x10) as usize,
+        groups: be32(entry, 0x14) as usize,
+    }).collect();
+    raw_data.sort_by_key(|raw| raw.offset);
+    let groups: Vec<Group> = group_table.chunks_exact(group_len as usize).map(|entry| {
+        let size = be32(entry, 4);
+        Group {
+            offset: (be32(entry, 0) as u64) << 2,
+            size: (if rvz { size & !RVZ_FLAG } else { size }) as u64,
+            compressed: !rvz || size & RVZ_FLAG != 0,
+            packed: if rvz { be32(entry, 8) as u64 } else { 0 },
+        }
+    }).collect();
+
+    let head_len = (DISC_HEADER_LEN as u64).min(iso_size);
+    output.write_all(&disc[0x10..0x10 + head_len as usize])?;
+    let mut written = head_len;
+    for raw in &raw_data {
+        let end = raw.offset.checked_add(raw.size).filter(|&end| end <= iso_size)
+            .ok_or_else(|| invalid("raw data past the end of the disc"))?;
+        if raw.offset < written.max(DISC_HEADER_LEN as u64) && raw.size > 0 {
+            return Err(invalid("overlapping raw data"));
+        }
+        let table = raw.group.checked_add(raw.groups).and_then(|last| groups.get(raw.group..last))
+            .ok_or_else(|| invalid("raw data past the end of the group table"))?;
+
+        // Groups start on the block boundary at or before the data
+        let base = raw.offset - raw.offset % BLOCK_SIZE;
+        for (i, group) in table.iter().enumerate() {
+            let start = base + i as u64 * chunk_size;
+            if start >= end {
+                break;
+            }
+            let len = chunk_size.min(end - start);
+            let data = read_group(&mut input, group, &codec, len, start, input_len)?;
+            let skip = written.saturating_sub(start);
+            write_zeros(output, start.saturating_sub(written))?;
+            output.write_all(&data[skip.min(len) as usize..])?;
+            written = written.max(start + len);
+        }
+    }
+    write_zeros(output, iso_size - written)?;
+    Ok(())
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Read `size` bytes at `offset`, which must lie within the file
+fn read_stored(input: &mut dyn ReadSeek, offset: u64, size: u64, input_len: u64) -> io::Result<Vec<u8>> {
+    if offset.checked_add(size).is_none_or(|end| end > input_len) || size > MAX_SIZE {
+        return Err(invalid("data past the end of the file"));
+    }
+    input.seek(SeekFrom::Start(offset))?;
+    let mut stored = vec![0u8; size as usize];
+    input.read_exact(&mut stored)?;
+    Ok(stored)
+}
+
+/// The `len` bytes of the disc a group holds, starting at disc offset `start`
+fn read_group(input: &mut dyn ReadSeek, group: &Group, codec: &Codec, len: u64, start: u64, input_len: u64) -> io::Result<Vec<u8>> {
+    // An empty group is all zeros
+    if group.size == 0 {
+        return Ok(vec![0; len as usize]);
+    }
+    let stored = read_stored(input, group.offset, group.size, input_len)?;
+    let unpacked_len = if group.packed != 0 { group.packed.min(MAX_SIZE) } else { len };
+    let data = if group.compressed {
+        codec.decode(&stored, unpacked_len)?
+    } else {
+        stored
+    };
+    if group.packed != 0 {
+        return unpack(&data, len, start);
+    }
+    if (data.len() as u64) < len {
+        return Err(invalid("short group"));
+    }
+    Ok(data)
+}
+
+/// A group's compression, with the properties an LZMA stream needs
+struct Codec<'a> {
+    method: Method,
+    props: &'a [u8],
+}
+
+impl Codec<'_> {
+    /// Decompress `data`, which must give at least `len` bytes
+    fn decode(&self, data: &[u8], len: u64) -> io::Result<Vec<u8>> {
+        let lzma_error = |e: lzma_rs::error::Error| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+        let mut out = Vec::with_capacity(len as usize);
+        match self.method {
+            Method::None => out.extend_from_slice(data),
+            Method::Purge => purge(data, len, &mut out)?,
+            Method::Bzip2 => {
+                bzip2::read::BzDecoder::new(data).take(len).read_to_end(&mut out)?;
+            }
+            Method::Lzma => {
+                // The properties byte and dictionary size an .lzma header would start with
+                let mut stream = self.props.get(..5).ok_or_else(|| invalid("missing LZMA properties"))?.to_vec();
+                stream.extend_from_slice(data);
+                let options = lzma_rs::decompress::Options {
+                    unpacked_size: lzma_rs::decompress::UnpackedSize::UseProvided(Some(len)),
+                    ..Default::default()
+                };
+                lzma_rs::lzma_decompress_with_options(&mut &stream[..], &mut out, &options).map_err(lzma_error)?;
+            }
+            Method::Lzma2 => lzma_rs::lzma2_decompress(&mut { data }, &mut out).map_err(lzma_error)?,
+            Method::Zstd => {
+                let decoder = ruzstd::decoding::StreamingDecoder::new(data)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+                decoder.take(len).read_to_end(&mut out)?;
+            }
+        }
+        if (out.len() as u64) < len {
+            return Err(invalid("short group"));
+        }
+        out.truncate(len as usize);
+        Ok(out)
+    }
+}
+
+/// WIA purge: segments of (offset, size, bytes) over a zeroed group, then a
+/// SHA-1 of the segments
+fn purge(data: &[u8], len: u64, out: &mut Vec<u8>) -> io::Result<()> {
+    out.resize(len as usize, 0);
+    let mut segments = data.get(..data.len().saturating_sub(20)).unwrap_or_default();
+    while !segments.is_empty() {
+        let head = segments.get(..8).ok_or_else(|| invalid("bad purge segment"))?;
+        let (offset, size) = (be32(head, 0) as usize, be32(head, 4) as usize);
+        let bytes = segments.get(8..8 + size).ok_or_else(|| invalid("bad purge segment"))?;
+        out.get_mut(offset..offset + size).ok_or_else(|| invalid("bad purge segment"))?.copy_from_slice(bytes);
+        segments = &segments[8 + size..];
+    }
+    Ok(())
+}
+
+/// Expand an RVZ packed group: runs of a size followed by either the bytes
+/// themselves or, for junk, the seed of the generator that makes them
+fn unpack(mut data: &[u8], len: u64, start: u64) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(len as usize);
+    while (out.len() as u64) < len {
+        let head = data.get(..4).ok_or_else(|| invalid("short packed group"))?;
+        let size = be32(head, 0);
+        data = &data[4..];
+        let run = (size & !RVZ_FLAG) as usize;
+        if run as u64 > len - out.len() as u64 {
+            return Err(invalid("packed run past the end of the group"));
+        }
+        if size & RVZ_FLAG != 0 {
+            let seed = data.get(..SEED_LEN * 4).ok_or_else(|| invalid("short packed group"))?;
+            data = &data[SEED_LEN * 4..];
+            let offset = start + out.len() as u64;
+            let mut junk = Junk::new(seed);
+            junk.skip((offset % BLOCK_SIZE) as usize);
+            let from = out.len();
+            out.resize(from + run, 0);
+            junk.fill(&mut out[from..]);
+        } else {
+            out.extend_from_slice(data.get(..run).ok_or_else(|| invalid("short packed group"))?);
+            data = &data[run..];
+        }
+    }
+    Ok(out)
+}
+
+/// Words in the junk generator's seed
+const SEED_LEN: usize = 17;
+const LFG_K: usize = 521;
+const LFG_J: usize = 32;
+
+/// The lagged Fibonacci generator Nintendo's tools fill unused disc space
+/// with, which RVZ stores as just its seed
+struct Junk {
+    buffer: [u32; LFG_K],
+    position: usize,
+}
+
+impl Junk {
+    fn new(seed: &[u8]) -> Self {
+        let mut buffer = [0u32; LFG_K];
+        for (word, bytes) in buffer.iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in SEED_LEN..LFG_K {
+            buffer[i] = (buffer[i - 17] << 23) ^ (buffer[i - 16] >> 9) ^ buffer[i - 1];
+        }
+        // The output takes bits 18-25 of each word rather than 16-23 for its second byte
+        for word in &mut buffer {
+            *word = (*word & 0xFF00_FFFF) | ((*word >> 2) & 0x00FF_0000);
+        }
+        let mut junk = Junk { buffer, position: 0 };
+        for _ in 0..4 {
+            junk.forward();
+        }
+        junk
+    }
+
+    fn forward(&mut self) {
+        for i in 0..LFG_J {
+            self.buffer[i] ^= self.buffer[i + LFG_K - LFG_J];
+        }
+        for i in LFG_J..LFG_K {
+            self.buffer[i] ^= self.buffer[i - LFG_J];
+        }
+    }
+
+    fn skip(&mut self, count: usize) {
+        self.position += count;
+        while self.position >= LFG_K * 4 {
+            self.forward();
+            self.position -= LFG_K * 4;
+        }
+    }
+
+    fn fill(&mut self, out: &mut [u8]) {
+        for byte in out {
+            *byte = self.buffer[self.position / 4].to_be_bytes()[self.position % 4];
+            self.skip(1);
+        }
+    }
+}
+
+/// Gaps between raw data are written from this, so they need no allocation
+const ZEROS: [u8; 4096] = [0; 4096];
+
+fn write_zeros(output: &mut impl Write, mut count: u64) -> io::Result<()> {
+    while count > 0 {
+        let n = count.min(ZEROS.len() as u64) as usize;
+        output.write_all(&ZEROS[..n])?;
+        count -= n as u64;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::compressed::{payload_name, Compression};
+    use std::io::Cursor;
+
+    /// A GameCube RVZ with a zstd group, a stored group packed with a junk
+    /// run, and an empty group, and its tables zstd-compressed too
+    #[test]
+    fn test_gamecube_rvz() {
+        let chunk = BLOCK_SIZE as usize;
+        let iso_size = 3 * chunk - 0x100;
+        let mut iso: Vec<u8> = (0..iso_size).map(|i| (i % 251) as u8).collect();
+        iso[..6].copy_from_slice(b"GALE01");
+
+        let seed: Vec<u8> = (0..SEED_LEN as u32 * 4).map(|i| (i * 37 + 11) as u8).collect();
+        let mut junk = Junk::new(&seed);
+        junk.skip(0x100);
+        junk.fill(&mut iso[chunk + 0x100..2 * chunk]);
+        iso[2 * chunk..].fill(0);
+
+        let zstd = |data: &[u8]| ruzstd::encoding::compress_to_vec(data, ruzstd::encoding::CompressionLevel::Fastest);
+        let group0 = zstd(&iso[..chunk]);
+        let mut group1 = ((0x100u32).to_be_bytes()).to_vec();
+        group1.extend_from_slice(&iso[chunk..chunk + 0x100]);
+        group1.extend_from_slice(&((chunk as u32 - 0x100) | RVZ_FLAG).to_be_bytes());
+        group1.extend_from_slice(&seed);
+
+        let mut file = vec![0u8; HEADER_LEN];
+        let mut groups = Vec::new();
+        for (data, compressed, packed) in [(&group0, true, 0), (&group1, false, group1.len())] {
+            let size = data.len() as u32 | if compressed { RVZ_FLAG } else { 0 };
+            groups.extend_from_slice(&((file.len() as u32) >> 2).to_be_bytes());
+            groups.extend_from_slice(&size.to_be_bytes());
+            groups.extend_from_slice(&(packed as u32).to_be_bytes());
+            file.extend_from_slice(data);
+            file.resize(file.len().next_multiple_of(4), 0);
+        }
+        groups.extend_from_slice(&[0; 12]);
+        let mut raw = 0x80u64.to_be_bytes().to_vec();
+        raw.extend_from_slice(&(iso_size as u64 - 0x80).to_be_bytes());
+        raw.extend_from_slice(&0u32.to_be_bytes());
+        raw.extend_from_slice(&3u32.to_be_bytes());
+
+        let (raw, groups) = (zstd(&raw), zstd(&groups));
+        file[..4].copy_from_slice(b"RVZ\x01");
+        file[0x24..0x2C].copy_from_slice(&(iso_size as u64).to_be_bytes());
+        let (disc, tables) = (0x48, file.len() as u64);
+        file[disc..disc + 4].copy_from_slice(&DISC_TYPE_GAMECUBE.to_be_bytes());
+        file[disc + 0x04..disc + 0x08].copy_from_slice(&5u32.to_be_bytes());
+        file[disc + 0x0C..disc + 0x10].copy_from_slice(&(chunk as u32).to_be_bytes());
+        file[disc + 0x10..disc + 0x90].copy_from_slice(&iso[..0x80]);
+        file[disc + 0xB4..disc + 0xB8].copy_from_slice(&1u32.to_be_bytes());
+        file[disc + 0xB8..disc + 0xC0].copy_from_slice(&tables.to_be_bytes());
+        file[disc + 0xC0..disc + 0xC4].copy_from_slice(&(raw.len() as u32).to_be_bytes());
+        file[disc + 0xC4..disc + 0xC8].copy_from_slice(&3u32.to_be_bytes());
+        file[disc + 0xC8..disc + 0xD0].copy_from_slice(&(tables + raw.len() as u64).to_be_bytes());
+        file[disc + 0xD0..disc + 0xD4].copy_from_slice(&(groups.len() as u32).to_be_bytes());
+        file.extend_from_slice(&raw);
+        file.extend_from_slice(&groups);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.rvz");
+        std::fs::write(&path, &file).unwrap();
+        assert_eq!(Compression::detect(&path).unwrap(), Some(Compression::Rvz));
+        assert_eq!(payload_name(&path), std::path::Path::new("game.iso"));
+        let mut out = Vec::new();
+        assert_eq!(Compression::Rvz.decompress(&path, &mut out).unwrap(), iso_size as u64);
+        assert_eq!(out, iso);
+
+        // A Wii disc isn't read, and a group table claiming more than the file holds is rejected
+        let mut wii = file.clone();
+        wii[disc + 3] = 2;
+        assert!(decompress(Cursor::new(&wii), true, &mut Vec::new()).is_err());
+        let mut huge = file.clone();
+        huge[disc + 0xD0..disc + 0xD4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(decompress(Cursor::new(&huge), true, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// src/scanner/zip_check.rs - Structural checks of ZIP archives before their members are trusted
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::error::Result;
+use crate::types::FileHash;
+use super::identify::is_zip;
+
+/// Local file header signature, and the size of its fixed part
+const LOCAL_HEADER_MAGIC: &[u8; 4] = b"PK\x03\x04";
+const LOCAL_HEADER_LEN: usize = 30;
+
+/// General purpose flag: CRC and sizes follow the data instead of the header
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+
+/// An archive that should be downloaded again
+#[derive(Debug)]
+pub struct CorruptZip {
+    pub path: PathBuf,
+    pub problems: Vec<String>,
+}
+
+/// Check a ZIP archive: the central directory has to be readable, every
+/// local header has to agree with its central directory entry, and every
+/// member has to decompress to its stored size and CRC. Returns what is
+/// wrong; empty if the archive is sound.
+pub fn check_zip(path: &Path) -> Result<Vec<String>> {
+    let mut archive = match ZipArchive::new(BufReader::new(File::open(path)?)) {
+        Ok(archive) => archive,
+        Err(e) => return Ok(vec![format!("central directory: {}", e)]),
+    };
+    let mut raw = File::open(path)?;
+    let mut problems = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut member = match archive.by_index(index) {
+            Ok(member) => member,
+            Err(e) => {
+                problems.push(format!("entry {}: {}", index, e));
+                continue;
+            }
+        };
+        let name = member.name().to_string();
+        if let Err(problem) = compare_local_header(&mut raw, &member) {
+            problems.push(format!("{}: {}", name, problem));
+            continue;
+        }
+        if member.is_dir() {
+            continue;
+        }
+        // The reader checks the stored CRC once the member is read to its end
+        let expected = member.size();
+        match std::io::copy(&mut member, &mut std::io::sink()) {
+            Ok(size) if size != expected => {
+                problems.push(format!("{}: {} bytes, central directory says {}", name, size, expected));
+            }
+            Ok(_) => {}
+            Err(e) => problems.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    Ok(problems)
+}
+
+/// The local header of a member against what the central directory says
+fn compare_local_header(raw: &mut File, member: &zip::read::ZipFile) -> std::result::Result<(), String> {
+    let mut header = [0u8; LOCAL_HEADER_LEN];
+    raw.seek(SeekFrom::Start(member.header_start()))
+        .and_then(|_| raw.read_exact(&mut header))
+        .map_err(|_| "local header is past the end of the file".to_string())?;
+    if &header[..4] != LOCAL_HEADER_MAGIC {
+        return Err("no local header where the central directory points".to_string());
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([header[offset], header[offset + 1]]);
+    let u32_at = |offset: usize| u32::from_le_bytes([header[offset], header[offset + 1], header[offset + 2], header[offset + 3]]);
+
+    let mut name = vec![0u8; u16_at(26) as usize];
+    raw.read_exact(&mut name).map_err(|_| "local header is past the end of the file".to_string())?;
+    if name != member.name_raw() {
+        return Err(format!("local header names it {}", String::from_utf8_lossy(&name)));
+    }
+
+    let central_method = match member.compression() {
+        CompressionMethod::Stored => Some(0),
+        CompressionMethod::Deflated => Some(8),
+        _ => None,
+    };
+    if central_method.is_some_and(|method| method != u16_at(8)) {
+        return Err("compression method differs from the central directory".to_string());
+    }
+
+    // With a data descriptor the local header leaves CRC and sizes at zero
+    if u16_at(6) & FLAG_DATA_DESCRIPTOR == 0 {
+        if u32_at(14) != member.crc32() {
+            return Err(format!("local header CRC {:08x}, central directory {:08x}", u32_at(14), member.crc32()));
+        }
+        // 0xFFFFFFFF means the size is in the ZIP64 extra field
+        let compressed = u32_at(18);
+        if compressed != u32::MAX && u64::from(compressed) != member.compressed_size() {
+            return Err(format!("local header size {}, central directory {}", compressed, member.compressed_size()));
+        }
+    }
+    Ok(())
+}
+
+/// Unmatched files that are ZIP archives and fail `check_zip`
+pub fn find_corrupt_zips(file_hashes: &[FileHash]) -> Result<Vec<CorruptZip>> {
+    let mut corrupt = Vec::new();
+    for file in file_hashes.iter().filter(|f| f.matching_entries.is_empty()) {
+        if !is_zip(&file.path)? {
+            continue;
+        }
+        let problems = check_zip(&file.path)?;
+        if !problems.is_empty() {
+            corrupt.push(CorruptZip { path: file.path.clone(), problems });
+        }
+    }
+    Ok(corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_check_zip_finds_damage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("set.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("a.bin", options).unwrap();
+        writer.write_all(b"abcdefgh").unwrap();
+        writer.finish().unwrap();
+        assert!(check_zip(&path).unwrap().is_empty());
+
+        // Flip a byte of the stored data: the CRC no longer matches
+        let mut bytes = std::fs::read(&path).unwrap();
+        let data = bytes.windows(8).position(|w| w == b"abcdefgh").unwrap();
+        bytes[data] = b'x';
+        std::fs::write(&path, &bytes).unwrap();
+        let problems = check_zip(&path).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("a.bin: "));
+
+        // Cut off the central directory
+        std::fs::write(&path, &bytes[..data + 8]).unwrap();
+        assert!(check_zip(&path).unwrap()[0].starts_with("central directory"));
+    }
+}
@@ -0,0 +1,87 @@
+// src/search.rs - Fuzzy search over game names
+
+use crate::logger::sort;
+
+/// How well a name matches the query; lower is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchKind {
+    Exact,
+    Prefix,
+    Substring,
+    AllWords,
+    Subsequence,
+}
+
+/// Best matches for `query` among `names`, best first, at most `limit`.
+///
+/// Matching ignores case. Names containing the whole query rank above names
+/// containing each of its words, which rank above names that merely contain
+/// the query's letters in order (so "smb3" finds "Super Mario Bros. 3").
+pub fn fuzzy_search<'a>(query: &str, names: impl IntoIterator<Item = &'a str>, limit: usize) -> Vec<&'a str> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut hits: Vec<(MatchKind, &str)> = names.into_iter()
+        .filter_map(|name| match_kind(&query, &words, name).map(|kind| (kind, name)))
+        .collect();
+
+    hits.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| sort::natural_cmp(a.1, b.1)));
+    hits.dedup_by(|a, b| a.1 == b.1);
+    hits.into_iter().take(limit).map(|(_, name)| name).collect()
+}
+
+fn match_kind(query: &str, words: &[&str], name: &str) -> Option<MatchKind> {
+    let name = name.to_lowercase();
+
+    if name == query {
+        Some(MatchKind::Exact)
+    } else if name.starts_with(query) {
+        Some(MatchKind::Prefix)
+    } else if name.contains(query) {
+        Some(MatchKind::Substring)
+    } else if words.iter().all(|word| name.contains(word)) {
+        Some(MatchKind::AllWords)
+    } else if is_subsequence(query, &name) {
+        Some(MatchKind::Subsequence)
+    } else {
+        None
+    }
+}
+
+/// Whether the query's letters and digits appear in the name in order
+fn is_subsequence(query: &str, name: &str) -> bool {
+    let mut name_chars = name.chars();
+    query.chars()
+        .filter(|c| c.is_alphanumeric())
+        .all(|q| name_chars.any(|n| n == q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_search_ranking() {
+        let names = [
+            "Super Mario Bros. 3 (USA)",
+            "Super Mario Land (World)",
+            "Mario Kart",
+            "Dr. Mario (World)",
+            "Tetris (World)",
+        ];
+
+        assert_eq!(fuzzy_search("mario", names, 10), vec![
+            "Mario Kart",
+            "Dr. Mario (World)",
+            "Super Mario Bros. 3 (USA)",
+            "Super Mario Land (World)",
+        ]);
+        assert_eq!(fuzzy_search("land mario", names, 10), vec!["Super Mario Land (World)"]);
+        assert_eq!(fuzzy_search("smb3", names, 10), vec!["Super Mario Bros. 3 (USA)"]);
+        assert_eq!(fuzzy_search("mario", names, 1), vec!["Mario Kart"]);
+        assert!(fuzzy_search("zelda", names, 10).is_empty());
+    }
+}
@@ -1,14 +1,42 @@
 // src/types.rs - Shared type definitions
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RomEntry {
     pub name: String,
     pub game: String,
     pub hashes: RomHashes,
+    pub size: Option<u64>,
     pub is_disk: bool,
+    /// MAME marks some ROMs and disks as not needed for the game to run
+    #[serde(default)]
+    pub optional: bool,
+    /// Name of the same ROM in the parent or BIOS set (MAME `merge=`)
+    #[serde(default)]
+    pub merge: Option<String>,
+    #[serde(default)]
+    pub status: RomStatus,
+}
+
+/// A DAT's `status=` for a ROM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RomStatus {
+    #[default]
+    Good,
+    /// The only dump known is bad; its hashes are what a bad copy has
+    BadDump,
+    /// No one has dumped it, so there are no hashes to match
+    NoDump,
+}
+
+impl RomEntry {
+    /// Whether a file of `size` bytes could be this entry. Disks are CHD
+    /// files, whose size never equals the DAT size.
+    pub fn size_matches(&self, size: u64) -> bool {
+        self.is_disk || self.size.is_none_or(|rom_size| rom_size == size)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -16,35 +44,269 @@ pub struct RomHashes {
     pub sha1: Option<String>,
     pub md5: Option<String>,
     pub crc: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl RomHashes {
+    /// Every hash the DAT gives, strongest first
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        [&self.sha256, &self.sha1, &self.md5, &self.crc].into_iter().flatten()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// Hex digests computed for a file; those of algorithms that weren't
+/// selected are empty
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigests {
+    pub sha1: String,
+    pub md5: String,
+    pub crc: String,
+    pub sha256: String,
+}
+
+impl FileDigests {
+    /// Every digest that was computed, strongest first
+    pub fn computed(&self) -> impl Iterator<Item = &str> {
+        [&self.sha256, &self.sha1, &self.md5, &self.crc].into_iter().map(String::as_str).filter(|h| !h.is_empty())
+    }
+
+    /// The hash a file is known by: SHA1 when it was computed, else the
+    /// strongest one that was
+    pub fn primary(&self) -> &str {
+        [&self.sha1, &self.sha256, &self.md5, &self.crc]
+            .into_iter()
+            .find(|h| !h.is_empty())
+            .map(|h| h.as_str())
+            .unwrap_or("")
+    }
+}
+
+/// Digest algorithms to compute while scanning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashAlgorithms {
+    pub sha1: bool,
+    pub md5: bool,
+    pub crc: bool,
+    pub sha256: bool,
+}
+
+impl HashAlgorithms {
+    pub fn all() -> Self {
+        HashAlgorithms { sha1: true, md5: true, crc: true, sha256: true }
+    }
+
+    pub fn none() -> Self {
+        HashAlgorithms { sha1: false, md5: false, crc: false, sha256: false }
+    }
+
+    /// Select only the algorithms that at least one DAT entry carries.
+    /// Falls back to CRC32, MD5 and SHA1 if the DAT has no hashes at all.
+    pub fn from_rom_db(rom_db: &RomDb) -> Self {
+        let mut needed = Self::none();
+        for entry in rom_db.values().flatten() {
+            needed.sha1 |= entry.hashes.sha1.is_some();
+            needed.md5 |= entry.hashes.md5.is_some();
+            needed.crc |= entry.hashes.crc.is_some();
+            needed.sha256 |= entry.hashes.sha256.is_some();
+            if needed == Self::all() {
+                break;
+            }
+        }
+
+        if needed == Self::none() {
+            HashAlgorithms { sha256: false, ..Self::all() }
+        } else {
+            needed
+        }
+    }
+
+    /// Check whether previously computed hashes (empty = not computed) cover this selection
+    pub fn is_satisfied_by(&self, digests: &FileDigests) -> bool {
+        (!self.sha1 || !digests.sha1.is_empty()) &&
+        (!self.md5 || !digests.md5.is_empty()) &&
+        (!self.crc || !digests.crc.is_empty()) &&
+        (!self.sha256 || !digests.sha256.is_empty())
+    }
+
+    /// Names of the selected algorithms, for display
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.crc { names.push("CRC32"); }
+        if self.md5 { names.push("MD5"); }
+        if self.sha1 { names.push("SHA1"); }
+        if self.sha256 { names.push("SHA256"); }
+        names
+    }
 }
 
 // Maps hash -> list of rom entries that share this hash
 pub type RomDb = HashMap<String, Vec<RomEntry>>;
 
-// Maps sha1 -> list of (game name, rom name) tuples for all satisfied ROMs
+// Maps game name -> all ROM entries of that game, in DAT order
+pub type GameRoms = HashMap<String, Vec<RomEntry>>;
+
+// Maps file hash (SHA1 when computed) -> list of (game name, rom name) tuples for all satisfied ROMs
 pub type KnownRoms = HashMap<String, Vec<(String, String)>>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ScanResult {
     pub have: HashSet<String>,
     pub missing: HashSet<String>,
     pub duplicate: Vec<String>,
     pub unknown: Vec<String>,
-    pub shared_roms: HashMap<String, Vec<String>>, // hash -> list of games that share this ROM
+    pub shared_roms: BTreeMap<String, Vec<String>>, // hash -> sorted list of games that share this ROM
+}
+
+/// How a MAME-style DAT distributes ROMs between parents and clones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatType {
+    Standard,
+    NonMerged,
+    Split,
+    Merged,
 }
 
 #[derive(Debug)]
 pub struct ParsedDat {
+    pub name: String,  // DAT file name without extension, used as the system name
+    pub header: DatHeader,
     pub rom_db: RomDb,
     pub all_games: HashSet<String>,
+    pub game_roms: GameRoms,
+    /// Description, year and manufacturer of the games that have any
+    pub game_info: HashMap<String, GameInfo>,
+    /// Device sets with ROMs that each MAME machine needs alongside its own
+    pub device_refs: HashMap<String, Vec<String>>,
+    /// Clone -> parent, from `cloneof`
+    pub parent_clone_map: HashMap<String, String>,
+    /// Game -> the set it takes merged ROMs from (its parent, or a BIOS), from `romof`
+    pub rom_of: HashMap<String, String>,
+    /// The games that count toward have and missing when a language filter
+    /// or a 1G1R audit narrows them down
+    pub counted: Option<HashSet<String>>,
+    pub issues: Vec<DatIssue>,
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]  // md5 and crc are collected but not directly read in current implementation
+impl ParsedDat {
+    /// A game's short name with its description, year and manufacturer
+    pub fn display_name(&self, game: &str) -> String {
+        match self.game_info.get(game) {
+            Some(info) => info.display(game),
+            None => game.to_string(),
+        }
+    }
+    
+    /// The games have and missing are counted over: all of them, or the
+    /// ones a language filter or 1G1R audit leaves
+    pub fn counted_games(&self) -> &HashSet<String> {
+        self.counted.as_ref().unwrap_or(&self.all_games)
+    }
+    
+    /// Stable identity for per-DAT state. The header name survives DAT
+    /// updates, where the file name usually carries a date or version.
+    pub fn db_namespace(&self) -> &str {
+        self.header.name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// What a DAT's `<header>` says about it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatHeader {
+    /// Without version or date, so it stays the same across releases
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    /// `<clrmamepro forcemerging>`: how the DAT's sets are laid out, which
+    /// beats guessing from the file name
+    pub force_merging: Option<DatType>,
+    /// `<clrmamepro forcenodump>`: whether ROMs no one has dumped are still
+    /// needed (`required`) or not (`obsolete`, `ignore`)
+    pub nodumps_required: Option<bool>,
+    /// `<clrmamepro forcepacking>`: whether sets are meant to be zipped
+    pub zipped_sets: Option<bool>,
+    /// `<clrmamepro header>`: the header skipper file whose headers the
+    /// DAT's hashes leave out, e.g. `No-Intro_NES.xml`
+    pub skipper: Option<String>,
+}
+
+impl DatHeader {
+    /// `name version (date) [author]`, leaving out whatever is missing and
+    /// a date the version already is; the description stands in for a
+    /// missing name. None for a DAT without a header.
+    pub fn summary(&self) -> Option<String> {
+        let mut text = self.name.as_ref().or(self.description.as_ref())?.clone();
+        if let Some(version) = &self.version {
+            text.push(' ');
+            text.push_str(version);
+        }
+        if let Some(date) = self.date.as_ref().filter(|date| Some(*date) != self.version.as_ref()) {
+            text.push_str(&format!(" ({})", date));
+        }
+        if let Some(author) = &self.author {
+            text.push_str(&format!(" [{}]", author));
+        }
+        Some(text)
+    }
+}
+
+/// Descriptive metadata that MAME and some Logiqx DATs carry per game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameInfo {
+    pub description: Option<String>,
+    pub year: Option<String>,
+    pub manufacturer: Option<String>,
+    /// Languages of the game's `<release>` entries, as the DAT writes them
+    pub languages: Vec<String>,
+}
+
+impl GameInfo {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.year.is_none() && self.manufacturer.is_none() && self.languages.is_empty()
+    }
+
+    /// `name - description (year, manufacturer)`, leaving out whatever is
+    /// missing; a description equal to the name (as in No-Intro DATs) is
+    /// not repeated
+    pub fn display(&self, game: &str) -> String {
+        let mut text = game.to_string();
+        if let Some(description) = self.description.as_ref().filter(|d| *d != game) {
+            text.push_str(" - ");
+            text.push_str(description);
+        }
+        let details: Vec<&str> = [&self.year, &self.manufacturer].into_iter().flatten().map(String::as_str).collect();
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        text
+    }
+}
+
+/// A problem found in the DAT file, with its location
+#[derive(Debug, Clone)]
+pub struct DatIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct FileHash {
     pub path: std::path::PathBuf,
-    pub sha1: String,
-    pub md5: String,
-    pub crc: String,
+    pub digests: FileDigests,
+    pub size: u64,
     pub matching_entries: Vec<RomEntry>,
+}
+
+impl FileHash {
+    /// Strongest hash that was actually computed for this file, SHA1
+    /// first. Hashes skipped because the DAT doesn't use them are empty.
+    pub fn primary_hash(&self) -> &str {
+        self.digests.primary()
+    }
 }
\ No newline at end of file